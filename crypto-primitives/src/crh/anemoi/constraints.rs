@@ -0,0 +1,190 @@
+use crate::crh::{
+    anemoi::{TwoToOneCRH, CRH},
+    constraints::CRHSchemeGadget as CRHGadgetTrait,
+    constraints::TwoToOneCRHSchemeGadget as TwoToOneCRHGadgetTrait,
+    CRHScheme, TwoToOneCRHScheme,
+};
+use crate::sponge::{
+    anemoi::{constraints::AnemoiSpongeVar, AnemoiConfig},
+    constraints::CryptographicSpongeVar,
+};
+
+use crate::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    fields::fp::FpVar,
+    GR1CSVar,
+};
+use ark_relations::gr1cs::{Namespace, SynthesisError};
+use ark_std::{borrow::Borrow, marker::PhantomData};
+
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+
+#[derive(Clone)]
+pub struct CRHParametersVar<F: PrimeField + Absorb> {
+    pub parameters: AnemoiConfig<F>,
+}
+
+pub struct CRHGadget<F: PrimeField + Absorb> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> CRHGadgetTrait<CRH<F>, F> for CRHGadget<F> {
+    type InputVar = [FpVar<F>];
+    type OutputVar = FpVar<F>;
+    type ParametersVar = CRHParametersVar<F>;
+
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        input: &Self::InputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        let cs = input.cs();
+
+        if cs.is_none() {
+            let mut constant_input = Vec::new();
+            for var in input.iter() {
+                constant_input.push(var.value()?);
+            }
+            Ok(FpVar::Constant(
+                CRH::<F>::evaluate(&parameters.parameters, constant_input).unwrap(),
+            ))
+        } else {
+            let mut sponge = AnemoiSpongeVar::new(cs, &parameters.parameters);
+            sponge.absorb(&input)?;
+            let res = sponge.squeeze_field_elements(1)?;
+
+            Ok(res[0].clone())
+        }
+    }
+}
+
+pub struct TwoToOneCRHGadget<F: PrimeField + Absorb> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> TwoToOneCRHGadgetTrait<TwoToOneCRH<F>, F> for TwoToOneCRHGadget<F> {
+    type InputVar = FpVar<F>;
+    type OutputVar = FpVar<F>;
+    type ParametersVar = CRHParametersVar<F>;
+
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        left_input: &Self::InputVar,
+        right_input: &Self::InputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        Self::compress(parameters, left_input, right_input)
+    }
+
+    fn compress(
+        parameters: &Self::ParametersVar,
+        left_input: &Self::OutputVar,
+        right_input: &Self::OutputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        // Jive compresses two elements with a permutation of width 2
+        if parameters.parameters.rate + parameters.parameters.capacity != 2 {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let cs = left_input.cs().or(right_input.cs());
+
+        if cs.is_none() {
+            Ok(FpVar::Constant(
+                TwoToOneCRH::<F>::compress(
+                    &parameters.parameters,
+                    left_input.value()?,
+                    right_input.value()?,
+                )
+                .unwrap(),
+            ))
+        } else {
+            let res = AnemoiSpongeVar::jive(
+                cs,
+                &parameters.parameters,
+                &[left_input.clone(), right_input.clone()],
+            )?;
+            Ok(res[0].clone())
+        }
+    }
+}
+
+impl<F: PrimeField + Absorb> AllocVar<AnemoiConfig<F>, F> for CRHParametersVar<F> {
+    fn new_variable<T: Borrow<AnemoiConfig<F>>>(
+        _cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        _mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        f().map(|param| {
+            let parameters = param.borrow().clone();
+            Self { parameters }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::crh::anemoi::constraints::{CRHGadget, CRHParametersVar, TwoToOneCRHGadget};
+    use crate::crh::anemoi::{TwoToOneCRH, CRH};
+    use crate::crh::{CRHScheme, CRHSchemeGadget};
+    use crate::crh::{TwoToOneCRHScheme, TwoToOneCRHSchemeGadget};
+    use crate::sponge::anemoi::AnemoiConfig;
+    use ark_ed_on_bls12_381::Fr;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_r1cs_std::{fields::fp::FpVar, GR1CSVar};
+    use ark_relations::gr1cs::ConstraintSystem;
+    use ark_std::UniformRand;
+
+    #[test]
+    fn test_consistency() {
+        let mut test_rng = ark_std::test_rng();
+
+        let params = CRH::<Fr>::setup(&mut test_rng).unwrap();
+        let test_a: Vec<_> = (0..5).map(|_| Fr::rand(&mut test_rng)).collect();
+        let test_b: Vec<_> = (0..5).map(|_| Fr::rand(&mut test_rng)).collect();
+
+        let crh_a = CRH::<Fr>::evaluate(&params, test_a.clone()).unwrap();
+        let crh_b = CRH::<Fr>::evaluate(&params, test_b.clone()).unwrap();
+        let crh = TwoToOneCRH::<Fr>::compress(&params, crh_a, crh_b).unwrap();
+
+        for constant in [false, true] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let alloc = |elems: &[Fr]| -> Vec<FpVar<Fr>> {
+                elems
+                    .iter()
+                    .map(|elem| {
+                        if constant {
+                            FpVar::Constant(*elem)
+                        } else {
+                            FpVar::new_witness(cs.clone(), || Ok(elem)).unwrap()
+                        }
+                    })
+                    .collect()
+            };
+            let test_a_g = alloc(&test_a);
+            let test_b_g = alloc(&test_b);
+
+            let params_g = CRHParametersVar::<Fr>::new_witness(cs.clone(), || Ok(&params)).unwrap();
+            let crh_a_g = CRHGadget::<Fr>::evaluate(&params_g, &test_a_g).unwrap();
+            let crh_b_g = CRHGadget::<Fr>::evaluate(&params_g, &test_b_g).unwrap();
+            let crh_g = TwoToOneCRHGadget::<Fr>::compress(&params_g, &crh_a_g, &crh_b_g).unwrap();
+
+            assert_eq!(crh_a, crh_a_g.value().unwrap());
+            assert_eq!(crh_b, crh_b_g.value().unwrap());
+            assert_eq!(crh, crh_g.value().unwrap());
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_wrong_width() {
+        let params = AnemoiConfig::<Fr>::generate(2, 2, 128).unwrap();
+        let (left, right) = (Fr::from(1u8), Fr::from(2u8));
+        assert!(TwoToOneCRH::<Fr>::compress(&params, left, right).is_err());
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let params_g = CRHParametersVar::<Fr>::new_witness(cs.clone(), || Ok(&params)).unwrap();
+        let left_g = FpVar::new_witness(cs.clone(), || Ok(left)).unwrap();
+        let right_g = FpVar::new_witness(cs, || Ok(right)).unwrap();
+        assert!(TwoToOneCRHGadget::<Fr>::compress(&params_g, &left_g, &right_g).is_err());
+    }
+}
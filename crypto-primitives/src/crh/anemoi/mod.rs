@@ -0,0 +1,101 @@
+use crate::{
+    crh::{CRHScheme, TwoToOneCRHScheme},
+    sponge::{
+        anemoi::{AnemoiConfig, AnemoiSponge},
+        Absorb, CryptographicSponge,
+    },
+    Error,
+};
+use ark_ff::PrimeField;
+use ark_std::vec::Vec;
+use ark_std::{borrow::Borrow, marker::PhantomData, rand::Rng};
+
+#[cfg(feature = "constraints")]
+pub mod constraints;
+
+/// The security level, in bits, of the parameters returned by `setup`.
+pub const SETUP_SECURITY_LEVEL: u64 = 128;
+
+/// The Anemoi collision-resistant hash function introduced in [BBCPSVW22][anemoi]
+///
+/// [anemoi]: https://eprint.iacr.org/2022/840
+pub struct CRH<F: PrimeField + Absorb> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> CRHScheme for CRH<F> {
+    /// The input to Anemoi is a list of field elements.
+    type Input = [F];
+    /// The output of Anemoi is a single field element. One can change this to a list of field elements to squeeze more outputs.
+    type Output = F;
+    /// The parameters for the Anemoi sponge, e.g. the number of rounds, round constants, Flystel specifications, etc.
+    type Parameters = AnemoiConfig<F>;
+
+    /// Compute the parameters of Anemoi with one column for the Anemoi sponge.
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        AnemoiConfig::generate(1, 1, SETUP_SECURITY_LEVEL)
+    }
+
+    /// Evaluate the Anemoi sponge on the input.
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        input: T,
+    ) -> Result<Self::Output, Error> {
+        let input = input.borrow();
+
+        let mut sponge = AnemoiSponge::new(parameters);
+        sponge.absorb(&input);
+        let res: Vec<F> = sponge.squeeze_field_elements::<F>(1);
+        Ok(res[0])
+    }
+}
+
+/// The 2-to-1 version of the Anemoi collision-resistant hash function introduced in
+/// [BBCPSVW22][anemoi] used in Merkle trees. It compresses the inputs with one call to the
+/// permutation in the Jive mode, which is cheaper than absorbing them in the sponge.
+///
+/// The parameters should have a width `rate + capacity` of 2, otherwise `compress` returns an
+/// error.
+///
+/// [anemoi]: https://eprint.iacr.org/2022/840
+pub struct TwoToOneCRH<F: PrimeField + Absorb> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> TwoToOneCRHScheme for TwoToOneCRH<F> {
+    /// Each of the inputs to the list are field elements.
+    type Input = F;
+    /// The output of Jive is a single field element.
+    type Output = F;
+    /// The parameters for the Anemoi permutation, e.g. the number of rounds, round constants, Flystel specifications, etc.
+    type Parameters = AnemoiConfig<F>;
+
+    /// Compute the parameters of Anemoi with one column for the Anemoi permutation.
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        AnemoiConfig::generate(1, 1, SETUP_SECURITY_LEVEL)
+    }
+
+    /// Evaluate Jive on the inputs left and right.
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        left_input: T,
+        right_input: T,
+    ) -> Result<Self::Output, Error> {
+        Self::compress(parameters, left_input, right_input)
+    }
+
+    /// Compress the inputs left and right using Jive.
+    fn compress<T: Borrow<Self::Output>>(
+        parameters: &Self::Parameters,
+        left_input: T,
+        right_input: T,
+    ) -> Result<Self::Output, Error> {
+        // Jive compresses two elements with a permutation of width 2
+        let width = parameters.rate + parameters.capacity;
+        if width != 2 {
+            return Err(Error::IncorrectInputLength(width));
+        }
+        let res = AnemoiSponge::jive(parameters, &[*left_input.borrow(), *right_input.borrow()]);
+        Ok(res[0])
+    }
+}
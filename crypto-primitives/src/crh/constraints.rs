@@ -1,8 +1,8 @@
-use crate::crh::{CRHScheme, TwoToOneCRHScheme};
+use crate::crh::{CRHScheme, NToOneCRHScheme, TwoToOneCRHScheme};
 use ark_ff::Field;
 use ark_r1cs_std::prelude::*;
 use ark_relations::gr1cs::SynthesisError;
-use ark_std::fmt::Debug;
+use ark_std::{borrow::Borrow, fmt::Debug};
 
 pub trait CRHSchemeGadget<H: CRHScheme, ConstraintF: Field>: Sized {
     type InputVar: ?Sized;
@@ -47,3 +47,27 @@ pub trait TwoToOneCRHSchemeGadget<H: TwoToOneCRHScheme, ConstraintF: Field>: Siz
         right_input: &Self::OutputVar,
     ) -> Result<Self::OutputVar, SynthesisError>;
 }
+
+pub trait NToOneCRHSchemeGadget<H: NToOneCRHScheme, ConstraintF: Field>: Sized {
+    type InputVar: ?Sized;
+    type OutputVar: EqGadget<ConstraintF>
+        + ToBytesGadget<ConstraintF>
+        + CondSelectGadget<ConstraintF>
+        + AllocVar<H::Output, ConstraintF>
+        + GR1CSVar<ConstraintF>
+        + Debug
+        + Clone
+        + Sized;
+
+    type ParametersVar: AllocVar<H::Parameters, ConstraintF> + Clone;
+
+    fn evaluate<T: Borrow<Self::InputVar>>(
+        parameters: &Self::ParametersVar,
+        inputs: &[T],
+    ) -> Result<Self::OutputVar, SynthesisError>;
+
+    fn compress<T: Borrow<Self::OutputVar>>(
+        parameters: &Self::ParametersVar,
+        inputs: &[T],
+    ) -> Result<Self::OutputVar, SynthesisError>;
+}
@@ -0,0 +1,265 @@
+use crate::{
+    crh::{
+        domain_separated::{
+            InnerCRH, LeafCRH, PoseidonLeafCRH, PoseidonTwoToOneCRH, INNER_PREFIX, LEAF_PREFIX,
+            POSEIDON_INNER_TAG, POSEIDON_LEAF_TAG,
+        },
+        poseidon::constraints::CRHParametersVar,
+        CRHScheme, CRHSchemeGadget, TwoToOneCRHScheme, TwoToOneCRHSchemeGadget,
+    },
+    sponge::{
+        constraints::CryptographicSpongeVar, poseidon::constraints::PoseidonSpongeVar, Absorb,
+    },
+};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_relations::gr1cs::{ConstraintSystemRef, SynthesisError};
+use ark_std::marker::PhantomData;
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+
+/// Gadget for [`LeafCRH`], hashing a leaf as `H(0x00 || leaf)` with the gadget `HG` of `H`.
+pub struct LeafCRHGadget<H: CRHScheme<Input = [u8]>, HG> {
+    _hash: PhantomData<H>,
+    _hash_gadget: PhantomData<HG>,
+}
+
+impl<H, HG, F> CRHSchemeGadget<LeafCRH<H>, F> for LeafCRHGadget<H, HG>
+where
+    H: CRHScheme<Input = [u8]>,
+    HG: CRHSchemeGadget<H, F, InputVar = [UInt8<F>]>,
+    F: PrimeField,
+{
+    type InputVar = [UInt8<F>];
+    type OutputVar = HG::OutputVar;
+    type ParametersVar = HG::ParametersVar;
+
+    #[tracing::instrument(target = "gr1cs", skip(parameters))]
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        input: &Self::InputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        let mut buffer = Vec::with_capacity(1 + input.len());
+        buffer.push(UInt8::constant(LEAF_PREFIX));
+        buffer.extend_from_slice(input);
+        HG::evaluate(parameters, &buffer)
+    }
+}
+
+/// Gadget for [`InnerCRH`], hashing two children as `H(0x01 || left || right)` with the gadget
+/// `HG` of `H`.
+///
+/// The children are converted to bytes with [`ToBytesGadget::to_bytes_le`], which should match
+/// `AsRef<[u8]>` on the native digests, as it does for SHA-256.
+pub struct InnerCRHGadget<H: CRHScheme<Input = [u8]>, HG> {
+    _hash: PhantomData<H>,
+    _hash_gadget: PhantomData<HG>,
+}
+
+impl<H, HG, F> TwoToOneCRHSchemeGadget<InnerCRH<H>, F> for InnerCRHGadget<H, HG>
+where
+    H: CRHScheme<Input = [u8]>,
+    H::Output: AsRef<[u8]>,
+    HG: CRHSchemeGadget<H, F, InputVar = [UInt8<F>]>,
+    F: PrimeField,
+{
+    type InputVar = HG::OutputVar;
+    type OutputVar = HG::OutputVar;
+    type ParametersVar = HG::ParametersVar;
+
+    #[tracing::instrument(target = "gr1cs", skip(parameters))]
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        left_input: &Self::InputVar,
+        right_input: &Self::InputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        Self::compress(parameters, left_input, right_input)
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(parameters))]
+    fn compress(
+        parameters: &Self::ParametersVar,
+        left_input: &Self::OutputVar,
+        right_input: &Self::OutputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        let mut buffer = vec![UInt8::constant(INNER_PREFIX)];
+        buffer.extend(left_input.to_bytes_le()?);
+        buffer.extend(right_input.to_bytes_le()?);
+        HG::evaluate(parameters, &buffer)
+    }
+}
+
+/// Returns a Poseidon sponge gadget whose capacity element is the constant `tag`.
+fn tagged_sponge_var<F: PrimeField + Absorb>(
+    cs: ConstraintSystemRef<F>,
+    parameters: &CRHParametersVar<F>,
+    tag: u64,
+) -> PoseidonSpongeVar<F> {
+    let mut sponge = PoseidonSpongeVar::new(cs, &parameters.parameters);
+    sponge.state[0] = FpVar::constant(F::from(tag));
+    sponge
+}
+
+/// Gadget for [`PoseidonLeafCRH`].
+pub struct PoseidonLeafCRHGadget<F: PrimeField + Absorb> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> CRHSchemeGadget<PoseidonLeafCRH<F>, F> for PoseidonLeafCRHGadget<F> {
+    type InputVar = [FpVar<F>];
+    type OutputVar = FpVar<F>;
+    type ParametersVar = CRHParametersVar<F>;
+
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        input: &Self::InputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        let cs = input.cs();
+
+        if cs.is_none() {
+            let mut constant_input = Vec::new();
+            for var in input.iter() {
+                constant_input.push(var.value()?);
+            }
+            Ok(FpVar::Constant(
+                PoseidonLeafCRH::<F>::evaluate(&parameters.parameters, constant_input).unwrap(),
+            ))
+        } else {
+            let mut sponge = tagged_sponge_var(cs, parameters, POSEIDON_LEAF_TAG);
+            sponge.absorb(&input)?;
+            let res = sponge.squeeze_field_elements(1)?;
+            Ok(res[0].clone())
+        }
+    }
+}
+
+/// Gadget for [`PoseidonTwoToOneCRH`].
+pub struct PoseidonTwoToOneCRHGadget<F: PrimeField + Absorb> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> TwoToOneCRHSchemeGadget<PoseidonTwoToOneCRH<F>, F>
+    for PoseidonTwoToOneCRHGadget<F>
+{
+    type InputVar = FpVar<F>;
+    type OutputVar = FpVar<F>;
+    type ParametersVar = CRHParametersVar<F>;
+
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        left_input: &Self::InputVar,
+        right_input: &Self::InputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        Self::compress(parameters, left_input, right_input)
+    }
+
+    fn compress(
+        parameters: &Self::ParametersVar,
+        left_input: &Self::OutputVar,
+        right_input: &Self::OutputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        let cs = left_input.cs().or(right_input.cs());
+
+        if cs.is_none() {
+            Ok(FpVar::Constant(
+                PoseidonTwoToOneCRH::<F>::compress(
+                    &parameters.parameters,
+                    left_input.value()?,
+                    right_input.value()?,
+                )
+                .unwrap(),
+            ))
+        } else {
+            let mut sponge = tagged_sponge_var(cs, parameters, POSEIDON_INNER_TAG);
+            sponge.absorb(left_input)?;
+            sponge.absorb(right_input)?;
+            let res = sponge.squeeze_field_elements(1)?;
+            Ok(res[0].clone())
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::crh::{
+        domain_separated::{
+            constraints::{
+                InnerCRHGadget, LeafCRHGadget, PoseidonLeafCRHGadget, PoseidonTwoToOneCRHGadget,
+            },
+            test::poseidon_parameters,
+            InnerCRH, LeafCRH, PoseidonLeafCRH, PoseidonTwoToOneCRH,
+        },
+        poseidon::constraints::CRHParametersVar,
+        sha256::{constraints::Sha256Gadget, Sha256},
+        CRHScheme, CRHSchemeGadget, TwoToOneCRHScheme, TwoToOneCRHSchemeGadget,
+    };
+    use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+    use ark_relations::gr1cs::ConstraintSystem;
+    use ark_std::UniformRand;
+
+    type F = ark_ed_on_bls12_381::Fr;
+
+    #[test]
+    fn sha256_consistency_test() {
+        let cs = ConstraintSystem::<F>::new_ref();
+        let leaf = b"domain separated leaf";
+        let leaf_var = UInt8::new_witness_vec(cs.clone(), &leaf[..]).unwrap();
+        let params_var =
+            <Sha256Gadget<F> as CRHSchemeGadget<Sha256, F>>::ParametersVar::new_constant(
+                cs.clone(),
+                (),
+            )
+            .unwrap();
+
+        let leaf_hash = LeafCRH::<Sha256>::evaluate(&(), &leaf[..]).unwrap();
+        let leaf_hash_var =
+            LeafCRHGadget::<Sha256, Sha256Gadget<F>>::evaluate(&params_var, &leaf_var).unwrap();
+        assert_eq!(leaf_hash_var.value().unwrap().to_vec(), leaf_hash);
+
+        let inner = InnerCRH::<Sha256>::compress(&(), &leaf_hash, &leaf_hash).unwrap();
+        let inner_var = InnerCRHGadget::<Sha256, Sha256Gadget<F>>::compress(
+            &params_var,
+            &leaf_hash_var,
+            &leaf_hash_var,
+        )
+        .unwrap();
+        assert_eq!(inner_var.value().unwrap().to_vec(), inner);
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn poseidon_consistency_test() {
+        let mut rng = ark_std::test_rng();
+        let params = poseidon_parameters();
+        let inputs: Vec<F> = (0..3).map(|_| F::rand(&mut rng)).collect();
+
+        for constant in [false, true] {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let params_var = CRHParametersVar::new_constant(cs.clone(), &params).unwrap();
+            let inputs_var: Vec<_> = inputs
+                .iter()
+                .map(|x| {
+                    if constant {
+                        FpVar::Constant(*x)
+                    } else {
+                        FpVar::new_witness(cs.clone(), || Ok(*x)).unwrap()
+                    }
+                })
+                .collect();
+
+            let leaf = PoseidonLeafCRH::<F>::evaluate(&params, inputs.as_slice()).unwrap();
+            let leaf_var = PoseidonLeafCRHGadget::<F>::evaluate(&params_var, &inputs_var).unwrap();
+            assert_eq!(leaf_var.value().unwrap(), leaf);
+
+            let inner = PoseidonTwoToOneCRH::<F>::compress(&params, inputs[0], inputs[1]).unwrap();
+            let inner_var = PoseidonTwoToOneCRHGadget::<F>::compress(
+                &params_var,
+                &inputs_var[0],
+                &inputs_var[1],
+            )
+            .unwrap();
+            assert_eq!(inner_var.value().unwrap(), inner);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+}
@@ -0,0 +1,282 @@
+//! Domain-separated hashes for the leaves and the inner nodes of merkle trees.
+//!
+//! If the leaf hash and the two to one hash of a merkle tree are built from the same primitive, the
+//! concatenation of two sibling digests is a leaf hashing to their parent, so a prover can open an
+//! inner node as if it were a leaf. Hashing leaves and inner nodes in separate domains rules this
+//! out, for both [`Path::verify`](crate::merkle_tree::Path::verify) and its gadget.
+//!
+//! * [`LeafCRH`] and [`InnerCRH`] wrap any hash of bytes with the prefixes of
+//!   [RFC 6962](https://www.rfc-editor.org/rfc/rfc6962#section-2.1): leaves are hashed as
+//!   `H(0x00 || leaf)` and inner nodes as `H(0x01 || left || right)`. With SHA-256, this is
+//!   the Certificate Transparency merkle tree hash.
+//! * [`PoseidonLeafCRH`] and [`PoseidonTwoToOneCRH`] set the capacity element of the Poseidon
+//!   sponge to a domain tag, which costs no constraint in-circuit.
+use crate::{
+    crh::{poseidon::SETUP_SECURITY_LEVEL, CRHScheme, TwoToOneCRHScheme},
+    sponge::{
+        poseidon::{find_poseidon_alpha, find_poseidon_parameters, PoseidonConfig, PoseidonSponge},
+        Absorb, CryptographicSponge,
+    },
+    Error,
+};
+use ark_ff::PrimeField;
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+use ark_std::{borrow::Borrow, marker::PhantomData, rand::Rng};
+
+#[cfg(feature = "constraints")]
+pub mod constraints;
+
+/// The byte prepended to leaves by [`LeafCRH`].
+pub const LEAF_PREFIX: u8 = 0x00;
+/// The byte prepended to pairs of children by [`InnerCRH`].
+pub const INNER_PREFIX: u8 = 0x01;
+
+/// The capacity element of the sponge used by [`PoseidonLeafCRH`].
+pub const POSEIDON_LEAF_TAG: u64 = 1;
+/// The capacity element of the sponge used by [`PoseidonTwoToOneCRH`].
+pub const POSEIDON_INNER_TAG: u64 = 2;
+
+/// Hashes a leaf as `H(0x00 || leaf)`.
+pub struct LeafCRH<H: CRHScheme<Input = [u8]>> {
+    _hash: PhantomData<H>,
+}
+
+impl<H: CRHScheme<Input = [u8]>> CRHScheme for LeafCRH<H> {
+    type Input = [u8];
+    type Output = H::Output;
+    type Parameters = H::Parameters;
+
+    fn setup<R: Rng>(rng: &mut R) -> Result<Self::Parameters, Error> {
+        H::setup(rng)
+    }
+
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        input: T,
+    ) -> Result<Self::Output, Error> {
+        let input = input.borrow();
+        let mut buffer = Vec::with_capacity(1 + input.len());
+        buffer.push(LEAF_PREFIX);
+        buffer.extend_from_slice(input);
+        H::evaluate(parameters, buffer.as_slice())
+    }
+}
+
+/// Hashes two children as `H(0x01 || left || right)`, where the children are digests of `H`,
+/// seen as bytes.
+///
+/// The input of this hash is the output of `H`, so it is used with
+/// [`IdentityDigestConverter`](crate::merkle_tree::IdentityDigestConverter) as the leaf to inner
+/// digest converter, along with [`LeafCRH<H>`] as the leaf hash.
+pub struct InnerCRH<H: CRHScheme<Input = [u8]>> {
+    _hash: PhantomData<H>,
+}
+
+impl<H: CRHScheme<Input = [u8]>> TwoToOneCRHScheme for InnerCRH<H>
+where
+    H::Output: AsRef<[u8]>,
+{
+    type Input = H::Output;
+    type Output = H::Output;
+    type Parameters = H::Parameters;
+
+    fn setup<R: Rng>(rng: &mut R) -> Result<Self::Parameters, Error> {
+        H::setup(rng)
+    }
+
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        left_input: T,
+        right_input: T,
+    ) -> Result<Self::Output, Error> {
+        Self::compress(parameters, left_input, right_input)
+    }
+
+    fn compress<T: Borrow<Self::Output>>(
+        parameters: &Self::Parameters,
+        left_input: T,
+        right_input: T,
+    ) -> Result<Self::Output, Error> {
+        let left_input = left_input.borrow().as_ref();
+        let right_input = right_input.borrow().as_ref();
+        let mut buffer = Vec::with_capacity(1 + left_input.len() + right_input.len());
+        buffer.push(INNER_PREFIX);
+        buffer.extend_from_slice(left_input);
+        buffer.extend_from_slice(right_input);
+        H::evaluate(parameters, buffer.as_slice())
+    }
+}
+
+/// Returns the parameters of rate 2 for the smallest valid alpha, exactly as
+/// [`poseidon::CRH`](crate::crh::poseidon::CRH) does, since the domain tags only change the
+/// initial capacity element.
+fn setup_parameters<F: PrimeField>() -> PoseidonConfig<F> {
+    find_poseidon_parameters(2, find_poseidon_alpha::<F>(), SETUP_SECURITY_LEVEL)
+}
+
+/// Returns a Poseidon sponge whose capacity element is `tag`.
+fn tagged_sponge<F: PrimeField + Absorb>(
+    parameters: &PoseidonConfig<F>,
+    tag: u64,
+) -> PoseidonSponge<F> {
+    let mut sponge = PoseidonSponge::new(parameters);
+    sponge.state[0] = F::from(tag);
+    sponge
+}
+
+/// Poseidon hash of field elements, whose sponge starts with [`POSEIDON_LEAF_TAG`] as capacity
+/// element.
+pub struct PoseidonLeafCRH<F: PrimeField + Absorb> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> CRHScheme for PoseidonLeafCRH<F> {
+    type Input = [F];
+    type Output = F;
+    type Parameters = PoseidonConfig<F>;
+
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(setup_parameters())
+    }
+
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        input: T,
+    ) -> Result<Self::Output, Error> {
+        let mut sponge = tagged_sponge(parameters, POSEIDON_LEAF_TAG);
+        sponge.absorb(&input.borrow());
+        Ok(sponge.squeeze_field_elements::<F>(1)[0])
+    }
+}
+
+/// Poseidon two to one hash, whose sponge starts with [`POSEIDON_INNER_TAG`] as capacity element.
+pub struct PoseidonTwoToOneCRH<F: PrimeField + Absorb> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> TwoToOneCRHScheme for PoseidonTwoToOneCRH<F> {
+    type Input = F;
+    type Output = F;
+    type Parameters = PoseidonConfig<F>;
+
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(setup_parameters())
+    }
+
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        left_input: T,
+        right_input: T,
+    ) -> Result<Self::Output, Error> {
+        Self::compress(parameters, left_input, right_input)
+    }
+
+    fn compress<T: Borrow<Self::Output>>(
+        parameters: &Self::Parameters,
+        left_input: T,
+        right_input: T,
+    ) -> Result<Self::Output, Error> {
+        let mut sponge = tagged_sponge(parameters, POSEIDON_INNER_TAG);
+        sponge.absorb(left_input.borrow());
+        sponge.absorb(right_input.borrow());
+        Ok(sponge.squeeze_field_elements::<F>(1)[0])
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        crh::{
+            domain_separated::{
+                InnerCRH, LeafCRH, PoseidonLeafCRH, PoseidonTwoToOneCRH, INNER_PREFIX,
+            },
+            poseidon,
+            sha256::Sha256,
+            CRHScheme, TwoToOneCRHScheme,
+        },
+        sponge::poseidon::{find_poseidon_ark_and_mds, PoseidonConfig},
+    };
+    use ark_ff::PrimeField;
+    use ark_std::UniformRand;
+
+    type F = ark_ed_on_bls12_381::Fr;
+
+    pub(super) fn poseidon_parameters() -> PoseidonConfig<F> {
+        let (ark, mds) = find_poseidon_ark_and_mds::<F>(F::MODULUS_BIT_SIZE as u64, 2, 8, 31, 0);
+        PoseidonConfig::new(8, 31, 17, mds, ark, 2, 1)
+    }
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn rfc6962_test() {
+        // the hash of an empty leaf, from the Certificate Transparency test vectors
+        assert_eq!(
+            LeafCRH::<Sha256>::evaluate(&(), &[0u8; 0][..]).unwrap(),
+            from_hex("6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d")
+        );
+
+        let (left, right) = (vec![1u8; 32], vec![2u8; 32]);
+        let leaf = LeafCRH::<Sha256>::evaluate(&(), [&left[..], &right[..]].concat().as_slice());
+        let inner = InnerCRH::<Sha256>::compress(&(), &left, &right).unwrap();
+        assert_eq!(
+            inner,
+            <Sha256 as CRHScheme>::evaluate(
+                &(),
+                [&[INNER_PREFIX][..], &left, &right].concat().as_slice()
+            )
+            .unwrap()
+        );
+        assert_eq!(
+            InnerCRH::<Sha256>::evaluate(&(), &left, &right).unwrap(),
+            inner
+        );
+        // the concatenation of two children does not hash to their parent as a leaf
+        assert_ne!(leaf.unwrap(), inner);
+    }
+
+    #[test]
+    fn poseidon_tags_test() {
+        let mut rng = ark_std::test_rng();
+        let params = poseidon_parameters();
+        let (left, right) = (F::rand(&mut rng), F::rand(&mut rng));
+
+        // the same inputs hash differently in each domain, and without domain separation
+        let leaf = PoseidonLeafCRH::<F>::evaluate(&params, [left, right]).unwrap();
+        let inner = PoseidonTwoToOneCRH::<F>::compress(&params, left, right).unwrap();
+        let untagged = poseidon::TwoToOneCRH::<F>::compress(&params, left, right).unwrap();
+        assert_ne!(leaf, inner);
+        assert_ne!(leaf, untagged);
+        assert_ne!(inner, untagged);
+        assert_eq!(
+            PoseidonTwoToOneCRH::<F>::evaluate(&params, left, right).unwrap(),
+            inner
+        );
+    }
+
+    #[test]
+    fn setup_test() {
+        let mut rng = ark_std::test_rng();
+        let leaf_params = PoseidonLeafCRH::<F>::setup(&mut rng).unwrap();
+        let inner_params = PoseidonTwoToOneCRH::<F>::setup(&mut rng).unwrap();
+        assert_eq!(leaf_params.rate, 2);
+        assert_eq!(inner_params.rate, 2);
+        let params = poseidon::CRH::<F>::setup(&mut rng).unwrap();
+        assert_eq!(
+            (&leaf_params.ark, &leaf_params.mds),
+            (&params.ark, &params.mds)
+        );
+
+        let (left, right) = (F::rand(&mut rng), F::rand(&mut rng));
+        assert_ne!(
+            PoseidonLeafCRH::<F>::evaluate(&leaf_params, [left, right]).unwrap(),
+            PoseidonTwoToOneCRH::<F>::compress(&inner_params, left, right).unwrap()
+        );
+    }
+}
@@ -0,0 +1,148 @@
+use crate::{
+    crh::{poseidon::arity_tag, CRHScheme, NToOneCRHScheme, TwoToOneCRHScheme},
+    sponge::{
+        griffin::{GriffinConfig, GriffinSponge},
+        Absorb, CryptographicSponge,
+    },
+    Error,
+};
+use ark_ff::PrimeField;
+use ark_std::vec::Vec;
+use ark_std::{borrow::Borrow, marker::PhantomData, rand::Rng};
+
+#[cfg(feature = "constraints")]
+pub mod constraints;
+
+/// The security level, in bits, of the parameters returned by `setup`.
+pub const SETUP_SECURITY_LEVEL: u64 = 128;
+
+/// The Griffin collision-resistant hash function introduced in [GHRSWW22][griffin]
+///
+/// [griffin]: https://eprint.iacr.org/2022/403
+pub struct CRH<F: PrimeField + Absorb> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> CRHScheme for CRH<F> {
+    /// The input to Griffin is a list of field elements.
+    type Input = [F];
+    /// The output of Griffin is a single field element. One can change this to a list of field elements to squeeze more outputs.
+    type Output = F;
+    /// The parameters for the Griffin sponge, e.g. the number of rounds, round constants, coefficients of the nonlinear layer, etc.
+    type Parameters = GriffinConfig<F>;
+
+    /// Compute the parameters of Griffin with rate 2 for the Griffin sponge.
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        GriffinConfig::generate(2, 1, SETUP_SECURITY_LEVEL)
+    }
+
+    /// Evaluate the Griffin sponge on the input.
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        input: T,
+    ) -> Result<Self::Output, Error> {
+        let input = input.borrow();
+
+        let mut sponge = GriffinSponge::new(parameters);
+        sponge.absorb(&input);
+        let res: Vec<F> = sponge.squeeze_field_elements::<F>(1);
+        Ok(res[0])
+    }
+}
+
+/// The 2-to-1 version of the Griffin collision-resistant hash function introduced in [GHRSWW22][griffin] used in Merkle trees.
+///
+/// [griffin]: https://eprint.iacr.org/2022/403
+pub struct TwoToOneCRH<F: PrimeField + Absorb> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> TwoToOneCRHScheme for TwoToOneCRH<F> {
+    /// Each of the inputs to the list are field elements.
+    type Input = F;
+    /// The output of Griffin is a single field element. One can change this to a list of field elements to squeeze more outputs.
+    type Output = F;
+    /// The parameters for the Griffin sponge, e.g. the number of rounds, round constants, coefficients of the nonlinear layer, etc.
+    type Parameters = GriffinConfig<F>;
+
+    /// Compute the parameters of Griffin with rate 2 for the Griffin sponge.
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        GriffinConfig::generate(2, 1, SETUP_SECURITY_LEVEL)
+    }
+
+    /// Evaluate the Griffin sponge on the inputs left and right.
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        left_input: T,
+        right_input: T,
+    ) -> Result<Self::Output, Error> {
+        Self::compress(parameters, left_input, right_input)
+    }
+
+    /// Compress the inputs left and right using the Griffin sponge.
+    fn compress<T: Borrow<Self::Output>>(
+        parameters: &Self::Parameters,
+        left_input: T,
+        right_input: T,
+    ) -> Result<Self::Output, Error> {
+        let left_input = left_input.borrow();
+        let right_input = right_input.borrow();
+
+        let mut sponge = GriffinSponge::new(parameters);
+        sponge.absorb(left_input);
+        sponge.absorb(right_input);
+        let res = sponge.squeeze_field_elements::<F>(1);
+        Ok(res[0])
+    }
+}
+
+/// The n-to-1 version of the Griffin collision-resistant hash function introduced in [GHRSWW22][griffin]
+/// used in `ARITY`-ary Merkle trees.
+///
+/// `setup` returns parameters of rate `ARITY`, so that a node costs a single permutation, and only
+/// supports an `ARITY` such that `ARITY + 1` is 3 or a multiple of 4. The capacity element of the
+/// sponge is set to [`arity_tag`], so that nodes of different arities never collide.
+///
+/// [griffin]: https://eprint.iacr.org/2022/403
+pub struct NToOneCRH<F: PrimeField + Absorb, const ARITY: usize> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb, const ARITY: usize> NToOneCRHScheme for NToOneCRH<F, ARITY> {
+    /// Each of the inputs to the list are field elements.
+    type Input = F;
+    /// The output of Griffin is a single field element. One can change this to a list of field elements to squeeze more outputs.
+    type Output = F;
+    /// The parameters for the Griffin sponge, e.g. the number of rounds, round constants, coefficients of the nonlinear layer, etc.
+    type Parameters = GriffinConfig<F>;
+
+    /// Compute the parameters of Griffin with rate `ARITY` for the Griffin sponge.
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        GriffinConfig::generate(ARITY, 1, SETUP_SECURITY_LEVEL)
+    }
+
+    /// Evaluate the Griffin sponge on the inputs.
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        inputs: &[T],
+    ) -> Result<Self::Output, Error> {
+        Self::compress(parameters, inputs)
+    }
+
+    /// Compress the inputs using the Griffin sponge.
+    fn compress<T: Borrow<Self::Output>>(
+        parameters: &Self::Parameters,
+        inputs: &[T],
+    ) -> Result<Self::Output, Error> {
+        if inputs.len() != ARITY {
+            return Err(Error::IncorrectInputLength(inputs.len()));
+        }
+        let mut sponge = GriffinSponge::new(parameters);
+        sponge.state[0] = arity_tag(ARITY);
+        for input in inputs {
+            sponge.absorb(input.borrow());
+        }
+        let res = sponge.squeeze_field_elements::<F>(1);
+        Ok(res[0])
+    }
+}
@@ -3,12 +3,16 @@ use crate::Error;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::{borrow::Borrow, fmt::Debug, hash::Hash, rand::Rng};
 
+pub mod anemoi;
 pub mod bowe_hopwood;
 #[cfg(feature = "constraints")]
 pub mod constraints;
+pub mod domain_separated;
+pub mod griffin;
 pub mod injective_map;
 pub mod pedersen;
 pub mod poseidon;
+pub mod poseidon2;
 pub mod rescue;
 pub mod sha256;
 #[cfg(feature = "constraints")]
@@ -50,3 +54,25 @@ pub trait TwoToOneCRHScheme {
         right_input: T,
     ) -> Result<Self::Output, Error>;
 }
+
+/// CRH used by the inner nodes of k-ary merkle trees, compressing the `arity` children of a node
+/// into one digest. Merkle tree will convert leaf output to the input type first.
+pub trait NToOneCRHScheme {
+    /// Raw Input type of NToOneCRH
+    type Input: ?Sized;
+    /// Raw Output type of NToOneCRH
+    type Output: Clone + Eq + Debug + Hash + Default + CanonicalSerialize + CanonicalDeserialize;
+    type Parameters: Clone + CanonicalSerialize + CanonicalDeserialize + Sync;
+
+    fn setup<R: Rng>(r: &mut R) -> Result<Self::Parameters, Error>;
+
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        inputs: &[T],
+    ) -> Result<Self::Output, Error>;
+
+    fn compress<T: Borrow<Self::Output>>(
+        parameters: &Self::Parameters,
+        inputs: &[T],
+    ) -> Result<Self::Output, Error>;
+}
@@ -1,7 +1,8 @@
 use crate::{
     crh::{
-        poseidon::{TwoToOneCRH, CRH},
-        CRHScheme, CRHSchemeGadget as CRHGadgetTrait,
+        poseidon::{arity_tag, NToOneCRH, TwoToOneCRH, CRH},
+        CRHScheme, CRHSchemeGadget as CRHGadgetTrait, NToOneCRHScheme,
+        NToOneCRHSchemeGadget as NToOneCRHGadgetTrait,
         TwoToOneCRHSchemeGadget as TwoToOneCRHGadgetTrait,
     },
     sponge::{
@@ -16,7 +17,7 @@ use ark_r1cs_std::{
     fields::fp::FpVar,
     GR1CSVar,
 };
-use ark_relations::gr1cs::{Namespace, SynthesisError};
+use ark_relations::gr1cs::{ConstraintSystemRef, Namespace, SynthesisError};
 #[cfg(not(feature = "std"))]
 use ark_std::vec::Vec;
 use ark_std::{borrow::Borrow, marker::PhantomData};
@@ -100,6 +101,55 @@ impl<F: PrimeField + Absorb> TwoToOneCRHGadgetTrait<TwoToOneCRH<F>, F> for TwoTo
     }
 }
 
+pub struct NToOneCRHGadget<F: PrimeField + Absorb, const ARITY: usize> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb, const ARITY: usize> NToOneCRHGadgetTrait<NToOneCRH<F, ARITY>, F>
+    for NToOneCRHGadget<F, ARITY>
+{
+    type InputVar = FpVar<F>;
+    type OutputVar = FpVar<F>;
+    type ParametersVar = CRHParametersVar<F>;
+
+    fn evaluate<T: Borrow<Self::InputVar>>(
+        parameters: &Self::ParametersVar,
+        inputs: &[T],
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        Self::compress(parameters, inputs)
+    }
+
+    fn compress<T: Borrow<Self::OutputVar>>(
+        parameters: &Self::ParametersVar,
+        inputs: &[T],
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        if inputs.len() != ARITY {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let cs = inputs.iter().fold(ConstraintSystemRef::None, |cs, input| {
+            cs.or(input.borrow().cs())
+        });
+
+        if cs.is_none() {
+            let mut constant_input = Vec::new();
+            for var in inputs {
+                constant_input.push(var.borrow().value()?);
+            }
+            Ok(FpVar::Constant(
+                NToOneCRH::<F, ARITY>::compress(&parameters.parameters, &constant_input).unwrap(),
+            ))
+        } else {
+            let mut sponge = PoseidonSpongeVar::new(cs, &parameters.parameters);
+            sponge.state[0] = FpVar::Constant(arity_tag(ARITY));
+            for input in inputs {
+                sponge.absorb(input.borrow())?;
+            }
+            let res = sponge.squeeze_field_elements(1)?;
+            Ok(res[0].clone())
+        }
+    }
+}
+
 impl<F: PrimeField + Absorb> AllocVar<PoseidonConfig<F>, F> for CRHParametersVar<F> {
     fn new_variable<T: Borrow<PoseidonConfig<F>>>(
         _cs: impl Into<Namespace<F>>,
@@ -1,7 +1,7 @@
 use crate::{
-    crh::{CRHScheme, TwoToOneCRHScheme},
+    crh::{CRHScheme, NToOneCRHScheme, TwoToOneCRHScheme},
     sponge::{
-        poseidon::{PoseidonConfig, PoseidonSponge},
+        poseidon::{find_poseidon_alpha, find_poseidon_parameters, PoseidonConfig, PoseidonSponge},
         Absorb, CryptographicSponge,
     },
     Error,
@@ -11,6 +11,19 @@ use ark_std::{borrow::Borrow, marker::PhantomData, rand::Rng};
 
 #[cfg(feature = "constraints")]
 pub mod constraints;
+
+/// The security level, in bits, of the parameters returned by `setup`.
+pub const SETUP_SECURITY_LEVEL: u64 = 128;
+
+/// Returns the parameters of rate 2 for the smallest valid alpha, whose round numbers reach
+/// `SETUP_SECURITY_LEVEL` bits of security.
+///
+/// The constants are derived from the Grain LFSR as in the reference implementation, so that no
+/// randomness is needed.
+fn setup_parameters<F: PrimeField>() -> PoseidonConfig<F> {
+    find_poseidon_parameters(2, find_poseidon_alpha::<F>(), SETUP_SECURITY_LEVEL)
+}
+
 pub struct CRH<F: PrimeField + Absorb> {
     field_phantom: PhantomData<F>,
 }
@@ -21,9 +34,7 @@ impl<F: PrimeField + Absorb> CRHScheme for CRH<F> {
     type Parameters = PoseidonConfig<F>;
 
     fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
-        // automatic generation of parameters are not implemented yet
-        // therefore, the developers must specify the parameters themselves
-        unimplemented!()
+        Ok(setup_parameters())
     }
 
     fn evaluate<T: Borrow<Self::Input>>(
@@ -49,9 +60,7 @@ impl<F: PrimeField + Absorb> TwoToOneCRHScheme for TwoToOneCRH<F> {
     type Parameters = PoseidonConfig<F>;
 
     fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
-        // automatic generation of parameters are not implemented yet
-        // therefore, the developers must specify the parameters themselves
-        unimplemented!()
+        Ok(setup_parameters())
     }
 
     fn evaluate<T: Borrow<Self::Input>>(
@@ -77,3 +86,55 @@ impl<F: PrimeField + Absorb> TwoToOneCRHScheme for TwoToOneCRH<F> {
         Ok(res[0])
     }
 }
+
+/// Returns the capacity element of the sponge used by [`NToOneCRH`] with `arity` inputs, which is
+/// `2^arity - 1` as suggested for merkle trees in the Poseidon paper.
+pub fn arity_tag<F: PrimeField>(arity: usize) -> F {
+    F::from(2u8).pow([arity as u64]) - F::one()
+}
+
+/// Poseidon compression of `ARITY` field elements, used for the nodes of `ARITY`-ary merkle trees.
+///
+/// `setup` returns parameters of rate `ARITY`, so that a node costs a single permutation. The
+/// capacity element of the sponge is set to [`arity_tag`], so that nodes of different arities never
+/// collide.
+pub struct NToOneCRH<F: PrimeField + Absorb, const ARITY: usize> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb, const ARITY: usize> NToOneCRHScheme for NToOneCRH<F, ARITY> {
+    type Input = F;
+    type Output = F;
+    type Parameters = PoseidonConfig<F>;
+
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        Ok(find_poseidon_parameters(
+            ARITY,
+            find_poseidon_alpha::<F>(),
+            SETUP_SECURITY_LEVEL,
+        ))
+    }
+
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        inputs: &[T],
+    ) -> Result<Self::Output, Error> {
+        Self::compress(parameters, inputs)
+    }
+
+    fn compress<T: Borrow<Self::Output>>(
+        parameters: &Self::Parameters,
+        inputs: &[T],
+    ) -> Result<Self::Output, Error> {
+        if inputs.len() != ARITY {
+            return Err(Error::IncorrectInputLength(inputs.len()));
+        }
+        let mut sponge = PoseidonSponge::new(parameters);
+        sponge.state[0] = arity_tag(ARITY);
+        for input in inputs {
+            sponge.absorb(input.borrow());
+        }
+        let res = sponge.squeeze_field_elements::<F>(1);
+        Ok(res[0])
+    }
+}
@@ -0,0 +1,285 @@
+use crate::crh::{
+    constraints::CRHSchemeGadget as CRHGadgetTrait,
+    constraints::NToOneCRHSchemeGadget as NToOneCRHGadgetTrait,
+    constraints::TwoToOneCRHSchemeGadget as TwoToOneCRHGadgetTrait,
+    poseidon::arity_tag,
+    poseidon2::{NToOneCRH, TwoToOneCRH, CRH},
+    CRHScheme, NToOneCRHScheme,
+};
+use crate::sponge::{
+    constraints::CryptographicSpongeVar,
+    poseidon2::{constraints::Poseidon2SpongeVar, Poseidon2Config},
+};
+
+use crate::sponge::Absorb;
+use ark_ff::PrimeField;
+use ark_r1cs_std::{
+    alloc::{AllocVar, AllocationMode},
+    fields::fp::FpVar,
+    GR1CSVar,
+};
+use ark_relations::gr1cs::{ConstraintSystemRef, Namespace, SynthesisError};
+use ark_std::{borrow::Borrow, marker::PhantomData};
+
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+
+#[derive(Clone)]
+pub struct CRHParametersVar<F: PrimeField + Absorb> {
+    pub parameters: Poseidon2Config<F>,
+}
+
+pub struct CRHGadget<F: PrimeField + Absorb> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> CRHGadgetTrait<CRH<F>, F> for CRHGadget<F> {
+    type InputVar = [FpVar<F>];
+    type OutputVar = FpVar<F>;
+    type ParametersVar = CRHParametersVar<F>;
+
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        input: &Self::InputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        let cs = input.cs();
+
+        if cs.is_none() {
+            let mut constant_input = Vec::new();
+            for var in input.iter() {
+                constant_input.push(var.value()?);
+            }
+            Ok(FpVar::Constant(
+                CRH::<F>::evaluate(&parameters.parameters, constant_input).unwrap(),
+            ))
+        } else {
+            let mut sponge = Poseidon2SpongeVar::new(cs, &parameters.parameters);
+            sponge.absorb(&input)?;
+            let res = sponge.squeeze_field_elements(1)?;
+
+            Ok(res[0].clone())
+        }
+    }
+}
+
+pub struct TwoToOneCRHGadget<F: PrimeField + Absorb> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> TwoToOneCRHGadgetTrait<TwoToOneCRH<F>, F> for TwoToOneCRHGadget<F> {
+    type InputVar = FpVar<F>;
+    type OutputVar = FpVar<F>;
+    type ParametersVar = CRHParametersVar<F>;
+
+    fn evaluate(
+        parameters: &Self::ParametersVar,
+        left_input: &Self::InputVar,
+        right_input: &Self::InputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        Self::compress(parameters, left_input, right_input)
+    }
+
+    fn compress(
+        parameters: &Self::ParametersVar,
+        left_input: &Self::OutputVar,
+        right_input: &Self::OutputVar,
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        let cs = left_input.cs().or(right_input.cs());
+
+        if cs.is_none() {
+            Ok(FpVar::Constant(
+                CRH::<F>::evaluate(
+                    &parameters.parameters,
+                    vec![left_input.value()?, right_input.value()?],
+                )
+                .unwrap(),
+            ))
+        } else {
+            let mut sponge = Poseidon2SpongeVar::new(cs, &parameters.parameters);
+            sponge.absorb(left_input)?;
+            sponge.absorb(right_input)?;
+            let res = sponge.squeeze_field_elements(1)?;
+            Ok(res[0].clone())
+        }
+    }
+}
+
+pub struct NToOneCRHGadget<F: PrimeField + Absorb, const ARITY: usize> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb, const ARITY: usize> NToOneCRHGadgetTrait<NToOneCRH<F, ARITY>, F>
+    for NToOneCRHGadget<F, ARITY>
+{
+    type InputVar = FpVar<F>;
+    type OutputVar = FpVar<F>;
+    type ParametersVar = CRHParametersVar<F>;
+
+    fn evaluate<T: Borrow<Self::InputVar>>(
+        parameters: &Self::ParametersVar,
+        inputs: &[T],
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        Self::compress(parameters, inputs)
+    }
+
+    fn compress<T: Borrow<Self::OutputVar>>(
+        parameters: &Self::ParametersVar,
+        inputs: &[T],
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        if inputs.len() != ARITY {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let cs = inputs.iter().fold(ConstraintSystemRef::None, |cs, input| {
+            cs.or(input.borrow().cs())
+        });
+
+        if cs.is_none() {
+            let mut constant_input = Vec::new();
+            for var in inputs {
+                constant_input.push(var.borrow().value()?);
+            }
+            Ok(FpVar::Constant(
+                NToOneCRH::<F, ARITY>::compress(&parameters.parameters, &constant_input).unwrap(),
+            ))
+        } else {
+            let mut sponge = Poseidon2SpongeVar::new(cs, &parameters.parameters);
+            sponge.state[0] = FpVar::Constant(arity_tag(ARITY));
+            for input in inputs {
+                sponge.absorb(input.borrow())?;
+            }
+            let res = sponge.squeeze_field_elements(1)?;
+            Ok(res[0].clone())
+        }
+    }
+}
+
+impl<F: PrimeField + Absorb> AllocVar<Poseidon2Config<F>, F> for CRHParametersVar<F> {
+    fn new_variable<T: Borrow<Poseidon2Config<F>>>(
+        _cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        _mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        f().map(|param| {
+            let parameters = param.borrow().clone();
+            Self { parameters }
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::crh::poseidon2::constraints::{
+        CRHGadget, CRHParametersVar, NToOneCRHGadget, TwoToOneCRHGadget,
+    };
+    use crate::crh::poseidon2::{NToOneCRH, TwoToOneCRH, CRH};
+    use crate::crh::{CRHScheme, CRHSchemeGadget, NToOneCRHScheme, NToOneCRHSchemeGadget};
+    use crate::crh::{TwoToOneCRHScheme, TwoToOneCRHSchemeGadget};
+    use crate::sponge::poseidon2::test::poseidon2_parameters_for_test;
+    use ark_ed_on_bls12_381::Fr;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_r1cs_std::{fields::fp::FpVar, GR1CSVar};
+    use ark_relations::gr1cs::ConstraintSystem;
+    use ark_std::UniformRand;
+
+    #[test]
+    fn test_consistency() {
+        let mut test_rng = ark_std::test_rng();
+
+        let diag = (0..4).map(|_| Fr::rand(&mut test_rng)).collect();
+        let params = poseidon2_parameters_for_test::<Fr>(3, diag);
+        let test_a: Vec<_> = (0..5).map(|_| Fr::rand(&mut test_rng)).collect();
+        let test_b: Vec<_> = (0..5).map(|_| Fr::rand(&mut test_rng)).collect();
+
+        let crh_a = CRH::<Fr>::evaluate(&params, test_a.clone()).unwrap();
+        let crh_b = CRH::<Fr>::evaluate(&params, test_b.clone()).unwrap();
+        let crh = TwoToOneCRH::<Fr>::compress(&params, crh_a, crh_b).unwrap();
+
+        for constant in [false, true] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let alloc = |elems: &[Fr]| -> Vec<FpVar<Fr>> {
+                elems
+                    .iter()
+                    .map(|elem| {
+                        if constant {
+                            FpVar::Constant(*elem)
+                        } else {
+                            FpVar::new_witness(cs.clone(), || Ok(elem)).unwrap()
+                        }
+                    })
+                    .collect()
+            };
+            let test_a_g = alloc(&test_a);
+            let test_b_g = alloc(&test_b);
+
+            let params_g = CRHParametersVar::<Fr>::new_witness(cs.clone(), || Ok(&params)).unwrap();
+            let crh_a_g = CRHGadget::<Fr>::evaluate(&params_g, &test_a_g).unwrap();
+            let crh_b_g = CRHGadget::<Fr>::evaluate(&params_g, &test_b_g).unwrap();
+            let crh_g = TwoToOneCRHGadget::<Fr>::compress(&params_g, &crh_a_g, &crh_b_g).unwrap();
+
+            assert_eq!(crh_a, crh_a_g.value().unwrap());
+            assert_eq!(crh_b, crh_b_g.value().unwrap());
+            assert_eq!(crh, crh_g.value().unwrap());
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn test_setup() {
+        let mut test_rng = ark_std::test_rng();
+        let params = CRH::<Fr>::setup(&mut test_rng).unwrap();
+        let two_to_one_params = TwoToOneCRH::<Fr>::setup(&mut test_rng).unwrap();
+        let n_to_one_params = NToOneCRH::<Fr, 2>::setup(&mut test_rng).unwrap();
+        assert_eq!((params.rate, params.capacity), (2, 1));
+        assert_eq!(params.external_rc, two_to_one_params.external_rc);
+        assert_eq!(params.internal_rc, n_to_one_params.internal_rc);
+
+        let (left, right) = (Fr::rand(&mut test_rng), Fr::rand(&mut test_rng));
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let params_g = CRHParametersVar::<Fr>::new_constant(cs.clone(), &params).unwrap();
+        let left_g = FpVar::new_witness(cs.clone(), || Ok(left)).unwrap();
+        let right_g = FpVar::new_witness(cs.clone(), || Ok(right)).unwrap();
+        let crh_g = TwoToOneCRHGadget::<Fr>::compress(&params_g, &left_g, &right_g).unwrap();
+        assert_eq!(
+            TwoToOneCRH::<Fr>::compress(&params, left, right).unwrap(),
+            crh_g.value().unwrap()
+        );
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_n_to_one() {
+        let mut test_rng = ark_std::test_rng();
+
+        assert!(NToOneCRH::<Fr, 3>::setup(&mut test_rng).is_err());
+        let params = NToOneCRH::<Fr, 2>::setup(&mut test_rng).unwrap();
+        assert_eq!(params.rate, 2);
+        let inputs: Vec<_> = (0..2).map(|_| Fr::rand(&mut test_rng)).collect();
+        let crh = NToOneCRH::<Fr, 2>::compress(&params, &inputs).unwrap();
+        assert!(NToOneCRH::<Fr, 2>::compress(&params, &inputs[..1]).is_err());
+
+        // nodes of different arities are domain separated
+        assert_ne!(
+            crh,
+            TwoToOneCRH::<Fr>::compress(&params, inputs[0], inputs[1]).unwrap()
+        );
+
+        for constant in [false, true] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let inputs_g: Vec<_> = inputs
+                .iter()
+                .map(|elem| {
+                    if constant {
+                        FpVar::Constant(*elem)
+                    } else {
+                        FpVar::new_witness(cs.clone(), || Ok(elem)).unwrap()
+                    }
+                })
+                .collect();
+            let params_g = CRHParametersVar::<Fr>::new_witness(cs.clone(), || Ok(&params)).unwrap();
+            let crh_g = NToOneCRHGadget::<Fr, 2>::compress(&params_g, &inputs_g).unwrap();
+            assert_eq!(crh, crh_g.value().unwrap());
+            assert!(NToOneCRHGadget::<Fr, 2>::compress(&params_g, &inputs_g[..1]).is_err());
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+}
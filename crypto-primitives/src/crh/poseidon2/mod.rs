@@ -0,0 +1,158 @@
+use crate::{
+    crh::{
+        poseidon::{arity_tag, SETUP_SECURITY_LEVEL},
+        CRHScheme, NToOneCRHScheme, TwoToOneCRHScheme,
+    },
+    sponge::{
+        poseidon::find_poseidon_alpha,
+        poseidon2::{find_poseidon2_parameters, Poseidon2Config, Poseidon2Sponge},
+        Absorb, CryptographicSponge,
+    },
+    Error,
+};
+use ark_ff::PrimeField;
+use ark_std::vec::Vec;
+use ark_std::{borrow::Borrow, marker::PhantomData, rand::Rng};
+
+#[cfg(feature = "constraints")]
+pub mod constraints;
+
+/// Returns the parameters of rate 2 for the smallest valid alpha, whose round numbers reach
+/// `SETUP_SECURITY_LEVEL` bits of security.
+///
+/// The constants are derived from the Grain LFSR as in the reference implementation, so that no
+/// randomness is needed.
+fn setup_parameters<F: PrimeField>() -> Result<Poseidon2Config<F>, Error> {
+    find_poseidon2_parameters(2, find_poseidon_alpha::<F>(), SETUP_SECURITY_LEVEL)
+}
+
+/// The Poseidon2 collision-resistant hash function introduced in [GKS23][gks]
+///
+/// [gks]: https://eprint.iacr.org/2023/323
+pub struct CRH<F: PrimeField + Absorb> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> CRHScheme for CRH<F> {
+    /// The input to Poseidon2 is a list of field elements.
+    type Input = [F];
+    /// The output of Poseidon2 is a single field element. One can change this to a list of field elements to squeeze more outputs.
+    type Output = F;
+    /// The parameters for the Poseidon2 sponge, e.g. the number of rounds, round constants, s-box specifications, etc.
+    type Parameters = Poseidon2Config<F>;
+
+    /// Compute the parameters for the Poseidon2 sponge.
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        setup_parameters()
+    }
+
+    /// Evaluate the Poseidon2 sponge on the input.
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        input: T,
+    ) -> Result<Self::Output, Error> {
+        let input = input.borrow();
+
+        let mut sponge = Poseidon2Sponge::new(parameters);
+        sponge.absorb(&input);
+        let res: Vec<F> = sponge.squeeze_field_elements::<F>(1);
+        Ok(res[0])
+    }
+}
+
+/// The 2-to-1 version of the Poseidon2 collision-resistant hash function introduced in [GKS23][gks] used in Merkle trees.
+///
+/// [gks]: https://eprint.iacr.org/2023/323
+pub struct TwoToOneCRH<F: PrimeField + Absorb> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb> TwoToOneCRHScheme for TwoToOneCRH<F> {
+    /// Each of the inputs to the list are field elements.
+    type Input = F;
+    /// The output of Poseidon2 is a single field element. One can change this to a list of field elements to squeeze more outputs.
+    type Output = F;
+    /// The parameters for the Poseidon2 sponge, e.g. the number of rounds, round constants, s-box specifications, etc.
+    type Parameters = Poseidon2Config<F>;
+
+    /// Compute the parameters for the Poseidon2 sponge.
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        setup_parameters()
+    }
+
+    /// Evaluate the Poseidon2 sponge on the inputs left and right.
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        left_input: T,
+        right_input: T,
+    ) -> Result<Self::Output, Error> {
+        Self::compress(parameters, left_input, right_input)
+    }
+
+    /// Compress the inputs left and right using the Poseidon2 sponge.
+    fn compress<T: Borrow<Self::Output>>(
+        parameters: &Self::Parameters,
+        left_input: T,
+        right_input: T,
+    ) -> Result<Self::Output, Error> {
+        let left_input = left_input.borrow();
+        let right_input = right_input.borrow();
+
+        let mut sponge = Poseidon2Sponge::new(parameters);
+        sponge.absorb(left_input);
+        sponge.absorb(right_input);
+        let res = sponge.squeeze_field_elements::<F>(1);
+        Ok(res[0])
+    }
+}
+
+/// The n-to-1 version of the Poseidon2 collision-resistant hash function introduced in [GKS23][gks]
+/// used in `ARITY`-ary Merkle trees.
+///
+/// `setup` returns parameters of rate `ARITY`, so that a node costs a single permutation, and only
+/// supports an `ARITY` of 1 or 2, see [`find_poseidon2_parameters`]. The capacity element of the
+/// sponge is set to [`arity_tag`], so that nodes of different arities never collide.
+///
+/// [gks]: https://eprint.iacr.org/2023/323
+pub struct NToOneCRH<F: PrimeField + Absorb, const ARITY: usize> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb, const ARITY: usize> NToOneCRHScheme for NToOneCRH<F, ARITY> {
+    /// Each of the inputs to the list are field elements.
+    type Input = F;
+    /// The output of Poseidon2 is a single field element. One can change this to a list of field elements to squeeze more outputs.
+    type Output = F;
+    /// The parameters for the Poseidon2 sponge, e.g. the number of rounds, round constants, s-box specifications, etc.
+    type Parameters = Poseidon2Config<F>;
+
+    /// Compute the parameters of rate `ARITY` for the Poseidon2 sponge.
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        find_poseidon2_parameters(ARITY, find_poseidon_alpha::<F>(), SETUP_SECURITY_LEVEL)
+    }
+
+    /// Evaluate the Poseidon2 sponge on the inputs.
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        inputs: &[T],
+    ) -> Result<Self::Output, Error> {
+        Self::compress(parameters, inputs)
+    }
+
+    /// Compress the inputs using the Poseidon2 sponge.
+    fn compress<T: Borrow<Self::Output>>(
+        parameters: &Self::Parameters,
+        inputs: &[T],
+    ) -> Result<Self::Output, Error> {
+        if inputs.len() != ARITY {
+            return Err(Error::IncorrectInputLength(inputs.len()));
+        }
+        let mut sponge = Poseidon2Sponge::new(parameters);
+        sponge.state[0] = arity_tag(ARITY);
+        for input in inputs {
+            sponge.absorb(input.borrow());
+        }
+        let res = sponge.squeeze_field_elements::<F>(1);
+        Ok(res[0])
+    }
+}
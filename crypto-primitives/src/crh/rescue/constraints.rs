@@ -1,8 +1,10 @@
 use crate::crh::{
     constraints::CRHSchemeGadget as CRHGadgetTrait,
+    constraints::NToOneCRHSchemeGadget as NToOneCRHGadgetTrait,
     constraints::TwoToOneCRHSchemeGadget as TwoToOneCRHGadgetTrait,
-    rescue::{TwoToOneCRH, CRH},
-    CRHScheme,
+    poseidon::arity_tag,
+    rescue::{NToOneCRH, TwoToOneCRH, CRH},
+    CRHScheme, NToOneCRHScheme,
 };
 use crate::sponge::{
     constraints::CryptographicSpongeVar,
@@ -16,7 +18,7 @@ use ark_r1cs_std::{
     fields::fp::FpVar,
     GR1CSVar,
 };
-use ark_relations::gr1cs::{Namespace, SynthesisError};
+use ark_relations::gr1cs::{ConstraintSystemRef, Namespace, SynthesisError};
 use ark_std::{borrow::Borrow, marker::PhantomData};
 
 #[cfg(not(feature = "std"))]
@@ -102,6 +104,55 @@ impl<F: PrimeField + Absorb> TwoToOneCRHGadgetTrait<TwoToOneCRH<F>, F> for TwoTo
     }
 }
 
+pub struct NToOneCRHGadget<F: PrimeField + Absorb, const ARITY: usize> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb, const ARITY: usize> NToOneCRHGadgetTrait<NToOneCRH<F, ARITY>, F>
+    for NToOneCRHGadget<F, ARITY>
+{
+    type InputVar = FpVar<F>;
+    type OutputVar = FpVar<F>;
+    type ParametersVar = CRHParametersVar<F>;
+
+    fn evaluate<T: Borrow<Self::InputVar>>(
+        parameters: &Self::ParametersVar,
+        inputs: &[T],
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        Self::compress(parameters, inputs)
+    }
+
+    fn compress<T: Borrow<Self::OutputVar>>(
+        parameters: &Self::ParametersVar,
+        inputs: &[T],
+    ) -> Result<Self::OutputVar, SynthesisError> {
+        if inputs.len() != ARITY {
+            return Err(SynthesisError::Unsatisfiable);
+        }
+        let cs = inputs.iter().fold(ConstraintSystemRef::None, |cs, input| {
+            cs.or(input.borrow().cs())
+        });
+
+        if cs.is_none() {
+            let mut constant_input = Vec::new();
+            for var in inputs {
+                constant_input.push(var.borrow().value()?);
+            }
+            Ok(FpVar::Constant(
+                NToOneCRH::<F, ARITY>::compress(&parameters.parameters, &constant_input).unwrap(),
+            ))
+        } else {
+            let mut sponge = RescueSpongeVar::new(cs, &parameters.parameters);
+            sponge.state[0] = FpVar::Constant(arity_tag(ARITY));
+            for input in inputs {
+                sponge.absorb(input.borrow())?;
+            }
+            let res = sponge.squeeze_field_elements(1)?;
+            Ok(res[0].clone())
+        }
+    }
+}
+
 impl<F: PrimeField + Absorb> AllocVar<RescueConfig<F>, F> for CRHParametersVar<F> {
     fn new_variable<T: Borrow<RescueConfig<F>>>(
         _cs: impl Into<Namespace<F>>,
@@ -114,3 +165,90 @@ impl<F: PrimeField + Absorb> AllocVar<RescueConfig<F>, F> for CRHParametersVar<F
         })
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::crh::rescue::constraints::{
+        CRHGadget, CRHParametersVar, NToOneCRHGadget, TwoToOneCRHGadget,
+    };
+    use crate::crh::rescue::{NToOneCRH, TwoToOneCRH, CRH};
+    use crate::crh::{CRHScheme, CRHSchemeGadget};
+    use crate::crh::{NToOneCRHScheme, NToOneCRHSchemeGadget};
+    use crate::crh::{TwoToOneCRHScheme, TwoToOneCRHSchemeGadget};
+    use ark_ed_on_bls12_381::Fr;
+    use ark_r1cs_std::alloc::AllocVar;
+    use ark_r1cs_std::{fields::fp::FpVar, GR1CSVar};
+    use ark_relations::gr1cs::ConstraintSystem;
+    use ark_std::UniformRand;
+
+    #[test]
+    fn test_consistency() {
+        let mut test_rng = ark_std::test_rng();
+
+        let params = CRH::<Fr>::setup(&mut test_rng).unwrap();
+        let test_a: Vec<_> = (0..3).map(|_| Fr::rand(&mut test_rng)).collect();
+        let test_b: Vec<_> = (0..3).map(|_| Fr::rand(&mut test_rng)).collect();
+
+        let crh_a = CRH::<Fr>::evaluate(&params, test_a.clone()).unwrap();
+        let crh_b = CRH::<Fr>::evaluate(&params, test_b.clone()).unwrap();
+        let crh = TwoToOneCRH::<Fr>::compress(&params, crh_a, crh_b).unwrap();
+
+        let cs = ConstraintSystem::<Fr>::new_ref();
+        let test_a_g: Vec<_> = test_a
+            .iter()
+            .map(|elem| FpVar::new_witness(cs.clone(), || Ok(elem)).unwrap())
+            .collect();
+        let test_b_g: Vec<_> = test_b
+            .iter()
+            .map(|elem| FpVar::new_witness(cs.clone(), || Ok(elem)).unwrap())
+            .collect();
+
+        let params_g = CRHParametersVar::<Fr>::new_witness(cs.clone(), || Ok(params)).unwrap();
+        let crh_a_g = CRHGadget::<Fr>::evaluate(&params_g, &test_a_g).unwrap();
+        let crh_b_g = CRHGadget::<Fr>::evaluate(&params_g, &test_b_g).unwrap();
+        let crh_g = TwoToOneCRHGadget::<Fr>::compress(&params_g, &crh_a_g, &crh_b_g).unwrap();
+
+        assert_eq!(crh_a, crh_a_g.value().unwrap());
+        assert_eq!(crh_b, crh_b_g.value().unwrap());
+        assert_eq!(crh, crh_g.value().unwrap());
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn test_n_to_one() {
+        let mut test_rng = ark_std::test_rng();
+
+        let params = NToOneCRH::<Fr, 3>::setup(&mut test_rng).unwrap();
+        assert_eq!(params.rate, 3);
+        let inputs: Vec<_> = (0..3).map(|_| Fr::rand(&mut test_rng)).collect();
+        let crh = NToOneCRH::<Fr, 3>::compress(&params, &inputs).unwrap();
+        assert!(NToOneCRH::<Fr, 3>::compress(&params, &inputs[..2]).is_err());
+
+        // nodes of different arities are domain separated
+        let params_2 = NToOneCRH::<Fr, 2>::setup(&mut test_rng).unwrap();
+        let crh_2 = NToOneCRH::<Fr, 2>::compress(&params_2, &inputs[..2]).unwrap();
+        assert_ne!(
+            crh_2,
+            TwoToOneCRH::<Fr>::compress(&params_2, inputs[0], inputs[1]).unwrap()
+        );
+
+        for constant in [false, true] {
+            let cs = ConstraintSystem::<Fr>::new_ref();
+            let inputs_g: Vec<_> = inputs
+                .iter()
+                .map(|elem| {
+                    if constant {
+                        FpVar::Constant(*elem)
+                    } else {
+                        FpVar::new_witness(cs.clone(), || Ok(elem)).unwrap()
+                    }
+                })
+                .collect();
+            let params_g = CRHParametersVar::<Fr>::new_witness(cs.clone(), || Ok(&params)).unwrap();
+            let crh_g = NToOneCRHGadget::<Fr, 3>::compress(&params_g, &inputs_g).unwrap();
+            assert_eq!(crh, crh_g.value().unwrap());
+            assert!(NToOneCRHGadget::<Fr, 3>::compress(&params_g, &inputs_g[..2]).is_err());
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+}
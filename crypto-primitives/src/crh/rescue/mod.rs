@@ -1,5 +1,5 @@
 use crate::{
-    crh::{CRHScheme, TwoToOneCRHScheme},
+    crh::{poseidon::arity_tag, CRHScheme, NToOneCRHScheme, TwoToOneCRHScheme},
     sponge::{
         rescue::{RescueConfig, RescueSponge},
         Absorb, CryptographicSponge,
@@ -13,6 +13,9 @@ use ark_std::{borrow::Borrow, marker::PhantomData, rand::Rng};
 #[cfg(feature = "constraints")]
 pub mod constraints;
 
+/// The security level, in bits, of the parameters returned by `setup`.
+pub const SETUP_SECURITY_LEVEL: u64 = 128;
+
 /// The Rescue collision-resistant hash function introduced in [SAD20][sad]
 /// [sad]: https://eprint.iacr.org/2020/1143.pdf
 pub struct CRH<F: PrimeField + Absorb> {
@@ -27,9 +30,9 @@ impl<F: PrimeField + Absorb> CRHScheme for CRH<F> {
     /// The parameters for the Rescue sponge, e.g. the number of rounds, mdsm, s-box specifications, etc.
     type Parameters = RescueConfig<F>;
 
-    /// Compute the parameters for the Rescue sponge.
+    /// Compute the parameters of Rescue-Prime with rate 2 for the Rescue sponge.
     fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
-        unimplemented!("Automatic generation of parameters is not implemented yet; developers must specify the parameters manually")
+        RescueConfig::generate(2, 1, SETUP_SECURITY_LEVEL)
     }
 
     /// Evaluate the Rescue sponge on the input.
@@ -61,9 +64,9 @@ impl<F: PrimeField + Absorb> TwoToOneCRHScheme for TwoToOneCRH<F> {
     /// The parameters for the Rescue sponge, e.g. the number of rounds, mdsm, s-box specifications, etc.
     type Parameters = RescueConfig<F>;
 
-    /// Compute the parameters for the Rescue sponge.
+    /// Compute the parameters of Rescue-Prime with rate 2 for the Rescue sponge.
     fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
-        unimplemented!("Automatic generation of parameters is not implemented yet; developers must specify the parameters manually")
+        RescueConfig::generate(2, 1, SETUP_SECURITY_LEVEL)
     }
 
     /// Evaluate the Rescue sponge on the inputs left and right.
@@ -91,3 +94,54 @@ impl<F: PrimeField + Absorb> TwoToOneCRHScheme for TwoToOneCRH<F> {
         Ok(res[0])
     }
 }
+
+/// The n-to-1 version of the Rescue collision-resistant hash function introduced in [SAD20][sad]
+/// used in `ARITY`-ary Merkle trees.
+///
+/// `setup` returns parameters of rate `ARITY`, so that a node costs a single permutation. The
+/// capacity element of the sponge is set to [`arity_tag`], so that nodes of different arities never
+/// collide.
+///
+/// [sad]: https://eprint.iacr.org/2020/1143.pdf
+pub struct NToOneCRH<F: PrimeField + Absorb, const ARITY: usize> {
+    field_phantom: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb, const ARITY: usize> NToOneCRHScheme for NToOneCRH<F, ARITY> {
+    /// Each of the inputs to the list are field elements.
+    type Input = F;
+    /// The output of Rescue is a single field element. One can change this to a list of field elements to squeeze more outputs.
+    type Output = F;
+    /// The parameters for the Rescue sponge, e.g. the number of rounds, mdsm, s-box specifications, etc.
+    type Parameters = RescueConfig<F>;
+
+    /// Compute the parameters of Rescue-Prime with rate `ARITY` for the Rescue sponge.
+    fn setup<R: Rng>(_rng: &mut R) -> Result<Self::Parameters, Error> {
+        RescueConfig::generate(ARITY, 1, SETUP_SECURITY_LEVEL)
+    }
+
+    /// Evaluate the Rescue sponge on the inputs.
+    fn evaluate<T: Borrow<Self::Input>>(
+        parameters: &Self::Parameters,
+        inputs: &[T],
+    ) -> Result<Self::Output, Error> {
+        Self::compress(parameters, inputs)
+    }
+
+    /// Compress the inputs using the Rescue sponge.
+    fn compress<T: Borrow<Self::Output>>(
+        parameters: &Self::Parameters,
+        inputs: &[T],
+    ) -> Result<Self::Output, Error> {
+        if inputs.len() != ARITY {
+            return Err(Error::IncorrectInputLength(inputs.len()));
+        }
+        let mut sponge = RescueSponge::new(parameters);
+        sponge.state[0] = arity_tag(ARITY);
+        for input in inputs {
+            sponge.absorb(input.borrow());
+        }
+        let res = sponge.squeeze_field_elements::<F>(1);
+        Ok(res[0])
+    }
+}
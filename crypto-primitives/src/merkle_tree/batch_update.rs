@@ -0,0 +1,99 @@
+use crate::{
+    crh::CRHScheme,
+    merkle_tree::{Config, LeafPadding, LeafParam, MerkleTree, NodeStore, Path, TwoToOneParam},
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::borrow::Borrow;
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+
+/// Stores the witnesses of a sequence of leaf updates, taking a merkle tree from an old root to a
+/// new root.
+///
+/// `paths[i]` is the path of the `i`th updated leaf in the tree obtained after the first `i`
+/// updates, so that the proof can be checked by replaying the updates one after the other, like a
+/// rollup applying a batch of transactions. The same index may be updated several times.
+#[derive(Derivative, CanonicalSerialize, CanonicalDeserialize)]
+#[derivative(
+    PartialEq(bound = "P: Config"),
+    Clone(bound = "P: Config"),
+    Debug(bound = "P: Config"),
+    Default(bound = "P: Config")
+)]
+pub struct BatchUpdateProof<P: Config> {
+    /// The path of each updated leaf, before it is updated.
+    pub paths: Vec<Path<P>>,
+}
+
+impl<P: Config> BatchUpdateProof<P> {
+    /// Returns the number of updates.
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    /// Returns true iff the proof has no update.
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    /// Verify that replacing `old_leaves[i]` by `new_leaves[i]` at `self.paths[i].leaf_index`,
+    /// one update after the other, takes the tree whose root is `old_root` to the tree whose root
+    /// is `new_root`.
+    pub fn verify<L: Borrow<P::Leaf>>(
+        &self,
+        leaf_hash_params: &LeafParam<P>,
+        two_to_one_params: &TwoToOneParam<P>,
+        old_root: &P::InnerDigest,
+        new_root: &P::InnerDigest,
+        old_leaves: &[L],
+        new_leaves: &[L],
+    ) -> Result<bool, crate::Error> {
+        if old_leaves.len() != self.len() || new_leaves.len() != self.len() {
+            return Ok(false);
+        }
+        let mut root = old_root.clone();
+        for ((path, old_leaf), new_leaf) in self.paths.iter().zip(old_leaves).zip(new_leaves) {
+            if !path.verify(
+                leaf_hash_params,
+                two_to_one_params,
+                &root,
+                old_leaf.borrow(),
+            )? {
+                return Ok(false);
+            }
+            let new_leaf_hash = P::LeafHash::evaluate(leaf_hash_params, new_leaf.borrow())?;
+            root = path.root_from_leaf_digest(two_to_one_params, &new_leaf_hash)?;
+        }
+
+        Ok(&root == new_root)
+    }
+}
+
+impl<P: Config, S: NodeStore<P>> MerkleTree<P, S> {
+    /// Update the leaves at the given indices one after the other, and returns the witnesses of
+    /// the updates. The tree goes from its current root to its new root.
+    ///
+    /// Unlike [`MerkleTree::update_batch`], which rehashes the touched nodes once, this records
+    /// the path of every update, as needed by `BatchUpdateVar` to apply the updates in a circuit.
+    ///
+    /// Panics if the tree is padded with [`LeafPadding::DuplicateOdd`], as updating a leaf may
+    /// then update its copy too, which a path does not account for.
+    pub fn update_with_proof<L: AsRef<P::Leaf>>(
+        &mut self,
+        updates: &[(usize, L)],
+    ) -> Result<BatchUpdateProof<P>, crate::Error> {
+        assert!(
+            !matches!(self.padding, LeafPadding::DuplicateOdd),
+            "batch update proofs do not support duplicated nodes"
+        );
+        let paths = updates
+            .iter()
+            .map(|(index, new_leaf)| {
+                let path = self.generate_proof(*index)?;
+                self.update(*index, new_leaf.as_ref())?;
+                Ok(path)
+            })
+            .collect::<Result<Vec<_>, crate::Error>>()?;
+        Ok(BatchUpdateProof { paths })
+    }
+}
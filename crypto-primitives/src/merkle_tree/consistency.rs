@@ -0,0 +1,260 @@
+use crate::{
+    crh::TwoToOneCRHScheme,
+    merkle_tree::{Config, DigestConverter, MerkleTree, NodeStore, TwoToOneParam},
+    Error,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+
+/// Stores a proof that the log made of the first `new_size` leaves of a merkle tree extends the log
+/// made of its first `old_size` leaves, following
+/// [RFC 9162, section 2.1.4](https://www.rfc-editor.org/rfc/rfc9162#section-2.1.4).
+///
+/// The root of a log of `n` leaves is the hash of a left-filled tree, see
+/// [`MerkleTree::rfc9162_root`]: it does not depend on the leaves from `n` on, nor on the padding of
+/// the tree. With [`LeafCRH`](crate::crh::domain_separated::LeafCRH) and
+/// [`InnerCRH`](crate::crh::domain_separated::InnerCRH) over SHA-256, roots and proofs are those of
+/// Certificate Transparency.
+///
+/// Leaf digests and inner digests should have the same type, since a subtree made of a single leaf
+/// is hashed with larger subtrees.
+#[derive(Derivative, CanonicalSerialize, CanonicalDeserialize)]
+#[derivative(
+    PartialEq(bound = "P: Config"),
+    Clone(bound = "P: Config"),
+    Debug(bound = "P: Config")
+)]
+pub struct ConsistencyProof<P: Config> {
+    /// The number of leaves of the old log.
+    pub old_size: usize,
+    /// The number of leaves of the new log.
+    pub new_size: usize,
+    /// The subtree hashes of `SUBPROOF(old_size, D[new_size], true)`, in order.
+    pub nodes: Vec<P::InnerDigest>,
+}
+
+/// Returns the largest power of two smaller than `n`, for `n > 1`.
+#[inline]
+pub(super) fn rfc9162_split(n: usize) -> usize {
+    1 << (usize::BITS - 1 - (n - 1).leading_zeros())
+}
+
+/// Hashes the roots of two adjacent subtrees of `left_size` and `right_size` leaves.
+///
+/// Two single leaves are hashed like the leaves of a [`MerkleTree`], and every other pair like its
+/// inner nodes, so that perfect subtrees have the digests of the tree.
+fn rfc9162_node<P, D>(
+    two_to_one_params: &TwoToOneParam<P>,
+    left: D,
+    left_size: usize,
+    right: D,
+    right_size: usize,
+) -> Result<D, Error>
+where
+    P: Config<LeafDigest = D, InnerDigest = D>,
+    D: Clone + Eq,
+{
+    if left_size == 1 && right_size == 1 {
+        P::TwoToOneHash::evaluate(
+            two_to_one_params,
+            P::LeafInnerDigestConverter::convert(left)?,
+            P::LeafInnerDigestConverter::convert(right)?,
+        )
+    } else {
+        P::TwoToOneHash::compress(two_to_one_params, left, right)
+    }
+}
+
+/// Verifies `SUBPROOF(m, D[n], b)`, whose hashes are popped from the end of `nodes`.
+///
+/// Returns the roots of `D[0:m]` and `D[n]`, or `None` if `nodes` is too short. When `b` is true
+/// and `m` is `n`, `old_root` is taken as the root of `D[0:m]`.
+fn verify_subproof<P, D>(
+    two_to_one_params: &TwoToOneParam<P>,
+    m: usize,
+    n: usize,
+    b: bool,
+    old_root: &D,
+    nodes: &mut Vec<D>,
+) -> Result<Option<(D, D)>, Error>
+where
+    P: Config<LeafDigest = D, InnerDigest = D>,
+    D: Clone + Eq,
+{
+    if m == n {
+        if b {
+            return Ok(Some((old_root.clone(), old_root.clone())));
+        }
+        return Ok(nodes.pop().map(|node| (node.clone(), node)));
+    }
+    let k = rfc9162_split(n);
+    if m <= k {
+        let right = match nodes.pop() {
+            Some(right) => right,
+            None => return Ok(None),
+        };
+        let (old, new) = match verify_subproof::<P, D>(two_to_one_params, m, k, b, old_root, nodes)?
+        {
+            Some(roots) => roots,
+            None => return Ok(None),
+        };
+        let new = rfc9162_node::<P, D>(two_to_one_params, new, k, right, n - k)?;
+        Ok(Some((old, new)))
+    } else {
+        let left = match nodes.pop() {
+            Some(left) => left,
+            None => return Ok(None),
+        };
+        let (old, new) =
+            match verify_subproof::<P, D>(two_to_one_params, m - k, n - k, false, old_root, nodes)?
+            {
+                Some(roots) => roots,
+                None => return Ok(None),
+            };
+        let old = rfc9162_node::<P, D>(two_to_one_params, left.clone(), k, old, m - k)?;
+        let new = rfc9162_node::<P, D>(two_to_one_params, left, k, new, n - k)?;
+        Ok(Some((old, new)))
+    }
+}
+
+impl<P: Config> ConsistencyProof<P> {
+    /// Verify that the log of `self.new_size` leaves with root `new_root` extends the log of
+    /// `self.old_size` leaves with root `old_root`, where roots are computed as in
+    /// [`MerkleTree::rfc9162_root`].
+    pub fn verify<D>(
+        &self,
+        two_to_one_params: &TwoToOneParam<P>,
+        old_root: &D,
+        new_root: &D,
+    ) -> Result<bool, Error>
+    where
+        P: Config<LeafDigest = D, InnerDigest = D>,
+        D: Clone + Eq,
+    {
+        if self.old_size == 0 || self.old_size > self.new_size {
+            return Ok(false);
+        }
+        if self.old_size == self.new_size {
+            return Ok(self.nodes.is_empty() && old_root == new_root);
+        }
+        let mut nodes = self.nodes.clone();
+        let roots = verify_subproof::<P, D>(
+            two_to_one_params,
+            self.old_size,
+            self.new_size,
+            true,
+            old_root,
+            &mut nodes,
+        )?;
+        Ok(match roots {
+            Some((old, new)) => nodes.is_empty() && &old == old_root && &new == new_root,
+            None => false,
+        })
+    }
+}
+
+impl<P: Config, S: NodeStore<P>> MerkleTree<P, S> {
+    /// Returns the root of the log made of the first `size` leaves of this tree, that is the
+    /// merkle tree hash of
+    /// [RFC 9162, section 2.1.1](https://www.rfc-editor.org/rfc/rfc9162#section-2.1.1).
+    ///
+    /// The `size` leaves form a left-filled tree, whose left subtree is the perfect subtree over the
+    /// largest power of two smaller than `size` leaves, and whose right subtree is the left-filled
+    /// tree over the other leaves. Perfect subtrees are read from the tree, so this costs
+    /// `O(log(size))` hashes. Returns an error if `size` is zero or exceeds the number of leaves.
+    pub fn rfc9162_root<D>(&self, size: usize) -> Result<D, Error>
+    where
+        P: Config<LeafDigest = D, InnerDigest = D>,
+        D: Clone + Eq,
+    {
+        if size == 0 || size > 1 << (self.height() - 1) {
+            return Err(Error::IncorrectInputLength(size));
+        }
+        self.rfc9162_subtree_root(0, size)
+    }
+
+    /// Returns a proof that the log made of the first `new_size` leaves of this tree extends the log
+    /// made of its first `old_size` leaves, as defined in
+    /// [RFC 9162, section 2.1.4.1](https://www.rfc-editor.org/rfc/rfc9162#section-2.1.4.1).
+    ///
+    /// Returns an error unless `0 < old_size <= new_size` and `new_size` is at most the number of
+    /// leaves of the tree.
+    pub fn generate_consistency_proof<D>(
+        &self,
+        old_size: usize,
+        new_size: usize,
+    ) -> Result<ConsistencyProof<P>, Error>
+    where
+        P: Config<LeafDigest = D, InnerDigest = D>,
+        D: Clone + Eq,
+    {
+        if old_size == 0 || old_size > new_size {
+            return Err(Error::IncorrectInputLength(old_size));
+        }
+        if new_size > 1 << (self.height() - 1) {
+            return Err(Error::IncorrectInputLength(new_size));
+        }
+        let mut nodes = Vec::new();
+        if old_size < new_size {
+            self.rfc9162_subproof(old_size, 0, new_size, true, &mut nodes)?;
+        }
+        Ok(ConsistencyProof {
+            old_size,
+            new_size,
+            nodes,
+        })
+    }
+
+    /// Appends `SUBPROOF(m, D[start:start + n], b)` to `nodes`.
+    fn rfc9162_subproof<D>(
+        &self,
+        m: usize,
+        start: usize,
+        n: usize,
+        b: bool,
+        nodes: &mut Vec<D>,
+    ) -> Result<(), Error>
+    where
+        P: Config<LeafDigest = D, InnerDigest = D>,
+        D: Clone + Eq,
+    {
+        if m == n {
+            if !b {
+                nodes.push(self.rfc9162_subtree_root(start, n)?);
+            }
+            return Ok(());
+        }
+        let k = rfc9162_split(n);
+        if m <= k {
+            self.rfc9162_subproof(m, start, k, b, nodes)?;
+            nodes.push(self.rfc9162_subtree_root(start + k, n - k)?);
+        } else {
+            self.rfc9162_subproof(m - k, start + k, n - k, false, nodes)?;
+            nodes.push(self.rfc9162_subtree_root(start, k)?);
+        }
+        Ok(())
+    }
+
+    /// Returns the root of the left-filled tree over the `n` leaves from `start` on.
+    ///
+    /// `start` is a multiple of the largest power of two which is at most `n`, so perfect subtrees
+    /// are nodes of the tree.
+    fn rfc9162_subtree_root<D>(&self, start: usize, n: usize) -> Result<D, Error>
+    where
+        P: Config<LeafDigest = D, InnerDigest = D>,
+        D: Clone + Eq,
+    {
+        if n == 1 {
+            return self.store.leaf(start);
+        }
+        if n.is_power_of_two() {
+            let level = self.height() - 1 - n.trailing_zeros() as usize;
+            return self.store.inner(level, start / n);
+        }
+        let k = rfc9162_split(n);
+        let left = self.rfc9162_subtree_root(start, k)?;
+        let right = self.rfc9162_subtree_root(start + k, n - k)?;
+        rfc9162_node::<P, D>(&self.two_to_one_hash_param, left, k, right, n - k)
+    }
+}
@@ -1,13 +1,21 @@
 use crate::{
-    crh::{CRHSchemeGadget, TwoToOneCRHSchemeGadget},
-    merkle_tree::{Config, IdentityDigestConverter, Path},
+    crh::{CRHSchemeGadget, NToOneCRHSchemeGadget, TwoToOneCRHSchemeGadget},
+    merkle_tree::{
+        consistency::rfc9162_split, prefix_decode_path, BatchUpdateProof, Config, ConsistencyProof,
+        IdentityDigestConverter, KAryConfig, KAryPath, MmrProof, MultiPath, NonMembershipProof,
+        Path, RootHistory, SubtreePath,
+    },
 };
 use ark_ff::PrimeField;
-use ark_r1cs_std::prelude::*;
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
 use ark_relations::gr1cs::{Namespace, SynthesisError};
 #[cfg(not(feature = "std"))]
 use ark_std::vec::Vec;
-use ark_std::{borrow::Borrow, fmt::Debug};
+use ark_std::{
+    borrow::Borrow,
+    collections::{BTreeMap, BTreeSet},
+    fmt::Debug,
+};
 
 pub trait DigestVarConverter<From, To: ?Sized> {
     type TargetType: Borrow<To>;
@@ -186,6 +194,16 @@ impl<P: Config, F: PrimeField, PG: ConfigGadget<P, F>> PathVar<P, F, PG> {
         leaf: &PG::Leaf,
     ) -> Result<PG::InnerDigest, SynthesisError> {
         let claimed_leaf_hash = PG::LeafHash::evaluate(leaf_params, leaf)?;
+        self.calculate_root_from_leaf_digest(two_to_one_params, &claimed_leaf_hash)
+    }
+
+    /// Calculate the root of the Merkle tree assuming that `leaf_digest` is the digest of the leaf
+    /// on the path defined by `self`.
+    fn calculate_root_from_leaf_digest(
+        &self,
+        two_to_one_params: &TwoToOneParam<PG, P, F>,
+        claimed_leaf_hash: &PG::LeafDigest,
+    ) -> Result<PG::InnerDigest, SynthesisError> {
         let leaf_sibling_hash = &self.leaf_sibling;
 
         // calculate hash for the bottom non_leaf_layer
@@ -196,10 +214,10 @@ impl<P: Config, F: PrimeField, PG: ConfigGadget<P, F>> PathVar<P, F, PG> {
 
         let left_hash = self
             .leaf_is_right_child
-            .select(leaf_sibling_hash, &claimed_leaf_hash)?;
+            .select(leaf_sibling_hash, claimed_leaf_hash)?;
         let right_hash = self
             .leaf_is_right_child
-            .select(&claimed_leaf_hash, leaf_sibling_hash)?;
+            .select(claimed_leaf_hash, leaf_sibling_hash)?;
 
         // convert leaf digest to inner digest
         let left_hash = PG::LeafInnerConverter::convert(left_hash)?;
@@ -222,6 +240,41 @@ impl<P: Config, F: PrimeField, PG: ConfigGadget<P, F>> PathVar<P, F, PG> {
         Ok(curr_hash)
     }
 
+    /// Calculate the roots of the Merkle tree before and after replacing the leaf whose digest is
+    /// `old_leaf_hash` by the leaf whose digest is `new_leaf_hash`, on the path defined by `self`.
+    ///
+    /// Both roots are hashed in a single pass over the path, each level selecting the children of
+    /// the old and new nodes with its position bit and the sibling they share.
+    fn calculate_old_and_new_roots(
+        &self,
+        two_to_one_params: &TwoToOneParam<PG, P, F>,
+        old_leaf_hash: &PG::LeafDigest,
+        new_leaf_hash: &PG::LeafDigest,
+    ) -> Result<(PG::InnerDigest, PG::InnerDigest), SynthesisError> {
+        let leaf_parent = |leaf_hash: &PG::LeafDigest| {
+            let bit = &self.leaf_is_right_child;
+            let left_hash = bit.select(&self.leaf_sibling, leaf_hash)?;
+            let right_hash = bit.select(leaf_hash, &self.leaf_sibling)?;
+            let left_hash = PG::LeafInnerConverter::convert(left_hash)?;
+            let right_hash = PG::LeafInnerConverter::convert(right_hash)?;
+            PG::TwoToOneHash::evaluate(two_to_one_params, left_hash.borrow(), right_hash.borrow())
+        };
+        let parent = |bit: &Boolean<F>, sibling: &PG::InnerDigest, hash: &PG::InnerDigest| {
+            let left_hash = bit.select(sibling, hash)?;
+            let right_hash = bit.select(hash, sibling)?;
+            PG::TwoToOneHash::compress(two_to_one_params, &left_hash, &right_hash)
+        };
+
+        let mut old_hash = leaf_parent(old_leaf_hash)?;
+        let mut new_hash = leaf_parent(new_leaf_hash)?;
+        for (bit, sibling) in self.path.iter().rev().zip(self.auth_path.iter().rev()) {
+            old_hash = parent(bit, sibling, &old_hash)?;
+            new_hash = parent(bit, sibling, &new_hash)?;
+        }
+
+        Ok((old_hash, new_hash))
+    }
+
     /// Check that hashing a Merkle tree path according to `self`, and
     /// with `leaf` as the leaf, leads to a Merkle tree root equalling `root`.
     #[tracing::instrument(target = "gr1cs", skip(self, leaf_params, two_to_one_params))]
@@ -269,4 +322,1199 @@ impl<P: Config, F: PrimeField, PG: ConfigGadget<P, F>> PathVar<P, F, PG> {
             self.update_leaf(leaf_params, two_to_one_params, old_root, old_leaf, new_leaf)?;
         Ok(actual_new_root.is_eq(&new_root)?)
     }
+
+    /// Check that `self` opens `value` under `key` in the key-indexed sparse merkle tree with root
+    /// `root`, like [`verify_key_membership`](crate::merkle_tree::verify_key_membership).
+    ///
+    /// The leaf position is derived from `key` in-circuit, and the sibling of the value must be
+    /// the leaf holding `key`.
+    #[tracing::instrument(target = "gr1cs", skip(self, leaf_params, two_to_one_params))]
+    pub fn verify_key_membership(
+        &self,
+        leaf_params: &LeafParam<PG, P, F>,
+        two_to_one_params: &TwoToOneParam<PG, P, F>,
+        root: &PG::InnerDigest,
+        key: &PG::Leaf,
+        value: &PG::Leaf,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let key_digest = PG::LeafHash::evaluate(leaf_params, key)?;
+        let mut position = key_leaf_position(&key_digest)?;
+        position[0] = Boolean::TRUE;
+        let mut path = self.clone();
+        path.set_leaf_position(position);
+        Ok(
+            path.verify_membership(leaf_params, two_to_one_params, root, value)?
+                & &self.leaf_sibling.is_eq(&key_digest)?,
+        )
+    }
+}
+
+/// Represents a merkle tree path gadget whose height is chosen at proving time, up to a maximum
+/// height fixed when it is allocated.
+///
+/// The path is padded at the top to the maximum height. The height of the tree is a variable given
+/// when the path is allocated, from which each level gets an `active` flag which is false on the
+/// padding levels. Padding levels are skipped with a conditional select when hashing up to the
+/// root, so a single circuit can verify paths from trees of any height between 2 and the maximum
+/// height.
+#[derive(Debug, Derivative)]
+#[derivative(Clone(bound = "P: Config, F: PrimeField, PG: ConfigGadget<P, F>"))]
+pub struct VariableHeightPathVar<P: Config, F: PrimeField, PG: ConfigGadget<P, F>> {
+    /// `path[i]` is 0 (false) iff ith non-leaf node from top to bottom is left, including the
+    /// padding levels.
+    path: Vec<Boolean<F>>,
+    /// `auth_path[i]` is the entry of sibling of ith non-leaf node from top to bottom, including
+    /// the padding levels.
+    auth_path: Vec<PG::InnerDigest>,
+    /// `active[i]` is true iff the ith level of `auth_path` is part of the tree.
+    active: Vec<Boolean<F>>,
+    /// The height of the tree.
+    height: FpVar<F>,
+    /// The sibling of leaf.
+    leaf_sibling: PG::LeafDigest,
+    /// Is this leaf the right child?
+    leaf_is_right_child: Boolean<F>,
+}
+
+impl<P: Config, F: PrimeField, PG: ConfigGadget<P, F>> VariableHeightPathVar<P, F, PG> {
+    /// Allocates a path of a tree of height `height`, padding it to `max_height`.
+    ///
+    /// The levels of the path are enforced to be active exactly when they are below the top
+    /// `max_height - height` levels, and `height` to be between 2 and `max_height`. Panics if
+    /// `max_height` is less than 2, or if the path is longer than `max_height`.
+    #[tracing::instrument(target = "gr1cs", skip(cs, height, f))]
+    pub fn new_variable_with_max_height<T: Borrow<Path<P>>>(
+        cs: impl Into<Namespace<F>>,
+        max_height: usize,
+        height: &FpVar<F>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        assert!(max_height >= 2, "maximum height should be at least two");
+        let ns = cs.into();
+        let cs = ns.cs();
+        let num_levels = max_height - 2;
+
+        // the ith level from the top is active iff `height >= max_height - i`
+        let mut active = Vec::with_capacity(num_levels);
+        let mut is_active = Boolean::FALSE;
+        for i in 0..num_levels {
+            let h = FpVar::constant(F::from((max_height - i) as u64));
+            is_active |= height.is_eq(&h)?;
+            active.push(is_active.clone());
+        }
+        (is_active | &height.is_eq(&FpVar::constant(F::from(2u64)))?)
+            .enforce_equal(&Boolean::TRUE)?;
+
+        f().and_then(|val| {
+            let val = val.borrow();
+            assert!(
+                val.auth_path.len() <= num_levels,
+                "path should be at most as high as the maximum height"
+            );
+
+            // pad the path at the top
+            let num_padding = num_levels - val.auth_path.len();
+            let pos_list: Vec<_> = val.position_list().collect();
+            let bits: Vec<_> = ark_std::iter::repeat(false)
+                .take(num_padding)
+                .chain(pos_list[..pos_list.len() - 1].iter().cloned())
+                .collect();
+            let auth_path: Vec<_> = ark_std::iter::repeat(P::InnerDigest::default())
+                .take(num_padding)
+                .chain(val.auth_path.iter().cloned())
+                .collect();
+
+            let leaf_sibling = PG::LeafDigest::new_variable(
+                ark_relations::ns!(cs, "leaf_sibling"),
+                || Ok(val.leaf_sibling_hash.clone()),
+                mode,
+            )?;
+            let leaf_is_right_child = Boolean::new_variable(
+                ark_relations::ns!(cs, "leaf_position_bit"),
+                || Ok(val.leaf_index & 1 == 1),
+                mode,
+            )?;
+            let path =
+                Vec::new_variable(ark_relations::ns!(cs, "path_bits"), || Ok(&bits[..]), mode)?;
+            let auth_path = Vec::new_variable(
+                ark_relations::ns!(cs, "auth_path_nodes"),
+                || Ok(&auth_path[..]),
+                mode,
+            )?;
+
+            // the position bits of the padding levels are zero, so the leaf index is canonical
+            for (bit, active) in path.iter().zip(&active) {
+                (bit & &!active).enforce_equal(&Boolean::FALSE)?;
+            }
+
+            Ok(VariableHeightPathVar {
+                path,
+                auth_path,
+                active,
+                height: height.clone(),
+                leaf_sibling,
+                leaf_is_right_child,
+            })
+        })
+    }
+
+    /// Returns the maximum height of the trees whose paths fit in `self`.
+    pub fn max_height(&self) -> usize {
+        self.auth_path.len() + 2
+    }
+
+    /// Returns the height of the tree of the path.
+    pub fn height(&self) -> FpVar<F> {
+        self.height.clone()
+    }
+
+    /// Return the leaf position index in little-endian form, padded with zeros to
+    /// `self.max_height() - 1` bits.
+    pub fn get_leaf_position(&self) -> Vec<Boolean<F>> {
+        ark_std::iter::once(self.leaf_is_right_child.clone())
+            .chain(self.path.clone().into_iter().rev())
+            .collect()
+    }
+
+    /// Calculate the root of the Merkle tree assuming that `leaf` is the leaf on the path defined
+    /// by `self`.
+    #[tracing::instrument(target = "gr1cs", skip(self, leaf_params, two_to_one_params))]
+    pub fn calculate_root(
+        &self,
+        leaf_params: &LeafParam<PG, P, F>,
+        two_to_one_params: &TwoToOneParam<PG, P, F>,
+        leaf: &PG::Leaf,
+    ) -> Result<PG::InnerDigest, SynthesisError> {
+        let claimed_leaf_hash = PG::LeafHash::evaluate(leaf_params, leaf)?;
+        let left_hash = self
+            .leaf_is_right_child
+            .select(&self.leaf_sibling, &claimed_leaf_hash)?;
+        let right_hash = self
+            .leaf_is_right_child
+            .select(&claimed_leaf_hash, &self.leaf_sibling)?;
+        let left_hash = PG::LeafInnerConverter::convert(left_hash)?;
+        let right_hash = PG::LeafInnerConverter::convert(right_hash)?;
+        let mut curr_hash =
+            PG::TwoToOneHash::evaluate(two_to_one_params, left_hash.borrow(), right_hash.borrow())?;
+
+        // hash every level from bottom to top, keeping the current hash on padding levels
+        for ((bit, sibling), active) in self
+            .path
+            .iter()
+            .rev()
+            .zip(self.auth_path.iter().rev())
+            .zip(self.active.iter().rev())
+        {
+            let left_hash = bit.select(sibling, &curr_hash)?;
+            let right_hash = bit.select(&curr_hash, sibling)?;
+            let parent = PG::TwoToOneHash::compress(two_to_one_params, &left_hash, &right_hash)?;
+            curr_hash = active.select(&parent, &curr_hash)?;
+        }
+
+        Ok(curr_hash)
+    }
+
+    /// Check that hashing a Merkle tree path according to `self`, and
+    /// with `leaf` as the leaf, leads to a Merkle tree root equalling `root`.
+    #[tracing::instrument(target = "gr1cs", skip(self, leaf_params, two_to_one_params))]
+    pub fn verify_membership(
+        &self,
+        leaf_params: &LeafParam<PG, P, F>,
+        two_to_one_params: &TwoToOneParam<PG, P, F>,
+        root: &PG::InnerDigest,
+        leaf: &PG::Leaf,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let expected_root = self.calculate_root(leaf_params, two_to_one_params, leaf)?;
+        Ok(expected_root.is_eq(root)?)
+    }
+}
+
+/// Represents a gadget for [`NonMembershipProof`] of a key-indexed sparse merkle tree.
+#[derive(Debug, Derivative)]
+#[derivative(Clone(bound = "P: Config, F: PrimeField, PG: ConfigGadget<P, F>"))]
+pub struct NonMembershipProofVar<P: Config, F: PrimeField, PG: ConfigGadget<P, F>> {
+    /// The path to the key leaf of the slot in which the key would be stored.
+    path: PathVar<P, F, PG>,
+    /// The digest stored in the key leaf.
+    key_digest: PG::LeafDigest,
+}
+
+impl<P, F, PG: ConfigGadget<P, F>> AllocVar<NonMembershipProof<P>, F>
+    for NonMembershipProofVar<P, F, PG>
+where
+    P: Config,
+    F: PrimeField,
+{
+    #[tracing::instrument(target = "gr1cs", skip(cs, f))]
+    fn new_variable<T: Borrow<NonMembershipProof<P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        f().and_then(|val| {
+            let path = PathVar::new_variable(
+                ark_relations::ns!(cs, "path"),
+                || Ok(&val.borrow().path),
+                mode,
+            )?;
+            let key_digest = PG::LeafDigest::new_variable(
+                ark_relations::ns!(cs, "key_digest"),
+                || Ok(val.borrow().key_digest.clone()),
+                mode,
+            )?;
+            Ok(NonMembershipProofVar { path, key_digest })
+        })
+    }
+}
+
+impl<P: Config, F: PrimeField, PG: ConfigGadget<P, F>> NonMembershipProofVar<P, F, PG> {
+    /// Check that the slot of `key` does not hold `key` in the Merkle tree with root `root`.
+    ///
+    /// The slot is derived from `key` in-circuit exactly as
+    /// [`key_position`](crate::merkle_tree::key_position) does natively, so the leaf index of
+    /// the witnessed path is not trusted.
+    #[tracing::instrument(target = "gr1cs", skip(self, leaf_params, two_to_one_params))]
+    pub fn verify(
+        &self,
+        leaf_params: &LeafParam<PG, P, F>,
+        two_to_one_params: &TwoToOneParam<PG, P, F>,
+        root: &PG::InnerDigest,
+        key: &PG::Leaf,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let key_digest = PG::LeafHash::evaluate(leaf_params, key)?;
+        let mut path = self.path.clone();
+        path.set_leaf_position(key_leaf_position(&key_digest)?);
+
+        let expected_root =
+            path.calculate_root_from_leaf_digest(two_to_one_params, &self.key_digest)?;
+        Ok(expected_root.is_eq(root)? & &self.key_digest.is_neq(&key_digest)?)
+    }
+}
+
+/// Returns the little-endian position of the key leaf of the slot of the key with digest
+/// `key_digest`, as [`key_position`](crate::merkle_tree::key_position) computes it natively.
+fn key_leaf_position<F: PrimeField, D: ToBytesGadget<F>>(
+    key_digest: &D,
+) -> Result<Vec<Boolean<F>>, SynthesisError> {
+    let mut slot = key_digest.to_bytes_le()?.to_bits_le()?;
+    // natively, slots are truncated to `usize::BITS - 1` bits
+    slot.truncate(usize::BITS as usize - 1);
+    Ok(ark_std::iter::once(Boolean::FALSE).chain(slot).collect())
+}
+
+/// Represents a gadget for [`MultiPath`], opening several leaves of the same tree at once.
+///
+/// The leaf indexes are part of the circuit structure: they are allocated as constants, and only
+/// the siblings which cannot be computed from the opened leaves are witnessed. Each ancestor of
+/// the opened leaves is hashed exactly once, so paths sharing a prefix share their hashes.
+#[derive(Debug, Derivative)]
+#[derivative(Clone(bound = "P: Config, F: PrimeField, PG: ConfigGadget<P, F>"))]
+pub struct MultiPathVar<P: Config, F: PrimeField, PG: ConfigGadget<P, F>> {
+    /// The sorted and deduplicated indexes of the opened leaves.
+    leaf_indexes: Vec<usize>,
+    /// The height of the tree.
+    height: usize,
+    /// The siblings of the opened leaves which are not opened themselves, keyed by position.
+    leaf_siblings: BTreeMap<usize, PG::LeafDigest>,
+    /// The siblings of the ancestors of the opened leaves which are not ancestors themselves,
+    /// keyed by (number of levels above the leaves, position).
+    auth_nodes: BTreeMap<(usize, usize), PG::InnerDigest>,
+}
+
+impl<P, F, PG: ConfigGadget<P, F>> AllocVar<MultiPath<P>, F> for MultiPathVar<P, F, PG>
+where
+    P: Config,
+    F: PrimeField,
+{
+    #[tracing::instrument(target = "gr1cs", skip(cs, f))]
+    fn new_variable<T: Borrow<MultiPath<P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        f().and_then(|val| {
+            let multi_path = val.borrow();
+            let height = multi_path.auth_paths_suffixes[0].len() + 2;
+            let leaf_indexes = multi_path.leaf_indexes.clone();
+
+            // decode the auth paths
+            let mut auth_paths = Vec::with_capacity(leaf_indexes.len());
+            let mut prev_path = Vec::new();
+            for (prefix_len, suffix) in multi_path
+                .auth_paths_prefix_lenghts
+                .iter()
+                .zip(&multi_path.auth_paths_suffixes)
+            {
+                prev_path = prefix_decode_path(&prev_path, *prefix_len, suffix);
+                auth_paths.push(prev_path.clone());
+            }
+
+            // witness the siblings of opened leaves which are not opened
+            let opened: BTreeSet<usize> = leaf_indexes.iter().copied().collect();
+            let mut leaf_siblings = BTreeMap::new();
+            for (i, index) in leaf_indexes.iter().enumerate() {
+                let sibling = index ^ 1;
+                if !opened.contains(&sibling) && !leaf_siblings.contains_key(&sibling) {
+                    let digest = PG::LeafDigest::new_variable(
+                        ark_relations::ns!(cs, "leaf_sibling"),
+                        || Ok(multi_path.leaf_siblings_hashes[i].clone()),
+                        mode,
+                    )?;
+                    leaf_siblings.insert(sibling, digest);
+                }
+            }
+
+            // witness the siblings of ancestors which are not ancestors, level by level
+            let mut auth_nodes = BTreeMap::new();
+            for level in 1..height - 1 {
+                let ancestors: BTreeSet<usize> =
+                    leaf_indexes.iter().map(|index| index >> level).collect();
+                for (i, index) in leaf_indexes.iter().enumerate() {
+                    let sibling = (index >> level) ^ 1;
+                    if !ancestors.contains(&sibling) && !auth_nodes.contains_key(&(level, sibling))
+                    {
+                        let node = PG::InnerDigest::new_variable(
+                            ark_relations::ns!(cs, "auth_path_node"),
+                            || Ok(auth_paths[i][height - 2 - level].clone()),
+                            mode,
+                        )?;
+                        auth_nodes.insert((level, sibling), node);
+                    }
+                }
+            }
+
+            Ok(MultiPathVar {
+                leaf_indexes,
+                height,
+                leaf_siblings,
+                auth_nodes,
+            })
+        })
+    }
+}
+
+impl<P: Config, F: PrimeField, PG: ConfigGadget<P, F>> MultiPathVar<P, F, PG> {
+    /// Returns the indexes of the opened leaves, in increasing order.
+    pub fn leaf_indexes(&self) -> &[usize] {
+        &self.leaf_indexes
+    }
+
+    /// Calculate the root of the Merkle tree assuming that `leaves` are the leaves at
+    /// `self.leaf_indexes()`, in the same order.
+    #[tracing::instrument(target = "gr1cs", skip(self, leaf_params, two_to_one_params, leaves))]
+    pub fn calculate_root<L: Borrow<PG::Leaf>>(
+        &self,
+        leaf_params: &LeafParam<PG, P, F>,
+        two_to_one_params: &TwoToOneParam<PG, P, F>,
+        leaves: impl IntoIterator<Item = L>,
+    ) -> Result<PG::InnerDigest, SynthesisError> {
+        let leaf_hashes = self
+            .leaf_indexes
+            .iter()
+            .zip(leaves)
+            .map(|(index, leaf)| Ok((*index, PG::LeafHash::evaluate(leaf_params, leaf.borrow())?)))
+            .collect::<Result<BTreeMap<_, _>, SynthesisError>>()?;
+        assert_eq!(
+            leaf_hashes.len(),
+            self.leaf_indexes.len(),
+            "one leaf should be provided for each leaf index"
+        );
+
+        // hash the bottom non-leaf layer
+        let parents: BTreeSet<usize> = self.leaf_indexes.iter().map(|index| index >> 1).collect();
+        let mut nodes = BTreeMap::new();
+        for parent in parents {
+            let child = |position| {
+                leaf_hashes
+                    .get(&position)
+                    .unwrap_or_else(|| &self.leaf_siblings[&position])
+                    .clone()
+            };
+            let left_hash = PG::LeafInnerConverter::convert(child(2 * parent))?;
+            let right_hash = PG::LeafInnerConverter::convert(child(2 * parent + 1))?;
+            let node = PG::TwoToOneHash::evaluate(
+                two_to_one_params,
+                left_hash.borrow(),
+                right_hash.borrow(),
+            )?;
+            nodes.insert(parent, node);
+        }
+
+        // then hash every ancestor once, from bottom to top
+        for level in 1..self.height - 1 {
+            let parents: BTreeSet<usize> = nodes.keys().map(|position| position >> 1).collect();
+            let mut parent_nodes = BTreeMap::new();
+            for parent in parents {
+                let child = |position| {
+                    nodes
+                        .get(&position)
+                        .unwrap_or_else(|| &self.auth_nodes[&(level, position)])
+                };
+                let node = PG::TwoToOneHash::compress(
+                    two_to_one_params,
+                    child(2 * parent),
+                    child(2 * parent + 1),
+                )?;
+                parent_nodes.insert(parent, node);
+            }
+            nodes = parent_nodes;
+        }
+
+        Ok(nodes.remove(&0).unwrap())
+    }
+
+    /// Check that hashing the Merkle tree paths according to `self`, with `leaves` as the leaves
+    /// at `self.leaf_indexes()`, leads to a Merkle tree root equalling `root`.
+    #[tracing::instrument(target = "gr1cs", skip(self, leaf_params, two_to_one_params, leaves))]
+    pub fn verify_membership<L: Borrow<PG::Leaf>>(
+        &self,
+        leaf_params: &LeafParam<PG, P, F>,
+        two_to_one_params: &TwoToOneParam<PG, P, F>,
+        root: &PG::InnerDigest,
+        leaves: impl IntoIterator<Item = L>,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let expected_root = self.calculate_root(leaf_params, two_to_one_params, leaves)?;
+        Ok(expected_root.is_eq(root)?)
+    }
+}
+
+/// Represents a gadget for [`MmrProof`], an inclusion proof in a Merkle Mountain Range.
+///
+/// The size of the range and the peak holding the leaf are part of the circuit structure, while
+/// the position of the leaf inside its peak is witnessed by a [`PathVar`].
+#[derive(Debug, Derivative)]
+#[derivative(Clone(bound = "P: Config, F: PrimeField, PG: ConfigGadget<P, F>"))]
+pub struct MmrProofVar<P: Config, F: PrimeField, PG: ConfigGadget<P, F>> {
+    /// The path from the leaf to the root of its peak.
+    path: PathVar<P, F, PG>,
+    /// The peaks on the left of the peak holding the leaf, from left to right.
+    left_peaks: Vec<PG::InnerDigest>,
+    /// The peaks on the right of the peak holding the leaf, from left to right.
+    right_peaks: Vec<PG::InnerDigest>,
+}
+
+impl<P, F, PG: ConfigGadget<P, F>> AllocVar<MmrProof<P>, F> for MmrProofVar<P, F, PG>
+where
+    P: Config,
+    F: PrimeField,
+{
+    #[tracing::instrument(target = "gr1cs", skip(cs, f))]
+    fn new_variable<T: Borrow<MmrProof<P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        f().and_then(|val| {
+            let proof = val.borrow();
+            let path =
+                PathVar::new_variable(ark_relations::ns!(cs, "path"), || Ok(&proof.path), mode)?;
+            let left_peaks = Vec::new_variable(
+                ark_relations::ns!(cs, "left_peaks"),
+                || Ok(&proof.left_peaks[..]),
+                mode,
+            )?;
+            let right_peaks = Vec::new_variable(
+                ark_relations::ns!(cs, "right_peaks"),
+                || Ok(&proof.right_peaks[..]),
+                mode,
+            )?;
+            Ok(MmrProofVar {
+                path,
+                left_peaks,
+                right_peaks,
+            })
+        })
+    }
+}
+
+impl<P: Config, F: PrimeField, PG: ConfigGadget<P, F>> MmrProofVar<P, F, PG> {
+    /// Returns the path from the leaf to the root of its peak.
+    pub fn path(&self) -> &PathVar<P, F, PG> {
+        &self.path
+    }
+
+    /// Calculate the root of the Merkle Mountain Range assuming that `leaf` is the leaf proven by
+    /// `self`.
+    #[tracing::instrument(target = "gr1cs", skip(self, leaf_params, two_to_one_params))]
+    pub fn calculate_root(
+        &self,
+        leaf_params: &LeafParam<PG, P, F>,
+        two_to_one_params: &TwoToOneParam<PG, P, F>,
+        leaf: &PG::Leaf,
+    ) -> Result<PG::InnerDigest, SynthesisError> {
+        let peak = self
+            .path
+            .calculate_root(leaf_params, two_to_one_params, leaf)?;
+
+        // bag the peaks from right to left
+        let peaks: Vec<_> = self
+            .left_peaks
+            .iter()
+            .chain(ark_std::iter::once(&peak))
+            .chain(&self.right_peaks)
+            .collect();
+        let (last, rest) = peaks.split_last().unwrap();
+        let mut bag = (*last).clone();
+        for peak in rest.iter().rev() {
+            bag = PG::TwoToOneHash::compress(two_to_one_params, *peak, &bag)?;
+        }
+        Ok(bag)
+    }
+
+    /// Check that the leaf proven by `self` is `leaf`, in the Merkle Mountain Range with root
+    /// `root`.
+    #[tracing::instrument(target = "gr1cs", skip(self, leaf_params, two_to_one_params))]
+    pub fn verify_membership(
+        &self,
+        leaf_params: &LeafParam<PG, P, F>,
+        two_to_one_params: &TwoToOneParam<PG, P, F>,
+        root: &PG::InnerDigest,
+        leaf: &PG::Leaf,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let expected_root = self.calculate_root(leaf_params, two_to_one_params, leaf)?;
+        Ok(expected_root.is_eq(root)?)
+    }
+}
+
+/// Represents a gadget for [`ConsistencyProof`], proving that a log of RFC 9162 extends another
+/// one.
+///
+/// The sizes of both logs are constants, so they fix the shape of the circuit.
+#[derive(Debug, Derivative)]
+#[derivative(Clone(bound = "P: Config, F: PrimeField, PG: ConfigGadget<P, F>"))]
+pub struct ConsistencyProofVar<P: Config, F: PrimeField, PG: ConfigGadget<P, F>> {
+    old_size: usize,
+    new_size: usize,
+    /// The subtree hashes of the proof, in order.
+    nodes: Vec<PG::InnerDigest>,
+}
+
+impl<P, F, PG: ConfigGadget<P, F>> AllocVar<ConsistencyProof<P>, F>
+    for ConsistencyProofVar<P, F, PG>
+where
+    P: Config,
+    F: PrimeField,
+{
+    #[tracing::instrument(target = "gr1cs", skip(cs, f))]
+    fn new_variable<T: Borrow<ConsistencyProof<P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        f().and_then(|val| {
+            let proof = val.borrow();
+            let nodes = Vec::new_variable(
+                ark_relations::ns!(cs, "nodes"),
+                || Ok(&proof.nodes[..]),
+                mode,
+            )?;
+            Ok(ConsistencyProofVar {
+                old_size: proof.old_size,
+                new_size: proof.new_size,
+                nodes,
+            })
+        })
+    }
+}
+
+/// Gadget counterpart of the hash of two adjacent subtrees used by [`ConsistencyProof::verify`].
+fn rfc9162_node_var<P, F, PG, DV>(
+    two_to_one_params: &TwoToOneParam<PG, P, F>,
+    left: DV,
+    left_size: usize,
+    right: DV,
+    right_size: usize,
+) -> Result<DV, SynthesisError>
+where
+    P: Config,
+    F: PrimeField,
+    PG: ConfigGadget<P, F, LeafDigest = DV, InnerDigest = DV>,
+{
+    if left_size == 1 && right_size == 1 {
+        let left = PG::LeafInnerConverter::convert(left)?;
+        let right = PG::LeafInnerConverter::convert(right)?;
+        PG::TwoToOneHash::evaluate(two_to_one_params, left.borrow(), right.borrow())
+    } else {
+        PG::TwoToOneHash::compress(two_to_one_params, &left, &right)
+    }
+}
+
+/// Gadget counterpart of the verification of `SUBPROOF(m, D[n], b)` by
+/// [`ConsistencyProof::verify`], popping hashes from the end of `nodes`.
+fn verify_subproof_var<P, F, PG, DV>(
+    two_to_one_params: &TwoToOneParam<PG, P, F>,
+    m: usize,
+    n: usize,
+    b: bool,
+    old_root: &DV,
+    nodes: &mut Vec<DV>,
+) -> Result<Option<(DV, DV)>, SynthesisError>
+where
+    P: Config,
+    F: PrimeField,
+    PG: ConfigGadget<P, F, LeafDigest = DV, InnerDigest = DV>,
+    DV: Clone,
+{
+    if m == n {
+        if b {
+            return Ok(Some((old_root.clone(), old_root.clone())));
+        }
+        return Ok(nodes.pop().map(|node| (node.clone(), node)));
+    }
+    let k = rfc9162_split(n);
+    if m <= k {
+        let right = match nodes.pop() {
+            Some(right) => right,
+            None => return Ok(None),
+        };
+        let (old, new) =
+            match verify_subproof_var::<P, F, PG, DV>(two_to_one_params, m, k, b, old_root, nodes)?
+            {
+                Some(roots) => roots,
+                None => return Ok(None),
+            };
+        let new = rfc9162_node_var::<P, F, PG, DV>(two_to_one_params, new, k, right, n - k)?;
+        Ok(Some((old, new)))
+    } else {
+        let left = match nodes.pop() {
+            Some(left) => left,
+            None => return Ok(None),
+        };
+        let (old, new) = match verify_subproof_var::<P, F, PG, DV>(
+            two_to_one_params,
+            m - k,
+            n - k,
+            false,
+            old_root,
+            nodes,
+        )? {
+            Some(roots) => roots,
+            None => return Ok(None),
+        };
+        let old = rfc9162_node_var::<P, F, PG, DV>(two_to_one_params, left.clone(), k, old, m - k)?;
+        let new = rfc9162_node_var::<P, F, PG, DV>(two_to_one_params, left, k, new, n - k)?;
+        Ok(Some((old, new)))
+    }
+}
+
+impl<P: Config, F: PrimeField, PG: ConfigGadget<P, F>> ConsistencyProofVar<P, F, PG> {
+    /// Check that the log with root `new_root` extends the log with root `old_root`.
+    ///
+    /// Returns `Boolean::FALSE` if the proof does not have the shape given by its sizes.
+    #[tracing::instrument(target = "gr1cs", skip(self, two_to_one_params, old_root, new_root))]
+    pub fn verify<DV>(
+        &self,
+        two_to_one_params: &TwoToOneParam<PG, P, F>,
+        old_root: &DV,
+        new_root: &DV,
+    ) -> Result<Boolean<F>, SynthesisError>
+    where
+        PG: ConfigGadget<P, F, LeafDigest = DV, InnerDigest = DV>,
+        DV: EqGadget<F> + Clone,
+    {
+        if self.old_size == 0 || self.old_size > self.new_size {
+            return Ok(Boolean::FALSE);
+        }
+        if self.old_size == self.new_size {
+            if !self.nodes.is_empty() {
+                return Ok(Boolean::FALSE);
+            }
+            return old_root.is_eq(new_root);
+        }
+        let mut nodes = self.nodes.clone();
+        let roots = verify_subproof_var::<P, F, PG, DV>(
+            two_to_one_params,
+            self.old_size,
+            self.new_size,
+            true,
+            old_root,
+            &mut nodes,
+        )?;
+        match roots {
+            Some((old, new)) if nodes.is_empty() => {
+                Ok(old.is_eq(old_root)? & &new.is_eq(new_root)?)
+            }
+            _ => Ok(Boolean::FALSE),
+        }
+    }
+}
+
+/// Represents a gadget for a [`RootHistory`], the list of roots a membership proof is accepted
+/// against.
+///
+/// The roots are typically allocated as public inputs. So that the shape of the circuit only
+/// depends on the capacity of the history, a history which is not full is padded with copies of
+/// its latest root, and allocating an empty history fails. Checking a root costs one equality per root; for long histories, committing to
+/// the roots with a [`MerkleTree`](super::MerkleTree) and opening it with a [`PathVar`] is cheaper.
+#[derive(Debug, Derivative)]
+#[derivative(Clone(bound = "P: Config, F: PrimeField, PG: ConfigGadget<P, F>"))]
+pub struct RootHistoryVar<P: Config, F: PrimeField, PG: ConfigGadget<P, F>> {
+    roots: Vec<PG::InnerDigest>,
+}
+
+impl<P, F, PG: ConfigGadget<P, F>> AllocVar<RootHistory<P::InnerDigest>, F>
+    for RootHistoryVar<P, F, PG>
+where
+    P: Config,
+    F: PrimeField,
+{
+    #[tracing::instrument(target = "gr1cs", skip(cs, f))]
+    fn new_variable<T: Borrow<RootHistory<P::InnerDigest>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        f().and_then(|val| {
+            let history = val.borrow();
+            // an empty history has no root to pad with
+            let latest = history
+                .latest()
+                .cloned()
+                .ok_or(SynthesisError::AssignmentMissing)?;
+            let roots: Vec<_> = history
+                .iter()
+                .cloned()
+                .chain(ark_std::iter::repeat(latest))
+                .take(history.capacity())
+                .collect();
+            let roots =
+                Vec::new_variable(ark_relations::ns!(cs, "roots"), || Ok(&roots[..]), mode)?;
+            Ok(RootHistoryVar { roots })
+        })
+    }
+}
+
+impl<P: Config, F: PrimeField, PG: ConfigGadget<P, F>> RootHistoryVar<P, F, PG> {
+    /// Returns the roots of the history, from oldest to newest, padded to its capacity.
+    pub fn roots(&self) -> &[PG::InnerDigest] {
+        &self.roots
+    }
+
+    /// Check that `root` is one of the roots of the history.
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    pub fn contains(&self, root: &PG::InnerDigest) -> Result<Boolean<F>, SynthesisError> {
+        let is_eq = self
+            .roots
+            .iter()
+            .map(|known_root| known_root.is_eq(root))
+            .collect::<Result<Vec<_>, _>>()?;
+        Boolean::kary_or(&is_eq)
+    }
+}
+
+/// Represents a gadget for a [`SubtreePath`], the path from a subtree root to the root of the
+/// merkle tree.
+#[derive(Debug, Derivative)]
+#[derivative(Clone(bound = "P: Config, F: PrimeField, PG: ConfigGadget<P, F>"))]
+pub struct SubtreePathVar<P: Config, F: PrimeField, PG: ConfigGadget<P, F>> {
+    /// `path[i]` is 0 (false) iff ith on-path node below the root, from top to bottom, is left.
+    path: Vec<Boolean<F>>,
+    /// `auth_path[i]` is the entry of sibling of ith on-path node below the root, from top to
+    /// bottom.
+    auth_path: Vec<PG::InnerDigest>,
+}
+
+impl<P, F, PG: ConfigGadget<P, F>> AllocVar<SubtreePath<P>, F> for SubtreePathVar<P, F, PG>
+where
+    P: Config,
+    F: PrimeField,
+{
+    #[tracing::instrument(target = "gr1cs", skip(cs, f))]
+    fn new_variable<T: Borrow<SubtreePath<P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        f().and_then(|val| {
+            let pos_list: Vec<_> = val.borrow().position_list().collect();
+            let path = Vec::new_variable(
+                ark_relations::ns!(cs, "path_bits"),
+                || Ok(&pos_list[..]),
+                mode,
+            )?;
+            let auth_path = Vec::new_variable(
+                ark_relations::ns!(cs, "auth_path_nodes"),
+                || Ok(&val.borrow().auth_path[..]),
+                mode,
+            )?;
+            Ok(SubtreePathVar { path, auth_path })
+        })
+    }
+}
+
+impl<P: Config, F: PrimeField, PG: ConfigGadget<P, F>> SubtreePathVar<P, F, PG> {
+    /// Returns the level of the subtree root, that is its distance to the root.
+    pub fn level(&self) -> usize {
+        self.auth_path.len()
+    }
+
+    /// Return the index of the subtree root in its level, in little-endian form.
+    pub fn get_subtree_position(&self) -> Vec<Boolean<F>> {
+        self.path.iter().rev().cloned().collect()
+    }
+
+    /// Calculate the root of the Merkle tree assuming that `subtree_root` is the subtree root on
+    /// the path defined by `self`.
+    #[tracing::instrument(target = "gr1cs", skip(self, two_to_one_params))]
+    pub fn calculate_root(
+        &self,
+        two_to_one_params: &TwoToOneParam<PG, P, F>,
+        subtree_root: &PG::InnerDigest,
+    ) -> Result<PG::InnerDigest, SynthesisError> {
+        let mut curr_hash = subtree_root.clone();
+        for (bit, sibling) in self.path.iter().rev().zip(self.auth_path.iter().rev()) {
+            let left_hash = bit.select(sibling, &curr_hash)?;
+            let right_hash = bit.select(&curr_hash, sibling)?;
+
+            curr_hash = PG::TwoToOneHash::compress(two_to_one_params, &left_hash, &right_hash)?;
+        }
+
+        Ok(curr_hash)
+    }
+
+    /// Check that hashing a Merkle tree path according to `self`, and with `subtree_root` as the
+    /// subtree root, leads to a Merkle tree root equalling `root`.
+    #[tracing::instrument(target = "gr1cs", skip(self, two_to_one_params))]
+    pub fn verify_membership(
+        &self,
+        two_to_one_params: &TwoToOneParam<PG, P, F>,
+        root: &PG::InnerDigest,
+        subtree_root: &PG::InnerDigest,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let expected_root = self.calculate_root(two_to_one_params, subtree_root)?;
+        Ok(expected_root.is_eq(root)?)
+    }
+}
+
+/// Represents a gadget for a [`BatchUpdateProof`], applying a sequence of leaf updates to a
+/// merkle tree in a circuit.
+///
+/// Each update checks that its path is at the given index, hashes the old and new leaves up its
+/// path in a single pass, checks the old root against the root left by the previous update, and
+/// carries the new root to the next update, so that a state transition made of several updates
+/// does not allocate its intermediate roots.
+#[derive(Debug, Derivative)]
+#[derivative(Clone(bound = "P: Config, F: PrimeField, PG: ConfigGadget<P, F>"))]
+pub struct BatchUpdateVar<P: Config, F: PrimeField, PG: ConfigGadget<P, F>> {
+    /// `paths[i]` is the path of the ith updated leaf, before it is updated.
+    paths: Vec<PathVar<P, F, PG>>,
+}
+
+impl<P, F, PG: ConfigGadget<P, F>> AllocVar<BatchUpdateProof<P>, F> for BatchUpdateVar<P, F, PG>
+where
+    P: Config,
+    F: PrimeField,
+{
+    #[tracing::instrument(target = "gr1cs", skip(cs, f))]
+    fn new_variable<T: Borrow<BatchUpdateProof<P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        f().and_then(|val| {
+            let paths = Vec::new_variable(
+                ark_relations::ns!(cs, "paths"),
+                || Ok(&val.borrow().paths[..]),
+                mode,
+            )?;
+            Ok(BatchUpdateVar { paths })
+        })
+    }
+}
+
+impl<P: Config, F: PrimeField, PG: ConfigGadget<P, F>> BatchUpdateVar<P, F, PG> {
+    /// Returns the paths of the updated leaves, before they are updated.
+    pub fn paths(&self) -> &[PathVar<P, F, PG>] {
+        &self.paths
+    }
+
+    /// Return the positions of the updated leaves, each in little-endian form.
+    pub fn get_leaf_positions(&self) -> Vec<Vec<Boolean<F>>> {
+        self.paths.iter().map(PathVar::get_leaf_position).collect()
+    }
+
+    /// Replays the updates from `old_root`, and returns whether every path is at its index and
+    /// every old leaf is in the tree left by the previous updates, along with the root after each
+    /// update.
+    fn apply<L: Borrow<PG::Leaf>>(
+        &self,
+        leaf_params: &LeafParam<PG, P, F>,
+        two_to_one_params: &TwoToOneParam<PG, P, F>,
+        old_root: &PG::InnerDigest,
+        updates: &[(FpVar<F>, L, L)],
+    ) -> Result<(Boolean<F>, Vec<PG::InnerDigest>), SynthesisError> {
+        assert_eq!(updates.len(), self.paths.len(), "one update per path");
+        let mut is_valid = Boolean::TRUE;
+        let mut roots = Vec::with_capacity(self.paths.len());
+        for (path, (index, old_leaf, new_leaf)) in self.paths.iter().zip(updates) {
+            let position = Boolean::le_bits_to_fp(&path.get_leaf_position())?;
+            let old_leaf_hash = PG::LeafHash::evaluate(leaf_params, old_leaf.borrow())?;
+            let new_leaf_hash = PG::LeafHash::evaluate(leaf_params, new_leaf.borrow())?;
+            let (expected_old_root, new_root) = path.calculate_old_and_new_roots(
+                two_to_one_params,
+                &old_leaf_hash,
+                &new_leaf_hash,
+            )?;
+            let root = roots.last().unwrap_or(old_root);
+            is_valid = is_valid & &position.is_eq(index)? & &expected_old_root.is_eq(root)?;
+            roots.push(new_root);
+        }
+        Ok((is_valid, roots))
+    }
+
+    /// Apply `updates`, made of an index, an old leaf and a new leaf, to the tree whose root is
+    /// `old_root`. The `i`th update checks that `self.paths()[i]` is the path of the leaf at its
+    /// index, and that its old leaf is this leaf in the tree left by the first `i` updates, and
+    /// then computes the root after replacing it by its new leaf. Returns the root after each
+    /// update, the last one being the new root.
+    ///
+    /// Panics if the number of updates is not the number of paths.
+    #[tracing::instrument(target = "gr1cs", skip(self, leaf_params, two_to_one_params, updates))]
+    pub fn update_leaves<L: Borrow<PG::Leaf>>(
+        &self,
+        leaf_params: &LeafParam<PG, P, F>,
+        two_to_one_params: &TwoToOneParam<PG, P, F>,
+        old_root: &PG::InnerDigest,
+        updates: &[(FpVar<F>, L, L)],
+    ) -> Result<Vec<PG::InnerDigest>, SynthesisError> {
+        let (is_valid, roots) = self.apply(leaf_params, two_to_one_params, old_root, updates)?;
+        is_valid.enforce_equal(&Boolean::TRUE)?;
+        Ok(roots)
+    }
+
+    /// Check that applying `updates` to the tree whose root is `old_root` leads to the tree whose
+    /// root is `new_root`, as in [`BatchUpdateVar::update_leaves`]. Return a boolean indicating
+    /// whether every path is at its index, every old leaf is in the tree and the final root equals
+    /// `new_root`.
+    ///
+    /// Panics if the number of updates is not the number of paths.
+    #[tracing::instrument(target = "gr1cs", skip(self, leaf_params, two_to_one_params, updates))]
+    pub fn update_and_check<L: Borrow<PG::Leaf>>(
+        &self,
+        leaf_params: &LeafParam<PG, P, F>,
+        two_to_one_params: &TwoToOneParam<PG, P, F>,
+        old_root: &PG::InnerDigest,
+        new_root: &PG::InnerDigest,
+        updates: &[(FpVar<F>, L, L)],
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let (is_valid, roots) = self.apply(leaf_params, two_to_one_params, old_root, updates)?;
+        let actual_new_root = roots.last().unwrap_or(old_root);
+        Ok(is_valid & &actual_new_root.is_eq(new_root)?)
+    }
+}
+
+/// Gadget counterpart of [`KAryConfig`].
+pub trait KAryConfigGadget<P: KAryConfig, F: PrimeField> {
+    type Leaf: Debug + ?Sized;
+    type LeafDigest: AllocVar<P::LeafDigest, F>
+        + EqGadget<F>
+        + ToBytesGadget<F>
+        + CondSelectGadget<F>
+        + GR1CSVar<F>
+        + Debug
+        + Clone
+        + Sized;
+    type LeafInnerConverter: DigestVarConverter<
+        Self::LeafDigest,
+        <Self::NToOneHash as NToOneCRHSchemeGadget<P::NToOneHash, F>>::InputVar,
+    >;
+    type InnerDigest: AllocVar<P::InnerDigest, F>
+        + EqGadget<F>
+        + ToBytesGadget<F>
+        + CondSelectGadget<F>
+        + GR1CSVar<F>
+        + Debug
+        + Clone
+        + Sized;
+
+    type LeafHash: CRHSchemeGadget<
+        P::LeafHash,
+        F,
+        InputVar = Self::Leaf,
+        OutputVar = Self::LeafDigest,
+    >;
+    type NToOneHash: NToOneCRHSchemeGadget<P::NToOneHash, F, OutputVar = Self::InnerDigest>;
+}
+
+type KAryLeafParamVar<PG, P, F> = <<PG as KAryConfigGadget<P, F>>::LeafHash as CRHSchemeGadget<
+    <P as KAryConfig>::LeafHash,
+    F,
+>>::ParametersVar;
+type NToOneParamVar<PG, P, F> =
+    <<PG as KAryConfigGadget<P, F>>::NToOneHash as NToOneCRHSchemeGadget<
+        <P as KAryConfig>::NToOneHash,
+        F,
+    >>::ParametersVar;
+
+/// Represents a k-ary merkle tree path gadget.
+///
+/// The position of the on-path node at each level is a one-hot vector of `P::ARITY` booleans,
+/// which is enforced to have exactly one bit set at allocation.
+#[derive(Debug, Derivative)]
+#[derivative(Clone(bound = "P: KAryConfig, F: PrimeField, PG: KAryConfigGadget<P, F>"))]
+pub struct KAryPathVar<P: KAryConfig, F: PrimeField, PG: KAryConfigGadget<P, F>> {
+    /// `positions[i]` is the one-hot position of the ith on-path node from top to bottom, the
+    /// last one being the leaf.
+    positions: Vec<Vec<Boolean<F>>>,
+    /// `auth_path[i]` is the siblings of the ith non-leaf node from top to bottom.
+    auth_path: Vec<Vec<PG::InnerDigest>>,
+    /// The siblings of the leaf.
+    leaf_siblings: Vec<PG::LeafDigest>,
+}
+
+impl<P, F, PG: KAryConfigGadget<P, F>> AllocVar<KAryPath<P>, F> for KAryPathVar<P, F, PG>
+where
+    P: KAryConfig,
+    F: PrimeField,
+{
+    #[tracing::instrument(target = "gr1cs", skip(cs, f))]
+    fn new_variable<T: Borrow<KAryPath<P>>>(
+        cs: impl Into<Namespace<F>>,
+        f: impl FnOnce() -> Result<T, SynthesisError>,
+        mode: AllocationMode,
+    ) -> Result<Self, SynthesisError> {
+        let ns = cs.into();
+        let cs = ns.cs();
+        f().and_then(|val| {
+            let path = val.borrow();
+            let leaf_siblings = Vec::new_variable(
+                ark_relations::ns!(cs, "leaf_siblings"),
+                || Ok(&path.leaf_siblings[..]),
+                mode,
+            )?;
+            let auth_path = path
+                .auth_path
+                .iter()
+                .map(|siblings| {
+                    Vec::new_variable(
+                        ark_relations::ns!(cs, "auth_path_nodes"),
+                        || Ok(&siblings[..]),
+                        mode,
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let positions = path
+                .position_list()
+                .map(|position| {
+                    let one_hot: Vec<_> = (0..P::ARITY).map(|i| i == position).collect();
+                    let one_hot = Vec::<Boolean<F>>::new_variable(
+                        ark_relations::ns!(cs, "position"),
+                        || Ok(&one_hot[..]),
+                        mode,
+                    )?;
+                    if mode != AllocationMode::Constant {
+                        one_hot
+                            .iter()
+                            .fold(FpVar::zero(), |sum, bit| sum + FpVar::from(bit.clone()))
+                            .enforce_equal(&FpVar::one())?;
+                    }
+                    Ok(one_hot)
+                })
+                .collect::<Result<Vec<_>, SynthesisError>>()?;
+            Ok(KAryPathVar {
+                positions,
+                auth_path,
+                leaf_siblings,
+            })
+        })
+    }
+}
+
+impl<P: KAryConfig, F: PrimeField, PG: KAryConfigGadget<P, F>> KAryPathVar<P, F, PG> {
+    /// Return the leaf index of the path, as a field element.
+    pub fn get_leaf_index(&self) -> Result<FpVar<F>, SynthesisError> {
+        let arity = FpVar::constant(F::from(P::ARITY as u64));
+        let mut index = FpVar::zero();
+        for one_hot in &self.positions {
+            let digit = one_hot
+                .iter()
+                .enumerate()
+                .fold(FpVar::zero(), |sum, (i, bit)| {
+                    sum + FpVar::from(bit.clone()) * F::from(i as u64)
+                });
+            index = index * &arity + digit;
+        }
+        Ok(index)
+    }
+
+    /// Returns the children of a node given the one-hot position of the on-path child, and its
+    /// `ARITY - 1` siblings from left to right.
+    fn children<T: CondSelectGadget<F>>(
+        one_hot: &[Boolean<F>],
+        node: &T,
+        siblings: &[T],
+    ) -> Result<Vec<T>, SynthesisError> {
+        // the jth child is the on-path node if it is at position j, and otherwise the sibling at
+        // j - 1 if the on-path node is before j, or at j if it is after j
+        let mut is_before = Boolean::FALSE;
+        let mut children = Vec::with_capacity(one_hot.len());
+        for (j, bit) in one_hot.iter().enumerate() {
+            let sibling = if j == 0 {
+                siblings[0].clone()
+            } else if j == one_hot.len() - 1 {
+                siblings[j - 1].clone()
+            } else {
+                is_before.select(&siblings[j - 1], &siblings[j])?
+            };
+            children.push(bit.select(node, &sibling)?);
+            is_before |= bit;
+        }
+        Ok(children)
+    }
+
+    /// Calculate the root of the Merkle tree assuming that `leaf` is the leaf on the path defined by `self`.
+    #[tracing::instrument(target = "gr1cs", skip(self, leaf_params, n_to_one_params))]
+    pub fn calculate_root(
+        &self,
+        leaf_params: &KAryLeafParamVar<PG, P, F>,
+        n_to_one_params: &NToOneParamVar<PG, P, F>,
+        leaf: &PG::Leaf,
+    ) -> Result<PG::InnerDigest, SynthesisError> {
+        let claimed_leaf_hash = PG::LeafHash::evaluate(leaf_params, leaf)?;
+        let (leaf_position, positions) = self.positions.split_last().unwrap();
+
+        // hash the leaf with its siblings
+        let children = Self::children(leaf_position, &claimed_leaf_hash, &self.leaf_siblings)?
+            .into_iter()
+            .map(PG::LeafInnerConverter::convert)
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut curr_hash = PG::NToOneHash::evaluate(n_to_one_params, &children)?;
+
+        // To traverse up a MT, we iterate over the path from bottom to top (i.e. in reverse)
+        for (one_hot, siblings) in positions.iter().rev().zip(self.auth_path.iter().rev()) {
+            let children = Self::children(one_hot, &curr_hash, siblings)?;
+            curr_hash = PG::NToOneHash::compress(n_to_one_params, &children)?;
+        }
+
+        Ok(curr_hash)
+    }
+
+    /// Check that hashing a Merkle tree path according to `self`, and
+    /// with `leaf` as the leaf, leads to a Merkle tree root equalling `root`.
+    #[tracing::instrument(target = "gr1cs", skip(self, leaf_params, n_to_one_params))]
+    pub fn verify_membership(
+        &self,
+        leaf_params: &KAryLeafParamVar<PG, P, F>,
+        n_to_one_params: &NToOneParamVar<PG, P, F>,
+        root: &PG::InnerDigest,
+        leaf: &PG::Leaf,
+    ) -> Result<Boolean<F>, SynthesisError> {
+        let expected_root = self.calculate_root(leaf_params, n_to_one_params, leaf)?;
+        Ok(expected_root.is_eq(root)?)
+    }
 }
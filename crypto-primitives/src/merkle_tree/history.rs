@@ -0,0 +1,123 @@
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+    Write,
+};
+use ark_std::collections::VecDeque;
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+
+/// Keeps the last `capacity` roots of a merkle tree, from oldest to newest.
+///
+/// Protocols such as mixers and rollups accept membership proofs against any recent root, so that
+/// a proof does not become stale when the tree is updated while it is being generated. Trees keep
+/// a history once enabled with [`MerkleTree::with_root_history`](super::MerkleTree::with_root_history)
+/// or [`IncrementalMerkleTree::with_root_history`](super::IncrementalMerkleTree::with_root_history),
+/// and push their new root after every update or append.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RootHistory<D> {
+    roots: VecDeque<D>,
+    capacity: usize,
+}
+
+impl<D: Clone + Eq> RootHistory<D> {
+    /// Returns an empty history keeping the last `capacity` roots.
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "capacity of the history should be positive");
+        RootHistory {
+            roots: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    /// Returns the number of roots the history keeps.
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of roots in the history.
+    pub fn len(&self) -> usize {
+        self.roots.len()
+    }
+
+    /// Returns true iff no root has been pushed yet.
+    pub fn is_empty(&self) -> bool {
+        self.roots.is_empty()
+    }
+
+    /// Pushes a new root, evicting the oldest one if the history is full.
+    pub fn push(&mut self, root: D) {
+        if self.roots.len() == self.capacity {
+            self.roots.pop_front();
+        }
+        self.roots.push_back(root);
+    }
+
+    /// Returns the most recent root.
+    pub fn latest(&self) -> Option<&D> {
+        self.roots.back()
+    }
+
+    /// Returns true iff `root` is one of the roots in the history.
+    pub fn contains(&self, root: &D) -> bool {
+        self.roots.contains(root)
+    }
+
+    /// Returns the roots in the history, from oldest to newest.
+    pub fn iter(&self) -> impl '_ + DoubleEndedIterator<Item = &D> {
+        self.roots.iter()
+    }
+}
+
+impl<D: CanonicalSerialize> CanonicalSerialize for RootHistory<D> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.capacity.serialize_with_mode(&mut writer, compress)?;
+        // the roots are written as a `Vec<D>`: their number as a `u64`, then the roots
+        (self.roots.len() as u64).serialize_with_mode(&mut writer, compress)?;
+        for root in &self.roots {
+            root.serialize_with_mode(&mut writer, compress)?;
+        }
+        Ok(())
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.capacity.serialized_size(compress)
+            + 8
+            + self
+                .roots
+                .iter()
+                .map(|root| root.serialized_size(compress))
+                .sum::<usize>()
+    }
+}
+
+impl<D: Valid> Valid for RootHistory<D> {
+    fn check(&self) -> Result<(), SerializationError> {
+        if self.capacity == 0 || self.roots.len() > self.capacity {
+            return Err(SerializationError::InvalidData);
+        }
+        self.roots.iter().try_for_each(Valid::check)
+    }
+}
+
+impl<D: CanonicalDeserialize> CanonicalDeserialize for RootHistory<D> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let capacity = usize::deserialize_with_mode(&mut reader, compress, validate)?;
+        let roots = Vec::<D>::deserialize_with_mode(reader, compress, validate)?;
+        // the sizes are checked even without validation, as `push` relies on them
+        if capacity == 0 || roots.len() > capacity {
+            return Err(SerializationError::InvalidData);
+        }
+        Ok(RootHistory {
+            roots: roots.into(),
+            capacity,
+        })
+    }
+}
@@ -0,0 +1,230 @@
+use crate::{
+    crh::{CRHScheme, TwoToOneCRHScheme},
+    merkle_tree::{
+        empty_hashes, shr, Config, DigestConverter, LeafParam, Path, RootHistory, TwoToOneParam,
+    },
+};
+use ark_std::collections::BTreeMap;
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+
+/// Defines an append-only merkle tree that only stores its frontier.
+///
+/// Leaves are appended from left to right into a tree of fixed height, and every leaf that has not
+/// been appended yet has digest `P::LeafDigest::default()`. The root is therefore the same as the
+/// root of a [`MerkleTree`](super::MerkleTree) built from the appended leaves, padded with default
+/// leaf digests.
+///
+/// Only the rightmost completed left child of every level (the "frontier") is kept, so memory is
+/// `O(height)`, and each append costs `height - 1` hashes. Leaves can be marked right after they
+/// are appended; their [`Path`] is then kept up to date as later leaves are appended, at the cost
+/// of `O(height)` memory per marked leaf.
+#[derive(Derivative)]
+#[derivative(Clone(bound = "P: Config"))]
+pub struct IncrementalMerkleTree<P: Config> {
+    /// The digest of the last leaf appended at an even position.
+    frontier_leaf: P::LeafDigest,
+    /// `frontier[i]` is the last node computed at an even position, `i + 1` levels above the
+    /// leaves.
+    frontier: Vec<P::InnerDigest>,
+    /// `empty_hashes[i]` is the digest of an empty subtree whose root is at level `i + 1`
+    empty_hashes: Vec<P::InnerDigest>,
+    /// The authentication paths of marked leaves, keyed by leaf index
+    witnesses: BTreeMap<usize, Path<P>>,
+    /// The number of appended leaves
+    num_leaves: usize,
+    /// The current root of the tree
+    root: P::InnerDigest,
+    /// Store the inner hash parameters
+    two_to_one_hash_param: TwoToOneParam<P>,
+    /// Store the leaf hash parameters
+    leaf_hash_param: LeafParam<P>,
+    /// Stores the height of the tree
+    height: usize,
+    /// Keeps the last roots of the tree, if enabled
+    root_history: Option<RootHistory<P::InnerDigest>>,
+}
+
+impl<P: Config> IncrementalMerkleTree<P> {
+    /// Create an empty incremental merkle tree of the given height.
+    pub fn blank(
+        leaf_hash_param: &LeafParam<P>,
+        two_to_one_hash_param: &TwoToOneParam<P>,
+        height: usize,
+    ) -> Result<Self, crate::Error> {
+        assert!(height > 1, "height of the tree should be greater than one");
+        let empty_hashes = empty_hashes::<P>(two_to_one_hash_param, height)?;
+        Ok(IncrementalMerkleTree {
+            frontier_leaf: P::LeafDigest::default(),
+            frontier: empty_hashes[..height - 2].to_vec(),
+            root: empty_hashes[height - 2].clone(),
+            empty_hashes,
+            witnesses: BTreeMap::new(),
+            num_leaves: 0,
+            two_to_one_hash_param: two_to_one_hash_param.clone(),
+            leaf_hash_param: leaf_hash_param.clone(),
+            height,
+            root_history: None,
+        })
+    }
+
+    /// Returns the root of the tree.
+    pub fn root(&self) -> P::InnerDigest {
+        self.root.clone()
+    }
+
+    /// Enables keeping the last `capacity` roots of the tree, starting with the current root.
+    /// Every append then pushes the new root to the history.
+    pub fn with_root_history(mut self, capacity: usize) -> Self {
+        let mut root_history = RootHistory::new(capacity);
+        root_history.push(self.root());
+        self.root_history = Some(root_history);
+        self
+    }
+
+    /// Returns the last roots of the tree, if [`IncrementalMerkleTree::with_root_history`] was
+    /// used.
+    pub fn root_history(&self) -> Option<&RootHistory<P::InnerDigest>> {
+        self.root_history.as_ref()
+    }
+
+    /// Returns true iff `root` is one of the roots in the history of the tree, or its current
+    /// root if the history is not enabled.
+    pub fn is_known_root(&self, root: &P::InnerDigest) -> bool {
+        match &self.root_history {
+            Some(root_history) => root_history.contains(root),
+            None => &self.root == root,
+        }
+    }
+
+    /// Returns the height of the tree.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the number of appended leaves, which is also the index of the next leaf.
+    pub fn num_leaves(&self) -> usize {
+        self.num_leaves
+    }
+
+    /// Returns true iff no more leaves can be appended.
+    pub fn is_full(&self) -> bool {
+        shr(self.num_leaves, self.height - 1) != 0
+    }
+
+    /// Append `new_leaf` to the tree, and returns its index.
+    pub fn append(&mut self, new_leaf: &P::Leaf) -> Result<usize, crate::Error> {
+        let leaf_digest = P::LeafHash::evaluate(&self.leaf_hash_param, new_leaf)?;
+        self.append_leaf_digest(leaf_digest)
+    }
+
+    /// Append a leaf with digest `leaf_digest` to the tree, and returns its index.
+    pub fn append_leaf_digest(
+        &mut self,
+        leaf_digest: P::LeafDigest,
+    ) -> Result<usize, crate::Error> {
+        assert!(!self.is_full(), "tree is full");
+        let index = self.num_leaves;
+
+        // compute the new nodes on the path from the leaf to the root, bottom to top, where
+        // not-yet-appended nodes on the right are empty
+        let mut path_bottom_to_top = Vec::with_capacity(self.height - 1);
+        let (left_leaf, right_leaf) = if index & 1 == 0 {
+            (leaf_digest.clone(), P::LeafDigest::default())
+        } else {
+            (self.frontier_leaf.clone(), leaf_digest.clone())
+        };
+        path_bottom_to_top.push(P::TwoToOneHash::evaluate(
+            &self.two_to_one_hash_param,
+            P::LeafInnerDigestConverter::convert(left_leaf)?,
+            P::LeafInnerDigestConverter::convert(right_leaf)?,
+        )?);
+        for level in 1..self.height - 1 {
+            let current = path_bottom_to_top.last().unwrap();
+            let (left, right) = if shr(index, level) & 1 == 0 {
+                (current, &self.empty_hashes[level - 1])
+            } else {
+                (&self.frontier[level - 1], current)
+            };
+            let parent = P::TwoToOneHash::compress(&self.two_to_one_hash_param, left, right)?;
+            path_bottom_to_top.push(parent);
+        }
+
+        // every marked leaf to the left finds the new leaf's ancestor as a sibling at the level
+        // where their paths merge
+        for (&marked, path) in self.witnesses.iter_mut() {
+            let level = (usize::BITS - (marked ^ index).leading_zeros() - 1) as usize;
+            if level == 0 {
+                path.leaf_sibling_hash = leaf_digest.clone();
+            } else {
+                let position = path.auth_path.len() - level;
+                path.auth_path[position] = path_bottom_to_top[level - 1].clone();
+            }
+        }
+
+        // left children will be needed as siblings by later appends
+        if index & 1 == 0 {
+            self.frontier_leaf = leaf_digest;
+        }
+        for level in 1..self.height - 1 {
+            if shr(index, level) & 1 == 0 {
+                self.frontier[level - 1] = path_bottom_to_top[level - 1].clone();
+            }
+        }
+
+        self.root = path_bottom_to_top.pop().unwrap();
+        if let Some(root_history) = &mut self.root_history {
+            root_history.push(self.root.clone());
+        }
+        self.num_leaves += 1;
+        Ok(index)
+    }
+
+    /// Mark the last appended leaf, so that its authentication path is kept up to date by later
+    /// appends. Returns the index of the marked leaf, or `None` if the tree is empty.
+    pub fn mark(&mut self) -> Option<usize> {
+        let index = self.num_leaves.checked_sub(1)?;
+        // everything on the right is still empty, everything on the left is in the frontier
+        let leaf_sibling_hash = if index & 1 == 0 {
+            P::LeafDigest::default()
+        } else {
+            self.frontier_leaf.clone()
+        };
+        let mut auth_path: Vec<_> = (1..self.height - 1)
+            .map(|level| {
+                if shr(index, level) & 1 == 0 {
+                    self.empty_hashes[level - 1].clone()
+                } else {
+                    self.frontier[level - 1].clone()
+                }
+            })
+            .collect();
+        // we want to make path from root to bottom
+        auth_path.reverse();
+        self.witnesses.insert(
+            index,
+            Path {
+                leaf_sibling_hash,
+                auth_path,
+                leaf_index: index,
+            },
+        );
+        Some(index)
+    }
+
+    /// Stop tracking the leaf at `index`. Returns `false` if it was not marked.
+    pub fn unmark(&mut self, index: usize) -> bool {
+        self.witnesses.remove(&index).is_some()
+    }
+
+    /// Returns the indices of all marked leaves, in increasing order.
+    pub fn marked(&self) -> impl '_ + Iterator<Item = usize> {
+        self.witnesses.keys().copied()
+    }
+
+    /// Returns the authentication path of the marked leaf at `index` against the current root,
+    /// or `None` if it is not marked.
+    pub fn witness(&self, index: usize) -> Option<Path<P>> {
+        self.witnesses.get(&index).cloned()
+    }
+}
@@ -0,0 +1,294 @@
+use crate::{
+    crh::{CRHScheme, NToOneCRHScheme},
+    merkle_tree::DigestConverter,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+use ark_std::{borrow::Borrow, fmt::Debug, hash::Hash};
+
+/// k-ary merkle tree has two types of hashes.
+/// * `LeafHash`: Convert leaf to leaf digest
+/// * `NToOneHash`: Compress `ARITY` inner digests to one inner digest
+pub trait KAryConfig {
+    /// The number of children of each non-leaf node, which should be at least two.
+    const ARITY: usize;
+
+    type Leaf: ?Sized + Send; // merkle tree does not store the leaf
+                              // leaf layer
+    type LeafDigest: Clone
+        + Eq
+        + Debug
+        + Hash
+        + Default
+        + CanonicalSerialize
+        + CanonicalDeserialize
+        + Send
+        + Sync;
+
+    // transition between leaf layer to inner layer
+    type LeafInnerDigestConverter: DigestConverter<
+        Self::LeafDigest,
+        <Self::NToOneHash as NToOneCRHScheme>::Input,
+    >;
+    // inner layer
+    type InnerDigest: Clone
+        + Eq
+        + Debug
+        + Hash
+        + Default
+        + CanonicalSerialize
+        + CanonicalDeserialize
+        + Send
+        + Sync;
+
+    /// leaf -> leaf digest
+    type LeafHash: CRHScheme<Input = Self::Leaf, Output = Self::LeafDigest>;
+    /// `ARITY` inner digests -> inner digest
+    type NToOneHash: NToOneCRHScheme<Output = Self::InnerDigest>;
+}
+
+pub type NToOneParam<P> = <<P as KAryConfig>::NToOneHash as NToOneCRHScheme>::Parameters;
+pub type KAryLeafParam<P> = <<P as KAryConfig>::LeafHash as CRHScheme>::Parameters;
+
+/// Stores the hashes of a path in a k-ary merkle tree.
+///
+/// For each level, the siblings are the `ARITY - 1` other children of the node on the path, from
+/// left to right.
+/// ```tree_diagram
+///               [A]
+///        /    /     \     \
+///      B    [C]      D     E
+///          / | \  \
+///        F  G [H]  I
+/// ```
+/// In a 4-ary tree, suppose we want to prove H, then `leaf_siblings` is `[F, G, I]`, and
+/// `auth_path` is `[[B, D, E]]`.
+#[derive(Derivative, CanonicalSerialize, CanonicalDeserialize)]
+#[derivative(
+    PartialEq(bound = "P: KAryConfig"),
+    Clone(bound = "P: KAryConfig"),
+    Debug(bound = "P: KAryConfig"),
+    Default(bound = "P: KAryConfig")
+)]
+pub struct KAryPath<P: KAryConfig> {
+    /// The siblings of the leaf, from left to right.
+    pub leaf_siblings: Vec<P::LeafDigest>,
+    /// The siblings of the non-leaf nodes on the path, from top to bottom (not including the
+    /// root).
+    pub auth_path: Vec<Vec<P::InnerDigest>>,
+    /// The index of the leaf.
+    pub leaf_index: usize,
+}
+
+impl<P: KAryConfig> KAryPath<P> {
+    /// The position of the on-path node among its siblings, at every level from top to bottom,
+    /// including the leaf level. The positions are the base-`ARITY` digits of the leaf index,
+    /// most significant first.
+    pub fn position_list(&'_ self) -> impl '_ + DoubleEndedIterator<Item = usize> {
+        (0..=self.auth_path.len())
+            .rev()
+            .map(move |level| (self.leaf_index / P::ARITY.pow(level as u32)) % P::ARITY)
+    }
+
+    /// Verify that a leaf is at `self.leaf_index` of the merkle tree.
+    pub fn verify<L: Borrow<P::Leaf>>(
+        &self,
+        leaf_hash_params: &KAryLeafParam<P>,
+        n_to_one_params: &NToOneParam<P>,
+        root_hash: &P::InnerDigest,
+        leaf: L,
+    ) -> Result<bool, crate::Error> {
+        let num_leaves = P::ARITY.checked_pow(self.auth_path.len() as u32 + 1);
+        if self.leaf_siblings.len() != P::ARITY - 1
+            || self.auth_path.iter().any(|s| s.len() != P::ARITY - 1)
+            || num_leaves.map_or(false, |n| self.leaf_index >= n)
+        {
+            return Ok(false);
+        }
+        let mut positions = self.position_list().rev();
+
+        // hash the leaf with its siblings
+        let claimed_leaf_hash = P::LeafHash::evaluate(leaf_hash_params, leaf)?;
+        let children = insert_at(
+            &self.leaf_siblings,
+            positions.next().unwrap(),
+            claimed_leaf_hash,
+        )
+        .into_iter()
+        .map(P::LeafInnerDigestConverter::convert)
+        .collect::<Result<Vec<_>, _>>()?;
+        let mut curr_path_node = P::NToOneHash::evaluate(n_to_one_params, &children)?;
+
+        // check hash along the path from bottom to root
+        for (siblings, position) in self.auth_path.iter().rev().zip(positions) {
+            let children = insert_at(siblings, position, curr_path_node);
+            curr_path_node = P::NToOneHash::compress(n_to_one_params, &children)?;
+        }
+
+        // check if final hash is root
+        Ok(&curr_path_node == root_hash)
+    }
+}
+
+/// Returns `siblings` with `node` inserted at `position`.
+fn insert_at<T: Clone>(siblings: &[T], position: usize, node: T) -> Vec<T> {
+    let mut children = siblings.to_vec();
+    children.insert(position, node);
+    children
+}
+
+/// Defines a k-ary merkle tree data structure, where every non-leaf node has `P::ARITY` children.
+///
+/// Algebraic hashes such as Poseidon and Rescue hash `k` elements at once for much less than
+/// `k - 1` times the cost of hashing two, so wider trees are cheaper to open in-circuit.
+/// If the number of leaves is not a power of `P::ARITY`, the leaves are padded with
+/// `P::LeafDigest::default()`.
+#[derive(Derivative)]
+#[derivative(Clone(bound = "P: KAryConfig"))]
+pub struct KAryMerkleTree<P: KAryConfig> {
+    /// stores the non-leaf nodes level by level, from the root to the bottom non-leaf level
+    non_leaf_nodes: Vec<Vec<P::InnerDigest>>,
+    /// store the hash of leaf nodes from left to right
+    leaf_nodes: Vec<P::LeafDigest>,
+    /// Store the inner hash parameters
+    n_to_one_hash_param: NToOneParam<P>,
+    /// Store the leaf hash parameters
+    leaf_hash_param: KAryLeafParam<P>,
+    /// Stores the height of the MerkleTree
+    height: usize,
+}
+
+impl<P: KAryConfig> KAryMerkleTree<P> {
+    /// Create an empty merkle tree of the given height such that all leaves are zero-filled.
+    pub fn blank(
+        leaf_hash_param: &KAryLeafParam<P>,
+        n_to_one_hash_param: &NToOneParam<P>,
+        height: usize,
+    ) -> Result<Self, crate::Error> {
+        assert!(height > 1, "height of the tree should be greater than one");
+        let leaf_digests = vec![P::LeafDigest::default(); P::ARITY.pow(height as u32 - 1)];
+        Self::new_with_leaf_digest(leaf_hash_param, n_to_one_hash_param, leaf_digests)
+    }
+
+    /// Returns a new k-ary merkle tree.
+    pub fn new<L: AsRef<P::Leaf>>(
+        leaf_hash_param: &KAryLeafParam<P>,
+        n_to_one_hash_param: &NToOneParam<P>,
+        leaves: impl IntoIterator<Item = L>,
+    ) -> Result<Self, crate::Error> {
+        let leaf_digests = leaves
+            .into_iter()
+            .map(|input| P::LeafHash::evaluate(leaf_hash_param, input.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        Self::new_with_leaf_digest(leaf_hash_param, n_to_one_hash_param, leaf_digests)
+    }
+
+    /// Returns a new k-ary merkle tree from the digests of its leaves.
+    pub fn new_with_leaf_digest(
+        leaf_hash_param: &KAryLeafParam<P>,
+        n_to_one_hash_param: &NToOneParam<P>,
+        mut leaf_digests: Vec<P::LeafDigest>,
+    ) -> Result<Self, crate::Error> {
+        assert!(P::ARITY >= 2, "arity of the tree should be at least two");
+        assert!(!leaf_digests.is_empty(), "`leaves` should not be empty");
+
+        // pad the leaves to a power of the arity
+        let mut height = 2;
+        while P::ARITY.pow(height as u32 - 1) < leaf_digests.len() {
+            height += 1;
+        }
+        leaf_digests.resize(P::ARITY.pow(height as u32 - 1), P::LeafDigest::default());
+
+        // compute the bottom non-leaf level, then every level up to the root
+        let mut non_leaf_nodes = Vec::with_capacity(height - 1);
+        let bottom = leaf_digests
+            .chunks(P::ARITY)
+            .map(|children| {
+                let children = children
+                    .iter()
+                    .cloned()
+                    .map(P::LeafInnerDigestConverter::convert)
+                    .collect::<Result<Vec<_>, _>>()?;
+                P::NToOneHash::evaluate(n_to_one_hash_param, &children)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        non_leaf_nodes.push(bottom);
+        for _ in 2..height {
+            let level = non_leaf_nodes
+                .last()
+                .unwrap()
+                .chunks(P::ARITY)
+                .map(|children| P::NToOneHash::compress(n_to_one_hash_param, children))
+                .collect::<Result<Vec<_>, _>>()?;
+            non_leaf_nodes.push(level);
+        }
+        non_leaf_nodes.reverse();
+
+        Ok(KAryMerkleTree {
+            non_leaf_nodes,
+            leaf_nodes: leaf_digests,
+            n_to_one_hash_param: n_to_one_hash_param.clone(),
+            leaf_hash_param: leaf_hash_param.clone(),
+            height,
+        })
+    }
+
+    /// Returns the root of the Merkle tree.
+    pub fn root(&self) -> P::InnerDigest {
+        self.non_leaf_nodes[0][0].clone()
+    }
+
+    /// Returns the height of the Merkle tree.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the authentication path from leaf at `index` to root.
+    pub fn generate_proof(&self, index: usize) -> Result<KAryPath<P>, crate::Error> {
+        assert!(index < self.leaf_nodes.len(), "index out of range");
+        fn siblings<T: Clone>(nodes: &[T], position: usize, arity: usize) -> Vec<T> {
+            let start = position - position % arity;
+            let mut siblings = nodes[start..start + arity].to_vec();
+            siblings.remove(position - start);
+            siblings
+        }
+        let auth_path = (1..self.height - 1)
+            .map(|level| {
+                let position = index / P::ARITY.pow((self.height - 1 - level) as u32);
+                siblings(&self.non_leaf_nodes[level], position, P::ARITY)
+            })
+            .collect();
+        Ok(KAryPath {
+            leaf_siblings: siblings(&self.leaf_nodes, index, P::ARITY),
+            auth_path,
+            leaf_index: index,
+        })
+    }
+
+    /// Update the leaf at `index` to updated leaf, recomputing the nodes on its path to the root.
+    pub fn update(&mut self, index: usize, new_leaf: &P::Leaf) -> Result<(), crate::Error> {
+        assert!(index < self.leaf_nodes.len(), "index out of range");
+        self.leaf_nodes[index] = P::LeafHash::evaluate(&self.leaf_hash_param, new_leaf)?;
+
+        let mut position = index / P::ARITY;
+        let start = position * P::ARITY;
+        let children = self.leaf_nodes[start..start + P::ARITY]
+            .iter()
+            .cloned()
+            .map(P::LeafInnerDigestConverter::convert)
+            .collect::<Result<Vec<_>, _>>()?;
+        let bottom = self.height - 2;
+        self.non_leaf_nodes[bottom][position] =
+            P::NToOneHash::evaluate(&self.n_to_one_hash_param, &children)?;
+
+        for level in (0..bottom).rev() {
+            let start = position - position % P::ARITY;
+            let children = &self.non_leaf_nodes[level + 1][start..start + P::ARITY];
+            position /= P::ARITY;
+            self.non_leaf_nodes[level][position] =
+                P::NToOneHash::compress(&self.n_to_one_hash_param, children)?;
+        }
+        Ok(())
+    }
+}
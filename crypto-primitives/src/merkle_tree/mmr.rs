@@ -0,0 +1,481 @@
+use crate::{
+    crh::{CRHScheme, TwoToOneCRHScheme},
+    merkle_tree::{Config, DigestConverter, LeafParam, Path, TwoToOneParam},
+    Error,
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+use ark_std::{borrow::Borrow, collections::BTreeMap};
+
+/// Returns the peaks of a Merkle Mountain Range with `size` leaves, from left to right, as
+/// `(height, offset)` pairs where the peak is the root of the perfect subtree over leaves
+/// `offset..offset + (1 << height)`.
+fn peak_layout(size: usize) -> impl Iterator<Item = (usize, usize)> {
+    (0..usize::BITS as usize)
+        .rev()
+        .filter(move |height| (size >> height) & 1 == 1)
+        // the peak starts after the bits of `size` above `height`
+        .map(move |height| {
+            let mask = usize::MAX >> (usize::BITS as usize - 1 - height);
+            (height, size & !mask)
+        })
+}
+
+/// Returns the peak holding a single leaf. It is the root of the two-leaf tree whose right leaf is
+/// `P::LeafDigest::default()`, so that every peak is an inner digest.
+fn lone_peak<P: Config>(
+    two_to_one_params: &TwoToOneParam<P>,
+    leaf_digest: P::LeafDigest,
+) -> Result<P::InnerDigest, Error> {
+    P::TwoToOneHash::evaluate(
+        two_to_one_params,
+        P::LeafInnerDigestConverter::convert(leaf_digest)?,
+        P::LeafInnerDigestConverter::convert(P::LeafDigest::default())?,
+    )
+}
+
+/// Bag the peaks of a Merkle Mountain Range into its root, folding them from right to left.
+/// Returns an error if there is no peak.
+fn bag_peaks<P: Config>(
+    two_to_one_params: &TwoToOneParam<P>,
+    peaks: &[P::InnerDigest],
+) -> Result<P::InnerDigest, Error> {
+    let (last, rest) = peaks.split_last().ok_or(Error::IncorrectInputLength(0))?;
+    rest.iter().rev().try_fold(last.clone(), |bag, peak| {
+        P::TwoToOneHash::compress(two_to_one_params, peak, &bag)
+    })
+}
+
+/// Computes the peaks of a Merkle Mountain Range with `new_size` leaves from the peaks of the
+/// range with its first `old_size` leaves. Digests which cannot be derived from the old peaks are
+/// obtained from `next_leaf` and `next_node`, in a deterministic order.
+///
+/// Returns `None` if one of them returns `None`, or if an old peak holding a single leaf does not
+/// match the leaf digest.
+fn extend_peaks<P: Config>(
+    two_to_one_params: &TwoToOneParam<P>,
+    old_size: usize,
+    old_peaks: &[P::InnerDigest],
+    new_size: usize,
+    next_leaf: &mut impl FnMut(usize) -> Option<P::LeafDigest>,
+    next_node: &mut impl FnMut(usize, usize) -> Option<P::InnerDigest>,
+) -> Result<Option<Vec<P::InnerDigest>>, Error> {
+    // old peaks, keyed by (height, index at that height)
+    let known: BTreeMap<_, _> = peak_layout(old_size)
+        .zip(old_peaks)
+        .map(|((height, offset), peak)| ((height, offset >> height), peak.clone()))
+        .collect();
+
+    let mut peaks = Vec::new();
+    for (height, offset) in peak_layout(new_size) {
+        let peak = if let Some(peak) = known.get(&(height, offset >> height)) {
+            Some(peak.clone())
+        } else if height == 0 {
+            next_leaf(offset)
+                .map(|leaf| lone_peak::<P>(two_to_one_params, leaf))
+                .transpose()?
+        } else {
+            subtree_root::<P>(
+                two_to_one_params,
+                height,
+                offset >> height,
+                old_size,
+                &known,
+                next_leaf,
+                next_node,
+            )?
+        };
+        match peak {
+            Some(peak) => peaks.push(peak),
+            None => return Ok(None),
+        }
+    }
+    Ok(Some(peaks))
+}
+
+/// Computes the root of the perfect subtree of height `height` at `index`, see [`extend_peaks`].
+fn subtree_root<P: Config>(
+    two_to_one_params: &TwoToOneParam<P>,
+    height: usize,
+    index: usize,
+    old_size: usize,
+    known: &BTreeMap<(usize, usize), P::InnerDigest>,
+    next_leaf: &mut impl FnMut(usize) -> Option<P::LeafDigest>,
+    next_node: &mut impl FnMut(usize, usize) -> Option<P::InnerDigest>,
+) -> Result<Option<P::InnerDigest>, Error> {
+    if let Some(node) = known.get(&(height, index)) {
+        return Ok(Some(node.clone()));
+    }
+    if index << height >= old_size {
+        // the subtree only has new leaves
+        return Ok(next_node(height, index));
+    }
+
+    // the subtree straddles the old size, so it is hashed from its children
+    if height == 1 {
+        // the left leaf is the old peak holding a single leaf
+        let (left, right) = match (next_leaf(2 * index), next_leaf(2 * index + 1)) {
+            (Some(left), Some(right)) => (left, right),
+            _ => return Ok(None),
+        };
+        if known.get(&(0, 2 * index)) != Some(&lone_peak::<P>(two_to_one_params, left.clone())?) {
+            return Ok(None);
+        }
+        let node = P::TwoToOneHash::evaluate(
+            two_to_one_params,
+            P::LeafInnerDigestConverter::convert(left)?,
+            P::LeafInnerDigestConverter::convert(right)?,
+        )?;
+        return Ok(Some(node));
+    }
+    let left = subtree_root::<P>(
+        two_to_one_params,
+        height - 1,
+        2 * index,
+        old_size,
+        known,
+        next_leaf,
+        next_node,
+    )?;
+    let right = subtree_root::<P>(
+        two_to_one_params,
+        height - 1,
+        2 * index + 1,
+        old_size,
+        known,
+        next_leaf,
+        next_node,
+    )?;
+    match (left, right) {
+        (Some(left), Some(right)) => Ok(Some(P::TwoToOneHash::compress(
+            two_to_one_params,
+            left,
+            right,
+        )?)),
+        _ => Ok(None),
+    }
+}
+
+/// Stores an inclusion proof for a leaf of a Merkle Mountain Range of `size` leaves.
+///
+/// The leaf is proven against the root of its peak with a [`Path`], whose leaf index is relative
+/// to the first leaf of the peak. A peak holding a single leaf is the root of a two-leaf tree
+/// whose right leaf is `P::LeafDigest::default()`, so its path has this default sibling and no
+/// inner nodes.
+#[derive(Derivative, CanonicalSerialize, CanonicalDeserialize)]
+#[derivative(
+    PartialEq(bound = "P: Config"),
+    Clone(bound = "P: Config"),
+    Debug(bound = "P: Config")
+)]
+pub struct MmrProof<P: Config> {
+    /// The number of leaves of the Merkle Mountain Range.
+    pub size: usize,
+    /// The index of the leaf.
+    pub leaf_index: usize,
+    /// The path from the leaf to the root of its peak.
+    pub path: Path<P>,
+    /// The peaks on the left of the peak holding the leaf, from left to right.
+    pub left_peaks: Vec<P::InnerDigest>,
+    /// The peaks on the right of the peak holding the leaf, from left to right.
+    pub right_peaks: Vec<P::InnerDigest>,
+}
+
+impl<P: Config> MmrProof<P> {
+    /// Verify that `leaf` is at `self.leaf_index` of the Merkle Mountain Range of `self.size`
+    /// leaves with root `root`.
+    pub fn verify<L: Borrow<P::Leaf>>(
+        &self,
+        leaf_hash_params: &LeafParam<P>,
+        two_to_one_params: &TwoToOneParam<P>,
+        root: &P::InnerDigest,
+        leaf: L,
+    ) -> Result<bool, Error> {
+        if self.leaf_index >= self.size {
+            return Ok(false);
+        }
+        let layout: Vec<_> = peak_layout(self.size).collect();
+        let position = layout
+            .iter()
+            .position(|(height, offset)| self.leaf_index - offset < 1 << height)
+            .unwrap();
+        let (height, offset) = layout[position];
+        if self.left_peaks.len() != position
+            || self.right_peaks.len() != layout.len() - position - 1
+            || self.path.leaf_index != self.leaf_index - offset
+            || self.path.auth_path.len() != height.max(1) - 1
+        {
+            return Ok(false);
+        }
+
+        let leaf_digest = P::LeafHash::evaluate(leaf_hash_params, leaf)?;
+        let peak = self
+            .path
+            .root_from_leaf_digest(two_to_one_params, &leaf_digest)?;
+        let peaks: Vec<_> = self
+            .left_peaks
+            .iter()
+            .cloned()
+            .chain(ark_std::iter::once(peak))
+            .chain(self.right_peaks.iter().cloned())
+            .collect();
+        Ok(&bag_peaks::<P>(two_to_one_params, &peaks)? == root)
+    }
+}
+
+/// Stores a proof that a Merkle Mountain Range of `new_size` leaves extends the one made of its
+/// first `old_size` leaves.
+#[derive(Derivative, CanonicalSerialize, CanonicalDeserialize)]
+#[derivative(
+    PartialEq(bound = "P: Config"),
+    Clone(bound = "P: Config"),
+    Debug(bound = "P: Config")
+)]
+pub struct MmrConsistencyProof<P: Config> {
+    /// The number of leaves of the old Merkle Mountain Range.
+    pub old_size: usize,
+    /// The number of leaves of the new Merkle Mountain Range.
+    pub new_size: usize,
+    /// The peaks of the old Merkle Mountain Range, from left to right.
+    pub old_peaks: Vec<P::InnerDigest>,
+    /// The leaf digests needed to compute the new peaks, in the order they are used.
+    pub leaves: Vec<P::LeafDigest>,
+    /// The inner nodes needed to compute the new peaks, in the order they are used.
+    pub nodes: Vec<P::InnerDigest>,
+}
+
+impl<P: Config> MmrConsistencyProof<P> {
+    /// Verify that the Merkle Mountain Range of `self.new_size` leaves with root `new_root`
+    /// extends the one of `self.old_size` leaves with root `old_root`.
+    pub fn verify(
+        &self,
+        two_to_one_params: &TwoToOneParam<P>,
+        old_root: &P::InnerDigest,
+        new_root: &P::InnerDigest,
+    ) -> Result<bool, Error> {
+        if self.old_size == 0
+            || self.old_size > self.new_size
+            || self.old_peaks.len() != peak_layout(self.old_size).count()
+            || &bag_peaks::<P>(two_to_one_params, &self.old_peaks)? != old_root
+        {
+            return Ok(false);
+        }
+
+        let mut leaves = self.leaves.iter().cloned();
+        let mut nodes = self.nodes.iter().cloned();
+        let new_peaks = extend_peaks::<P>(
+            two_to_one_params,
+            self.old_size,
+            &self.old_peaks,
+            self.new_size,
+            &mut |_| leaves.next(),
+            &mut |_, _| nodes.next(),
+        )?;
+        match new_peaks {
+            Some(new_peaks) if leaves.next().is_none() && nodes.next().is_none() => {
+                Ok(&bag_peaks::<P>(two_to_one_params, &new_peaks)? == new_root)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+/// Defines a Merkle Mountain Range, an append-only accumulator whose root commits to every leaf
+/// appended so far.
+///
+/// The leaves are split into perfect binary trees of decreasing heights, the "peaks", whose roots
+/// are hashed exactly as a [`MerkleTree`](super::MerkleTree) with the same [`Config`], so that a
+/// range with a power-of-two number of leaves has the same root as the corresponding tree.
+/// The root of the range bags the peaks from right to left: `H(p_0, H(p_1, ... H(p_{k-1}, p_k)))`.
+/// A peak holding a single leaf is hashed with `P::LeafDigest::default()` as its right sibling.
+///
+/// Every node ever computed is kept, so inclusion proofs can be produced against any historical
+/// size, along with consistency proofs between two sizes.
+#[derive(Derivative)]
+#[derivative(Clone(bound = "P: Config"))]
+pub struct MerkleMountainRange<P: Config> {
+    /// store the hash of leaf nodes from left to right
+    leaf_nodes: Vec<P::LeafDigest>,
+    /// `non_leaf_nodes[h - 1][i]` is the root of the perfect subtree of height `h` over leaves
+    /// `i << h..(i + 1) << h`
+    non_leaf_nodes: Vec<Vec<P::InnerDigest>>,
+    /// Store the inner hash parameters
+    two_to_one_hash_param: TwoToOneParam<P>,
+    /// Store the leaf hash parameters
+    leaf_hash_param: LeafParam<P>,
+}
+
+impl<P: Config> MerkleMountainRange<P> {
+    /// Create an empty Merkle Mountain Range.
+    pub fn new(leaf_hash_param: &LeafParam<P>, two_to_one_hash_param: &TwoToOneParam<P>) -> Self {
+        MerkleMountainRange {
+            leaf_nodes: Vec::new(),
+            non_leaf_nodes: Vec::new(),
+            two_to_one_hash_param: two_to_one_hash_param.clone(),
+            leaf_hash_param: leaf_hash_param.clone(),
+        }
+    }
+
+    /// Returns the number of appended leaves.
+    pub fn num_leaves(&self) -> usize {
+        self.leaf_nodes.len()
+    }
+
+    /// Append `new_leaf`, and returns its index.
+    pub fn append(&mut self, new_leaf: &P::Leaf) -> Result<usize, Error> {
+        let leaf_digest = P::LeafHash::evaluate(&self.leaf_hash_param, new_leaf)?;
+        self.append_leaf_digest(leaf_digest)
+    }
+
+    /// Append a leaf with digest `leaf_digest`, and returns its index.
+    pub fn append_leaf_digest(&mut self, leaf_digest: P::LeafDigest) -> Result<usize, Error> {
+        let index = self.leaf_nodes.len();
+        self.leaf_nodes.push(leaf_digest);
+        if index & 1 == 0 {
+            return Ok(index);
+        }
+
+        // merge the completed subtrees, bottom to top
+        let mut node = P::TwoToOneHash::evaluate(
+            &self.two_to_one_hash_param,
+            P::LeafInnerDigestConverter::convert(self.leaf_nodes[index - 1].clone())?,
+            P::LeafInnerDigestConverter::convert(self.leaf_nodes[index].clone())?,
+        )?;
+        let mut level = 0;
+        loop {
+            if self.non_leaf_nodes.len() == level {
+                self.non_leaf_nodes.push(Vec::new());
+            }
+            let nodes = &mut self.non_leaf_nodes[level];
+            nodes.push(node);
+            if nodes.len() & 1 == 1 {
+                break;
+            }
+            node = P::TwoToOneHash::compress(
+                &self.two_to_one_hash_param,
+                &nodes[nodes.len() - 2],
+                &nodes[nodes.len() - 1],
+            )?;
+            level += 1;
+        }
+        Ok(index)
+    }
+
+    /// Returns the peak of height `height` starting at leaf `offset`.
+    fn peak(&self, height: usize, offset: usize) -> Result<P::InnerDigest, Error> {
+        if height == 0 {
+            lone_peak::<P>(&self.two_to_one_hash_param, self.leaf_nodes[offset].clone())
+        } else {
+            Ok(self.non_leaf_nodes[height - 1][offset >> height].clone())
+        }
+    }
+
+    /// Returns the peaks of the range made of the first `size` leaves, from left to right.
+    ///
+    /// Returns an error unless `size` is positive and at most the number of leaves.
+    pub fn peaks(&self, size: usize) -> Result<Vec<P::InnerDigest>, Error> {
+        if size == 0 || size > self.num_leaves() {
+            return Err(Error::IncorrectInputLength(size));
+        }
+        peak_layout(size)
+            .map(|(height, offset)| self.peak(height, offset))
+            .collect()
+    }
+
+    /// Returns the root of the range made of the first `size` leaves, or an error unless `size` is
+    /// positive and at most the number of leaves.
+    pub fn root_at(&self, size: usize) -> Result<P::InnerDigest, Error> {
+        bag_peaks::<P>(&self.two_to_one_hash_param, &self.peaks(size)?)
+    }
+
+    /// Returns the current root, or an error if no leaf was appended.
+    pub fn root(&self) -> Result<P::InnerDigest, Error> {
+        self.root_at(self.num_leaves())
+    }
+
+    /// Returns an inclusion proof of the leaf at `index` against the root of the range made of the
+    /// first `size` leaves.
+    ///
+    /// Returns an error unless `index < size` and `size` is at most the number of leaves.
+    pub fn generate_proof(&self, index: usize, size: usize) -> Result<MmrProof<P>, Error> {
+        if index >= size {
+            return Err(Error::IncorrectInputLength(index));
+        }
+        let mut left_peaks = self.peaks(size)?;
+        let layout: Vec<_> = peak_layout(size).collect();
+        let position = layout
+            .iter()
+            .position(|(height, offset)| index - offset < 1 << height)
+            .unwrap();
+        let (height, offset) = layout[position];
+        let right_peaks = left_peaks.split_off(position + 1);
+        left_peaks.pop();
+
+        // path from the leaf to the root of its peak
+        let local_index = index - offset;
+        let leaf_sibling_hash = if height == 0 {
+            P::LeafDigest::default()
+        } else {
+            self.leaf_nodes[index ^ 1].clone()
+        };
+        let auth_path = (1..height)
+            .rev()
+            .map(|level| self.non_leaf_nodes[level - 1][(index >> level) ^ 1].clone())
+            .collect();
+        Ok(MmrProof {
+            size,
+            leaf_index: index,
+            path: Path {
+                leaf_sibling_hash,
+                auth_path,
+                leaf_index: local_index,
+            },
+            left_peaks,
+            right_peaks,
+        })
+    }
+
+    /// Returns a proof that the range made of the first `new_size` leaves extends the range made
+    /// of the first `old_size` leaves.
+    ///
+    /// Returns an error unless `0 < old_size <= new_size` and `new_size` is at most the number of
+    /// leaves.
+    pub fn generate_consistency_proof(
+        &self,
+        old_size: usize,
+        new_size: usize,
+    ) -> Result<MmrConsistencyProof<P>, Error> {
+        if old_size > new_size {
+            return Err(Error::IncorrectInputLength(old_size));
+        }
+        let old_peaks = self.peaks(old_size)?;
+        let new_peaks = self.peaks(new_size)?;
+
+        let mut leaves = Vec::new();
+        let mut nodes = Vec::new();
+        let extended = extend_peaks::<P>(
+            &self.two_to_one_hash_param,
+            old_size,
+            &old_peaks,
+            new_size,
+            &mut |index| {
+                leaves.push(self.leaf_nodes[index].clone());
+                leaves.last().cloned()
+            },
+            &mut |height, index| {
+                nodes.push(self.non_leaf_nodes[height - 1][index].clone());
+                nodes.last().cloned()
+            },
+        )?;
+        debug_assert_eq!(extended, Some(new_peaks));
+
+        Ok(MmrConsistencyProof {
+            old_size,
+            new_size,
+            old_peaks,
+            leaves,
+            nodes,
+        })
+    }
+}
@@ -6,12 +6,15 @@ use crate::{
     sponge::Absorb,
     Error,
 };
-use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+    Write,
+};
 #[cfg(not(feature = "std"))]
 use ark_std::vec::Vec;
 use ark_std::{
     borrow::Borrow,
-    collections::BTreeSet,
+    collections::{BTreeMap, BTreeSet},
     fmt::Debug,
     hash::{BuildHasherDefault, Hash},
 };
@@ -22,6 +25,36 @@ use rayon::prelude::*;
 #[cfg(feature = "constraints")]
 pub mod constraints;
 
+mod sparse;
+pub use sparse::*;
+
+mod incremental;
+pub use incremental::*;
+
+mod store;
+pub use store::*;
+
+mod kary;
+pub use kary::*;
+
+mod mmr;
+pub use mmr::*;
+
+mod consistency;
+pub use consistency::*;
+
+mod history;
+pub use history::*;
+
+mod subtree;
+pub use subtree::*;
+
+mod streaming;
+pub use streaming::*;
+
+mod batch_update;
+pub use batch_update::*;
+
 #[cfg(test)]
 mod tests;
 
@@ -159,7 +192,7 @@ impl<P: Config> Path<P> {
     #[allow(unused)] // this function is actually used when r1cs feature is on
     fn position_list(&'_ self) -> impl '_ + Iterator<Item = bool> {
         (0..self.auth_path.len() + 1)
-            .map(move |i| ((self.leaf_index >> i) & 1) != 0)
+            .map(move |i| (shr(self.leaf_index, i) & 1) != 0)
             .rev()
     }
 }
@@ -178,9 +211,26 @@ impl<P: Config> Path<P> {
     ) -> Result<bool, crate::Error> {
         // calculate leaf hash
         let claimed_leaf_hash = P::LeafHash::evaluate(&leaf_hash_params, leaf)?;
+        let curr_path_node = self.root_from_leaf_digest(two_to_one_params, &claimed_leaf_hash)?;
+
+        // check if final hash is root
+        if &curr_path_node != root_hash {
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
+    /// Compute the root of the merkle tree assuming that `leaf_digest` is the digest of the leaf
+    /// at `self.leaf_index`.
+    fn root_from_leaf_digest(
+        &self,
+        two_to_one_params: &TwoToOneParam<P>,
+        leaf_digest: &P::LeafDigest,
+    ) -> Result<P::InnerDigest, crate::Error> {
         // check hash along the path from bottom to root
         let (left_child, right_child) =
-            select_left_right_child(self.leaf_index, &claimed_leaf_hash, &self.leaf_sibling_hash)?;
+            select_left_right_child(self.leaf_index, leaf_digest, &self.leaf_sibling_hash)?;
 
         // leaf layer to inner layer conversion
         let left_child = P::LeafInnerDigestConverter::convert(left_child)?;
@@ -203,12 +253,7 @@ impl<P: Config> Path<P> {
             index >>= 1;
         }
 
-        // check if final hash is root
-        if &curr_path_node != root_hash {
-            return Ok(false);
-        }
-
-        Ok(true)
+        Ok(curr_path_node)
     }
 }
 
@@ -371,32 +416,70 @@ fn select_left_right_child<L: Clone>(
     Ok((left_child.clone(), right_child.clone()))
 }
 
+/// Policy used by [`MerkleTree`] to fill the leaves past the end when the number of leaves is not
+/// a power of two.
+///
+/// Every policy keeps the height of the tree fixed to `log2(num_leaves.next_power_of_two()) + 1`,
+/// so that proofs of all leaves are [`Path`]s of the same length and can be checked by `PathVar`.
+///
+/// Note that padding makes it possible to open a padded position as if it were a leaf. Protocols
+/// that care about the number of leaves should commit to it separately.
+#[derive(Derivative)]
+#[derivative(
+    Clone(bound = "P: Config"),
+    Debug(bound = "P: Config"),
+    PartialEq(bound = "P: Config")
+)]
+pub enum LeafPadding<P: Config> {
+    /// Pad with `P::LeafDigest::default()`, like the empty leaves of [`MerkleTree::blank`].
+    DefaultDigest,
+    /// Pad with the given leaf digest, typically the hash of an application-defined sentinel leaf.
+    Sentinel(P::LeafDigest),
+    /// Bitcoin-style padding: whenever a level has an odd number of nodes, the last node is paired
+    /// with a copy of itself. Nodes past the copy are never used.
+    ///
+    /// Promoting the odd node to the next level unchanged would give leaves paths of different
+    /// lengths and would require converting leaf digests into inner digests, so the node is
+    /// duplicated instead. As in Bitcoin, the leaf lists `[a, b, c]` and `[a, b, c, c]` have the
+    /// same root. Only the first `num_leaves` leaves can be updated in this mode.
+    DuplicateOdd,
+}
+
 /// Defines a merkle tree data structure.
-/// This merkle tree has runtime fixed height, and assumes number of leaves is 2^height.
+/// This merkle tree has runtime fixed height. If the number of leaves is not a power of two,
+/// the leaves are padded according to a [`LeafPadding`] policy.
+///
+/// The nodes are kept in a [`NodeStore`], which is in memory by default. Trees larger than memory
+/// can be built in a [`FileNodeStore`] with [`MerkleTree::new_in_store`], and reopened with
+/// [`MerkleTree::open`].
 ///
 /// TODO: add RFC-6962 compatible merkle tree in the future.
-/// For this release, padding will not be supported because of security concerns: if the leaf hash and two to one hash uses same underlying
-/// CRH, a malicious prover can prove a leaf while the actual node is an inner node. In the future, we can prefix leaf hashes in different layers to
-/// solve the problem.
+/// Note that if the leaf hash and two to one hash uses same underlying CRH, a malicious prover can
+/// prove a leaf while the actual node is an inner node. Prefixing leaf hashes in different layers
+/// solves the problem.
 #[derive(Derivative)]
-#[derivative(Clone(bound = "P: Config"))]
-pub struct MerkleTree<P: Config> {
-    /// stores the non-leaf nodes in level order. The first element is the root node.
-    /// The ith nodes (starting at 1st) children are at indices `2*i`, `2*i+1`
-    non_leaf_nodes: Vec<P::InnerDigest>,
-    /// store the hash of leaf nodes from left to right
-    leaf_nodes: Vec<P::LeafDigest>,
+#[derivative(Clone(bound = "P: Config, S: Clone"))]
+pub struct MerkleTree<P: Config, S: NodeStore<P> = MemoryNodeStore<P>> {
+    /// stores the leaf and non-leaf nodes
+    store: S,
+    /// caches the root node
+    root: P::InnerDigest,
     /// Store the inner hash parameters
     two_to_one_hash_param: TwoToOneParam<P>,
     /// Store the leaf hash parameters
     leaf_hash_param: LeafParam<P>,
-    /// Stores the height of the MerkleTree
-    height: usize,
+    /// Stores how leaves past `num_leaves` are padded
+    padding: LeafPadding<P>,
+    /// Keeps the last roots of the tree, if enabled
+    root_history: Option<RootHistory<P::InnerDigest>>,
 }
 
+/// The number of nodes hashed at once when building a tree level by level.
+const BUILD_CHUNK_SIZE: usize = 1 << 16;
+
 impl<P: Config> MerkleTree<P> {
     /// Create an empty merkle tree such that all leaves are zero-filled.
-    /// Consider using a sparse merkle tree if you need the tree to be low memory
+    /// Consider using a [`SparseMerkleTree`] if you need the tree to be low memory
     pub fn blank(
         leaf_hash_param: &LeafParam<P>,
         two_to_one_hash_param: &TwoToOneParam<P>,
@@ -407,174 +490,423 @@ impl<P: Config> MerkleTree<P> {
         Self::new_with_leaf_digest(leaf_hash_param, two_to_one_hash_param, leaf_digests)
     }
 
-    /// Returns a new merkle tree. `leaves.len()` should be power of two.
+    /// Returns a new merkle tree. If `leaves.len()` is not a power of two, the leaves are padded
+    /// with `P::LeafDigest::default()`.
     pub fn new<L: AsRef<P::Leaf> + Send>(
         leaf_hash_param: &LeafParam<P>,
         two_to_one_hash_param: &TwoToOneParam<P>,
         #[cfg(not(feature = "parallel"))] leaves: impl IntoIterator<Item = L>,
         #[cfg(feature = "parallel")] leaves: impl IntoParallelIterator<Item = L>,
+    ) -> Result<Self, crate::Error> {
+        Self::new_with_padding(
+            leaf_hash_param,
+            two_to_one_hash_param,
+            leaves,
+            LeafPadding::DefaultDigest,
+        )
+    }
+
+    /// Returns a new merkle tree, padding the leaves according to `padding` if `leaves.len()` is
+    /// not a power of two.
+    pub fn new_with_padding<L: AsRef<P::Leaf> + Send>(
+        leaf_hash_param: &LeafParam<P>,
+        two_to_one_hash_param: &TwoToOneParam<P>,
+        #[cfg(not(feature = "parallel"))] leaves: impl IntoIterator<Item = L>,
+        #[cfg(feature = "parallel")] leaves: impl IntoParallelIterator<Item = L>,
+        padding: LeafPadding<P>,
     ) -> Result<Self, crate::Error> {
         let leaf_digests: Vec<_> = cfg_into_iter!(leaves)
             .map(|input| P::LeafHash::evaluate(leaf_hash_param, input.as_ref()))
             .collect::<Result<Vec<_>, _>>()?;
 
-        Self::new_with_leaf_digest(leaf_hash_param, two_to_one_hash_param, leaf_digests)
+        Self::new_with_leaf_digest_and_padding(
+            leaf_hash_param,
+            two_to_one_hash_param,
+            leaf_digests,
+            padding,
+        )
     }
 
+    /// Returns a new merkle tree from the digests of its leaves. If `leaf_digests.len()` is not
+    /// a power of two, the leaves are padded with `P::LeafDigest::default()`.
     pub fn new_with_leaf_digest(
         leaf_hash_param: &LeafParam<P>,
         two_to_one_hash_param: &TwoToOneParam<P>,
         leaf_digests: Vec<P::LeafDigest>,
     ) -> Result<Self, crate::Error> {
-        let leaf_nodes_size = leaf_digests.len();
-        assert!(
-            leaf_nodes_size.is_power_of_two() && leaf_nodes_size > 1,
-            "`leaves.len() should be power of two and greater than one"
-        );
-        let non_leaf_nodes_size = leaf_nodes_size - 1;
-
-        let tree_height = tree_height(leaf_nodes_size);
+        Self::new_with_leaf_digest_and_padding(
+            leaf_hash_param,
+            two_to_one_hash_param,
+            leaf_digests,
+            LeafPadding::DefaultDigest,
+        )
+    }
 
-        let hash_of_empty: P::InnerDigest = P::InnerDigest::default();
+    /// Returns a new merkle tree from the digests of its leaves, padding them according to
+    /// `padding` if `leaf_digests.len()` is not a power of two.
+    pub fn new_with_leaf_digest_and_padding(
+        leaf_hash_param: &LeafParam<P>,
+        two_to_one_hash_param: &TwoToOneParam<P>,
+        mut leaf_digests: Vec<P::LeafDigest>,
+        padding: LeafPadding<P>,
+    ) -> Result<Self, crate::Error> {
+        let num_leaves = leaf_digests.len();
+        assert!(num_leaves > 0, "`leaves` should not be empty");
+        leaf_digests.resize(
+            num_leaves.next_power_of_two().max(2),
+            P::LeafDigest::default(),
+        );
+        let store = MemoryNodeStore::from_leaf_digests(leaf_digests);
+        Self::build(
+            leaf_hash_param,
+            two_to_one_hash_param,
+            store,
+            num_leaves,
+            padding,
+        )
+    }
+}
 
-        // initialize the merkle tree as array of nodes in level order
-        let mut non_leaf_nodes: Vec<P::InnerDigest> = cfg_into_iter!(0..non_leaf_nodes_size)
-            .map(|_| hash_of_empty.clone())
-            .collect();
+impl<P: Config, S: NodeStore<P>> MerkleTree<P, S> {
+    /// Returns a new merkle tree whose nodes are kept in `store`.
+    ///
+    /// The leaves are hashed and written to the store in chunks, so `leaves` does not need to fit
+    /// in memory. The height of the store should be the height of the tree, that is
+    /// `log2(num_leaves.next_power_of_two()) + 1`, and the leaves are padded according to `padding`.
+    pub fn new_in_store<L: AsRef<P::Leaf> + Send>(
+        leaf_hash_param: &LeafParam<P>,
+        two_to_one_hash_param: &TwoToOneParam<P>,
+        mut store: S,
+        leaves: impl IntoIterator<Item = L>,
+        padding: LeafPadding<P>,
+    ) -> Result<Self, crate::Error> {
+        let mut leaves = leaves.into_iter();
+        let mut num_leaves = 0;
+        loop {
+            let chunk: Vec<L> = leaves.by_ref().take(BUILD_CHUNK_SIZE).collect();
+            if chunk.is_empty() {
+                break;
+            }
+            let count = chunk.len();
+            assert!(
+                num_leaves + count <= 1 << (store.height() - 1),
+                "too many leaves for the height of the store"
+            );
+            let leaf_digests = cfg_into_iter!(chunk)
+                .map(|input| P::LeafHash::evaluate(leaf_hash_param, input.as_ref()))
+                .collect::<Result<Vec<_>, _>>()?;
+            store.put_leaves(num_leaves, leaf_digests)?;
+            num_leaves += count;
+        }
+        assert!(num_leaves > 0, "`leaves` should not be empty");
+        assert_eq!(
+            tree_height(num_leaves.next_power_of_two().max(2)),
+            store.height(),
+            "the height of the store does not match the number of leaves"
+        );
+        Self::build(
+            leaf_hash_param,
+            two_to_one_hash_param,
+            store,
+            num_leaves,
+            padding,
+        )
+    }
 
-        // Compute the starting indices for each non-leaf level of the tree
-        let mut index = 0;
-        let mut level_indices = Vec::with_capacity(tree_height - 1);
-        for _ in 0..(tree_height - 1) {
-            level_indices.push(index);
-            index = left_child(index);
+    /// Reopens a merkle tree from a store populated by a previous [`MerkleTree`], for example with
+    /// [`MerkleTree::new_in_store`]. `padding` should be the padding the tree was built with.
+    ///
+    /// Returns an error if the stored tree was not padded with `padding`. This is checked on the
+    /// first padded node of every level, so it costs `O(height)` hashes.
+    pub fn open(
+        leaf_hash_param: &LeafParam<P>,
+        two_to_one_hash_param: &TwoToOneParam<P>,
+        store: S,
+        padding: LeafPadding<P>,
+    ) -> Result<Self, crate::Error> {
+        let tree_height = store.height();
+        let leaf_nodes_size = 1 << (tree_height - 1);
+        let num_leaves = store.num_leaves();
+        if num_leaves == 0 || num_leaves > leaf_nodes_size {
+            return Err(SerializationError::InvalidData.into());
         }
 
-        // compute the hash values for the non-leaf bottom layer
-        {
-            let start_index = level_indices.pop().unwrap();
-            let upper_bound = left_child(start_index);
+        // check the first padded leaves
+        let padding_leaf = match &padding {
+            LeafPadding::Sentinel(sentinel) => sentinel.clone(),
+            _ => P::LeafDigest::default(),
+        };
+        let mut start = num_leaves;
+        if let Some(position) = padding.duplicate_position(num_leaves, 0, leaf_nodes_size) {
+            if store.leaf(position)? != store.leaf(position - 1)? {
+                return Err(SerializationError::InvalidData.into());
+            }
+            start += 1;
+        }
+        if start < leaf_nodes_size && store.leaf(start)? != padding_leaf {
+            return Err(SerializationError::InvalidData.into());
+        }
 
-            cfg_iter_mut!(non_leaf_nodes[start_index..upper_bound])
-                .enumerate()
-                .try_for_each(|(i, n)| {
-                    // `left_child(current_index)` and `right_child(current_index) returns the position of
-                    // leaf in the whole tree (represented as a list in level order). We need to shift it
-                    // by `-upper_bound` to get the index in `leaf_nodes` list.
+        // check the first node of every level which depends on padding
+        for level in (0..tree_height - 1).rev() {
+            let level_size = 1 << level;
+            let height_above_leaves = tree_height - 1 - level;
+            let position = ((num_leaves - 1) >> height_above_leaves) + 1;
+            if position >= level_size {
+                continue;
+            }
+            let expected =
+                if padding.duplicate_position(num_leaves, height_above_leaves, level_size)
+                    == Some(position)
+                {
+                    store.inner(level, position - 1)?
+                } else {
+                    Self::hash_chunk(two_to_one_hash_param, &store, level, position, 1, None)?
+                        .remove(0)
+                };
+            if store.inner(level, position)? != expected {
+                return Err(SerializationError::InvalidData.into());
+            }
+        }
 
-                    // similarly, we need to rescale i by start_index
-                    // to get the index outside the slice and in the level-ordered list of nodes
+        Ok(MerkleTree {
+            root: store.inner(0, 0)?,
+            store,
+            leaf_hash_param: leaf_hash_param.clone(),
+            two_to_one_hash_param: two_to_one_hash_param.clone(),
+            padding,
+            root_history: None,
+        })
+    }
 
-                    let current_index = i + start_index;
-                    let left_leaf_index = left_child(current_index) - upper_bound;
-                    let right_leaf_index = right_child(current_index) - upper_bound;
+    /// Pads the first `num_leaves` leaves of `store`, and computes every non-leaf node level by
+    /// level, hashing `BUILD_CHUNK_SIZE` nodes at a time.
+    fn build(
+        leaf_hash_param: &LeafParam<P>,
+        two_to_one_hash_param: &TwoToOneParam<P>,
+        mut store: S,
+        num_leaves: usize,
+        padding: LeafPadding<P>,
+    ) -> Result<Self, crate::Error> {
+        let tree_height = store.height();
+        let leaf_nodes_size = 1 << (tree_height - 1);
 
-                    *n = P::TwoToOneHash::evaluate(
-                        two_to_one_hash_param,
-                        P::LeafInnerDigestConverter::convert(
-                            leaf_digests[left_leaf_index].clone(),
-                        )?,
-                        P::LeafInnerDigestConverter::convert(
-                            leaf_digests[right_leaf_index].clone(),
-                        )?,
-                    )?;
-                    Ok::<(), crate::Error>(())
-                })?;
-        }
-
-        // compute the hash values for nodes in every other layer in the tree
-        level_indices.reverse();
-        for &start_index in &level_indices {
-            // The layer beginning `start_index` ends at `upper_bound` (exclusive).
-            let upper_bound = left_child(start_index);
-
-            let (nodes_at_level, nodes_at_prev_level) =
-                non_leaf_nodes[..].split_at_mut(upper_bound);
-            // Iterate over the nodes at the current level, and compute the hash of each node
-            cfg_iter_mut!(nodes_at_level[start_index..])
-                .enumerate()
-                .try_for_each(|(i, n)| {
-                    // `left_child(current_index)` and `right_child(current_index) returns the position of
-                    // leaf in the whole tree (represented as a list in level order). We need to shift it
-                    // by `-upper_bound` to get the index in `leaf_nodes` list.
-
-                    // similarly, we need to rescale i by start_index
-                    // to get the index outside the slice and in the level-ordered list of nodes
-                    let current_index = i + start_index;
-                    let left_leaf_index = left_child(current_index) - upper_bound;
-                    let right_leaf_index = right_child(current_index) - upper_bound;
-
-                    // need for unwrap as Box<Error> does not implement trait Send
-                    *n = P::TwoToOneHash::compress(
-                        two_to_one_hash_param,
-                        nodes_at_prev_level[left_leaf_index].clone(),
-                        nodes_at_prev_level[right_leaf_index].clone(),
-                    )?;
-                    Ok::<_, crate::Error>(())
-                })?;
+        // pad the leaves
+        let padding_leaf = match &padding {
+            LeafPadding::Sentinel(sentinel) => sentinel.clone(),
+            _ => P::LeafDigest::default(),
+        };
+        let mut start = num_leaves;
+        if let Some(position) = padding.duplicate_position(num_leaves, 0, leaf_nodes_size) {
+            store.put_leaf(position, store.leaf(position - 1)?)?;
+            start += 1;
+        }
+        while start < leaf_nodes_size {
+            let count = BUILD_CHUNK_SIZE.min(leaf_nodes_size - start);
+            store.put_leaves(start, vec![padding_leaf.clone(); count])?;
+            start += count;
         }
+        store.set_num_leaves(num_leaves)?;
+
+        // compute the hash values for every non-leaf layer, from the bottom to the root
+        for level in (0..tree_height - 1).rev() {
+            let level_size = 1 << level;
+            let duplicate =
+                padding.duplicate_position(num_leaves, tree_height - 1 - level, level_size);
+            for start in (0..level_size).step_by(BUILD_CHUNK_SIZE) {
+                let count = BUILD_CHUNK_SIZE.min(level_size - start);
+                let nodes = Self::hash_chunk(
+                    two_to_one_hash_param,
+                    &store,
+                    level,
+                    start,
+                    count,
+                    duplicate,
+                )?;
+                store.put_inners(level, start, nodes)?;
+            }
+        }
+        store.flush()?;
+
         Ok(MerkleTree {
-            leaf_nodes: leaf_digests,
-            non_leaf_nodes,
-            height: tree_height,
+            root: store.inner(0, 0)?,
+            store,
             leaf_hash_param: leaf_hash_param.clone(),
             two_to_one_hash_param: two_to_one_hash_param.clone(),
+            padding,
+            root_history: None,
         })
     }
 
+    /// Hashes the children of `count` consecutive nodes of level `level` starting at `start`,
+    /// copying the node at `duplicate` from its left sibling if it is in the chunk.
+    fn hash_chunk(
+        two_to_one_hash_param: &TwoToOneParam<P>,
+        store: &S,
+        level: usize,
+        start: usize,
+        count: usize,
+        duplicate: Option<usize>,
+    ) -> Result<Vec<P::InnerDigest>, crate::Error> {
+        let mut nodes = if level == store.height() - 2 {
+            let leaves = store.leaves(2 * start, 2 * count)?;
+            cfg_into_iter!(0..count)
+                .map(|i| {
+                    P::TwoToOneHash::evaluate(
+                        two_to_one_hash_param,
+                        P::LeafInnerDigestConverter::convert(leaves[2 * i].clone())?,
+                        P::LeafInnerDigestConverter::convert(leaves[2 * i + 1].clone())?,
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        } else {
+            let children = store.inners(level + 1, 2 * start, 2 * count)?;
+            cfg_into_iter!(0..count)
+                .map(|i| {
+                    P::TwoToOneHash::compress(
+                        two_to_one_hash_param,
+                        &children[2 * i],
+                        &children[2 * i + 1],
+                    )
+                })
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        // `count` is even unless it is the whole level, so a node and its copy are always in the
+        // same chunk
+        if let Some(position) = duplicate.filter(|p| (start..start + count).contains(p)) {
+            nodes[position - start] = nodes[position - start - 1].clone();
+        }
+        Ok(nodes)
+    }
+
+    /// Rehashes every non-leaf node of the tree from its children, and checks it against the
+    /// stored node and the cached root.
+    ///
+    /// This is useful to check a tree loaded from an untrusted source. Leaves are not checked,
+    /// except for the copies required by [`LeafPadding::DuplicateOdd`].
+    pub fn verify_nodes(&self) -> Result<bool, crate::Error> {
+        let tree_height = self.height();
+        let num_leaves = self.num_leaves();
+        let leaf_nodes_size = 1 << (tree_height - 1);
+        if let Some(position) = self
+            .padding
+            .duplicate_position(num_leaves, 0, leaf_nodes_size)
+        {
+            if self.store.leaf(position)? != self.store.leaf(position - 1)? {
+                return Ok(false);
+            }
+        }
+
+        for level in (0..tree_height - 1).rev() {
+            let level_size = 1 << level;
+            let duplicate =
+                self.padding
+                    .duplicate_position(num_leaves, tree_height - 1 - level, level_size);
+            for start in (0..level_size).step_by(BUILD_CHUNK_SIZE) {
+                let count = BUILD_CHUNK_SIZE.min(level_size - start);
+                let nodes = Self::hash_chunk(
+                    &self.two_to_one_hash_param,
+                    &self.store,
+                    level,
+                    start,
+                    count,
+                    duplicate,
+                )?;
+                if nodes != self.store.inners(level, start, count)? {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(self.store.inner(0, 0)? == self.root)
+    }
+
     /// Returns the root of the Merkle tree.
     pub fn root(&self) -> P::InnerDigest {
-        self.non_leaf_nodes[0].clone()
+        self.root.clone()
     }
 
     /// Returns the height of the Merkle tree.
     pub fn height(&self) -> usize {
-        self.height
+        self.store.height()
     }
 
-    /// Given the `index` of a leaf, returns the digest of its leaf sibling
-    pub fn get_leaf_sibling_hash(&self, index: usize) -> P::LeafDigest {
-        if index & 1 == 0 {
-            // leaf is left child
-            self.leaf_nodes[index + 1].clone()
-        } else {
-            // leaf is right child
-            self.leaf_nodes[index - 1].clone()
-        }
+    /// Returns the number of leaves the tree was built from, before padding.
+    pub fn num_leaves(&self) -> usize {
+        self.store.num_leaves()
     }
 
-    /// Returns the authentication path from leaf at `index` to root, as a Vec of digests
-    fn compute_auth_path(&self, index: usize) -> Vec<P::InnerDigest> {
-        // gather basic tree information
-        let tree_height = tree_height(self.leaf_nodes.len());
+    /// Returns the padding policy of the tree.
+    pub fn padding(&self) -> &LeafPadding<P> {
+        &self.padding
+    }
 
-        // Get Leaf hash, and leaf sibling hash,
-        let leaf_index_in_tree = convert_index_to_last_level(index, tree_height);
+    /// Enables keeping the last `capacity` roots of the tree, starting with the current root.
+    /// Every update then pushes the new root to the history.
+    pub fn with_root_history(mut self, capacity: usize) -> Self {
+        let mut root_history = RootHistory::new(capacity);
+        root_history.push(self.root());
+        self.root_history = Some(root_history);
+        self
+    }
 
-        // path.len() = `tree height - 2`, the two missing elements being the leaf sibling hash and the root
-        let mut path = Vec::with_capacity(tree_height - 2);
-        // Iterate from the bottom layer after the leaves, to the top, storing all sibling node's hash values.
-        let mut current_node = parent(leaf_index_in_tree).unwrap();
-        while !is_root(current_node) {
-            let sibling_node = sibling(current_node).unwrap();
-            path.push(self.non_leaf_nodes[sibling_node].clone());
-            current_node = parent(current_node).unwrap();
+    /// Returns the last roots of the tree, if [`MerkleTree::with_root_history`] was used.
+    pub fn root_history(&self) -> Option<&RootHistory<P::InnerDigest>> {
+        self.root_history.as_ref()
+    }
+
+    /// Returns true iff `root` is one of the roots in the history of the tree, or its current
+    /// root if the history is not enabled.
+    pub fn is_known_root(&self, root: &P::InnerDigest) -> bool {
+        match &self.root_history {
+            Some(root_history) => root_history.contains(root),
+            None => &self.root == root,
+        }
+    }
+
+    /// Sets the cached root, and pushes it to the root history.
+    fn set_root(&mut self, root: P::InnerDigest) {
+        if let Some(root_history) = &mut self.root_history {
+            root_history.push(root.clone());
         }
+        self.root = root;
+    }
+
+    /// Returns the store holding the nodes of the tree.
+    pub fn store(&self) -> &S {
+        &self.store
+    }
 
-        debug_assert_eq!(path.len(), tree_height - 2);
+    /// Consumes the tree and returns the store holding its nodes.
+    pub fn into_store(self) -> S {
+        self.store
+    }
 
-        // we want to make path from root to bottom
-        path.reverse();
-        path
+    /// Given the `index` of a leaf, returns the digest of its leaf sibling
+    pub fn get_leaf_sibling_hash(&self, index: usize) -> Result<P::LeafDigest, crate::Error> {
+        // the sibling of a left child is on the right, and vice versa
+        self.store.leaf(index ^ 1)
+    }
+
+    /// Returns the authentication path from leaf at `index` to root, as a Vec of digests
+    fn compute_auth_path(&self, index: usize) -> Result<Vec<P::InnerDigest>, crate::Error> {
+        let tree_height = self.height();
+        // path.len() = `tree height - 2`, the two missing elements being the leaf sibling hash and the root
+        // Iterate from the top layer after the root, to the bottom, storing all sibling node's hash values.
+        (1..tree_height - 1)
+            .map(|level| {
+                let position = index >> (tree_height - 1 - level);
+                self.store.inner(level, position ^ 1)
+            })
+            .collect()
     }
 
     /// Returns the authentication path from leaf at `index` to root.
     pub fn generate_proof(&self, index: usize) -> Result<Path<P>, crate::Error> {
-        let path = self.compute_auth_path(index);
+        let path = self.compute_auth_path(index)?;
         Ok(Path {
             leaf_index: index,
             auth_path: path,
-            leaf_sibling_hash: self.get_leaf_sibling_hash(index),
+            leaf_sibling_hash: self.get_leaf_sibling_hash(index)?,
         })
     }
 
@@ -605,9 +937,9 @@ impl<P: Config> MerkleTree<P> {
         let mut prev_path = Vec::new();
 
         for index in &indexes {
-            leaf_siblings_hashes.push(self.get_leaf_sibling_hash(*index));
+            leaf_siblings_hashes.push(self.get_leaf_sibling_hash(*index)?);
 
-            let path = self.compute_auth_path(*index);
+            let path = self.compute_auth_path(*index)?;
 
             // incremental encoding
             let (prefix_len, suffix) = prefix_encode_path(&prev_path, &path);
@@ -635,45 +967,50 @@ impl<P: Config> MerkleTree<P> {
         let new_leaf_hash: P::LeafDigest = P::LeafHash::evaluate(&self.leaf_hash_param, new_leaf)?;
 
         // calculate leaf sibling hash and locate its position (left or right)
+        let leaf_sibling = if self.is_duplicate(0, index ^ 1) {
+            // the sibling is a copy of the leaf itself
+            new_leaf_hash.clone()
+        } else {
+            self.store.leaf(index ^ 1)?
+        };
         let (leaf_left, leaf_right) = if index & 1 == 0 {
             // leaf on left
-            (&new_leaf_hash, &self.leaf_nodes[index + 1])
+            (new_leaf_hash.clone(), leaf_sibling)
         } else {
-            (&self.leaf_nodes[index - 1], &new_leaf_hash)
+            (leaf_sibling, new_leaf_hash.clone())
         };
 
         // calculate the updated hash at bottom non-leaf-level
-        let mut path_bottom_to_top = Vec::with_capacity(self.height - 1);
+        let tree_height = self.height();
+        let mut path_bottom_to_top = Vec::with_capacity(tree_height - 1);
         {
             path_bottom_to_top.push(P::TwoToOneHash::evaluate(
                 &self.two_to_one_hash_param,
-                P::LeafInnerDigestConverter::convert(leaf_left.clone())?,
-                P::LeafInnerDigestConverter::convert(leaf_right.clone())?,
+                P::LeafInnerDigestConverter::convert(leaf_left)?,
+                P::LeafInnerDigestConverter::convert(leaf_right)?,
             )?);
         }
 
         // then calculate the updated hash from bottom to root
-        let leaf_index_in_tree = convert_index_to_last_level(index, self.height);
-        let mut prev_index = parent(leaf_index_in_tree).unwrap();
-        while !is_root(prev_index) {
-            let (left_child, right_child) = if is_left_child(prev_index) {
-                (
-                    path_bottom_to_top.last().unwrap(),
-                    &self.non_leaf_nodes[sibling(prev_index).unwrap()],
-                )
+        for height_above_leaves in 1..tree_height - 1 {
+            let position = index >> height_above_leaves;
+            let current = path_bottom_to_top.last().unwrap();
+            let sibling_node = if self.is_duplicate(height_above_leaves, position ^ 1) {
+                // the sibling is a copy of the updated node itself
+                current.clone()
             } else {
-                (
-                    &self.non_leaf_nodes[sibling(prev_index).unwrap()],
-                    path_bottom_to_top.last().unwrap(),
-                )
+                let level = tree_height - 1 - height_above_leaves;
+                self.store.inner(level, position ^ 1)?
+            };
+            let evaluated = if position & 1 == 0 {
+                P::TwoToOneHash::compress(&self.two_to_one_hash_param, current, &sibling_node)?
+            } else {
+                P::TwoToOneHash::compress(&self.two_to_one_hash_param, &sibling_node, current)?
             };
-            let evaluated =
-                P::TwoToOneHash::compress(&self.two_to_one_hash_param, left_child, right_child)?;
             path_bottom_to_top.push(evaluated);
-            prev_index = parent(prev_index).unwrap();
         }
 
-        debug_assert_eq!(path_bottom_to_top.len(), self.height - 1);
+        debug_assert_eq!(path_bottom_to_top.len(), tree_height - 1);
         let path_top_to_bottom: Vec<_> = path_bottom_to_top.into_iter().rev().collect();
         Ok((new_leaf_hash, path_top_to_bottom))
     }
@@ -690,15 +1027,9 @@ impl<P: Config> MerkleTree<P> {
     /// ```
     /// update(3, {new leaf}) would swap the leaf value at `[I]` and cause a recomputation of `[A]`, `[B]`, and `[E]`.
     pub fn update(&mut self, index: usize, new_leaf: &P::Leaf) -> Result<(), crate::Error> {
-        assert!(index < self.leaf_nodes.len(), "index out of range");
-        let (updated_leaf_hash, mut updated_path) = self.updated_path(index, new_leaf)?;
-        self.leaf_nodes[index] = updated_leaf_hash;
-        let mut curr_index = convert_index_to_last_level(index, self.height);
-        for _ in 0..self.height - 1 {
-            curr_index = parent(curr_index).unwrap();
-            self.non_leaf_nodes[curr_index] = updated_path.pop().unwrap();
-        }
-        Ok(())
+        self.check_index(index);
+        let (updated_leaf_hash, updated_path) = self.updated_path(index, new_leaf)?;
+        self.write_path(index, updated_leaf_hash, updated_path)
     }
 
     /// Update the leaf and check if the updated root is equal to `asserted_new_root`.
@@ -710,64 +1041,410 @@ impl<P: Config> MerkleTree<P> {
         new_leaf: &P::Leaf,
         asserted_new_root: &P::InnerDigest,
     ) -> Result<bool, crate::Error> {
-        assert!(index < self.leaf_nodes.len(), "index out of range");
-        let (updated_leaf_hash, mut updated_path) = self.updated_path(index, new_leaf)?;
+        self.check_index(index);
+        let (updated_leaf_hash, updated_path) = self.updated_path(index, new_leaf)?;
         if &updated_path[0] != asserted_new_root {
             return Ok(false);
         }
-        self.leaf_nodes[index] = updated_leaf_hash;
-        let mut curr_index = convert_index_to_last_level(index, self.height);
-        for _ in 0..self.height - 1 {
-            curr_index = parent(curr_index).unwrap();
-            self.non_leaf_nodes[curr_index] = updated_path.pop().unwrap();
+        self.write_path(index, updated_leaf_hash, updated_path)?;
+        Ok(true)
+    }
+
+    /// Update the leaves at the given indices, rehashing every touched ancestor only once.
+    ///
+    /// If an index appears several times, the last update wins. Updating `k` leaves costs at most
+    /// `k * (height - 1)` hashes, and much fewer when the leaves are close to each other.
+    pub fn update_batch<L: AsRef<P::Leaf> + Sync>(
+        &mut self,
+        updates: &[(usize, L)],
+    ) -> Result<(), crate::Error> {
+        let (leaves, levels) = self.updated_nodes(updates)?;
+        self.write_nodes(leaves, levels)
+    }
+
+    /// Update the leaves at the given indices, and check if the updated root is equal to
+    /// `asserted_new_root`.
+    ///
+    /// Tree will not be modified if the check fails.
+    pub fn check_update_batch<L: AsRef<P::Leaf> + Sync>(
+        &mut self,
+        updates: &[(usize, L)],
+        asserted_new_root: &P::InnerDigest,
+    ) -> Result<bool, crate::Error> {
+        let (leaves, levels) = self.updated_nodes(updates)?;
+        let new_root = levels[0].get(&0).unwrap_or(&self.root);
+        if new_root != asserted_new_root {
+            return Ok(false);
         }
+        self.write_nodes(leaves, levels)?;
         Ok(true)
     }
+
+    /// Given a batch of updates, returns the updated leaf digests, and the updated non-leaf nodes
+    /// of every level from the root to the bottom non-leaf level, keyed by position.
+    /// This does not mutate the underlying tree.
+    #[allow(clippy::type_complexity)]
+    fn updated_nodes<L: AsRef<P::Leaf> + Sync>(
+        &self,
+        updates: &[(usize, L)],
+    ) -> Result<
+        (
+            BTreeMap<usize, P::LeafDigest>,
+            Vec<BTreeMap<usize, P::InnerDigest>>,
+        ),
+        crate::Error,
+    > {
+        let tree_height = self.height();
+        updates
+            .iter()
+            .for_each(|(index, _)| self.check_index(*index));
+
+        // calculate the hash of the new leaves; later updates overwrite earlier ones
+        let leaf_digests = cfg_iter!(updates)
+            .map(|(_, leaf)| P::LeafHash::evaluate(&self.leaf_hash_param, leaf.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut leaves: BTreeMap<_, _> = updates
+            .iter()
+            .map(|(index, _)| *index)
+            .zip(leaf_digests)
+            .collect();
+        if let Some(position) = self.duplicate_of_updated(0, &leaves) {
+            leaves.insert(position, leaves[&(position - 1)].clone());
+        }
+
+        // rehash the parents of the updated nodes, level by level from the bottom
+        let mut levels = vec![BTreeMap::new(); tree_height - 1];
+        let parents: BTreeSet<usize> = leaves.keys().map(|index| index >> 1).collect();
+        let children = parents
+            .iter()
+            .map(|&parent| {
+                let child = |position| match leaves.get(&position) {
+                    Some(digest) => Ok(digest.clone()),
+                    None => self.store.leaf(position),
+                };
+                Ok((parent, child(2 * parent)?, child(2 * parent + 1)?))
+            })
+            .collect::<Result<Vec<_>, crate::Error>>()?;
+        let mut updated: BTreeMap<_, _> = cfg_into_iter!(children)
+            .map(|(parent, left, right)| {
+                let digest = P::TwoToOneHash::evaluate(
+                    &self.two_to_one_hash_param,
+                    P::LeafInnerDigestConverter::convert(left)?,
+                    P::LeafInnerDigestConverter::convert(right)?,
+                )?;
+                Ok((parent, digest))
+            })
+            .collect::<Result<Vec<_>, crate::Error>>()?
+            .into_iter()
+            .collect();
+
+        for level in (0..tree_height - 2).rev() {
+            let height_above_leaves = tree_height - 2 - level;
+            if let Some(position) = self.duplicate_of_updated(height_above_leaves, &updated) {
+                updated.insert(position, updated[&(position - 1)].clone());
+            }
+            let parents: BTreeSet<usize> = updated.keys().map(|index| index >> 1).collect();
+            let children = parents
+                .iter()
+                .map(|&parent| {
+                    let child = |position| match updated.get(&position) {
+                        Some(digest) => Ok(digest.clone()),
+                        None => self.store.inner(level + 1, position),
+                    };
+                    Ok((parent, child(2 * parent)?, child(2 * parent + 1)?))
+                })
+                .collect::<Result<Vec<_>, crate::Error>>()?;
+            let parent_digests = cfg_into_iter!(children)
+                .map(|(parent, left, right)| {
+                    let digest =
+                        P::TwoToOneHash::compress(&self.two_to_one_hash_param, left, right)?;
+                    Ok((parent, digest))
+                })
+                .collect::<Result<Vec<_>, crate::Error>>()?;
+            levels[level + 1] =
+                ark_std::mem::replace(&mut updated, parent_digests.into_iter().collect());
+        }
+        levels[0] = updated;
+
+        Ok((leaves, levels))
+    }
+
+    /// Returns the position of the copy of an updated node at the level `height_above_leaves`
+    /// levels above the leaves, if any.
+    fn duplicate_of_updated<T>(
+        &self,
+        height_above_leaves: usize,
+        updated: &BTreeMap<usize, T>,
+    ) -> Option<usize> {
+        let level_size = 1 << (self.height() - 1 - height_above_leaves);
+        self.padding
+            .duplicate_position(self.num_leaves(), height_above_leaves, level_size)
+            .filter(|position| updated.contains_key(&(position - 1)))
+    }
+
+    /// Write the updated leaf digests and non-leaf nodes returned by `updated_nodes`.
+    fn write_nodes(
+        &mut self,
+        leaves: BTreeMap<usize, P::LeafDigest>,
+        levels: Vec<BTreeMap<usize, P::InnerDigest>>,
+    ) -> Result<(), crate::Error> {
+        for (index, digest) in leaves {
+            self.store.put_leaf(index, digest)?;
+        }
+        for (level, nodes) in levels.into_iter().enumerate() {
+            for (position, digest) in nodes {
+                if level == 0 {
+                    self.set_root(digest.clone());
+                }
+                self.store.put_inner(level, position, digest)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Panics if the leaf at `index` cannot be updated.
+    fn check_index(&self, index: usize) {
+        let num_updatable = match self.padding {
+            LeafPadding::DuplicateOdd => self.num_leaves(),
+            _ => 1 << (self.height() - 1),
+        };
+        assert!(index < num_updatable, "index out of range");
+    }
+
+    /// Returns true iff the node at `position` of the level `height_above_leaves` levels above the
+    /// leaves is a copy of its left sibling.
+    fn is_duplicate(&self, height_above_leaves: usize, position: usize) -> bool {
+        let level_size = 1 << (self.height() - 1 - height_above_leaves);
+        self.padding
+            .duplicate_position(self.num_leaves(), height_above_leaves, level_size)
+            == Some(position)
+    }
+
+    /// Write the leaf digest at `index` and the nodes of `path_top_to_bottom` on its path to the
+    /// root, along with the copies required by the padding.
+    fn write_path(
+        &mut self,
+        index: usize,
+        leaf_digest: P::LeafDigest,
+        path_top_to_bottom: Vec<P::InnerDigest>,
+    ) -> Result<(), crate::Error> {
+        let tree_height = self.height();
+        if self.is_duplicate(0, index + 1) {
+            self.store.put_leaf(index + 1, leaf_digest.clone())?;
+        }
+        self.store.put_leaf(index, leaf_digest)?;
+        for (level, node) in path_top_to_bottom.into_iter().enumerate() {
+            let height_above_leaves = tree_height - 1 - level;
+            let position = index >> height_above_leaves;
+            if self.is_duplicate(height_above_leaves, position + 1) {
+                self.store.put_inner(level, position + 1, node.clone())?;
+            }
+            if level == 0 {
+                self.set_root(node.clone());
+            }
+            self.store.put_inner(level, position, node)?;
+        }
+        Ok(())
+    }
 }
 
-/// Returns the height of the tree, given the number of leaves.
-#[inline]
-fn tree_height(num_leaves: usize) -> usize {
-    if num_leaves == 1 {
-        return 1;
+impl<P: Config> LeafPadding<P> {
+    /// Returns the position in the level `height_above_leaves` levels above the leaves (of
+    /// `level_size` nodes) that holds a copy of its left sibling, if any.
+    #[inline]
+    fn duplicate_position(
+        &self,
+        num_leaves: usize,
+        height_above_leaves: usize,
+        level_size: usize,
+    ) -> Option<usize> {
+        if !matches!(self, LeafPadding::DuplicateOdd) {
+            return None;
+        }
+        // number of nodes at this level which depend on actual leaves
+        let active = ((num_leaves - 1) >> height_above_leaves) + 1;
+        if active % 2 == 1 && active < level_size {
+            Some(active)
+        } else {
+            None
+        }
     }
+}
 
-    (ark_std::log2(num_leaves) as usize) + 1
+impl<P: Config> CanonicalSerialize for LeafPadding<P> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        match self {
+            LeafPadding::DefaultDigest => 0u8.serialize_with_mode(writer, compress),
+            LeafPadding::Sentinel(sentinel) => {
+                1u8.serialize_with_mode(&mut writer, compress)?;
+                sentinel.serialize_with_mode(writer, compress)
+            }
+            LeafPadding::DuplicateOdd => 2u8.serialize_with_mode(writer, compress),
+        }
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        match self {
+            LeafPadding::Sentinel(sentinel) => 1 + sentinel.serialized_size(compress),
+            _ => 1,
+        }
+    }
 }
-/// Returns true iff the index represents the root.
-#[inline]
-fn is_root(index: usize) -> bool {
-    index == 0
+
+impl<P: Config> Valid for LeafPadding<P> {
+    fn check(&self) -> Result<(), SerializationError> {
+        match self {
+            LeafPadding::Sentinel(sentinel) => sentinel.check(),
+            _ => Ok(()),
+        }
+    }
 }
 
-/// Returns the index of the left child, given an index.
-#[inline]
-fn left_child(index: usize) -> usize {
-    2 * index + 1
+impl<P: Config> CanonicalDeserialize for LeafPadding<P> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        match u8::deserialize_with_mode(&mut reader, compress, validate)? {
+            0 => Ok(LeafPadding::DefaultDigest),
+            1 => Ok(LeafPadding::Sentinel(P::LeafDigest::deserialize_with_mode(
+                reader, compress, validate,
+            )?)),
+            2 => Ok(LeafPadding::DuplicateOdd),
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
 }
 
-/// Returns the index of the right child, given an index.
-#[inline]
-fn right_child(index: usize) -> usize {
-    2 * index + 2
+impl<P: Config> MerkleTree<P> {
+    /// Serializes the nodes of the tree, its root, its padding and its root history, but not its
+    /// hash parameters.
+    ///
+    /// This is useful when the parameters are large, or known from elsewhere: the tree is then
+    /// loaded with [`MerkleTree::deserialize_without_params`].
+    pub fn serialize_without_params<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.padding.serialize_with_mode(&mut writer, compress)?;
+        self.store.serialize_with_mode(&mut writer, compress)?;
+        self.root.serialize_with_mode(&mut writer, compress)?;
+        self.root_history.serialize_with_mode(&mut writer, compress)
+    }
+
+    /// Returns the size in bytes of the output of [`MerkleTree::serialize_without_params`].
+    pub fn serialized_size_without_params(&self, compress: Compress) -> usize {
+        self.padding.serialized_size(compress)
+            + self.store.serialized_size(compress)
+            + self.root.serialized_size(compress)
+            + self.root_history.serialized_size(compress)
+    }
+
+    /// Loads a tree serialized with [`MerkleTree::serialize_without_params`], using the given hash
+    /// parameters.
+    ///
+    /// The stored nodes are always checked to match the stored root. With [`Validate::Yes`],
+    /// every node is also rehashed from its children, see [`MerkleTree::verify_nodes`].
+    pub fn deserialize_without_params<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+        leaf_hash_param: &LeafParam<P>,
+        two_to_one_hash_param: &TwoToOneParam<P>,
+    ) -> Result<Self, SerializationError> {
+        let padding = LeafPadding::deserialize_with_mode(&mut reader, compress, validate)?;
+        let store = MemoryNodeStore::deserialize_with_mode(&mut reader, compress, validate)?;
+        let root = P::InnerDigest::deserialize_with_mode(&mut reader, compress, validate)?;
+        let root_history =
+            Option::<RootHistory<_>>::deserialize_with_mode(&mut reader, compress, validate)?;
+        if store.inner(0, 0).ok().as_ref() != Some(&root)
+            || root_history
+                .as_ref()
+                .map_or(false, |history| history.latest() != Some(&root))
+        {
+            return Err(SerializationError::InvalidData);
+        }
+        let tree = MerkleTree {
+            store,
+            root,
+            two_to_one_hash_param: two_to_one_hash_param.clone(),
+            leaf_hash_param: leaf_hash_param.clone(),
+            padding,
+            root_history,
+        };
+        if validate == Validate::Yes {
+            tree.check()?;
+        }
+        Ok(tree)
+    }
 }
 
-/// Returns the index of the sibling, given an index.
-#[inline]
-fn sibling(index: usize) -> Option<usize> {
-    if index == 0 {
-        None
-    } else if is_left_child(index) {
-        Some(index + 1)
-    } else {
-        Some(index - 1)
+/// Serializes the hash parameters of the tree, followed by the output of
+/// [`MerkleTree::serialize_without_params`].
+impl<P: Config> CanonicalSerialize for MerkleTree<P> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.leaf_hash_param
+            .serialize_with_mode(&mut writer, compress)?;
+        self.two_to_one_hash_param
+            .serialize_with_mode(&mut writer, compress)?;
+        self.serialize_without_params(writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.leaf_hash_param.serialized_size(compress)
+            + self.two_to_one_hash_param.serialized_size(compress)
+            + self.serialized_size_without_params(compress)
+    }
+}
+
+impl<P: Config> Valid for MerkleTree<P> {
+    /// Rehashes every node of the tree, see [`MerkleTree::verify_nodes`].
+    fn check(&self) -> Result<(), SerializationError> {
+        match self.verify_nodes() {
+            Ok(true) => Ok(()),
+            _ => Err(SerializationError::InvalidData),
+        }
+    }
+}
+
+impl<P: Config> CanonicalDeserialize for MerkleTree<P> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let leaf_hash_param =
+            LeafParam::<P>::deserialize_with_mode(&mut reader, compress, validate)?;
+        let two_to_one_hash_param =
+            TwoToOneParam::<P>::deserialize_with_mode(&mut reader, compress, validate)?;
+        Self::deserialize_without_params(
+            reader,
+            compress,
+            validate,
+            &leaf_hash_param,
+            &two_to_one_hash_param,
+        )
     }
 }
 
-/// Returns true iff the given index represents a left child.
+/// Returns the height of the tree, given the number of leaves.
 #[inline]
-fn is_left_child(index: usize) -> bool {
-    index % 2 == 1
+fn tree_height(num_leaves: usize) -> usize {
+    if num_leaves == 1 {
+        return 1;
+    }
+
+    (ark_std::log2(num_leaves) as usize) + 1
 }
 
 /// Returns the index of the parent, given an index.
@@ -0,0 +1,433 @@
+use crate::{
+    crh::{CRHScheme, TwoToOneCRHScheme},
+    merkle_tree::{Config, DefaultHasher, DigestConverter, LeafParam, Path, TwoToOneParam},
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+use ark_std::{collections::BTreeSet, hash::BuildHasherDefault};
+use hashbrown::HashMap;
+
+/// Returns `index >> shift`, or zero if `shift` is at least `usize::BITS`.
+///
+/// Sparse trees can be far taller than 64 levels, so plain shifts would overflow.
+#[inline]
+pub(super) fn shr(index: usize, shift: usize) -> usize {
+    u32::try_from(shift)
+        .ok()
+        .and_then(|shift| index.checked_shr(shift))
+        .unwrap_or(0)
+}
+
+/// Returns the digests of empty subtrees for a tree of height `height`, where the `i`th entry is the
+/// digest of an empty subtree whose root is `i + 1` levels above the leaves. Empty leaves have
+/// digest `P::LeafDigest::default()`.
+pub(super) fn empty_hashes<P: Config>(
+    two_to_one_hash_param: &TwoToOneParam<P>,
+    height: usize,
+) -> Result<Vec<P::InnerDigest>, crate::Error> {
+    let mut empty_hashes = Vec::with_capacity(height - 1);
+    let empty_leaf = P::LeafDigest::default();
+    empty_hashes.push(P::TwoToOneHash::evaluate(
+        two_to_one_hash_param,
+        P::LeafInnerDigestConverter::convert(empty_leaf.clone())?,
+        P::LeafInnerDigestConverter::convert(empty_leaf)?,
+    )?);
+    for level in 1..height - 1 {
+        let child = &empty_hashes[level - 1];
+        let empty = P::TwoToOneHash::compress(two_to_one_hash_param, child, child)?;
+        empty_hashes.push(empty);
+    }
+    Ok(empty_hashes)
+}
+
+/// Derives the position of the value stored under `key` in a key-indexed sparse merkle tree of
+/// height `height`.
+///
+/// Every key owns a slot of two sibling leaves: the left one holds `P::LeafHash(key)` and the
+/// right one holds the value. The slot is made of the low `height - 2` bits of the little-endian
+/// uncompressed encoding of `P::LeafHash(key)`, truncated to `usize::BITS - 1` bits, and the
+/// returned position is that of the value leaf. Distinct keys may share a slot, but since openings
+/// check the key leaf, this may only prevent inserting a key, never prove a false
+/// (non-)membership statement.
+pub fn key_position<P: Config>(
+    leaf_hash_param: &LeafParam<P>,
+    key: &P::Leaf,
+    height: usize,
+) -> Result<usize, crate::Error> {
+    let key_digest = P::LeafHash::evaluate(leaf_hash_param, key)?;
+    Ok(slot_of::<P>(&key_digest, height)? << 1 | 1)
+}
+
+/// Returns the slot of the key with digest `key_digest`, see [`key_position`].
+fn slot_of<P: Config>(key_digest: &P::LeafDigest, height: usize) -> Result<usize, crate::Error> {
+    let mut bytes = Vec::new();
+    key_digest.serialize_uncompressed(&mut bytes)?;
+    let num_bits = (height - 2)
+        .min(usize::BITS as usize - 1)
+        .min(bytes.len() * 8);
+    Ok((0..num_bits)
+        .filter(|i| (bytes[i / 8] >> (i % 8)) & 1 == 1)
+        .fold(0, |acc, i| acc | (1 << i)))
+}
+
+/// Verify that `path` opens `value` under `key` in the key-indexed sparse merkle tree with root
+/// `root_hash`, as returned by [`SparseMerkleTree::generate_key_proof`].
+///
+/// Besides the usual path check, this checks that the path sits at the position of `key` and that
+/// the sibling of the value is the leaf holding `key`.
+pub fn verify_key_membership<P: Config>(
+    path: &Path<P>,
+    leaf_hash_params: &LeafParam<P>,
+    two_to_one_params: &TwoToOneParam<P>,
+    root_hash: &P::InnerDigest,
+    key: &P::Leaf,
+    value: &P::Leaf,
+) -> Result<bool, crate::Error> {
+    let height = path.auth_path.len() + 2;
+    let key_digest = P::LeafHash::evaluate(leaf_hash_params, key)?;
+    if path.leaf_index != slot_of::<P>(&key_digest, height)? << 1 | 1
+        || path.leaf_sibling_hash != key_digest
+    {
+        return Ok(false);
+    }
+    path.verify(leaf_hash_params, two_to_one_params, root_hash, value)
+}
+
+/// A proof that no value is stored under a key of a key-indexed [`SparseMerkleTree`].
+///
+/// This is the authentication path of the leaf holding the key of the slot of `key`, together with
+/// the digest stored in that leaf. The slot is either empty, or owned by a different key.
+#[derive(Derivative, CanonicalSerialize, CanonicalDeserialize)]
+#[derivative(
+    PartialEq(bound = "P: Config"),
+    Clone(bound = "P: Config"),
+    Debug(bound = "P: Config"),
+    Default(bound = "P: Config")
+)]
+pub struct NonMembershipProof<P: Config> {
+    /// The path to the key leaf of the slot in which `key` would be stored.
+    pub path: Path<P>,
+    /// The digest stored in the key leaf, `P::LeafDigest::default()` if the slot is empty.
+    pub key_digest: P::LeafDigest,
+}
+
+impl<P: Config> NonMembershipProof<P> {
+    /// Verify that the slot of `key` does not hold `key` in the tree with root `root_hash`.
+    ///
+    /// `verify` infers the tree height by setting `tree_height = self.path.auth_path.len() + 2`
+    pub fn verify(
+        &self,
+        leaf_hash_params: &LeafParam<P>,
+        two_to_one_params: &TwoToOneParam<P>,
+        root_hash: &P::InnerDigest,
+        key: &P::Leaf,
+    ) -> Result<bool, crate::Error> {
+        let height = self.path.auth_path.len() + 2;
+        let key_digest = P::LeafHash::evaluate(leaf_hash_params, key)?;
+        if self.path.leaf_index != slot_of::<P>(&key_digest, height)? << 1
+            || self.key_digest == key_digest
+        {
+            return Ok(false);
+        }
+        let root = self
+            .path
+            .root_from_leaf_digest(two_to_one_params, &self.key_digest)?;
+        Ok(&root == root_hash)
+    }
+}
+
+/// Defines a sparse merkle tree data structure.
+///
+/// Unlike [`MerkleTree`](super::MerkleTree), only leaves that differ from `P::LeafDigest::default()`
+/// and inner nodes that differ from the digest of an empty subtree are stored. The digests of empty
+/// subtrees are precomputed once per level, so a tree of height 256 costs `O(height)` memory when
+/// blank and `O(height)` hashes per modification.
+///
+/// An empty leaf has digest `P::LeafDigest::default()`, exactly like the leaves of
+/// [`MerkleTree::blank`](super::MerkleTree::blank), so a sparse tree and a dense tree with the
+/// same leaves have the same root. Proofs are regular [`Path`]s, and can be checked with
+/// [`Path::verify`] or the `PathVar` gadget.
+///
+/// Leaf indices are `usize`, so only the first `2^usize::BITS` leaves of a taller tree are
+/// addressable.
+///
+/// The tree can also be used as a key-value map, where each key owns a slot of two sibling leaves
+/// derived with [`key_position`], holding the key and its value. The `*_key` methods operate in
+/// this mode, values are opened with [`verify_key_membership`], and absent keys can be proven with a
+/// [`NonMembershipProof`].
+#[derive(Derivative)]
+#[derivative(Clone(bound = "P: Config"))]
+pub struct SparseMerkleTree<P: Config> {
+    /// stores the non-default leaf digests, keyed by leaf index
+    leaf_nodes: HashMap<usize, P::LeafDigest, BuildHasherDefault<DefaultHasher>>,
+    /// stores the non-empty inner nodes, keyed by `(level, index)` where level 1 is the lowest
+    /// non-leaf level and level `height - 1` contains only the root.
+    non_leaf_nodes: HashMap<(usize, usize), P::InnerDigest, BuildHasherDefault<DefaultHasher>>,
+    /// `empty_hashes[i]` is the digest of an empty subtree whose root is at level `i + 1`
+    empty_hashes: Vec<P::InnerDigest>,
+    /// Store the inner hash parameters
+    two_to_one_hash_param: TwoToOneParam<P>,
+    /// Store the leaf hash parameters
+    leaf_hash_param: LeafParam<P>,
+    /// Stores the height of the tree
+    height: usize,
+}
+
+impl<P: Config> SparseMerkleTree<P> {
+    /// Create an empty sparse merkle tree of the given height, such that all leaves are
+    /// `P::LeafDigest::default()`.
+    pub fn blank(
+        leaf_hash_param: &LeafParam<P>,
+        two_to_one_hash_param: &TwoToOneParam<P>,
+        height: usize,
+    ) -> Result<Self, crate::Error> {
+        assert!(height > 1, "height of the tree should be greater than one");
+        Ok(SparseMerkleTree {
+            leaf_nodes: HashMap::with_hasher(BuildHasherDefault::<DefaultHasher>::default()),
+            non_leaf_nodes: HashMap::with_hasher(BuildHasherDefault::<DefaultHasher>::default()),
+            empty_hashes: empty_hashes::<P>(two_to_one_hash_param, height)?,
+            two_to_one_hash_param: two_to_one_hash_param.clone(),
+            leaf_hash_param: leaf_hash_param.clone(),
+            height,
+        })
+    }
+
+    /// Returns a new sparse merkle tree of the given height, where every `(index, leaf)` pair of
+    /// `leaves` is set. If an index appears more than once, the last leaf wins.
+    pub fn new<L: AsRef<P::Leaf>>(
+        leaf_hash_param: &LeafParam<P>,
+        two_to_one_hash_param: &TwoToOneParam<P>,
+        height: usize,
+        leaves: impl IntoIterator<Item = (usize, L)>,
+    ) -> Result<Self, crate::Error> {
+        let mut tree = Self::blank(leaf_hash_param, two_to_one_hash_param, height)?;
+        let mut leaf_digests = Vec::new();
+        for (index, leaf) in leaves {
+            let digest = P::LeafHash::evaluate(leaf_hash_param, leaf.as_ref())?;
+            leaf_digests.push((index, digest));
+        }
+        tree.set_leaf_digests(leaf_digests)?;
+        Ok(tree)
+    }
+
+    /// Returns the root of the sparse merkle tree.
+    pub fn root(&self) -> P::InnerDigest {
+        self.node(self.height - 1, 0)
+    }
+
+    /// Returns the height of the sparse merkle tree.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Returns the number of non-default leaves in the tree.
+    pub fn num_leaves(&self) -> usize {
+        self.leaf_nodes.len()
+    }
+
+    /// Returns true iff the leaf at `index` differs from the default leaf digest.
+    pub fn contains(&self, index: usize) -> bool {
+        self.leaf_nodes.contains_key(&index)
+    }
+
+    /// Returns the digest of the leaf at `index`.
+    pub fn leaf_digest(&self, index: usize) -> P::LeafDigest {
+        self.leaf_nodes.get(&index).cloned().unwrap_or_default()
+    }
+
+    /// Returns the authentication path from leaf at `index` to root.
+    pub fn generate_proof(&self, index: usize) -> Result<Path<P>, crate::Error> {
+        assert!(self.is_valid_index(index), "index out of range");
+        // path.len() = `tree height - 2`, the two missing elements being the leaf sibling hash and the root
+        let mut auth_path: Vec<_> = (1..self.height - 1)
+            .map(|level| self.node(level, shr(index, level) ^ 1))
+            .collect();
+        // we want to make path from root to bottom
+        auth_path.reverse();
+        Ok(Path {
+            leaf_sibling_hash: self.leaf_digest(index ^ 1),
+            auth_path,
+            leaf_index: index,
+        })
+    }
+
+    /// Insert `new_leaf` at an empty `index`.
+    ///
+    /// Returns `false` and leaves the tree untouched if the slot is already occupied.
+    pub fn insert(&mut self, index: usize, new_leaf: &P::Leaf) -> Result<bool, crate::Error> {
+        assert!(self.is_valid_index(index), "index out of range");
+        if self.contains(index) {
+            return Ok(false);
+        }
+        let digest = P::LeafHash::evaluate(&self.leaf_hash_param, new_leaf)?;
+        self.set_leaf_digests(ark_std::iter::once((index, digest)))?;
+        Ok(true)
+    }
+
+    /// Update the leaf at `index` to `new_leaf`, regardless of whether it was occupied.
+    pub fn update(&mut self, index: usize, new_leaf: &P::Leaf) -> Result<(), crate::Error> {
+        assert!(self.is_valid_index(index), "index out of range");
+        let digest = P::LeafHash::evaluate(&self.leaf_hash_param, new_leaf)?;
+        self.set_leaf_digests(ark_std::iter::once((index, digest)))
+    }
+
+    /// Reset the leaf at `index` to the default leaf digest.
+    ///
+    /// Returns `false` if the slot was already empty.
+    pub fn remove(&mut self, index: usize) -> Result<bool, crate::Error> {
+        assert!(self.is_valid_index(index), "index out of range");
+        if !self.contains(index) {
+            return Ok(false);
+        }
+        self.set_leaf_digests(ark_std::iter::once((index, P::LeafDigest::default())))?;
+        Ok(true)
+    }
+
+    /// Returns the position at which the value of `key` is stored in key-indexed mode.
+    pub fn key_position(&self, key: &P::Leaf) -> Result<usize, crate::Error> {
+        key_position::<P>(&self.leaf_hash_param, key, self.height)
+    }
+
+    /// Returns true iff a value is stored under `key`.
+    pub fn contains_key(&self, key: &P::Leaf) -> Result<bool, crate::Error> {
+        let key_digest = P::LeafHash::evaluate(&self.leaf_hash_param, key)?;
+        let index = slot_of::<P>(&key_digest, self.height)? << 1;
+        Ok(self.leaf_nodes.get(&index) == Some(&key_digest))
+    }
+
+    /// Insert `value` under `key`.
+    ///
+    /// Returns `false` and leaves the tree untouched if the slot of `key` is already occupied,
+    /// either by the same key or by a colliding one.
+    pub fn insert_key(&mut self, key: &P::Leaf, value: &P::Leaf) -> Result<bool, crate::Error> {
+        let index = self.key_position(key)?;
+        if self.contains(index ^ 1) {
+            return Ok(false);
+        }
+        self.set_key_value(index, key, value)?;
+        Ok(true)
+    }
+
+    /// Update the value stored under `key`, inserting it if the slot of `key` is empty.
+    ///
+    /// Returns `false` and leaves the tree untouched if the slot is occupied by a colliding key.
+    pub fn update_key(&mut self, key: &P::Leaf, value: &P::Leaf) -> Result<bool, crate::Error> {
+        let index = self.key_position(key)?;
+        if self.contains(index ^ 1) && !self.contains_key(key)? {
+            return Ok(false);
+        }
+        self.set_key_value(index, key, value)?;
+        Ok(true)
+    }
+
+    /// Remove the value stored under `key`. Returns `false` if there was none.
+    pub fn remove_key(&mut self, key: &P::Leaf) -> Result<bool, crate::Error> {
+        if !self.contains_key(key)? {
+            return Ok(false);
+        }
+        let index = self.key_position(key)?;
+        self.set_leaf_digests([
+            (index ^ 1, P::LeafDigest::default()),
+            (index, P::LeafDigest::default()),
+        ])?;
+        Ok(true)
+    }
+
+    /// Returns the authentication path of the value stored under `key`, to be checked with
+    /// [`verify_key_membership`].
+    pub fn generate_key_proof(&self, key: &P::Leaf) -> Result<Path<P>, crate::Error> {
+        self.generate_proof(self.key_position(key)?)
+    }
+
+    /// Returns a proof that no value is stored under `key`, or `None` if there is one.
+    pub fn generate_non_membership_proof(
+        &self,
+        key: &P::Leaf,
+    ) -> Result<Option<NonMembershipProof<P>>, crate::Error> {
+        if self.contains_key(key)? {
+            return Ok(None);
+        }
+        let index = self.key_position(key)? ^ 1;
+        Ok(Some(NonMembershipProof {
+            path: self.generate_proof(index)?,
+            key_digest: self.leaf_digest(index),
+        }))
+    }
+
+    /// Stores the digests of `key` and `value` in the slot whose value leaf is at `index`.
+    fn set_key_value(
+        &mut self,
+        index: usize,
+        key: &P::Leaf,
+        value: &P::Leaf,
+    ) -> Result<(), crate::Error> {
+        let key_digest = P::LeafHash::evaluate(&self.leaf_hash_param, key)?;
+        let value_digest = P::LeafHash::evaluate(&self.leaf_hash_param, value)?;
+        self.set_leaf_digests([(index ^ 1, key_digest), (index, value_digest)])
+    }
+
+    /// Returns true iff `index` addresses a leaf of this tree.
+    #[inline]
+    fn is_valid_index(&self, index: usize) -> bool {
+        shr(index, self.height - 1) == 0
+    }
+
+    /// Returns the inner node at `(level, index)`, falling back to the empty subtree digest.
+    #[inline]
+    fn node(&self, level: usize, index: usize) -> P::InnerDigest {
+        self.non_leaf_nodes
+            .get(&(level, index))
+            .cloned()
+            .unwrap_or_else(|| self.empty_hashes[level - 1].clone())
+    }
+
+    /// Sets the given leaf digests and rehashes every touched ancestor once, level by level.
+    /// Nodes equal to the default or empty digest are pruned from storage.
+    pub(super) fn set_leaf_digests(
+        &mut self,
+        leaf_digests: impl IntoIterator<Item = (usize, P::LeafDigest)>,
+    ) -> Result<(), crate::Error> {
+        let mut touched = BTreeSet::new();
+        for (index, digest) in leaf_digests {
+            assert!(self.is_valid_index(index), "index out of range");
+            if digest == P::LeafDigest::default() {
+                self.leaf_nodes.remove(&index);
+            } else {
+                self.leaf_nodes.insert(index, digest);
+            }
+            touched.insert(index >> 1);
+        }
+
+        // compute the hash values for the non-leaf bottom layer
+        for &index in &touched {
+            let digest = P::TwoToOneHash::evaluate(
+                &self.two_to_one_hash_param,
+                P::LeafInnerDigestConverter::convert(self.leaf_digest(2 * index))?,
+                P::LeafInnerDigestConverter::convert(self.leaf_digest(2 * index + 1))?,
+            )?;
+            self.store_node(1, index, digest);
+        }
+
+        // compute the hash values for the touched nodes in every other layer
+        for level in 2..self.height {
+            touched = touched.into_iter().map(|index| index >> 1).collect();
+            for &index in &touched {
+                let left = self.node(level - 1, 2 * index);
+                let right = self.node(level - 1, 2 * index + 1);
+                let digest = P::TwoToOneHash::compress(&self.two_to_one_hash_param, left, right)?;
+                self.store_node(level, index, digest);
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    fn store_node(&mut self, level: usize, index: usize, digest: P::InnerDigest) {
+        if digest == self.empty_hashes[level - 1] {
+            self.non_leaf_nodes.remove(&(level, index));
+        } else {
+            self.non_leaf_nodes.insert((level, index), digest);
+        }
+    }
+}
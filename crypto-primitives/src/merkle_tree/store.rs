@@ -0,0 +1,520 @@
+use crate::{merkle_tree::Config, Error};
+use ark_serialize::{
+    CanonicalDeserialize, CanonicalSerialize, Compress, Read, SerializationError, Valid, Validate,
+    Write,
+};
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+
+/// Storage backend for the nodes of a [`MerkleTree`](super::MerkleTree).
+///
+/// Inner nodes are addressed by `(level, index)`, where `level` is the depth of the node (the root
+/// is at level 0 and the lowest non-leaf level is `height - 2`) and `index` is its position from
+/// left to right within the level. Leaves are addressed by their index.
+///
+/// A store has a fixed height, and also records the number of leaves of the tree, so that a tree can
+/// be reopened from a persistent store.
+pub trait NodeStore<P: Config> {
+    /// Returns the height of the tree stored.
+    fn height(&self) -> usize;
+
+    /// Returns the number of leaves of the tree stored, before padding.
+    fn num_leaves(&self) -> usize;
+
+    /// Records the number of leaves of the tree stored, before padding.
+    fn set_num_leaves(&mut self, num_leaves: usize) -> Result<(), Error>;
+
+    /// Returns the digest of the leaf at `index`.
+    fn leaf(&self, index: usize) -> Result<P::LeafDigest, Error>;
+
+    /// Returns the inner node at `index` of level `level`.
+    fn inner(&self, level: usize, index: usize) -> Result<P::InnerDigest, Error>;
+
+    /// Stores the digest of the leaf at `index`.
+    fn put_leaf(&mut self, index: usize, digest: P::LeafDigest) -> Result<(), Error>;
+
+    /// Stores the inner node at `index` of level `level`.
+    fn put_inner(
+        &mut self,
+        level: usize,
+        index: usize,
+        digest: P::InnerDigest,
+    ) -> Result<(), Error>;
+
+    /// Returns the digests of `count` consecutive leaves starting at `start`.
+    fn leaves(&self, start: usize, count: usize) -> Result<Vec<P::LeafDigest>, Error> {
+        (start..start + count).map(|i| self.leaf(i)).collect()
+    }
+
+    /// Returns `count` consecutive inner nodes of level `level` starting at `start`.
+    fn inners(
+        &self,
+        level: usize,
+        start: usize,
+        count: usize,
+    ) -> Result<Vec<P::InnerDigest>, Error> {
+        (start..start + count)
+            .map(|i| self.inner(level, i))
+            .collect()
+    }
+
+    /// Stores the digests of consecutive leaves starting at `start`.
+    fn put_leaves(&mut self, start: usize, digests: Vec<P::LeafDigest>) -> Result<(), Error> {
+        for (i, digest) in digests.into_iter().enumerate() {
+            self.put_leaf(start + i, digest)?;
+        }
+        Ok(())
+    }
+
+    /// Stores consecutive inner nodes of level `level` starting at `start`.
+    fn put_inners(
+        &mut self,
+        level: usize,
+        start: usize,
+        digests: Vec<P::InnerDigest>,
+    ) -> Result<(), Error> {
+        for (i, digest) in digests.into_iter().enumerate() {
+            self.put_inner(level, start + i, digest)?;
+        }
+        Ok(())
+    }
+
+    /// Makes sure that every stored node is persisted.
+    fn flush(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Stores all the nodes of a merkle tree in memory.
+#[derive(Derivative)]
+#[derivative(Clone(bound = "P: Config"), Debug(bound = "P: Config"))]
+pub struct MemoryNodeStore<P: Config> {
+    /// stores the non-leaf nodes in level order. The first element is the root node.
+    /// The ith nodes (starting at 0th) children are at indices `2*i+1`, `2*i+2`
+    non_leaf_nodes: Vec<P::InnerDigest>,
+    /// store the hash of leaf nodes from left to right
+    leaf_nodes: Vec<P::LeafDigest>,
+    /// Stores the number of leaves before padding
+    num_leaves: usize,
+}
+
+impl<P: Config> MemoryNodeStore<P> {
+    /// Returns a store for a tree of height `height`, where every node is the default digest.
+    pub fn new(height: usize) -> Self {
+        assert!(height > 1, "height of the tree should be greater than one");
+        Self::from_leaf_digests(vec![P::LeafDigest::default(); 1 << (height - 1)])
+    }
+
+    /// Returns a store whose leaves are `leaf_digests` and whose inner nodes are default digests.
+    /// `leaf_digests.len()` should be a power of two greater than one.
+    pub fn from_leaf_digests(leaf_digests: Vec<P::LeafDigest>) -> Self {
+        assert!(
+            leaf_digests.len().is_power_of_two() && leaf_digests.len() > 1,
+            "`leaves.len() should be power of two and greater than one"
+        );
+        MemoryNodeStore {
+            non_leaf_nodes: vec![P::InnerDigest::default(); leaf_digests.len() - 1],
+            num_leaves: leaf_digests.len(),
+            leaf_nodes: leaf_digests,
+        }
+    }
+}
+
+impl<P: Config> NodeStore<P> for MemoryNodeStore<P> {
+    fn height(&self) -> usize {
+        ark_std::log2(self.leaf_nodes.len()) as usize + 1
+    }
+
+    fn num_leaves(&self) -> usize {
+        self.num_leaves
+    }
+
+    fn set_num_leaves(&mut self, num_leaves: usize) -> Result<(), Error> {
+        self.num_leaves = num_leaves;
+        Ok(())
+    }
+
+    fn leaf(&self, index: usize) -> Result<P::LeafDigest, Error> {
+        Ok(self.leaf_nodes[index].clone())
+    }
+
+    fn inner(&self, level: usize, index: usize) -> Result<P::InnerDigest, Error> {
+        Ok(self.non_leaf_nodes[(1 << level) - 1 + index].clone())
+    }
+
+    fn put_leaf(&mut self, index: usize, digest: P::LeafDigest) -> Result<(), Error> {
+        self.leaf_nodes[index] = digest;
+        Ok(())
+    }
+
+    fn put_inner(
+        &mut self,
+        level: usize,
+        index: usize,
+        digest: P::InnerDigest,
+    ) -> Result<(), Error> {
+        self.non_leaf_nodes[(1 << level) - 1 + index] = digest;
+        Ok(())
+    }
+
+    fn leaves(&self, start: usize, count: usize) -> Result<Vec<P::LeafDigest>, Error> {
+        Ok(self.leaf_nodes[start..start + count].to_vec())
+    }
+
+    fn inners(
+        &self,
+        level: usize,
+        start: usize,
+        count: usize,
+    ) -> Result<Vec<P::InnerDigest>, Error> {
+        let start = (1 << level) - 1 + start;
+        Ok(self.non_leaf_nodes[start..start + count].to_vec())
+    }
+
+    fn put_leaves(&mut self, start: usize, digests: Vec<P::LeafDigest>) -> Result<(), Error> {
+        let end = start + digests.len();
+        self.leaf_nodes.splice(start..end, digests);
+        Ok(())
+    }
+
+    fn put_inners(
+        &mut self,
+        level: usize,
+        start: usize,
+        digests: Vec<P::InnerDigest>,
+    ) -> Result<(), Error> {
+        let start = (1 << level) - 1 + start;
+        let end = start + digests.len();
+        self.non_leaf_nodes.splice(start..end, digests);
+        Ok(())
+    }
+}
+
+impl<P: Config> CanonicalSerialize for MemoryNodeStore<P> {
+    fn serialize_with_mode<W: Write>(
+        &self,
+        mut writer: W,
+        compress: Compress,
+    ) -> Result<(), SerializationError> {
+        self.num_leaves.serialize_with_mode(&mut writer, compress)?;
+        self.leaf_nodes.serialize_with_mode(&mut writer, compress)?;
+        self.non_leaf_nodes
+            .serialize_with_mode(&mut writer, compress)
+    }
+
+    fn serialized_size(&self, compress: Compress) -> usize {
+        self.num_leaves.serialized_size(compress)
+            + self.leaf_nodes.serialized_size(compress)
+            + self.non_leaf_nodes.serialized_size(compress)
+    }
+}
+
+impl<P: Config> Valid for MemoryNodeStore<P> {
+    /// Checks that the store has the shape of a tree, which is needed for its accessors not to
+    /// panic. The digests are checked when they are deserialized.
+    fn check(&self) -> Result<(), SerializationError> {
+        let num_leaf_nodes = self.leaf_nodes.len();
+        if num_leaf_nodes.is_power_of_two()
+            && num_leaf_nodes > 1
+            && self.non_leaf_nodes.len() == num_leaf_nodes - 1
+            && self.num_leaves >= 1
+            && self.num_leaves <= num_leaf_nodes
+        {
+            Ok(())
+        } else {
+            Err(SerializationError::InvalidData)
+        }
+    }
+}
+
+impl<P: Config> CanonicalDeserialize for MemoryNodeStore<P> {
+    fn deserialize_with_mode<R: Read>(
+        mut reader: R,
+        compress: Compress,
+        validate: Validate,
+    ) -> Result<Self, SerializationError> {
+        let store = MemoryNodeStore {
+            num_leaves: usize::deserialize_with_mode(&mut reader, compress, validate)?,
+            leaf_nodes: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+            non_leaf_nodes: Vec::deserialize_with_mode(&mut reader, compress, validate)?,
+        };
+        // the shape is checked even without validation, as it is cheap
+        store.check()?;
+        Ok(store)
+    }
+}
+
+#[cfg(feature = "std")]
+pub use file::FileNodeStore;
+
+#[cfg(feature = "std")]
+mod file {
+    use super::NodeStore;
+    use crate::{merkle_tree::Config, Error};
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, SerializationError};
+    use ark_std::marker::PhantomData;
+    use std::{
+        fs::{File, OpenOptions},
+        io::{Read, Seek, SeekFrom, Write},
+        path::Path as FsPath,
+    };
+
+    const MAGIC: &[u8; 8] = b"arkmtns1";
+    /// magic, height, leaf slot size, inner slot size, number of leaves
+    const HEADER_SIZE: u64 = 8 + 4 * 8;
+    /// The largest slot size accepted when opening a store, far above the size of any digest.
+    const MAX_SLOT_SIZE: usize = 1 << 20;
+
+    /// Stores the nodes of a merkle tree in a file on local disk, so that trees larger than memory
+    /// can be built, and reopened across process restarts.
+    ///
+    /// Every node is stored in a fixed-size slot holding its uncompressed serialization, padded
+    /// with zeros. Inner nodes are stored in level order, followed by the leaves, so that each
+    /// level is contiguous and batch operations translate to a single read or write.
+    /// The slot sizes must be at least the serialized sizes of the digests of `P`; for fixed-size
+    /// digests, use the `uncompressed_size` of any digest.
+    pub struct FileNodeStore<P: Config> {
+        file: File,
+        height: usize,
+        leaf_slot_size: usize,
+        inner_slot_size: usize,
+        num_leaves: usize,
+        _config: PhantomData<fn() -> P>,
+    }
+
+    impl<P: Config> FileNodeStore<P> {
+        /// Creates a new store for a tree of height `height` at `path`, overwriting any
+        /// existing file. Slot sizes are limited to 1 MiB.
+        pub fn create(
+            path: impl AsRef<FsPath>,
+            height: usize,
+            leaf_slot_size: usize,
+            inner_slot_size: usize,
+        ) -> Result<Self, Error> {
+            assert!(height > 1, "height of the tree should be greater than one");
+            assert!(
+                (1..=MAX_SLOT_SIZE).contains(&leaf_slot_size)
+                    && (1..=MAX_SLOT_SIZE).contains(&inner_slot_size),
+                "slot sizes should be between 1 and {} bytes",
+                MAX_SLOT_SIZE
+            );
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(true)
+                .open(path)
+                .map_err(SerializationError::from)?;
+            let mut store = FileNodeStore {
+                file,
+                height,
+                leaf_slot_size,
+                inner_slot_size,
+                num_leaves: 1 << (height - 1),
+                _config: PhantomData,
+            };
+            let len = store.leaf_offset(1 << (height - 1));
+            store.file.set_len(len).map_err(SerializationError::from)?;
+            store.write_header()?;
+            Ok(store)
+        }
+
+        /// Opens a store previously created with [`FileNodeStore::create`].
+        ///
+        /// Returns an error if the header is malformed, for example with empty or oversized slots,
+        /// or if the file length does not match it.
+        pub fn open(path: impl AsRef<FsPath>) -> Result<Self, Error> {
+            let mut file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .open(path)
+                .map_err(SerializationError::from)?;
+            let mut header = [0u8; HEADER_SIZE as usize];
+            file.read_exact(&mut header)
+                .map_err(SerializationError::from)?;
+            if &header[..8] != MAGIC {
+                return Err(SerializationError::InvalidData.into());
+            }
+            let field = |i: usize| {
+                let mut bytes = [0u8; 8];
+                bytes.copy_from_slice(&header[8 + 8 * i..16 + 8 * i]);
+                usize::try_from(u64::from_le_bytes(bytes))
+                    .map_err(|_| Error::from(SerializationError::InvalidData))
+            };
+            let store = FileNodeStore {
+                file,
+                height: field(0)?,
+                leaf_slot_size: field(1)?,
+                inner_slot_size: field(2)?,
+                num_leaves: field(3)?,
+                _config: PhantomData,
+            };
+            if store.height < 2
+                || store.height > usize::BITS as usize
+                || !(1..=MAX_SLOT_SIZE).contains(&store.leaf_slot_size)
+                || !(1..=MAX_SLOT_SIZE).contains(&store.inner_slot_size)
+                || store.num_leaves > 1 << (store.height - 1)
+            {
+                return Err(SerializationError::InvalidData.into());
+            }
+            // computed with overflow checks, as the header is untrusted; every offset computed
+            // afterwards is at most this length
+            let capacity = 1u64 << (store.height - 1);
+            let expected_len = (capacity - 1)
+                .checked_mul(store.inner_slot_size as u64)
+                .zip(capacity.checked_mul(store.leaf_slot_size as u64))
+                .and_then(|(inner, leaves)| inner.checked_add(leaves))
+                .and_then(|len| len.checked_add(HEADER_SIZE))
+                .ok_or(SerializationError::InvalidData)?;
+            let len = store
+                .file
+                .metadata()
+                .map_err(SerializationError::from)?
+                .len();
+            if len != expected_len {
+                return Err(SerializationError::InvalidData.into());
+            }
+            Ok(store)
+        }
+
+        fn write_header(&mut self) -> Result<(), Error> {
+            let mut header = Vec::with_capacity(HEADER_SIZE as usize);
+            header.extend_from_slice(MAGIC);
+            for field in [
+                self.height,
+                self.leaf_slot_size,
+                self.inner_slot_size,
+                self.num_leaves,
+            ] {
+                header.extend_from_slice(&(field as u64).to_le_bytes());
+            }
+            self.write_at(0, &header)
+        }
+
+        fn inner_offset(&self, level: usize, index: usize) -> u64 {
+            HEADER_SIZE + (((1u64 << level) - 1 + index as u64) * self.inner_slot_size as u64)
+        }
+
+        fn leaf_offset(&self, index: usize) -> u64 {
+            self.inner_offset(0, (1 << (self.height - 1)) - 1)
+                + index as u64 * self.leaf_slot_size as u64
+        }
+
+        fn read_at(&self, offset: u64, len: usize) -> Result<Vec<u8>, Error> {
+            let mut file = &self.file;
+            let mut bytes = vec![0u8; len];
+            file.seek(SeekFrom::Start(offset))
+                .and_then(|_| file.read_exact(&mut bytes))
+                .map_err(SerializationError::from)?;
+            Ok(bytes)
+        }
+
+        fn write_at(&mut self, offset: u64, bytes: &[u8]) -> Result<(), Error> {
+            self.file
+                .seek(SeekFrom::Start(offset))
+                .and_then(|_| self.file.write_all(bytes))
+                .map_err(SerializationError::from)?;
+            Ok(())
+        }
+
+        /// Serializes `digests` into consecutive slots of `slot_size` bytes.
+        fn encode<T: CanonicalSerialize>(
+            digests: &[T],
+            slot_size: usize,
+        ) -> Result<Vec<u8>, Error> {
+            let mut bytes = Vec::with_capacity(digests.len() * slot_size);
+            for digest in digests {
+                let start = bytes.len();
+                digest.serialize_uncompressed(&mut bytes)?;
+                if bytes.len() - start > slot_size {
+                    return Err(SerializationError::NotEnoughSpace.into());
+                }
+                bytes.resize(start + slot_size, 0);
+            }
+            Ok(bytes)
+        }
+
+        /// Deserializes digests from consecutive slots of `slot_size` bytes.
+        fn decode<T: CanonicalDeserialize>(
+            bytes: &[u8],
+            slot_size: usize,
+        ) -> Result<Vec<T>, Error> {
+            bytes
+                .chunks(slot_size)
+                .map(|slot| Ok(T::deserialize_uncompressed(slot)?))
+                .collect()
+        }
+    }
+
+    impl<P: Config> NodeStore<P> for FileNodeStore<P> {
+        fn height(&self) -> usize {
+            self.height
+        }
+
+        fn num_leaves(&self) -> usize {
+            self.num_leaves
+        }
+
+        fn set_num_leaves(&mut self, num_leaves: usize) -> Result<(), Error> {
+            self.num_leaves = num_leaves;
+            self.write_header()
+        }
+
+        fn leaf(&self, index: usize) -> Result<P::LeafDigest, Error> {
+            Ok(self.leaves(index, 1)?.pop().unwrap())
+        }
+
+        fn inner(&self, level: usize, index: usize) -> Result<P::InnerDigest, Error> {
+            Ok(self.inners(level, index, 1)?.pop().unwrap())
+        }
+
+        fn put_leaf(&mut self, index: usize, digest: P::LeafDigest) -> Result<(), Error> {
+            self.put_leaves(index, vec![digest])
+        }
+
+        fn put_inner(
+            &mut self,
+            level: usize,
+            index: usize,
+            digest: P::InnerDigest,
+        ) -> Result<(), Error> {
+            self.put_inners(level, index, vec![digest])
+        }
+
+        fn leaves(&self, start: usize, count: usize) -> Result<Vec<P::LeafDigest>, Error> {
+            let bytes = self.read_at(self.leaf_offset(start), count * self.leaf_slot_size)?;
+            Self::decode(&bytes, self.leaf_slot_size)
+        }
+
+        fn inners(
+            &self,
+            level: usize,
+            start: usize,
+            count: usize,
+        ) -> Result<Vec<P::InnerDigest>, Error> {
+            let offset = self.inner_offset(level, start);
+            let bytes = self.read_at(offset, count * self.inner_slot_size)?;
+            Self::decode(&bytes, self.inner_slot_size)
+        }
+
+        fn put_leaves(&mut self, start: usize, digests: Vec<P::LeafDigest>) -> Result<(), Error> {
+            let bytes = Self::encode(&digests, self.leaf_slot_size)?;
+            self.write_at(self.leaf_offset(start), &bytes)
+        }
+
+        fn put_inners(
+            &mut self,
+            level: usize,
+            start: usize,
+            digests: Vec<P::InnerDigest>,
+        ) -> Result<(), Error> {
+            let bytes = Self::encode(&digests, self.inner_slot_size)?;
+            self.write_at(self.inner_offset(level, start), &bytes)
+        }
+
+        fn flush(&mut self) -> Result<(), Error> {
+            self.file.sync_all().map_err(SerializationError::from)?;
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use crate::{
+    crh::{CRHScheme, TwoToOneCRHScheme},
+    merkle_tree::{
+        empty_hashes, tree_height, Config, DigestConverter, LeafParam, Path, TwoToOneParam,
+        BUILD_CHUNK_SIZE,
+    },
+};
+use ark_std::collections::BTreeMap;
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// Computes the root of a merkle tree from a stream of leaves, without materializing the tree.
+///
+/// The root is the same as the root of the [`MerkleTree`](super::MerkleTree) built from the
+/// pushed leaves with [`LeafPadding::DefaultDigest`](super::LeafPadding::DefaultDigest), whose
+/// height is only known once the last leaf is pushed. This is useful to commit to a large file
+/// or to a leaf stream that does not fit in memory.
+///
+/// Only the rightmost completed left child of every level is kept, so memory is `O(height)`, and
+/// pushing `n` leaves costs `n - 1` hashes. The [`Path`]s of leaves whose indices are declared
+/// with [`MerkleRootBuilder::with_tracked_indices`] are computed in the same pass, at the cost of
+/// `O(height)` memory per tracked leaf.
+#[derive(Derivative)]
+#[derivative(Clone(bound = "P: Config"))]
+pub struct MerkleRootBuilder<P: Config> {
+    /// The digest of the last leaf pushed at an even position, until its sibling is pushed.
+    frontier_leaf: Option<P::LeafDigest>,
+    /// `frontier[i]` is the last node computed at an even position, `i + 1` levels above the
+    /// leaves, until its sibling is computed.
+    frontier: Vec<Option<P::InnerDigest>>,
+    /// The authentication paths of tracked leaves, keyed by leaf index. The authentication paths
+    /// are ordered from bottom to top until the builder is finished.
+    witnesses: BTreeMap<usize, Path<P>>,
+    /// The number of pushed leaves
+    num_leaves: usize,
+    /// Store the inner hash parameters
+    two_to_one_hash_param: TwoToOneParam<P>,
+    /// Store the leaf hash parameters
+    leaf_hash_param: LeafParam<P>,
+}
+
+impl<P: Config> MerkleRootBuilder<P> {
+    /// Returns a builder to which no leaf has been pushed yet.
+    pub fn new(leaf_hash_param: &LeafParam<P>, two_to_one_hash_param: &TwoToOneParam<P>) -> Self {
+        MerkleRootBuilder {
+            frontier_leaf: None,
+            frontier: Vec::new(),
+            witnesses: BTreeMap::new(),
+            num_leaves: 0,
+            two_to_one_hash_param: two_to_one_hash_param.clone(),
+            leaf_hash_param: leaf_hash_param.clone(),
+        }
+    }
+
+    /// Declares the indices of the leaves whose [`Path`] should be returned by
+    /// [`MerkleRootBuilder::finish`].
+    ///
+    /// Panics if a leaf has already been pushed.
+    pub fn with_tracked_indices(mut self, indices: impl IntoIterator<Item = usize>) -> Self {
+        assert_eq!(
+            self.num_leaves, 0,
+            "indices should be tracked before pushing leaves"
+        );
+        self.witnesses.extend(indices.into_iter().map(|index| {
+            let path = Path {
+                leaf_index: index,
+                ..Default::default()
+            };
+            (index, path)
+        }));
+        self
+    }
+
+    /// Returns the number of pushed leaves.
+    pub fn num_leaves(&self) -> usize {
+        self.num_leaves
+    }
+
+    /// Push `leaf` after the leaves pushed so far.
+    pub fn push(&mut self, leaf: &P::Leaf) -> Result<(), crate::Error> {
+        let leaf_digest = P::LeafHash::evaluate(&self.leaf_hash_param, leaf)?;
+        self.push_leaf_digest(leaf_digest)
+    }
+
+    /// Push every leaf of `leaves`, hashing `BUILD_CHUNK_SIZE` leaves at a time.
+    pub fn extend<L: AsRef<P::Leaf> + Send>(
+        &mut self,
+        leaves: impl IntoIterator<Item = L>,
+    ) -> Result<(), crate::Error> {
+        let mut leaves = leaves.into_iter();
+        loop {
+            let chunk: Vec<L> = leaves.by_ref().take(BUILD_CHUNK_SIZE).collect();
+            if chunk.is_empty() {
+                return Ok(());
+            }
+            let leaf_digests = cfg_into_iter!(chunk)
+                .map(|input| P::LeafHash::evaluate(&self.leaf_hash_param, input.as_ref()))
+                .collect::<Result<Vec<_>, _>>()?;
+            for leaf_digest in leaf_digests {
+                self.push_leaf_digest(leaf_digest)?;
+            }
+        }
+    }
+
+    /// Push a leaf whose digest is `leaf_digest` after the leaves pushed so far.
+    pub fn push_leaf_digest(&mut self, leaf_digest: P::LeafDigest) -> Result<(), crate::Error> {
+        let index = self.num_leaves;
+        self.num_leaves += 1;
+        if index & 1 == 0 {
+            self.frontier_leaf = Some(leaf_digest);
+            return Ok(());
+        }
+        let left = self
+            .frontier_leaf
+            .take()
+            .expect("a leaf at an odd position has a left sibling");
+        let mut node = self.hash_leaves(index - 1, left, leaf_digest)?;
+
+        // carry the new node up while it is a right child
+        let mut position = index >> 1;
+        for level in 0.. {
+            if level == self.frontier.len() {
+                self.frontier.push(None);
+            }
+            if position & 1 == 0 {
+                self.frontier[level] = Some(node);
+                break;
+            }
+            let left = self.frontier[level]
+                .take()
+                .expect("a node at an odd position has a left sibling");
+            node = self.hash_nodes(level + 1, position - 1, left, node)?;
+            position >>= 1;
+        }
+        Ok(())
+    }
+
+    /// Returns the root of the tree, padding the pushed leaves with `P::LeafDigest::default()` to
+    /// the next power of two, and the paths of the tracked leaves ordered by index.
+    ///
+    /// Panics if no leaf was pushed, or if a tracked index is not less than the number of pushed
+    /// leaves.
+    pub fn finish(mut self) -> Result<(P::InnerDigest, Vec<Path<P>>), crate::Error> {
+        assert!(self.num_leaves > 0, "no leaf was pushed");
+        assert!(
+            self.witnesses
+                .keys()
+                .next_back()
+                .map_or(true, |&index| index < self.num_leaves),
+            "tracked index out of range"
+        );
+        let height = tree_height(self.num_leaves.next_power_of_two().max(2));
+        let empty_hashes = empty_hashes::<P>(&self.two_to_one_hash_param, height)?;
+        let last = self.num_leaves - 1;
+
+        // hash the pending nodes of the frontier with the empty subtrees on their right, from the
+        // bottom to the root
+        let mut carry = match self.frontier_leaf.take() {
+            Some(left) => Some(self.hash_leaves(last, left, P::LeafDigest::default())?),
+            None => None,
+        };
+        for level in 1..height - 1 {
+            let position = last >> level;
+            let pending = self.frontier.get_mut(level - 1).and_then(Option::take);
+            carry = match (pending, carry) {
+                (Some(left), Some(right)) => {
+                    Some(self.hash_nodes(level, position - 1, left, right)?)
+                }
+                (Some(left), None) | (None, Some(left)) => {
+                    let right = empty_hashes[level - 1].clone();
+                    Some(self.hash_nodes(level, position, left, right)?)
+                }
+                (None, None) => None,
+            };
+        }
+        // if the number of leaves is a power of two, the root was computed by the last push
+        let root = carry
+            .or_else(|| self.frontier.get_mut(height - 2).and_then(Option::take))
+            .expect("the root should be computed");
+
+        let paths = self
+            .witnesses
+            .into_values()
+            .map(|mut path| {
+                path.auth_path.reverse();
+                path
+            })
+            .collect();
+        Ok((root, paths))
+    }
+
+    /// Hashes the leaves at `left_index` and `left_index + 1`, recording them in the paths of
+    /// the tracked leaves.
+    fn hash_leaves(
+        &mut self,
+        left_index: usize,
+        left: P::LeafDigest,
+        right: P::LeafDigest,
+    ) -> Result<P::InnerDigest, crate::Error> {
+        if let Some(path) = self.witnesses.get_mut(&left_index) {
+            path.leaf_sibling_hash = right.clone();
+        }
+        if let Some(path) = self.witnesses.get_mut(&(left_index + 1)) {
+            path.leaf_sibling_hash = left.clone();
+        }
+        P::TwoToOneHash::evaluate(
+            &self.two_to_one_hash_param,
+            P::LeafInnerDigestConverter::convert(left)?,
+            P::LeafInnerDigestConverter::convert(right)?,
+        )
+    }
+
+    /// Hashes the nodes at `left_position` and `left_position + 1` of the level `height` levels
+    /// above the leaves, recording them in the paths of the tracked leaves.
+    fn hash_nodes(
+        &mut self,
+        height: usize,
+        left_position: usize,
+        left: P::InnerDigest,
+        right: P::InnerDigest,
+    ) -> Result<P::InnerDigest, crate::Error> {
+        let start = left_position << height;
+        let middle = (left_position + 1) << height;
+        let end = (left_position + 2) << height;
+        for path in self
+            .witnesses
+            .range_mut(start..middle)
+            .map(|(_, path)| path)
+        {
+            path.auth_path.push(right.clone());
+        }
+        for path in self.witnesses.range_mut(middle..end).map(|(_, path)| path) {
+            path.auth_path.push(left.clone());
+        }
+        P::TwoToOneHash::compress(&self.two_to_one_hash_param, left, right)
+    }
+}
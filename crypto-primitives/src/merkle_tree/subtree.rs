@@ -0,0 +1,214 @@
+use crate::{
+    crh::TwoToOneCRHScheme,
+    merkle_tree::{
+        select_left_right_child, Config, MemoryNodeStore, MerkleTree, NodeStore, TwoToOneParam,
+        BUILD_CHUNK_SIZE,
+    },
+};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+
+/// Stores the path from an inner node of a merkle tree to the root, proving that a subtree root
+/// is at a given position of the tree.
+///
+/// A subtree of height `h` of a tree of height `height` is rooted at level `height - h`, where
+/// the level of a node is its distance to the root. Sharded provers can each build the subtree
+/// covering their leaves with [`MerkleTree::subtree`], and prove the position of its root in the
+/// global tree with a `SubtreePath` obtained from [`MerkleTree::generate_subtree_proof`].
+/// ```tree_diagram
+///         [A]
+///        /   \
+///      [B]    C
+///     / \    / \
+///    D  [E] F   H
+///       / \
+///      .. ..
+/// ```
+///  Suppose we want to prove that E is the subtree root at index 1 of level 2, then `auth_path`
+///  is `[C,D]`.
+#[derive(Derivative, CanonicalSerialize, CanonicalDeserialize)]
+#[derivative(
+    PartialEq(bound = "P: Config"),
+    Clone(bound = "P: Config"),
+    Debug(bound = "P: Config"),
+    Default(bound = "P: Config")
+)]
+pub struct SubtreePath<P: Config> {
+    /// The siblings of the nodes on the path, from the level below the root to the level of the
+    /// subtree root.
+    pub auth_path: Vec<P::InnerDigest>,
+    /// The index of the subtree root in its level.
+    pub index: usize,
+}
+
+impl<P: Config> SubtreePath<P> {
+    /// Returns the level of the subtree root, that is its distance to the root.
+    pub fn level(&self) -> usize {
+        self.auth_path.len()
+    }
+
+    /// The position of the on-path nodes, from top to bottom. `position[i]` is false iff the
+    /// `i`th on-path node below the root is a left child.
+    #[allow(unused)] // this function is actually used when r1cs feature is on
+    pub(super) fn position_list(&'_ self) -> impl '_ + Iterator<Item = bool> {
+        (0..self.level())
+            .map(move |i| (self.index >> i) & 1 != 0)
+            .rev()
+    }
+
+    /// Verify that `subtree_root` is the node at `self.index` of the level `self.level()` of the
+    /// merkle tree whose root is `root_hash`.
+    pub fn verify(
+        &self,
+        two_to_one_params: &TwoToOneParam<P>,
+        root_hash: &P::InnerDigest,
+        subtree_root: &P::InnerDigest,
+    ) -> Result<bool, crate::Error> {
+        // the index should fit in the level of the subtree root
+        if self.index.checked_shr(self.level() as u32).unwrap_or(0) != 0 {
+            return Ok(false);
+        }
+        let mut curr_path_node = subtree_root.clone();
+        let mut index = self.index;
+        for sibling in self.auth_path.iter().rev() {
+            let (left, right) = select_left_right_child(index, &curr_path_node, sibling)?;
+            curr_path_node = P::TwoToOneHash::compress(two_to_one_params, &left, &right)?;
+            index >>= 1;
+        }
+
+        Ok(&curr_path_node == root_hash)
+    }
+}
+
+/// Copies the nodes of the subtree of height `height` rooted at `from_index` of `from_level` in
+/// `from`, to the subtree rooted at `to_index` of `to_level` in `to`, `BUILD_CHUNK_SIZE` nodes at
+/// a time.
+fn copy_nodes<P: Config>(
+    from: &impl NodeStore<P>,
+    from_level: usize,
+    from_index: usize,
+    to: &mut impl NodeStore<P>,
+    to_level: usize,
+    to_index: usize,
+    height: usize,
+) -> Result<(), crate::Error> {
+    for level in 0..height - 1 {
+        let level_size = 1 << level;
+        for start in (0..level_size).step_by(BUILD_CHUNK_SIZE) {
+            let count = BUILD_CHUNK_SIZE.min(level_size - start);
+            let nodes = from.inners(from_level + level, (from_index << level) + start, count)?;
+            to.put_inners(to_level + level, (to_index << level) + start, nodes)?;
+        }
+    }
+    let num_leaves = 1 << (height - 1);
+    for start in (0..num_leaves).step_by(BUILD_CHUNK_SIZE) {
+        let count = BUILD_CHUNK_SIZE.min(num_leaves - start);
+        let leaves = from.leaves((from_index << (height - 1)) + start, count)?;
+        to.put_leaves((to_index << (height - 1)) + start, leaves)?;
+    }
+    Ok(())
+}
+
+impl<P: Config, S: NodeStore<P>> MerkleTree<P, S> {
+    /// Returns the subtree rooted at the node at `index` of `level`, as a merkle tree of height
+    /// `self.height() - level` with the same parameters and padding.
+    ///
+    /// The leaves of the subtree are the leaves of this tree below that node, and its number of
+    /// leaves counts those among the first `self.num_leaves()`.
+    ///
+    /// Panics if the node is a leaf or is out of range, or if all the leaves below it are
+    /// padding.
+    pub fn subtree(&self, level: usize, index: usize) -> Result<MerkleTree<P>, crate::Error> {
+        let tree_height = self.height();
+        assert!(
+            level < tree_height - 1,
+            "subtree root should be a non-leaf node"
+        );
+        assert!(index < 1 << level, "index out of range");
+        let height = tree_height - level;
+        let start = index << (height - 1);
+        assert!(
+            start < self.num_leaves(),
+            "subtree should contain at least one leaf"
+        );
+
+        let mut store = MemoryNodeStore::new(height);
+        copy_nodes(&self.store, level, index, &mut store, 0, 0, height)?;
+        store.set_num_leaves((self.num_leaves() - start).min(1 << (height - 1)))?;
+        Ok(MerkleTree {
+            root: store.inner(0, 0)?,
+            store,
+            leaf_hash_param: self.leaf_hash_param.clone(),
+            two_to_one_hash_param: self.two_to_one_hash_param.clone(),
+            padding: self.padding.clone(),
+            root_history: None,
+        })
+    }
+
+    /// Replaces the subtree rooted at the node at `index` of `level` by `subtree`, and rehashes
+    /// the nodes on the path from that node to the root.
+    ///
+    /// This lets a coordinator assemble the global tree from the subtrees built by sharded
+    /// provers. Only the nodes of `subtree` are used: its parameters and padding are ignored.
+    ///
+    /// Panics if the height of `subtree` is not `self.height() - level`, if the node is out of
+    /// range, or if one of the leaves below it cannot be updated.
+    pub fn graft_subtree<T: NodeStore<P>>(
+        &mut self,
+        level: usize,
+        index: usize,
+        subtree: &MerkleTree<P, T>,
+    ) -> Result<(), crate::Error> {
+        let tree_height = self.height();
+        let height = subtree.height();
+        assert_eq!(
+            level + height,
+            tree_height,
+            "the height of the subtree does not match its level"
+        );
+        assert!(index < 1 << level, "index out of range");
+        self.check_index(((index + 1) << (height - 1)) - 1);
+
+        copy_nodes(&subtree.store, 0, 0, &mut self.store, level, index, height)?;
+
+        // rehash the ancestors of the subtree root, along with the copies required by the padding
+        let mut node = subtree.root();
+        let mut position = index;
+        for level in (0..=level).rev() {
+            let height_above_leaves = tree_height - 1 - level;
+            if self.is_duplicate(height_above_leaves, position + 1) {
+                self.store.put_inner(level, position + 1, node.clone())?;
+            }
+            self.store.put_inner(level, position, node.clone())?;
+            if level == 0 {
+                break;
+            }
+            let sibling = self.store.inner(level, position ^ 1)?;
+            let (left, right) = select_left_right_child(position, &node, &sibling)?;
+            node = P::TwoToOneHash::compress(&self.two_to_one_hash_param, &left, &right)?;
+            position >>= 1;
+        }
+        self.set_root(node);
+        self.store.flush()
+    }
+
+    /// Returns the path from the node at `index` of `level` to the root.
+    ///
+    /// Panics if the node is a leaf or is out of range.
+    pub fn generate_subtree_proof(
+        &self,
+        level: usize,
+        index: usize,
+    ) -> Result<SubtreePath<P>, crate::Error> {
+        assert!(
+            level < self.height() - 1,
+            "subtree root should be a non-leaf node"
+        );
+        assert!(index < 1 << level, "index out of range");
+        let auth_path = (1..=level)
+            .map(|l| self.store.inner(l, (index >> (level - l)) ^ 1))
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(SubtreePath { auth_path, index })
+    }
+}
@@ -237,12 +237,20 @@ mod byte_mt_tests {
 mod field_mt_tests {
     use crate::crh::{poseidon, CRHSchemeGadget, TwoToOneCRHSchemeGadget};
     use crate::merkle_tree::{
-        constraints::{ConfigGadget, PathVar},
+        constraints::{
+            BatchUpdateVar, ConfigGadget, MultiPathVar, NonMembershipProofVar, PathVar,
+            RootHistoryVar, SubtreePathVar, VariableHeightPathVar,
+        },
         tests::test_utils::poseidon_parameters,
-        Config, IdentityDigestConverter, MerkleTree,
+        Config, IdentityDigestConverter, MerkleTree, RootHistory, SparseMerkleTree,
     };
     use ark_r1cs_std::{
-        alloc::AllocVar, convert::ToBitsGadget, fields::fp::FpVar, uint32::UInt32, GR1CSVar,
+        alloc::{AllocVar, AllocationMode},
+        boolean::Boolean,
+        convert::ToBitsGadget,
+        fields::fp::FpVar,
+        uint32::UInt32,
+        GR1CSVar,
     };
     use ark_relations::gr1cs::ConstraintSystem;
     use ark_std::{test_rng, One, UniformRand};
@@ -473,4 +481,1174 @@ mod field_mt_tests {
 
         merkle_tree_test(&leaves, true, Some((3, rand_leaves())))
     }
+
+    #[test]
+    fn non_membership_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+
+        let mut tree =
+            SparseMerkleTree::<FieldMTConfig>::blank(&leaf_crh_params, &two_to_one_params, 80)
+                .unwrap();
+        let keys: Vec<Vec<F>> = (0..8).map(|_| vec![F::rand(&mut rng)]).collect();
+        for key in &keys {
+            assert!(tree.insert_key(key, key).unwrap());
+        }
+        let root = tree.root();
+
+        for key in &keys {
+            assert!(tree.contains_key(key).unwrap());
+            assert!(tree.generate_non_membership_proof(key).unwrap().is_none());
+        }
+
+        let absent_key = vec![F::rand(&mut rng)];
+        let proof = tree
+            .generate_non_membership_proof(&absent_key)
+            .unwrap()
+            .unwrap();
+        assert!(proof
+            .verify(&leaf_crh_params, &two_to_one_params, &root, &absent_key)
+            .unwrap());
+        // the proof does not transfer to a key that is present
+        assert!(!proof
+            .verify(&leaf_crh_params, &two_to_one_params, &root, &keys[0])
+            .unwrap());
+
+        for (key, expected) in [(&absent_key, true), (&keys[0], false)] {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let root_var = FpVar::new_input(cs.clone(), || Ok(root)).unwrap();
+            let leaf_crh_params_var = <HG as CRHSchemeGadget<H, _>>::ParametersVar::new_constant(
+                ark_relations::ns!(cs, "leaf_crh_params"),
+                &leaf_crh_params,
+            )
+            .unwrap();
+            let two_to_one_crh_params_var =
+                <TwoToOneHG as TwoToOneCRHSchemeGadget<TwoToOneH, _>>::ParametersVar::new_constant(
+                    ark_relations::ns!(cs, "two_to_one_params"),
+                    &two_to_one_params,
+                )
+                .unwrap();
+            let key_var: Vec<_> = key
+                .iter()
+                .map(|x| FpVar::new_input(cs.clone(), || Ok(*x)).unwrap())
+                .collect();
+            let proof_var =
+                NonMembershipProofVar::<FieldMTConfig, F, FieldMTConfigVar>::new_witness(
+                    ark_relations::ns!(cs, "proof"),
+                    || Ok(&proof),
+                )
+                .unwrap();
+
+            let result = proof_var
+                .verify(
+                    &leaf_crh_params_var,
+                    &two_to_one_crh_params_var,
+                    &root_var,
+                    &key_var,
+                )
+                .unwrap();
+            assert_eq!(result.value().unwrap(), expected);
+            assert!(cs.is_satisfied().unwrap());
+        }
+
+        // the opening of `keys[0]` only verifies under `keys[0]`
+        let key_proof = tree.generate_key_proof(&keys[0]).unwrap();
+        for (key, expected) in [(&keys[0], true), (&keys[1], false)] {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let root_var = FpVar::new_input(cs.clone(), || Ok(root)).unwrap();
+            let leaf_crh_params_var = <HG as CRHSchemeGadget<H, _>>::ParametersVar::new_constant(
+                ark_relations::ns!(cs, "leaf_crh_params"),
+                &leaf_crh_params,
+            )
+            .unwrap();
+            let two_to_one_crh_params_var =
+                <TwoToOneHG as TwoToOneCRHSchemeGadget<TwoToOneH, _>>::ParametersVar::new_constant(
+                    ark_relations::ns!(cs, "two_to_one_params"),
+                    &two_to_one_params,
+                )
+                .unwrap();
+            let key_var: Vec<_> = key
+                .iter()
+                .map(|x| FpVar::new_input(cs.clone(), || Ok(*x)).unwrap())
+                .collect();
+            let value_var: Vec<_> = keys[0]
+                .iter()
+                .map(|x| FpVar::new_witness(cs.clone(), || Ok(*x)).unwrap())
+                .collect();
+            let path_var = PathVar::<FieldMTConfig, F, FieldMTConfigVar>::new_witness(
+                ark_relations::ns!(cs, "path"),
+                || Ok(&key_proof),
+            )
+            .unwrap();
+
+            let result = path_var
+                .verify_key_membership(
+                    &leaf_crh_params_var,
+                    &two_to_one_crh_params_var,
+                    &root_var,
+                    &key_var,
+                    &value_var,
+                )
+                .unwrap();
+            assert_eq!(result.value().unwrap(), expected);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn variable_height_path_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+        let max_height = 6;
+
+        let mut num_constraints = None;
+        for height in 2..=max_height {
+            let leaves: Vec<Vec<F>> = (0..1 << (height - 1))
+                .map(|_| vec![F::rand(&mut rng)])
+                .collect();
+            let tree = FieldMT::new(&leaf_crh_params, &two_to_one_params, &leaves).unwrap();
+            for (i, leaf) in leaves.iter().enumerate().step_by(3) {
+                for use_bad_root in [false, true] {
+                    let cs = ConstraintSystem::<F>::new_ref();
+                    let proof = tree.generate_proof(i).unwrap();
+                    let root = FpVar::new_witness(cs.clone(), || {
+                        if use_bad_root {
+                            Ok(tree.root() + F::one())
+                        } else {
+                            Ok(tree.root())
+                        }
+                    })
+                    .unwrap();
+                    let leaf_crh_params_var =
+                        <HG as CRHSchemeGadget<H, _>>::ParametersVar::new_constant(
+                            ark_relations::ns!(cs, "leaf_crh_params"),
+                            &leaf_crh_params,
+                        )
+                        .unwrap();
+                    let two_to_one_crh_params_var = <TwoToOneHG as TwoToOneCRHSchemeGadget<
+                        TwoToOneH,
+                        _,
+                    >>::ParametersVar::new_constant(
+                        ark_relations::ns!(cs, "two_to_one_params"),
+                        &two_to_one_params,
+                    )
+                    .unwrap();
+                    let leaf_g: Vec<_> = leaf
+                        .iter()
+                        .map(|x| FpVar::new_input(cs.clone(), || Ok(*x)).unwrap())
+                        .collect();
+                    let height_var =
+                        FpVar::new_input(cs.clone(), || Ok(F::from(height as u64))).unwrap();
+                    let path = VariableHeightPathVar::<_, _, FieldMTConfigVar>::new_variable_with_max_height(
+                        ark_relations::ns!(cs, "path"),
+                        max_height,
+                        &height_var,
+                        || Ok(&proof),
+                        AllocationMode::Witness,
+                    )
+                    .unwrap();
+                    assert_eq!(path.max_height(), max_height);
+
+                    let result = path
+                        .verify_membership(
+                            &leaf_crh_params_var,
+                            &two_to_one_crh_params_var,
+                            &root,
+                            &leaf_g,
+                        )
+                        .unwrap();
+                    assert_eq!(result.value().unwrap(), !use_bad_root);
+                    assert_eq!(path.height().value().unwrap(), F::from(height as u64));
+                    let position = path.get_leaf_position();
+                    assert_eq!(position.len(), max_height - 1);
+                    let index = position
+                        .iter()
+                        .rev()
+                        .fold(0, |index, bit| 2 * index + bit.value().unwrap() as usize);
+                    assert_eq!(index, i);
+                    assert!(cs.is_satisfied().unwrap());
+
+                    // the circuit does not depend on the height of the tree
+                    let count = cs.num_constraints();
+                    assert_eq!(*num_constraints.get_or_insert(count), count);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn variable_height_path_wrong_height_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+        let max_height = 5;
+        let leaves: Vec<Vec<F>> = (0..4).map(|_| vec![F::rand(&mut rng)]).collect();
+        let tree = FieldMT::new(&leaf_crh_params, &two_to_one_params, &leaves).unwrap();
+        let proof = tree.generate_proof(2).unwrap();
+
+        for (height, satisfied) in [(3u64, true), (2, false), (4, false), (5, false), (6, false)] {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let leaf_crh_params_var = <HG as CRHSchemeGadget<H, _>>::ParametersVar::new_constant(
+                ark_relations::ns!(cs, "leaf_crh_params"),
+                &leaf_crh_params,
+            )
+            .unwrap();
+            let two_to_one_crh_params_var =
+                <TwoToOneHG as TwoToOneCRHSchemeGadget<TwoToOneH, _>>::ParametersVar::new_constant(
+                    ark_relations::ns!(cs, "two_to_one_params"),
+                    &two_to_one_params,
+                )
+                .unwrap();
+            let root = FpVar::new_input(cs.clone(), || Ok(tree.root())).unwrap();
+            let leaf_g = vec![FpVar::new_input(cs.clone(), || Ok(leaves[2][0])).unwrap()];
+            let height_var = FpVar::new_input(cs.clone(), || Ok(F::from(height))).unwrap();
+            let path =
+                VariableHeightPathVar::<_, _, FieldMTConfigVar>::new_variable_with_max_height(
+                    ark_relations::ns!(cs, "path"),
+                    max_height,
+                    &height_var,
+                    || Ok(&proof),
+                    AllocationMode::Witness,
+                )
+                .unwrap();
+            let result = path
+                .verify_membership(
+                    &leaf_crh_params_var,
+                    &two_to_one_crh_params_var,
+                    &root,
+                    &leaf_g,
+                )
+                .unwrap();
+            assert_eq!(
+                result.value().unwrap() && cs.is_satisfied().unwrap(),
+                satisfied
+            );
+        }
+    }
+
+    #[test]
+    fn variable_height_path_constant_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+        let leaves: Vec<Vec<F>> = (0..4).map(|_| vec![F::rand(&mut rng)]).collect();
+        let tree = FieldMT::new(&leaf_crh_params, &two_to_one_params, &leaves).unwrap();
+        let proof = tree.generate_proof(1).unwrap();
+
+        let cs = ConstraintSystem::<F>::new_ref();
+        let leaf_crh_params_var = <HG as CRHSchemeGadget<H, _>>::ParametersVar::new_constant(
+            ark_relations::ns!(cs, "leaf_crh_params"),
+            &leaf_crh_params,
+        )
+        .unwrap();
+        let two_to_one_crh_params_var =
+            <TwoToOneHG as TwoToOneCRHSchemeGadget<TwoToOneH, _>>::ParametersVar::new_constant(
+                ark_relations::ns!(cs, "two_to_one_params"),
+                &two_to_one_params,
+            )
+            .unwrap();
+        let root = FpVar::new_constant(cs.clone(), tree.root()).unwrap();
+        let leaf_g = vec![FpVar::new_constant(cs.clone(), leaves[1][0]).unwrap()];
+        let height_var = FpVar::new_constant(cs.clone(), F::from(3u64)).unwrap();
+        let path = VariableHeightPathVar::<_, _, FieldMTConfigVar>::new_variable_with_max_height(
+            ark_relations::ns!(cs, "path"),
+            5,
+            &height_var,
+            || Ok(&proof),
+            AllocationMode::Constant,
+        )
+        .unwrap();
+        let result = path
+            .verify_membership(
+                &leaf_crh_params_var,
+                &two_to_one_crh_params_var,
+                &root,
+                &leaf_g,
+            )
+            .unwrap();
+        assert!(result.value().unwrap());
+        assert!(result.is_constant());
+        assert_eq!(cs.num_constraints(), 0);
+        assert_eq!(cs.num_witness_variables(), 0);
+    }
+
+    #[test]
+    fn root_history_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+        let leaves: Vec<Vec<F>> = (0..8).map(|_| vec![F::rand(&mut rng)]).collect();
+        let mut tree = FieldMT::new(&leaf_crh_params, &two_to_one_params, &leaves)
+            .unwrap()
+            .with_root_history(4);
+        let old_root = tree.root();
+        let old_proof = tree.generate_proof(2).unwrap();
+        tree.update(5, &[F::rand(&mut rng)]).unwrap();
+
+        let mut unrelated = RootHistory::new(4);
+        (0..4).for_each(|_| unrelated.push(F::rand(&mut rng)));
+
+        // an empty history cannot be allocated
+        let cs = ConstraintSystem::<F>::new_ref();
+        assert!(
+            RootHistoryVar::<FieldMTConfig, F, FieldMTConfigVar>::new_input(
+                ark_relations::ns!(cs, "history"),
+                || Ok(RootHistory::new(4)),
+            )
+            .is_err()
+        );
+
+        // a history which is not full is padded to its capacity
+        let mut num_constraints = None;
+        for history in [tree.root_history().unwrap().clone(), unrelated] {
+            for use_old_root in [true, false] {
+                let cs = ConstraintSystem::<F>::new_ref();
+                let history_var = RootHistoryVar::<FieldMTConfig, F, FieldMTConfigVar>::new_input(
+                    ark_relations::ns!(cs, "history"),
+                    || Ok(&history),
+                )
+                .unwrap();
+                assert_eq!(history_var.roots().len(), 4);
+
+                let leaf_crh_params_var =
+                    <HG as CRHSchemeGadget<H, _>>::ParametersVar::new_constant(
+                        ark_relations::ns!(cs, "leaf_crh_params"),
+                        &leaf_crh_params,
+                    )
+                    .unwrap();
+                let two_to_one_crh_params_var = <TwoToOneHG as TwoToOneCRHSchemeGadget<
+                    TwoToOneH,
+                    _,
+                >>::ParametersVar::new_constant(
+                    ark_relations::ns!(cs, "two_to_one_params"),
+                    &two_to_one_params,
+                )
+                .unwrap();
+                let (proof, leaf) = if use_old_root {
+                    (old_proof.clone(), &leaves[2])
+                } else {
+                    (tree.generate_proof(3).unwrap(), &leaves[3])
+                };
+                let leaf_g: Vec<_> = leaf
+                    .iter()
+                    .map(|x| FpVar::new_witness(cs.clone(), || Ok(*x)).unwrap())
+                    .collect();
+                let path = PathVar::<_, _, FieldMTConfigVar>::new_witness(
+                    ark_relations::ns!(cs, "path"),
+                    || Ok(&proof),
+                )
+                .unwrap();
+                let root = path
+                    .calculate_root(&leaf_crh_params_var, &two_to_one_crh_params_var, &leaf_g)
+                    .unwrap();
+                let is_known = history_var.contains(&root).unwrap();
+                assert_eq!(
+                    is_known.value().unwrap(),
+                    history.contains(&root.value().unwrap())
+                );
+                assert!(cs.is_satisfied().unwrap());
+
+                let count = cs.num_constraints();
+                assert_eq!(*num_constraints.get_or_insert(count), count);
+            }
+        }
+        assert!(tree.is_known_root(&old_root));
+    }
+
+    #[test]
+    fn subtree_path_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+        let leaves: Vec<Vec<F>> = (0..16).map(|_| vec![F::rand(&mut rng)]).collect();
+        let tree = FieldMT::new(&leaf_crh_params, &two_to_one_params, &leaves).unwrap();
+
+        // a shard proves a leaf of its subtree, then the position of the subtree in the tree
+        let subtree = tree.subtree(2, 1).unwrap();
+        let leaf_proof = subtree.generate_proof(3).unwrap();
+        let subtree_proof = tree.generate_subtree_proof(2, 1).unwrap();
+        for use_bad_root in [false, true] {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let root = FpVar::new_input(ark_relations::ns!(cs, "root"), || {
+                if use_bad_root {
+                    Ok(tree.root() + F::one())
+                } else {
+                    Ok(tree.root())
+                }
+            })
+            .unwrap();
+            let leaf_crh_params_var = <HG as CRHSchemeGadget<H, _>>::ParametersVar::new_constant(
+                ark_relations::ns!(cs, "leaf_crh_params"),
+                &leaf_crh_params,
+            )
+            .unwrap();
+            let two_to_one_crh_params_var =
+                <TwoToOneHG as TwoToOneCRHSchemeGadget<TwoToOneH, _>>::ParametersVar::new_constant(
+                    ark_relations::ns!(cs, "two_to_one_params"),
+                    &two_to_one_params,
+                )
+                .unwrap();
+            let leaf_g: Vec<_> = leaves[7]
+                .iter()
+                .map(|x| FpVar::new_witness(cs.clone(), || Ok(*x)).unwrap())
+                .collect();
+            let path = PathVar::<_, _, FieldMTConfigVar>::new_witness(
+                ark_relations::ns!(cs, "path"),
+                || Ok(&leaf_proof),
+            )
+            .unwrap();
+            let subtree_path = SubtreePathVar::<_, _, FieldMTConfigVar>::new_witness(
+                ark_relations::ns!(cs, "subtree_path"),
+                || Ok(&subtree_proof),
+            )
+            .unwrap();
+            assert_eq!(subtree_path.level(), 2);
+            let position: Vec<_> = subtree_path
+                .get_subtree_position()
+                .iter()
+                .map(|bit| bit.value().unwrap())
+                .collect();
+            assert_eq!(position, vec![true, false]);
+
+            let subtree_root = path
+                .calculate_root(&leaf_crh_params_var, &two_to_one_crh_params_var, &leaf_g)
+                .unwrap();
+            assert_eq!(subtree_root.value().unwrap(), subtree.root());
+            let result = subtree_path
+                .verify_membership(&two_to_one_crh_params_var, &root, &subtree_root)
+                .unwrap();
+            assert_eq!(result.value().unwrap(), !use_bad_root);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn batch_update_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+        let mut leaves: Vec<Vec<F>> = (0..8).map(|_| vec![F::rand(&mut rng)]).collect();
+        let mut tree = FieldMT::new(&leaf_crh_params, &two_to_one_params, &leaves).unwrap();
+        let old_root = tree.root();
+
+        let updates: Vec<(usize, Vec<F>)> = [3, 4, 3]
+            .iter()
+            .map(|&i| (i, vec![F::rand(&mut rng)]))
+            .collect();
+        let mut old_leaves = Vec::new();
+        for (i, leaf) in &updates {
+            old_leaves.push(leaves[*i].clone());
+            leaves[*i] = leaf.clone();
+        }
+        let indices: Vec<_> = updates.iter().map(|(i, _)| *i).collect();
+        let new_leaves: Vec<_> = updates.iter().map(|(_, leaf)| leaf.clone()).collect();
+        let proof = tree.update_with_proof(&updates).unwrap();
+        let new_root = tree.root();
+
+        for use_bad_root in [false, true] {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let old_root_var =
+                FpVar::new_input(ark_relations::ns!(cs, "old_root"), || Ok(old_root)).unwrap();
+            let new_root_var = FpVar::new_input(ark_relations::ns!(cs, "new_root"), || {
+                if use_bad_root {
+                    Ok(new_root + F::one())
+                } else {
+                    Ok(new_root)
+                }
+            })
+            .unwrap();
+            let leaf_crh_params_var = <HG as CRHSchemeGadget<H, _>>::ParametersVar::new_constant(
+                ark_relations::ns!(cs, "leaf_crh_params"),
+                &leaf_crh_params,
+            )
+            .unwrap();
+            let two_to_one_crh_params_var =
+                <TwoToOneHG as TwoToOneCRHSchemeGadget<TwoToOneH, _>>::ParametersVar::new_constant(
+                    ark_relations::ns!(cs, "two_to_one_params"),
+                    &two_to_one_params,
+                )
+                .unwrap();
+            let allocate = |indices: &[usize], old_leaves: &[Vec<F>]| {
+                indices
+                    .iter()
+                    .zip(old_leaves)
+                    .zip(&new_leaves)
+                    .map(|((i, old_leaf), new_leaf)| {
+                        (
+                            FpVar::new_input(cs.clone(), || Ok(F::from(*i as u64))).unwrap(),
+                            Vec::new_witness(cs.clone(), || Ok(&old_leaf[..])).unwrap(),
+                            Vec::new_witness(cs.clone(), || Ok(&new_leaf[..])).unwrap(),
+                        )
+                    })
+                    .collect::<Vec<_>>()
+            };
+            let updates_var = allocate(&indices, &old_leaves);
+            let batch = BatchUpdateVar::<_, _, FieldMTConfigVar>::new_witness(
+                ark_relations::ns!(cs, "batch"),
+                || Ok(&proof),
+            )
+            .unwrap();
+            let positions: Vec<u64> = batch
+                .get_leaf_positions()
+                .iter()
+                .map(|bits| {
+                    bits.iter()
+                        .rev()
+                        .fold(0, |acc, bit| 2 * acc + bit.value().unwrap() as u64)
+                })
+                .collect();
+            assert_eq!(positions, vec![3, 4, 3]);
+
+            let is_valid = batch
+                .update_and_check(
+                    &leaf_crh_params_var,
+                    &two_to_one_crh_params_var,
+                    &old_root_var,
+                    &new_root_var,
+                    &updates_var,
+                )
+                .unwrap();
+            assert_eq!(is_valid.value().unwrap(), !use_bad_root);
+
+            // the paths are bound to the indices
+            let is_valid = batch
+                .update_and_check(
+                    &leaf_crh_params_var,
+                    &two_to_one_crh_params_var,
+                    &old_root_var,
+                    &new_root_var,
+                    &allocate(&[4, 3, 3], &old_leaves),
+                )
+                .unwrap();
+            assert!(!is_valid.value().unwrap());
+
+            let roots = batch
+                .update_leaves(
+                    &leaf_crh_params_var,
+                    &two_to_one_crh_params_var,
+                    &old_root_var,
+                    &updates_var,
+                )
+                .unwrap();
+            assert_eq!(roots.len(), updates.len());
+            assert_eq!(roots.last().unwrap().value().unwrap(), new_root);
+            assert!(cs.is_satisfied().unwrap());
+        }
+
+        // replaying the updates against a wrong old leaf is unsatisfiable
+        let cs = ConstraintSystem::<F>::new_ref();
+        let old_root_var = FpVar::new_input(cs.clone(), || Ok(old_root)).unwrap();
+        let params_var =
+            <TwoToOneHG as TwoToOneCRHSchemeGadget<TwoToOneH, _>>::ParametersVar::new_constant(
+                cs.clone(),
+                &two_to_one_params,
+            )
+            .unwrap();
+        let mut wrong_leaves = old_leaves.clone();
+        wrong_leaves[2][0] += F::one();
+        let updates_var: Vec<_> = indices
+            .iter()
+            .zip(&wrong_leaves)
+            .zip(&new_leaves)
+            .map(|((i, old_leaf), new_leaf)| {
+                (
+                    FpVar::new_input(cs.clone(), || Ok(F::from(*i as u64))).unwrap(),
+                    Vec::new_witness(cs.clone(), || Ok(&old_leaf[..])).unwrap(),
+                    Vec::new_witness(cs.clone(), || Ok(&new_leaf[..])).unwrap(),
+                )
+            })
+            .collect();
+        let batch =
+            BatchUpdateVar::<_, _, FieldMTConfigVar>::new_witness(cs.clone(), || Ok(&proof))
+                .unwrap();
+        batch
+            .update_leaves(&params_var, &params_var, &old_root_var, &updates_var)
+            .unwrap();
+        assert!(!cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn multi_path_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+        let leaves: Vec<Vec<F>> = (0..16)
+            .map(|_| vec![F::rand(&mut rng), F::rand(&mut rng)])
+            .collect();
+        let tree = FieldMT::new(&leaf_crh_params, &two_to_one_params, &leaves).unwrap();
+        let root = tree.root();
+        let indexes = [0, 1, 5, 6, 15];
+        let multi_proof = tree.generate_multi_proof(indexes).unwrap();
+
+        let num_constraints = |multi: bool, opened_leaves: &[Vec<F>]| {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let root_var = FpVar::new_input(cs.clone(), || Ok(root)).unwrap();
+            let leaf_crh_params_var = <HG as CRHSchemeGadget<H, _>>::ParametersVar::new_constant(
+                ark_relations::ns!(cs, "leaf_crh_params"),
+                &leaf_crh_params,
+            )
+            .unwrap();
+            let two_to_one_crh_params_var =
+                <TwoToOneHG as TwoToOneCRHSchemeGadget<TwoToOneH, _>>::ParametersVar::new_constant(
+                    ark_relations::ns!(cs, "two_to_one_params"),
+                    &two_to_one_params,
+                )
+                .unwrap();
+            let leaf_vars: Vec<Vec<FpVar<F>>> = opened_leaves
+                .iter()
+                .map(|leaf| Vec::new_witness(cs.clone(), || Ok(leaf.clone())).unwrap())
+                .collect();
+
+            let result = if multi {
+                let multi_proof_var =
+                    MultiPathVar::<FieldMTConfig, F, FieldMTConfigVar>::new_witness(
+                        ark_relations::ns!(cs, "multi_proof"),
+                        || Ok(&multi_proof),
+                    )
+                    .unwrap();
+                assert_eq!(multi_proof_var.leaf_indexes(), &indexes[..]);
+                multi_proof_var
+                    .verify_membership(
+                        &leaf_crh_params_var,
+                        &two_to_one_crh_params_var,
+                        &root_var,
+                        leaf_vars.iter().map(|leaf| leaf.as_slice()),
+                    )
+                    .unwrap()
+            } else {
+                let mut result = Boolean::TRUE;
+                for (index, leaf) in indexes.iter().zip(&leaf_vars) {
+                    let proof = tree.generate_proof(*index).unwrap();
+                    let proof_var = PathVar::<FieldMTConfig, F, FieldMTConfigVar>::new_witness(
+                        ark_relations::ns!(cs, "proof"),
+                        || Ok(proof),
+                    )
+                    .unwrap();
+                    result &= proof_var
+                        .verify_membership(
+                            &leaf_crh_params_var,
+                            &two_to_one_crh_params_var,
+                            &root_var,
+                            leaf,
+                        )
+                        .unwrap();
+                }
+                result
+            };
+            (result.value().unwrap(), cs.num_constraints())
+        };
+
+        let opened_leaves: Vec<_> = indexes.iter().map(|i| leaves[*i].clone()).collect();
+        let (valid, multi_constraints) = num_constraints(true, &opened_leaves);
+        assert!(valid);
+        let (valid, single_constraints) = num_constraints(false, &opened_leaves);
+        assert!(valid);
+        assert!(multi_constraints < single_constraints);
+
+        let mut bad_leaves = opened_leaves.clone();
+        bad_leaves[2] = leaves[2].clone();
+        assert!(!num_constraints(true, &bad_leaves).0);
+    }
+}
+
+mod kary_mt_tests {
+    use crate::crh::{poseidon, CRHSchemeGadget, NToOneCRHSchemeGadget};
+    use crate::merkle_tree::{
+        constraints::{KAryConfigGadget, KAryPathVar},
+        tests::test_utils::poseidon_parameters,
+        IdentityDigestConverter, KAryConfig, KAryMerkleTree,
+    };
+    use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar, GR1CSVar};
+    use ark_relations::gr1cs::ConstraintSystem;
+    use ark_std::{test_rng, One, UniformRand};
+
+    type F = ark_ed_on_bls12_381::Fr;
+    type H = poseidon::CRH<F>;
+    type HG = poseidon::constraints::CRHGadget<F>;
+    type NToOneH<const ARITY: usize> = poseidon::NToOneCRH<F, ARITY>;
+    type NToOneHG<const ARITY: usize> = poseidon::constraints::NToOneCRHGadget<F, ARITY>;
+
+    struct KAryFieldMTConfig<const ARITY: usize>;
+    impl<const ARITY: usize> KAryConfig for KAryFieldMTConfig<ARITY> {
+        const ARITY: usize = ARITY;
+        type Leaf = [F];
+        type LeafDigest = F;
+        type LeafInnerDigestConverter = IdentityDigestConverter<F>;
+        type InnerDigest = F;
+        type LeafHash = H;
+        type NToOneHash = NToOneH<ARITY>;
+    }
+
+    struct KAryFieldMTConfigVar;
+    impl<const ARITY: usize> KAryConfigGadget<KAryFieldMTConfig<ARITY>, F> for KAryFieldMTConfigVar {
+        type Leaf = [FpVar<F>];
+        type LeafDigest = FpVar<F>;
+        type LeafInnerConverter = IdentityDigestConverter<FpVar<F>>;
+        type InnerDigest = FpVar<F>;
+        type LeafHash = HG;
+        type NToOneHash = NToOneHG<ARITY>;
+    }
+
+    fn kary_merkle_tree_test<const ARITY: usize>(num_leaves: usize, use_bad_root: bool) {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let n_to_one_params = leaf_crh_params.clone();
+        let leaves: Vec<Vec<F>> = (0..num_leaves)
+            .map(|_| vec![F::rand(&mut rng), F::rand(&mut rng)])
+            .collect();
+        let tree = KAryMerkleTree::<KAryFieldMTConfig<ARITY>>::new(
+            &leaf_crh_params,
+            &n_to_one_params,
+            &leaves,
+        )
+        .unwrap();
+        let root = tree.root();
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let proof = tree.generate_proof(i).unwrap();
+
+            let root = FpVar::new_witness(cs.clone(), || {
+                if use_bad_root {
+                    Ok(root + F::one())
+                } else {
+                    Ok(root)
+                }
+            })
+            .unwrap();
+            let leaf_crh_params_var = <HG as CRHSchemeGadget<H, _>>::ParametersVar::new_constant(
+                ark_relations::ns!(cs, "leaf_crh_params"),
+                &leaf_crh_params,
+            )
+            .unwrap();
+            let n_to_one_params_var = <NToOneHG<ARITY> as NToOneCRHSchemeGadget<
+                NToOneH<ARITY>,
+                _,
+            >>::ParametersVar::new_constant(
+                ark_relations::ns!(cs, "n_to_one_params"),
+                &n_to_one_params,
+            )
+            .unwrap();
+            let leaf_g: Vec<_> = leaf
+                .iter()
+                .map(|x| FpVar::new_input(cs.clone(), || Ok(*x)).unwrap())
+                .collect();
+            let path_var =
+                KAryPathVar::<KAryFieldMTConfig<ARITY>, F, KAryFieldMTConfigVar>::new_witness(
+                    ark_relations::ns!(cs, "path"),
+                    || Ok(&proof),
+                )
+                .unwrap();
+
+            assert_eq!(
+                path_var.get_leaf_index().unwrap().value().unwrap(),
+                F::from(i as u64)
+            );
+            let result = path_var
+                .verify_membership(&leaf_crh_params_var, &n_to_one_params_var, &root, &leaf_g)
+                .unwrap();
+            assert_eq!(result.value().unwrap(), !use_bad_root);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn good_root_test() {
+        kary_merkle_tree_test::<4>(20, false);
+        kary_merkle_tree_test::<8>(9, false);
+    }
+
+    #[test]
+    fn bad_root_test() {
+        kary_merkle_tree_test::<4>(20, true);
+    }
+}
+
+mod mmr_tests {
+    use crate::crh::{poseidon, CRHSchemeGadget, TwoToOneCRHSchemeGadget};
+    use crate::merkle_tree::{
+        constraints::{ConfigGadget, MmrProofVar},
+        tests::test_utils::poseidon_parameters,
+        Config, IdentityDigestConverter, MerkleMountainRange,
+    };
+    use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar, GR1CSVar};
+    use ark_relations::gr1cs::ConstraintSystem;
+    use ark_std::{test_rng, One, UniformRand};
+
+    type F = ark_ed_on_bls12_381::Fr;
+    type H = poseidon::CRH<F>;
+    type HG = poseidon::constraints::CRHGadget<F>;
+    type TwoToOneH = poseidon::TwoToOneCRH<F>;
+    type TwoToOneHG = poseidon::constraints::TwoToOneCRHGadget<F>;
+
+    struct FieldMTConfig;
+    impl Config for FieldMTConfig {
+        type Leaf = [F];
+        type LeafDigest = F;
+        type LeafInnerDigestConverter = IdentityDigestConverter<F>;
+        type InnerDigest = F;
+        type LeafHash = H;
+        type TwoToOneHash = TwoToOneH;
+    }
+
+    struct FieldMTConfigVar;
+    impl ConfigGadget<FieldMTConfig, F> for FieldMTConfigVar {
+        type Leaf = [FpVar<F>];
+        type LeafDigest = FpVar<F>;
+        type LeafInnerConverter = IdentityDigestConverter<FpVar<F>>;
+        type InnerDigest = FpVar<F>;
+        type LeafHash = HG;
+        type TwoToOneHash = TwoToOneHG;
+    }
+
+    fn mmr_test(use_bad_root: bool) {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+        let leaves: Vec<Vec<F>> = (0..11).map(|_| vec![F::rand(&mut rng)]).collect();
+        let mut mmr =
+            MerkleMountainRange::<FieldMTConfig>::new(&leaf_crh_params, &two_to_one_params);
+        for leaf in &leaves {
+            mmr.append(leaf).unwrap();
+        }
+        let root = mmr.root().unwrap();
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let proof = mmr.generate_proof(i, leaves.len()).unwrap();
+
+            let root = FpVar::new_witness(cs.clone(), || {
+                if use_bad_root {
+                    Ok(root + F::one())
+                } else {
+                    Ok(root)
+                }
+            })
+            .unwrap();
+            let leaf_crh_params_var = <HG as CRHSchemeGadget<H, _>>::ParametersVar::new_constant(
+                ark_relations::ns!(cs, "leaf_crh_params"),
+                &leaf_crh_params,
+            )
+            .unwrap();
+            let two_to_one_crh_params_var =
+                <TwoToOneHG as TwoToOneCRHSchemeGadget<TwoToOneH, _>>::ParametersVar::new_constant(
+                    ark_relations::ns!(cs, "two_to_one_params"),
+                    &two_to_one_params,
+                )
+                .unwrap();
+            let leaf_g: Vec<_> = leaf
+                .iter()
+                .map(|x| FpVar::new_input(cs.clone(), || Ok(*x)).unwrap())
+                .collect();
+            let proof_var = MmrProofVar::<FieldMTConfig, F, FieldMTConfigVar>::new_witness(
+                ark_relations::ns!(cs, "proof"),
+                || Ok(&proof),
+            )
+            .unwrap();
+
+            let result = proof_var
+                .verify_membership(
+                    &leaf_crh_params_var,
+                    &two_to_one_crh_params_var,
+                    &root,
+                    &leaf_g,
+                )
+                .unwrap();
+            assert_eq!(result.value().unwrap(), !use_bad_root);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn good_root_test() {
+        mmr_test(false);
+    }
+
+    #[test]
+    fn bad_root_test() {
+        mmr_test(true);
+    }
+}
+
+mod consistency_tests {
+    use crate::crh::{poseidon, TwoToOneCRHSchemeGadget};
+    use crate::merkle_tree::{
+        constraints::{ConfigGadget, ConsistencyProofVar},
+        tests::test_utils::poseidon_parameters,
+        Config, IdentityDigestConverter, MerkleTree,
+    };
+    use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar, GR1CSVar};
+    use ark_relations::gr1cs::ConstraintSystem;
+    use ark_std::{test_rng, One, UniformRand};
+
+    type F = ark_ed_on_bls12_381::Fr;
+    type H = poseidon::CRH<F>;
+    type HG = poseidon::constraints::CRHGadget<F>;
+    type TwoToOneH = poseidon::TwoToOneCRH<F>;
+    type TwoToOneHG = poseidon::constraints::TwoToOneCRHGadget<F>;
+
+    struct FieldMTConfig;
+    impl Config for FieldMTConfig {
+        type Leaf = [F];
+        type LeafDigest = F;
+        type LeafInnerDigestConverter = IdentityDigestConverter<F>;
+        type InnerDigest = F;
+        type LeafHash = H;
+        type TwoToOneHash = TwoToOneH;
+    }
+
+    struct FieldMTConfigVar;
+    impl ConfigGadget<FieldMTConfig, F> for FieldMTConfigVar {
+        type Leaf = [FpVar<F>];
+        type LeafDigest = FpVar<F>;
+        type LeafInnerConverter = IdentityDigestConverter<FpVar<F>>;
+        type InnerDigest = FpVar<F>;
+        type LeafHash = HG;
+        type TwoToOneHash = TwoToOneHG;
+    }
+
+    fn consistency_test(use_bad_root: bool) {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+        let mut tree =
+            MerkleTree::<FieldMTConfig>::blank(&leaf_crh_params, &two_to_one_params, 4).unwrap();
+        // roots[i - 1] is the root of the log made of the first i leaves
+        let mut roots = Vec::new();
+        for i in 0..7 {
+            tree.update(i, &[F::rand(&mut rng)]).unwrap();
+            roots.push(tree.rfc9162_root(i + 1).unwrap());
+        }
+        let new_size = roots.len();
+        let new_root = roots[new_size - 1];
+
+        for (old_size, old_root) in (1..).zip(&roots) {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let proof = tree.generate_consistency_proof(old_size, new_size).unwrap();
+
+            let old_root = FpVar::new_input(cs.clone(), || Ok(*old_root)).unwrap();
+            let new_root = FpVar::new_input(cs.clone(), || {
+                if use_bad_root {
+                    Ok(new_root + F::one())
+                } else {
+                    Ok(new_root)
+                }
+            })
+            .unwrap();
+            let two_to_one_crh_params_var =
+                <TwoToOneHG as TwoToOneCRHSchemeGadget<TwoToOneH, _>>::ParametersVar::new_constant(
+                    ark_relations::ns!(cs, "two_to_one_params"),
+                    &two_to_one_params,
+                )
+                .unwrap();
+            let proof_var = ConsistencyProofVar::<FieldMTConfig, F, FieldMTConfigVar>::new_witness(
+                ark_relations::ns!(cs, "proof"),
+                || Ok(&proof),
+            )
+            .unwrap();
+
+            let result = proof_var
+                .verify(&two_to_one_crh_params_var, &old_root, &new_root)
+                .unwrap();
+            assert_eq!(result.value().unwrap(), !use_bad_root);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn good_root_test() {
+        consistency_test(false);
+    }
+
+    #[test]
+    fn bad_root_test() {
+        consistency_test(true);
+    }
+}
+
+mod domain_separated_mt_tests {
+    use crate::crh::{
+        domain_separated::{
+            constraints::{PoseidonLeafCRHGadget, PoseidonTwoToOneCRHGadget},
+            PoseidonLeafCRH, PoseidonTwoToOneCRH,
+        },
+        poseidon::constraints::CRHParametersVar,
+    };
+    use crate::merkle_tree::{
+        constraints::{ConfigGadget, PathVar},
+        tests::test_utils::poseidon_parameters,
+        Config, IdentityDigestConverter, MerkleTree,
+    };
+    use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar, GR1CSVar};
+    use ark_relations::gr1cs::ConstraintSystem;
+    use ark_std::{test_rng, One, UniformRand};
+
+    type F = ark_ed_on_bls12_381::Fr;
+
+    struct FieldMTConfig;
+    impl Config for FieldMTConfig {
+        type Leaf = [F];
+        type LeafDigest = F;
+        type LeafInnerDigestConverter = IdentityDigestConverter<F>;
+        type InnerDigest = F;
+        type LeafHash = PoseidonLeafCRH<F>;
+        type TwoToOneHash = PoseidonTwoToOneCRH<F>;
+    }
+
+    struct FieldMTConfigVar;
+    impl ConfigGadget<FieldMTConfig, F> for FieldMTConfigVar {
+        type Leaf = [FpVar<F>];
+        type LeafDigest = FpVar<F>;
+        type LeafInnerConverter = IdentityDigestConverter<FpVar<F>>;
+        type InnerDigest = FpVar<F>;
+        type LeafHash = PoseidonLeafCRHGadget<F>;
+        type TwoToOneHash = PoseidonTwoToOneCRHGadget<F>;
+    }
+
+    fn domain_separated_test(use_bad_root: bool) {
+        let mut rng = test_rng();
+        let params = poseidon_parameters();
+        let leaves: Vec<Vec<F>> = (0..8)
+            .map(|_| vec![F::rand(&mut rng), F::rand(&mut rng)])
+            .collect();
+        let tree = MerkleTree::<FieldMTConfig>::new(&params, &params, &leaves).unwrap();
+        let root = tree.root();
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let proof = tree.generate_proof(i).unwrap();
+            assert!(proof
+                .verify(&params, &params, &root, leaf.as_slice())
+                .unwrap());
+
+            let root = FpVar::new_input(cs.clone(), || {
+                if use_bad_root {
+                    Ok(root + F::one())
+                } else {
+                    Ok(root)
+                }
+            })
+            .unwrap();
+            let params_var = CRHParametersVar::new_constant(cs.clone(), &params).unwrap();
+            let leaf_g: Vec<_> = leaf
+                .iter()
+                .map(|x| FpVar::new_witness(cs.clone(), || Ok(*x)).unwrap())
+                .collect();
+            let path = PathVar::<FieldMTConfig, F, FieldMTConfigVar>::new_witness(
+                ark_relations::ns!(cs, "path"),
+                || Ok(&proof),
+            )
+            .unwrap();
+
+            let result = path
+                .verify_membership(&params_var, &params_var, &root, &leaf_g)
+                .unwrap();
+            assert_eq!(result.value().unwrap(), !use_bad_root);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn good_root_test() {
+        domain_separated_test(false);
+    }
+
+    #[test]
+    fn bad_root_test() {
+        domain_separated_test(true);
+    }
+}
+
+mod anemoi_mt_tests {
+    use crate::crh::{
+        anemoi::{
+            constraints::{CRHGadget, CRHParametersVar, TwoToOneCRHGadget},
+            TwoToOneCRH, CRH,
+        },
+        CRHScheme,
+    };
+    use crate::merkle_tree::{
+        constraints::{ConfigGadget, PathVar},
+        Config, IdentityDigestConverter, MerkleTree,
+    };
+    use ark_r1cs_std::{alloc::AllocVar, fields::fp::FpVar, GR1CSVar};
+    use ark_relations::gr1cs::ConstraintSystem;
+    use ark_std::{test_rng, One, UniformRand};
+
+    type F = ark_ed_on_bls12_381::Fr;
+
+    struct FieldMTConfig;
+    impl Config for FieldMTConfig {
+        type Leaf = [F];
+        type LeafDigest = F;
+        type LeafInnerDigestConverter = IdentityDigestConverter<F>;
+        type InnerDigest = F;
+        type LeafHash = CRH<F>;
+        type TwoToOneHash = TwoToOneCRH<F>;
+    }
+
+    struct FieldMTConfigVar;
+    impl ConfigGadget<FieldMTConfig, F> for FieldMTConfigVar {
+        type Leaf = [FpVar<F>];
+        type LeafDigest = FpVar<F>;
+        type LeafInnerConverter = IdentityDigestConverter<FpVar<F>>;
+        type InnerDigest = FpVar<F>;
+        type LeafHash = CRHGadget<F>;
+        type TwoToOneHash = TwoToOneCRHGadget<F>;
+    }
+
+    fn anemoi_test(use_bad_root: bool) {
+        let mut rng = test_rng();
+        let params = CRH::<F>::setup(&mut rng).unwrap();
+        let leaves: Vec<Vec<F>> = (0..8)
+            .map(|_| vec![F::rand(&mut rng), F::rand(&mut rng)])
+            .collect();
+        let tree = MerkleTree::<FieldMTConfig>::new(&params, &params, &leaves).unwrap();
+        let root = tree.root();
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            let cs = ConstraintSystem::<F>::new_ref();
+            let proof = tree.generate_proof(i).unwrap();
+            assert!(proof
+                .verify(&params, &params, &root, leaf.as_slice())
+                .unwrap());
+
+            let root = FpVar::new_input(cs.clone(), || {
+                if use_bad_root {
+                    Ok(root + F::one())
+                } else {
+                    Ok(root)
+                }
+            })
+            .unwrap();
+            let params_var = CRHParametersVar::new_constant(cs.clone(), &params).unwrap();
+            let leaf_g: Vec<_> = leaf
+                .iter()
+                .map(|x| FpVar::new_witness(cs.clone(), || Ok(*x)).unwrap())
+                .collect();
+            let path = PathVar::<FieldMTConfig, F, FieldMTConfigVar>::new_witness(
+                ark_relations::ns!(cs, "path"),
+                || Ok(&proof),
+            )
+            .unwrap();
+
+            let result = path
+                .verify_membership(&params_var, &params_var, &root, &leaf_g)
+                .unwrap();
+            assert_eq!(result.value().unwrap(), !use_bad_root);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn good_root_test() {
+        anemoi_test(false);
+    }
+
+    #[test]
+    fn bad_root_test() {
+        anemoi_test(true);
+    }
 }
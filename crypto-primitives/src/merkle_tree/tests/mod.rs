@@ -308,3 +308,1659 @@ mod field_mt_tests {
         )
     }
 }
+
+mod padding_mt_tests {
+    use crate::{
+        crh::{poseidon, CRHScheme, TwoToOneCRHScheme},
+        merkle_tree::{
+            tests::test_utils::poseidon_parameters, Config, IdentityDigestConverter, LeafPadding,
+            MerkleTree, NodeStore,
+        },
+    };
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+    use ark_std::{test_rng, One, UniformRand};
+
+    type F = ark_ed_on_bls12_381::Fr;
+    type H = poseidon::CRH<F>;
+    type TwoToOneH = poseidon::TwoToOneCRH<F>;
+
+    struct FieldMTConfig;
+    impl Config for FieldMTConfig {
+        type Leaf = [F];
+        type LeafDigest = F;
+        type LeafInnerDigestConverter = IdentityDigestConverter<F>;
+        type InnerDigest = F;
+        type LeafHash = H;
+        type TwoToOneHash = TwoToOneH;
+    }
+
+    type FieldMT = MerkleTree<FieldMTConfig>;
+
+    /// Computes the root of a Bitcoin-style merkle tree, duplicating odd nodes.
+    fn duplicate_odd_root(leaves: &[Vec<F>]) -> F {
+        let params = poseidon_parameters();
+        let mut digests: Vec<F> = leaves
+            .iter()
+            .map(|leaf| H::evaluate(&params, leaf.as_slice()).unwrap())
+            .collect();
+        if digests.len() % 2 == 1 {
+            digests.push(*digests.last().unwrap());
+        }
+        let mut nodes: Vec<F> = digests
+            .chunks(2)
+            .map(|pair| TwoToOneH::evaluate(&params, pair[0], pair[1]).unwrap())
+            .collect();
+        while nodes.len() > 1 {
+            if nodes.len() % 2 == 1 {
+                nodes.push(*nodes.last().unwrap());
+            }
+            nodes = nodes
+                .chunks(2)
+                .map(|pair| TwoToOneH::compress(&params, pair[0], pair[1]).unwrap())
+                .collect();
+        }
+        nodes[0]
+    }
+
+    #[test]
+    fn padded_tree_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+
+        for num_leaves in [1usize, 3, 5, 6, 7, 13] {
+            let mut leaves: Vec<Vec<F>> = (0..num_leaves)
+                .map(|_| vec![F::rand(&mut rng), F::rand(&mut rng)])
+                .collect();
+            let paddings = [
+                LeafPadding::DefaultDigest,
+                LeafPadding::Sentinel(F::rand(&mut rng)),
+                LeafPadding::DuplicateOdd,
+            ];
+            for padding in paddings {
+                let mut tree = FieldMT::new_with_padding(
+                    &leaf_crh_params,
+                    &two_to_one_params,
+                    &leaves,
+                    padding.clone(),
+                )
+                .unwrap();
+                assert_eq!(tree.num_leaves(), num_leaves);
+                assert_eq!(
+                    tree.height(),
+                    ark_std::log2(num_leaves.next_power_of_two().max(2)) as usize + 1
+                );
+                if padding == LeafPadding::DuplicateOdd {
+                    assert_eq!(tree.root(), duplicate_odd_root(&leaves));
+                }
+
+                // update the first and last leaves, which may be duplicated
+                for i in [0, num_leaves - 1] {
+                    leaves[i] = vec![F::rand(&mut rng)];
+                    tree.update(i, &leaves[i]).unwrap();
+                }
+                let rebuilt = FieldMT::new_with_padding(
+                    &leaf_crh_params,
+                    &two_to_one_params,
+                    &leaves,
+                    padding.clone(),
+                )
+                .unwrap();
+                assert_eq!(tree.root(), rebuilt.root());
+
+                let root = tree.root();
+                for (i, leaf) in leaves.iter().enumerate() {
+                    let proof = tree.generate_proof(i).unwrap();
+                    assert!(proof
+                        .verify(&leaf_crh_params, &two_to_one_params, &root, leaf.as_slice())
+                        .unwrap());
+                }
+                let multi_proof = tree.generate_multi_proof(0..num_leaves).unwrap();
+                assert!(multi_proof
+                    .verify(&leaf_crh_params, &two_to_one_params, &root, leaves.clone())
+                    .unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn batch_update_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+
+        for num_leaves in [2usize, 7, 13, 16] {
+            let leaves: Vec<Vec<F>> = (0..num_leaves)
+                .map(|_| vec![F::rand(&mut rng), F::rand(&mut rng)])
+                .collect();
+            let paddings = [
+                LeafPadding::DefaultDigest,
+                LeafPadding::Sentinel(F::rand(&mut rng)),
+                LeafPadding::DuplicateOdd,
+            ];
+            for padding in paddings {
+                let mut tree = FieldMT::new_with_padding(
+                    &leaf_crh_params,
+                    &two_to_one_params,
+                    &leaves,
+                    padding.clone(),
+                )
+                .unwrap();
+                let mut expected = tree.clone();
+
+                // repeated and out of order indices, including the last leaf
+                let indices = [num_leaves - 1, 0, 1, num_leaves / 2, 0];
+                let updates: Vec<(usize, Vec<F>)> = indices
+                    .iter()
+                    .map(|&i| (i, vec![F::rand(&mut rng)]))
+                    .collect();
+                for (i, leaf) in &updates {
+                    expected.update(*i, leaf).unwrap();
+                }
+
+                // a wrong root leaves the tree untouched
+                let old_root = tree.root();
+                assert!(!tree.check_update_batch(&updates, &old_root).unwrap());
+                assert_eq!(tree.root(), old_root);
+
+                assert!(tree.check_update_batch(&updates, &expected.root()).unwrap());
+                assert_eq!(tree.root(), expected.root());
+                for i in 0..num_leaves {
+                    assert_eq!(
+                        tree.generate_proof(i).unwrap(),
+                        expected.generate_proof(i).unwrap()
+                    );
+                }
+
+                tree.update_batch::<Vec<F>>(&[]).unwrap();
+                assert_eq!(tree.root(), expected.root());
+            }
+        }
+    }
+
+    #[test]
+    fn serialization_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+
+        for num_leaves in [2usize, 7, 13] {
+            let leaves: Vec<Vec<F>> = (0..num_leaves).map(|_| vec![F::rand(&mut rng)]).collect();
+            let paddings = [
+                LeafPadding::DefaultDigest,
+                LeafPadding::Sentinel(F::rand(&mut rng)),
+                LeafPadding::DuplicateOdd,
+            ];
+            for padding in paddings {
+                let mut tree = FieldMT::new_with_padding(
+                    &leaf_crh_params,
+                    &two_to_one_params,
+                    &leaves,
+                    padding,
+                )
+                .unwrap();
+                tree.update(num_leaves - 1, &[F::rand(&mut rng)]).unwrap();
+                assert!(tree.verify_nodes().unwrap());
+
+                for compress in [Compress::Yes, Compress::No] {
+                    // with parameters
+                    let mut bytes = Vec::new();
+                    tree.serialize_with_mode(&mut bytes, compress).unwrap();
+                    assert_eq!(bytes.len(), tree.serialized_size(compress));
+                    let loaded =
+                        FieldMT::deserialize_with_mode(&bytes[..], compress, Validate::Yes)
+                            .unwrap();
+                    assert_eq!(loaded.root(), tree.root());
+                    assert_eq!(loaded.num_leaves(), tree.num_leaves());
+                    assert_eq!(loaded.padding(), tree.padding());
+                    for i in 0..num_leaves {
+                        assert_eq!(
+                            loaded.generate_proof(i).unwrap(),
+                            tree.generate_proof(i).unwrap()
+                        );
+                    }
+
+                    // without parameters
+                    let mut bytes_without_params = Vec::new();
+                    tree.serialize_without_params(&mut bytes_without_params, compress)
+                        .unwrap();
+                    assert_eq!(
+                        bytes_without_params.len(),
+                        tree.serialized_size_without_params(compress)
+                    );
+                    assert!(bytes_without_params.len() < bytes.len());
+                    let mut loaded = FieldMT::deserialize_without_params(
+                        &bytes_without_params[..],
+                        compress,
+                        Validate::Yes,
+                        &leaf_crh_params,
+                        &two_to_one_params,
+                    )
+                    .unwrap();
+                    assert_eq!(loaded.root(), tree.root());
+
+                    // a loaded tree can be updated like the original one
+                    let new_leaf = [F::rand(&mut rng)];
+                    let mut expected = tree.clone();
+                    expected.update(0, &new_leaf).unwrap();
+                    loaded.update(0, &new_leaf).unwrap();
+                    assert_eq!(loaded.root(), expected.root());
+                }
+
+                // a leaf changed without rehashing is only caught by validation
+                let mut tampered = tree.clone();
+                let leaf = tampered.store.leaf(0).unwrap();
+                tampered.store.put_leaf(0, leaf + F::one()).unwrap();
+                assert!(!tampered.verify_nodes().unwrap());
+                let mut bytes = Vec::new();
+                tampered.serialize_compressed(&mut bytes).unwrap();
+                assert!(FieldMT::deserialize_compressed(&bytes[..]).is_err());
+                assert!(FieldMT::deserialize_compressed_unchecked(&bytes[..]).is_ok());
+
+                // stored nodes not matching the stored root are always rejected
+                let mut tampered = tree.clone();
+                tampered
+                    .store
+                    .put_inner(0, 0, tree.root() + F::one())
+                    .unwrap();
+                let mut bytes = Vec::new();
+                tampered.serialize_compressed(&mut bytes).unwrap();
+                assert!(FieldMT::deserialize_compressed_unchecked(&bytes[..]).is_err());
+            }
+        }
+    }
+}
+
+mod sparse_mt_tests {
+    use crate::{
+        crh::poseidon,
+        merkle_tree::{
+            tests::test_utils::poseidon_parameters, verify_key_membership, Config,
+            IdentityDigestConverter, MerkleTree, SparseMerkleTree,
+        },
+    };
+    use ark_std::{test_rng, UniformRand};
+
+    type F = ark_ed_on_bls12_381::Fr;
+    type H = poseidon::CRH<F>;
+    type TwoToOneH = poseidon::TwoToOneCRH<F>;
+
+    struct FieldMTConfig;
+    impl Config for FieldMTConfig {
+        type Leaf = [F];
+        type LeafDigest = F;
+        type LeafInnerDigestConverter = IdentityDigestConverter<F>;
+        type InnerDigest = F;
+        type LeafHash = H;
+        type TwoToOneHash = TwoToOneH;
+    }
+
+    type FieldSMT = SparseMerkleTree<FieldMTConfig>;
+    type FieldMT = MerkleTree<FieldMTConfig>;
+
+    #[test]
+    fn matches_dense_tree_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+
+        let leaves: Vec<(usize, Vec<F>)> = [1usize, 6, 7, 12]
+            .iter()
+            .map(|&i| (i, vec![F::rand(&mut rng), F::rand(&mut rng)]))
+            .collect();
+
+        let mut leaf_digests = vec![F::default(); 16];
+        for (i, leaf) in &leaves {
+            leaf_digests[*i] =
+                <H as crate::crh::CRHScheme>::evaluate(&leaf_crh_params, leaf.as_slice()).unwrap();
+        }
+        let dense =
+            FieldMT::new_with_leaf_digest(&leaf_crh_params, &two_to_one_params, leaf_digests)
+                .unwrap();
+        let sparse = FieldSMT::new(&leaf_crh_params, &two_to_one_params, 5, leaves).unwrap();
+
+        assert_eq!(sparse.root(), dense.root());
+        for i in 0..16 {
+            assert_eq!(
+                sparse.generate_proof(i).unwrap(),
+                dense.generate_proof(i).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn tall_tree_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+
+        for height in [64, 65, 256] {
+            let mut tree = FieldSMT::blank(&leaf_crh_params, &two_to_one_params, height).unwrap();
+            let blank_root = tree.root();
+            let indexes = [0usize, 3, 1 << 40, usize::MAX >> (65 - height.min(65))];
+
+            let leaves: Vec<Vec<F>> = indexes
+                .iter()
+                .map(|_| vec![F::rand(&mut rng), F::rand(&mut rng)])
+                .collect();
+            for (i, leaf) in indexes.iter().zip(&leaves) {
+                assert!(tree.insert(*i, leaf).unwrap());
+                assert!(!tree.insert(*i, leaf).unwrap());
+            }
+            assert_eq!(tree.num_leaves(), indexes.len());
+
+            let root = tree.root();
+            for (i, leaf) in indexes.iter().zip(&leaves) {
+                let proof = tree.generate_proof(*i).unwrap();
+                assert_eq!(proof.auth_path.len(), height - 2);
+                assert!(proof
+                    .verify(&leaf_crh_params, &two_to_one_params, &root, leaf.as_slice())
+                    .unwrap());
+            }
+
+            // an empty leaf can be proven against the default digest
+            let proof = tree.generate_proof(5).unwrap();
+            assert_eq!(proof.leaf_sibling_hash, F::default());
+
+            // update then remove everything
+            let new_leaf = vec![F::rand(&mut rng)];
+            tree.update(3, &new_leaf).unwrap();
+            assert_ne!(tree.root(), root);
+            let proof = tree.generate_proof(3).unwrap();
+            assert!(proof
+                .verify(
+                    &leaf_crh_params,
+                    &two_to_one_params,
+                    &tree.root(),
+                    new_leaf.as_slice()
+                )
+                .unwrap());
+
+            for i in indexes.iter() {
+                assert!(tree.remove(*i).unwrap());
+                assert!(!tree.remove(*i).unwrap());
+            }
+            assert_eq!(tree.root(), blank_root);
+        }
+    }
+
+    #[test]
+    fn key_indexed_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+
+        let mut tree = FieldSMT::blank(&leaf_crh_params, &two_to_one_params, 128).unwrap();
+        let keys: Vec<Vec<F>> = (0..16).map(|_| vec![F::rand(&mut rng)]).collect();
+        for key in &keys {
+            let value = vec![key[0], F::rand(&mut rng)];
+            assert!(tree.insert_key(key, &value).unwrap());
+            let proof = tree.generate_key_proof(key).unwrap();
+            assert_eq!(proof.leaf_index, tree.key_position(key).unwrap());
+            assert!(verify_key_membership(
+                &proof,
+                &leaf_crh_params,
+                &two_to_one_params,
+                &tree.root(),
+                key,
+                &value
+            )
+            .unwrap());
+            // the opening is bound to its key
+            assert!(!verify_key_membership(
+                &proof,
+                &leaf_crh_params,
+                &two_to_one_params,
+                &tree.root(),
+                &[F::rand(&mut rng)],
+                &value
+            )
+            .unwrap());
+        }
+
+        let root = tree.root();
+        let absent_key = vec![F::rand(&mut rng)];
+        let proof = tree
+            .generate_non_membership_proof(&absent_key)
+            .unwrap()
+            .unwrap();
+        assert!(proof
+            .verify(&leaf_crh_params, &two_to_one_params, &root, &absent_key)
+            .unwrap());
+        assert!(!proof
+            .verify(&leaf_crh_params, &two_to_one_params, &root, &keys[3])
+            .unwrap());
+
+        // once removed, a key can be proven absent
+        assert!(tree.remove_key(&keys[3]).unwrap());
+        let proof = tree
+            .generate_non_membership_proof(&keys[3])
+            .unwrap()
+            .unwrap();
+        assert!(proof
+            .verify(&leaf_crh_params, &two_to_one_params, &tree.root(), &keys[3])
+            .unwrap());
+        assert!(!proof
+            .verify(&leaf_crh_params, &two_to_one_params, &root, &keys[3])
+            .unwrap());
+    }
+
+    #[test]
+    fn key_collision_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+
+        // a tree of height 3 has only two slots, so keys collide quickly
+        let mut tree = FieldSMT::blank(&leaf_crh_params, &two_to_one_params, 3).unwrap();
+        let key = vec![F::rand(&mut rng)];
+        let value = vec![F::rand(&mut rng)];
+        assert!(tree.insert_key(&key, &value).unwrap());
+        let colliding_key = ark_std::iter::repeat_with(|| vec![F::rand(&mut rng)])
+            .find(|other| tree.key_position(other).unwrap() == tree.key_position(&key).unwrap())
+            .unwrap();
+
+        assert!(!tree.contains_key(&colliding_key).unwrap());
+        assert!(!tree.insert_key(&colliding_key, &value).unwrap());
+        assert!(!tree.update_key(&colliding_key, &value).unwrap());
+        assert!(!tree.remove_key(&colliding_key).unwrap());
+
+        // the opening of `key` does not prove membership of the colliding key
+        let proof = tree.generate_key_proof(&colliding_key).unwrap();
+        assert!(!verify_key_membership(
+            &proof,
+            &leaf_crh_params,
+            &two_to_one_params,
+            &tree.root(),
+            &colliding_key,
+            &value
+        )
+        .unwrap());
+
+        // and the colliding key can be proven absent, while `key` cannot
+        let proof = tree
+            .generate_non_membership_proof(&colliding_key)
+            .unwrap()
+            .unwrap();
+        assert!(proof
+            .verify(
+                &leaf_crh_params,
+                &two_to_one_params,
+                &tree.root(),
+                &colliding_key
+            )
+            .unwrap());
+        assert!(!proof
+            .verify(&leaf_crh_params, &two_to_one_params, &tree.root(), &key)
+            .unwrap());
+        assert!(tree.generate_non_membership_proof(&key).unwrap().is_none());
+    }
+}
+
+mod incremental_mt_tests {
+    use crate::{
+        crh::{poseidon, CRHScheme},
+        merkle_tree::{
+            tests::test_utils::poseidon_parameters, Config, IdentityDigestConverter,
+            IncrementalMerkleTree, MerkleTree,
+        },
+    };
+    use ark_std::{test_rng, UniformRand};
+
+    type F = ark_ed_on_bls12_381::Fr;
+    type H = poseidon::CRH<F>;
+    type TwoToOneH = poseidon::TwoToOneCRH<F>;
+
+    struct FieldMTConfig;
+    impl Config for FieldMTConfig {
+        type Leaf = [F];
+        type LeafDigest = F;
+        type LeafInnerDigestConverter = IdentityDigestConverter<F>;
+        type InnerDigest = F;
+        type LeafHash = H;
+        type TwoToOneHash = TwoToOneH;
+    }
+
+    type FieldIMT = IncrementalMerkleTree<FieldMTConfig>;
+    type FieldMT = MerkleTree<FieldMTConfig>;
+
+    #[test]
+    fn root_history_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+        let capacity = 3;
+
+        let mut imt = FieldIMT::blank(&leaf_crh_params, &two_to_one_params, 4)
+            .unwrap()
+            .with_root_history(capacity);
+        let mut mt = FieldMT::blank(&leaf_crh_params, &two_to_one_params, 4)
+            .unwrap()
+            .with_root_history(capacity);
+        let mut roots = vec![mt.root()];
+        assert_eq!(imt.root(), mt.root());
+
+        for i in 0..6 {
+            let leaf = vec![F::rand(&mut rng)];
+            imt.append(&leaf).unwrap();
+            mt.update(i, &leaf).unwrap();
+            roots.push(mt.root());
+
+            for tree_history in [imt.root_history().unwrap(), mt.root_history().unwrap()] {
+                let expected = &roots[roots.len().saturating_sub(capacity)..];
+                assert!(tree_history.iter().eq(expected.iter()));
+                assert_eq!(tree_history.latest(), roots.last());
+            }
+            for (j, root) in roots.iter().enumerate() {
+                let known = j + capacity >= roots.len();
+                assert_eq!(imt.is_known_root(root), known);
+                assert_eq!(mt.is_known_root(root), known);
+            }
+        }
+
+        // a batch update pushes a single root
+        let updates = [(0, vec![F::rand(&mut rng)]), (5, vec![F::rand(&mut rng)])];
+        mt.update_batch(&updates).unwrap();
+        let history: Vec<_> = mt.root_history().unwrap().iter().cloned().collect();
+        assert_eq!(history, vec![roots[5], roots[6], mt.root()]);
+
+        // without a history, only the current root is known
+        let tree = FieldMT::blank(&leaf_crh_params, &two_to_one_params, 4).unwrap();
+        assert!(tree.root_history().is_none());
+        assert!(tree.is_known_root(&tree.root()));
+        assert!(!tree.is_known_root(&roots[1]));
+    }
+
+    #[test]
+    fn matches_dense_tree_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+
+        let height = 5;
+        let mut tree = FieldIMT::blank(&leaf_crh_params, &two_to_one_params, height).unwrap();
+        let mut leaf_digests = vec![F::default(); 1 << (height - 1)];
+
+        let blank = FieldMT::blank(&leaf_crh_params, &two_to_one_params, height).unwrap();
+        assert_eq!(tree.root(), blank.root());
+
+        let mut leaves = Vec::new();
+        for i in 0..(1 << (height - 1)) {
+            let leaf = vec![F::rand(&mut rng), F::rand(&mut rng)];
+            assert_eq!(tree.append(&leaf).unwrap(), i);
+            leaf_digests[i] = H::evaluate(&leaf_crh_params, leaf.as_slice()).unwrap();
+            leaves.push(leaf);
+            if i % 3 == 0 {
+                assert_eq!(tree.mark(), Some(i));
+            }
+
+            let dense = FieldMT::new_with_leaf_digest(
+                &leaf_crh_params,
+                &two_to_one_params,
+                leaf_digests.clone(),
+            )
+            .unwrap();
+            assert_eq!(tree.root(), dense.root());
+            for marked in tree.marked() {
+                let witness = tree.witness(marked).unwrap();
+                assert_eq!(witness, dense.generate_proof(marked).unwrap());
+                assert!(witness
+                    .verify(
+                        &leaf_crh_params,
+                        &two_to_one_params,
+                        &tree.root(),
+                        leaves[marked].as_slice()
+                    )
+                    .unwrap());
+            }
+        }
+        assert!(tree.is_full());
+
+        assert!(tree.unmark(3));
+        assert!(!tree.unmark(3));
+        assert!(tree.witness(3).is_none());
+    }
+}
+
+#[cfg(feature = "std")]
+mod file_store_mt_tests {
+    use crate::{
+        crh::poseidon,
+        merkle_tree::{
+            tests::test_utils::poseidon_parameters, Config, FileNodeStore, IdentityDigestConverter,
+            LeafPadding, MerkleTree, NodeStore,
+        },
+    };
+    use ark_serialize::CanonicalSerialize;
+    use ark_std::{test_rng, UniformRand};
+
+    type F = ark_ed_on_bls12_381::Fr;
+    type H = poseidon::CRH<F>;
+    type TwoToOneH = poseidon::TwoToOneCRH<F>;
+
+    struct FieldMTConfig;
+    impl Config for FieldMTConfig {
+        type Leaf = [F];
+        type LeafDigest = F;
+        type LeafInnerDigestConverter = IdentityDigestConverter<F>;
+        type InnerDigest = F;
+        type LeafHash = H;
+        type TwoToOneHash = TwoToOneH;
+    }
+
+    type FieldMT = MerkleTree<FieldMTConfig>;
+    type FileMT = MerkleTree<FieldMTConfig, FileNodeStore<FieldMTConfig>>;
+
+    #[test]
+    fn matches_memory_tree_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+        let slot_size = F::default().uncompressed_size();
+        let path = std::env::temp_dir().join(format!(
+            "ark-crypto-primitives-file-store-{}",
+            std::process::id()
+        ));
+
+        for padding in [LeafPadding::DefaultDigest, LeafPadding::DuplicateOdd] {
+            let mut leaves: Vec<Vec<F>> = (0..13)
+                .map(|_| vec![F::rand(&mut rng), F::rand(&mut rng)])
+                .collect();
+            let mut memory_tree = FieldMT::new_with_padding(
+                &leaf_crh_params,
+                &two_to_one_params,
+                &leaves,
+                padding.clone(),
+            )
+            .unwrap();
+
+            let store = FileNodeStore::create(&path, 5, slot_size, slot_size).unwrap();
+            let file_tree = FileMT::new_in_store(
+                &leaf_crh_params,
+                &two_to_one_params,
+                store,
+                &leaves,
+                padding.clone(),
+            )
+            .unwrap();
+            assert_eq!(file_tree.root(), memory_tree.root());
+            assert_eq!(file_tree.num_leaves(), leaves.len());
+            drop(file_tree);
+
+            // reopening with another padding fails
+            for other in [
+                LeafPadding::DefaultDigest,
+                LeafPadding::DuplicateOdd,
+                LeafPadding::Sentinel(F::rand(&mut rng)),
+            ] {
+                if other != padding {
+                    let store = FileNodeStore::open(&path).unwrap();
+                    assert!(
+                        FileMT::open(&leaf_crh_params, &two_to_one_params, store, other).is_err()
+                    );
+                }
+            }
+
+            // reopen the tree, and update it
+            let store = FileNodeStore::open(&path).unwrap();
+            assert_eq!(store.height(), 5);
+            let mut file_tree =
+                FileMT::open(&leaf_crh_params, &two_to_one_params, store, padding).unwrap();
+            assert_eq!(file_tree.root(), memory_tree.root());
+            assert_eq!(file_tree.num_leaves(), leaves.len());
+            for i in [0, 7, 12] {
+                leaves[i] = vec![F::rand(&mut rng)];
+                memory_tree.update(i, &leaves[i]).unwrap();
+                file_tree.update(i, &leaves[i]).unwrap();
+                assert_eq!(file_tree.root(), memory_tree.root());
+            }
+
+            let root = file_tree.root();
+            for (i, leaf) in leaves.iter().enumerate() {
+                let proof = file_tree.generate_proof(i).unwrap();
+                assert_eq!(proof, memory_tree.generate_proof(i).unwrap());
+                assert!(proof
+                    .verify(&leaf_crh_params, &two_to_one_params, &root, leaf.as_slice())
+                    .unwrap());
+            }
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn corrupt_header_test() {
+        use std::io::{Seek, SeekFrom, Write};
+
+        let slot_size = F::default().uncompressed_size();
+        let path = std::env::temp_dir().join(format!(
+            "ark-crypto-primitives-corrupt-header-{}",
+            std::process::id()
+        ));
+        // the header holds the magic, then the height, the leaf and inner slot sizes and the
+        // number of leaves as little-endian `u64`s
+        for (offset, value) in [(16, 0u64), (24, 0), (16, u64::MAX), (8, 65), (32, 1 << 10)] {
+            drop(FileNodeStore::<FieldMTConfig>::create(&path, 5, slot_size, slot_size).unwrap());
+            let mut file = std::fs::OpenOptions::new().write(true).open(&path).unwrap();
+            file.seek(SeekFrom::Start(offset)).unwrap();
+            file.write_all(&value.to_le_bytes()).unwrap();
+            drop(file);
+            assert!(FileNodeStore::<FieldMTConfig>::open(&path).is_err());
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
+
+mod kary_mt_tests {
+    use crate::{
+        crh::{poseidon, CRHScheme, NToOneCRHScheme},
+        merkle_tree::{
+            tests::test_utils::poseidon_parameters, Config, IdentityDigestConverter, KAryConfig,
+            KAryMerkleTree, MerkleTree,
+        },
+    };
+    use ark_std::{test_rng, UniformRand};
+
+    type F = ark_ed_on_bls12_381::Fr;
+    type H = poseidon::CRH<F>;
+    type TwoToOneH = poseidon::TwoToOneCRH<F>;
+    type NToOneH<const ARITY: usize> = poseidon::NToOneCRH<F, ARITY>;
+
+    struct FieldMTConfig;
+    impl Config for FieldMTConfig {
+        type Leaf = [F];
+        type LeafDigest = F;
+        type LeafInnerDigestConverter = IdentityDigestConverter<F>;
+        type InnerDigest = F;
+        type LeafHash = H;
+        type TwoToOneHash = TwoToOneH;
+    }
+
+    struct KAryFieldMTConfig<const ARITY: usize>;
+    impl<const ARITY: usize> KAryConfig for KAryFieldMTConfig<ARITY> {
+        const ARITY: usize = ARITY;
+        type Leaf = [F];
+        type LeafDigest = F;
+        type LeafInnerDigestConverter = IdentityDigestConverter<F>;
+        type InnerDigest = F;
+        type LeafHash = H;
+        type NToOneHash = NToOneH<ARITY>;
+    }
+
+    fn kary_merkle_tree_test<const ARITY: usize>(num_leaves: usize, height: usize) {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let n_to_one_params = leaf_crh_params.clone();
+        let mut leaves: Vec<Vec<F>> = (0..num_leaves)
+            .map(|_| vec![F::rand(&mut rng), F::rand(&mut rng)])
+            .collect();
+        let mut tree = KAryMerkleTree::<KAryFieldMTConfig<ARITY>>::new(
+            &leaf_crh_params,
+            &n_to_one_params,
+            &leaves,
+        )
+        .unwrap();
+        assert_eq!(tree.height(), height);
+
+        for i in [0, num_leaves / 2, num_leaves - 1] {
+            leaves[i] = vec![F::rand(&mut rng)];
+            tree.update(i, &leaves[i]).unwrap();
+        }
+        let rebuilt = KAryMerkleTree::<KAryFieldMTConfig<ARITY>>::new(
+            &leaf_crh_params,
+            &n_to_one_params,
+            &leaves,
+        )
+        .unwrap();
+        assert_eq!(tree.root(), rebuilt.root());
+
+        let root = tree.root();
+        for (i, leaf) in leaves.iter().enumerate() {
+            let proof = tree.generate_proof(i).unwrap();
+            assert_eq!(proof.auth_path.len(), height - 2);
+            assert!(proof
+                .verify(&leaf_crh_params, &n_to_one_params, &root, leaf.as_slice())
+                .unwrap());
+            // the proof does not verify at another position
+            let mut wrong_index = proof.clone();
+            wrong_index.leaf_index = i + 1;
+            assert!(!wrong_index
+                .verify(&leaf_crh_params, &n_to_one_params, &root, leaf.as_slice())
+                .unwrap());
+        }
+    }
+
+    #[test]
+    fn kary_tree_test() {
+        kary_merkle_tree_test::<4>(1, 2);
+        kary_merkle_tree_test::<4>(20, 4);
+        kary_merkle_tree_test::<8>(64, 3);
+        kary_merkle_tree_test::<16>(17, 3);
+    }
+
+    #[test]
+    fn binary_tree_is_domain_separated_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let n_to_one_params = leaf_crh_params.clone();
+        let leaves: Vec<Vec<F>> = (0..4).map(|_| vec![F::rand(&mut rng)]).collect();
+        let tree = KAryMerkleTree::<KAryFieldMTConfig<2>>::new(
+            &leaf_crh_params,
+            &n_to_one_params,
+            &leaves,
+        )
+        .unwrap();
+
+        let leaf_hashes: Vec<F> = leaves
+            .iter()
+            .map(|leaf| H::evaluate(&leaf_crh_params, leaf.as_slice()).unwrap())
+            .collect();
+        let node = |children: &[F]| NToOneH::<2>::compress(&n_to_one_params, children).unwrap();
+        let root = node(&[node(&leaf_hashes[..2]), node(&leaf_hashes[2..])]);
+        assert_eq!(tree.root(), root);
+
+        // the arity is part of the hash, so the binary tree differs from a `MerkleTree`, and a
+        // node does not collide with a node of higher arity padded with zeros
+        let binary_tree =
+            MerkleTree::<FieldMTConfig>::new(&leaf_crh_params, &n_to_one_params, &leaves).unwrap();
+        assert_ne!(tree.root(), binary_tree.root());
+        let zero = F::from(0u8);
+        assert_ne!(
+            node(&leaf_hashes[..2]),
+            NToOneH::<4>::compress(
+                &n_to_one_params,
+                &[leaf_hashes[0], leaf_hashes[1], zero, zero]
+            )
+            .unwrap()
+        );
+        assert!(NToOneH::<4>::compress(&n_to_one_params, &leaf_hashes[..2]).is_err());
+    }
+
+    #[test]
+    fn n_to_one_setup_test() {
+        let mut rng = test_rng();
+        for (arity, params) in [
+            (4, NToOneH::<4>::setup(&mut rng).unwrap()),
+            (8, NToOneH::<8>::setup(&mut rng).unwrap()),
+        ] {
+            // a node is absorbed in a single permutation
+            assert_eq!(params.rate, arity);
+            assert_eq!(params.capacity, 1);
+        }
+    }
+}
+
+mod mmr_tests {
+    use crate::{
+        crh::poseidon,
+        merkle_tree::{
+            tests::test_utils::poseidon_parameters, Config, IdentityDigestConverter,
+            MerkleMountainRange, MerkleTree,
+        },
+    };
+    use ark_std::{test_rng, One, UniformRand};
+
+    type F = ark_ed_on_bls12_381::Fr;
+    type H = poseidon::CRH<F>;
+    type TwoToOneH = poseidon::TwoToOneCRH<F>;
+
+    struct FieldMTConfig;
+    impl Config for FieldMTConfig {
+        type Leaf = [F];
+        type LeafDigest = F;
+        type LeafInnerDigestConverter = IdentityDigestConverter<F>;
+        type InnerDigest = F;
+        type LeafHash = H;
+        type TwoToOneHash = TwoToOneH;
+    }
+
+    type FieldMMR = MerkleMountainRange<FieldMTConfig>;
+    type FieldMT = MerkleTree<FieldMTConfig>;
+
+    #[test]
+    fn inclusion_proof_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+        let leaves: Vec<Vec<F>> = (0..19).map(|_| vec![F::rand(&mut rng)]).collect();
+
+        let mut mmr = FieldMMR::new(&leaf_crh_params, &two_to_one_params);
+        for (i, leaf) in leaves.iter().enumerate() {
+            assert_eq!(mmr.append(leaf).unwrap(), i);
+        }
+
+        for size in 1..=leaves.len() {
+            let root = mmr.root_at(size).unwrap();
+            // a single peak is the root of the merkle tree of the same leaves
+            if size.is_power_of_two() && size > 1 {
+                let tree =
+                    FieldMT::new(&leaf_crh_params, &two_to_one_params, &leaves[..size]).unwrap();
+                assert_eq!(root, tree.root());
+            }
+            for (i, leaf) in leaves[..size].iter().enumerate() {
+                let proof = mmr.generate_proof(i, size).unwrap();
+                assert!(proof
+                    .verify(&leaf_crh_params, &two_to_one_params, &root, leaf.as_slice())
+                    .unwrap());
+                assert!(!proof
+                    .verify(
+                        &leaf_crh_params,
+                        &two_to_one_params,
+                        &(root + F::one()),
+                        leaf.as_slice()
+                    )
+                    .unwrap());
+                let mut wrong_index = proof.clone();
+                wrong_index.leaf_index = (i + 1) % size;
+                if wrong_index.leaf_index != i {
+                    assert!(!wrong_index
+                        .verify(&leaf_crh_params, &two_to_one_params, &root, leaf.as_slice())
+                        .unwrap());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn consistency_proof_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+
+        let mut mmr = FieldMMR::new(&leaf_crh_params, &two_to_one_params);
+        for _ in 0..19 {
+            mmr.append(&[F::rand(&mut rng)]).unwrap();
+        }
+
+        for new_size in 1..=mmr.num_leaves() {
+            let new_root = mmr.root_at(new_size).unwrap();
+            for old_size in 1..=new_size {
+                let old_root = mmr.root_at(old_size).unwrap();
+                let proof = mmr.generate_consistency_proof(old_size, new_size).unwrap();
+                assert!(proof
+                    .verify(&two_to_one_params, &old_root, &new_root)
+                    .unwrap());
+                assert!(!proof
+                    .verify(&two_to_one_params, &old_root, &(new_root + F::one()))
+                    .unwrap());
+                assert!(!proof
+                    .verify(&two_to_one_params, &(old_root + F::one()), &new_root)
+                    .unwrap());
+                if let Some(leaf) = proof.leaves.first() {
+                    let mut tampered = proof.clone();
+                    tampered.leaves[0] = *leaf + F::one();
+                    assert!(!tampered
+                        .verify(&two_to_one_params, &old_root, &new_root)
+                        .unwrap());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn out_of_range_test() {
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+
+        let mut mmr = FieldMMR::new(&leaf_crh_params, &two_to_one_params);
+        assert!(mmr.root().is_err());
+        for i in 0..3u8 {
+            mmr.append(&[F::from(i)]).unwrap();
+        }
+        assert!(mmr.peaks(0).is_err());
+        assert!(mmr.peaks(4).is_err());
+        assert!(mmr.generate_proof(3, 3).is_err());
+        assert!(mmr.generate_proof(2, 4).is_err());
+        assert!(mmr.generate_consistency_proof(3, 2).is_err());
+        assert!(mmr.generate_consistency_proof(0, 2).is_err());
+        assert!(mmr.generate_consistency_proof(2, 4).is_err());
+    }
+}
+
+mod consistency_tests {
+    use crate::{
+        crh::{
+            domain_separated::{InnerCRH, LeafCRH},
+            poseidon,
+            sha256::Sha256,
+        },
+        merkle_tree::{
+            tests::test_utils::poseidon_parameters, Config, IdentityDigestConverter, LeafPadding,
+            MerkleTree,
+        },
+    };
+    use ark_std::{test_rng, One, UniformRand};
+
+    type F = ark_ed_on_bls12_381::Fr;
+    type H = poseidon::CRH<F>;
+    type TwoToOneH = poseidon::TwoToOneCRH<F>;
+
+    struct FieldMTConfig;
+    impl Config for FieldMTConfig {
+        type Leaf = [F];
+        type LeafDigest = F;
+        type LeafInnerDigestConverter = IdentityDigestConverter<F>;
+        type InnerDigest = F;
+        type LeafHash = H;
+        type TwoToOneHash = TwoToOneH;
+    }
+
+    /// The merkle tree of RFC 6962 and RFC 9162.
+    struct Rfc6962Config;
+    impl Config for Rfc6962Config {
+        type Leaf = [u8];
+        type LeafDigest = Vec<u8>;
+        type LeafInnerDigestConverter = IdentityDigestConverter<Vec<u8>>;
+        type InnerDigest = Vec<u8>;
+        type LeafHash = LeafCRH<Sha256>;
+        type TwoToOneHash = InnerCRH<Sha256>;
+    }
+
+    type FieldMT = MerkleTree<FieldMTConfig>;
+
+    fn from_hex(hex: &str) -> Vec<u8> {
+        (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn consistency_proof_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+        let mut tree = FieldMT::blank(&leaf_crh_params, &two_to_one_params, 5).unwrap();
+
+        // roots[i - 1] is the root of the log made of the first i leaves
+        let mut roots = Vec::new();
+        for new_size in 1..=16 {
+            tree.update(new_size - 1, &[F::rand(&mut rng)]).unwrap();
+            roots.push(tree.rfc9162_root(new_size).unwrap());
+            let new_root = roots[new_size - 1];
+            for old_size in 1..=new_size {
+                let old_root = roots[old_size - 1];
+                // the root of a log does not depend on the leaves appended later
+                assert_eq!(tree.rfc9162_root(old_size).unwrap(), old_root);
+
+                let proof = tree.generate_consistency_proof(old_size, new_size).unwrap();
+                assert!(proof
+                    .verify(&two_to_one_params, &old_root, &new_root)
+                    .unwrap());
+                assert!(!proof
+                    .verify(&two_to_one_params, &(old_root + F::one()), &new_root)
+                    .unwrap());
+                assert!(!proof
+                    .verify(&two_to_one_params, &old_root, &(new_root + F::one()))
+                    .unwrap());
+
+                for i in 0..proof.nodes.len() {
+                    let mut tampered = proof.clone();
+                    tampered.nodes[i] += F::one();
+                    assert!(!tampered
+                        .verify(&two_to_one_params, &old_root, &new_root)
+                        .unwrap());
+                }
+                if old_size < new_size {
+                    // the proof does not carry over to the log with one leaf less
+                    let mut shrunk = proof.clone();
+                    shrunk.new_size -= 1;
+                    assert!(!shrunk
+                        .verify(&two_to_one_params, &old_root, &roots[new_size - 2])
+                        .unwrap());
+                }
+            }
+        }
+        assert!(tree.generate_consistency_proof(0, 4).is_err());
+        assert!(tree.generate_consistency_proof(5, 4).is_err());
+        assert!(tree.generate_consistency_proof(4, 17).is_err());
+    }
+
+    #[test]
+    fn padding_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+        let leaves: Vec<Vec<F>> = (0..11).map(|_| vec![F::rand(&mut rng)]).collect();
+
+        // logs only depend on their leaves, whatever the padding of the tree
+        let trees: Vec<FieldMT> = [
+            LeafPadding::DefaultDigest,
+            LeafPadding::DuplicateOdd,
+            LeafPadding::Sentinel(F::rand(&mut rng)),
+        ]
+        .into_iter()
+        .map(|padding| {
+            FieldMT::new_with_padding(&leaf_crh_params, &two_to_one_params, &leaves, padding)
+                .unwrap()
+        })
+        .collect();
+        for size in 1..=leaves.len() {
+            let root = trees[0].rfc9162_root(size).unwrap();
+            for tree in &trees {
+                assert_eq!(tree.rfc9162_root(size).unwrap(), root);
+                let proof = tree.generate_consistency_proof(size, leaves.len()).unwrap();
+                assert!(proof
+                    .verify(
+                        &two_to_one_params,
+                        &root,
+                        &trees[0].rfc9162_root(leaves.len()).unwrap()
+                    )
+                    .unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn rfc6962_vectors_test() {
+        // the test vectors of Certificate Transparency
+        let leaves: Vec<Vec<u8>> = vec![
+            vec![],
+            vec![0x00],
+            vec![0x10],
+            vec![0x20, 0x21],
+            vec![0x30, 0x31],
+            vec![0x40, 0x41, 0x42, 0x43],
+            (0x50..0x58).collect(),
+            (0x60..0x70).collect(),
+        ];
+        let roots = [
+            "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+            "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+            "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+            "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+            "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+            "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+            "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+            "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328",
+        ];
+        let tree = MerkleTree::<Rfc6962Config>::new(&(), &(), &leaves).unwrap();
+        for (size, root) in (1..).zip(roots) {
+            assert_eq!(tree.rfc9162_root(size).unwrap(), from_hex(root));
+        }
+
+        let proofs: [(usize, usize, &[&str]); 3] = [
+            (
+                1,
+                8,
+                &[
+                    "96a296d224f285c67bee93c30f8a309157f0daa35dc5b87e410b78630a09cfc7",
+                    "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                    "6b47aaf29ee3c2af9af889bc1fb9254dabd31177f16232dd6aab035ca39bf6e4",
+                ],
+            ),
+            (
+                6,
+                8,
+                &[
+                    "0ebc5d3437fbe2db158b9f126a1d118e308181031d0a949f8dededebc558ef6a",
+                    "ca854ea128ed050b41b35ffc1b87b8eb2bde461e9e3b5596ece6b9d5975a0ae0",
+                    "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+                ],
+            ),
+            (
+                2,
+                5,
+                &[
+                    "5f083f0a1a33ca076a95279832580db3e0ef4584bdff1f54c8a360f50de3031e",
+                    "bc1a0643b12e4d2d7c77918f44e0f4f79a838b6cf9ec5b5c283e1f4d88599e6b",
+                ],
+            ),
+        ];
+        for (old_size, new_size, nodes) in proofs {
+            let proof = tree.generate_consistency_proof(old_size, new_size).unwrap();
+            let expected: Vec<_> = nodes.iter().map(|node| from_hex(node)).collect();
+            assert_eq!(proof.nodes, expected);
+            assert!(proof
+                .verify(
+                    &(),
+                    &from_hex(roots[old_size - 1]),
+                    &from_hex(roots[new_size - 1])
+                )
+                .unwrap());
+        }
+    }
+}
+
+mod domain_separated_mt_tests {
+    use crate::{
+        crh::{
+            domain_separated::{InnerCRH, LeafCRH, PoseidonLeafCRH, PoseidonTwoToOneCRH},
+            poseidon,
+            sha256::Sha256,
+            CRHScheme, TwoToOneCRHScheme,
+        },
+        merkle_tree::{
+            tests::test_utils::poseidon_parameters, Config, IdentityDigestConverter, MerkleTree,
+        },
+    };
+    use ark_std::{test_rng, UniformRand};
+
+    type F = ark_ed_on_bls12_381::Fr;
+
+    /// The merkle tree of RFC 6962.
+    struct Rfc6962Config;
+    impl Config for Rfc6962Config {
+        type Leaf = [u8];
+        type LeafDigest = Vec<u8>;
+        type LeafInnerDigestConverter = IdentityDigestConverter<Vec<u8>>;
+        type InnerDigest = Vec<u8>;
+        type LeafHash = LeafCRH<Sha256>;
+        type TwoToOneHash = InnerCRH<Sha256>;
+    }
+
+    struct FieldMTConfig;
+    impl Config for FieldMTConfig {
+        type Leaf = [F];
+        type LeafDigest = F;
+        type LeafInnerDigestConverter = IdentityDigestConverter<F>;
+        type InnerDigest = F;
+        type LeafHash = PoseidonLeafCRH<F>;
+        type TwoToOneHash = PoseidonTwoToOneCRH<F>;
+    }
+
+    #[test]
+    fn rfc6962_tree_test() {
+        let leaves: Vec<Vec<u8>> = (0u8..4).map(|i| vec![i; 3]).collect();
+        let tree = MerkleTree::<Rfc6962Config>::new(&(), &(), &leaves).unwrap();
+
+        let leaf_hashes: Vec<_> = leaves
+            .iter()
+            .map(|leaf| {
+                <Sha256 as CRHScheme>::evaluate(&(), [&[0x00][..], leaf].concat().as_slice())
+                    .unwrap()
+            })
+            .collect();
+        let node = |left: &[u8], right: &[u8]| {
+            <Sha256 as CRHScheme>::evaluate(&(), [&[0x01][..], left, right].concat().as_slice())
+                .unwrap()
+        };
+        let root = node(
+            &node(&leaf_hashes[0], &leaf_hashes[1]),
+            &node(&leaf_hashes[2], &leaf_hashes[3]),
+        );
+        assert_eq!(tree.root(), root);
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            let proof = tree.generate_proof(i).unwrap();
+            assert!(proof.verify(&(), &(), &root, leaf.as_slice()).unwrap());
+        }
+    }
+
+    #[test]
+    fn second_preimage_test() {
+        let mut rng = test_rng();
+        let params = poseidon_parameters();
+        let (left, right) = (F::rand(&mut rng), F::rand(&mut rng));
+
+        // without domain separation, the two children of a node hash to the node as a leaf
+        assert_eq!(
+            poseidon::CRH::<F>::evaluate(&params, [left, right]).unwrap(),
+            poseidon::TwoToOneCRH::<F>::compress(&params, left, right).unwrap()
+        );
+        assert_ne!(
+            PoseidonLeafCRH::<F>::evaluate(&params, [left, right]).unwrap(),
+            PoseidonTwoToOneCRH::<F>::compress(&params, left, right).unwrap()
+        );
+
+        let leaves: Vec<Vec<F>> = (0..4).map(|_| vec![F::rand(&mut rng)]).collect();
+        let tree = MerkleTree::<FieldMTConfig>::new(&params, &params, &leaves).unwrap();
+        for (i, leaf) in leaves.iter().enumerate() {
+            let proof = tree.generate_proof(i).unwrap();
+            assert!(proof
+                .verify(&params, &params, &tree.root(), leaf.as_slice())
+                .unwrap());
+        }
+    }
+}
+
+mod subtree_tests {
+    use crate::{
+        crh::poseidon,
+        merkle_tree::{
+            tests::test_utils::poseidon_parameters, Config, IdentityDigestConverter, LeafPadding,
+            MerkleTree,
+        },
+    };
+    use ark_std::{test_rng, One, UniformRand};
+
+    type F = ark_ed_on_bls12_381::Fr;
+    type H = poseidon::CRH<F>;
+    type TwoToOneH = poseidon::TwoToOneCRH<F>;
+
+    struct FieldMTConfig;
+    impl Config for FieldMTConfig {
+        type Leaf = [F];
+        type LeafDigest = F;
+        type LeafInnerDigestConverter = IdentityDigestConverter<F>;
+        type InnerDigest = F;
+        type LeafHash = H;
+        type TwoToOneHash = TwoToOneH;
+    }
+
+    type FieldMT = MerkleTree<FieldMTConfig>;
+
+    #[test]
+    fn subtree_proof_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+        let num_leaves = 13;
+        let leaves: Vec<Vec<F>> = (0..num_leaves)
+            .map(|_| vec![F::rand(&mut rng), F::rand(&mut rng)])
+            .collect();
+        let paddings = [
+            LeafPadding::DefaultDigest,
+            LeafPadding::Sentinel(F::rand(&mut rng)),
+            LeafPadding::DuplicateOdd,
+        ];
+        for padding in paddings {
+            let tree = FieldMT::new_with_padding(
+                &leaf_crh_params,
+                &two_to_one_params,
+                &leaves,
+                padding.clone(),
+            )
+            .unwrap();
+            let root = tree.root();
+
+            for level in 0..tree.height() - 1 {
+                let size = 1 << (tree.height() - 1 - level);
+                for index in (0..1 << level).filter(|i| i * size < num_leaves) {
+                    let subtree = tree.subtree(level, index).unwrap();
+                    assert_eq!(subtree.height(), tree.height() - level);
+                    assert!(subtree.verify_nodes().unwrap());
+
+                    // the leaves of the subtree have the same proofs below the subtree root
+                    let subtree_root = subtree.root();
+                    for i in 0..(num_leaves - index * size).min(size) {
+                        let leaf = &leaves[index * size + i];
+                        let proof = subtree.generate_proof(i).unwrap();
+                        assert!(proof
+                            .verify(
+                                &leaf_crh_params,
+                                &two_to_one_params,
+                                &subtree_root,
+                                leaf.as_slice()
+                            )
+                            .unwrap());
+                    }
+                    if (index + 1) * size <= num_leaves {
+                        let rebuilt = FieldMT::new(
+                            &leaf_crh_params,
+                            &two_to_one_params,
+                            &leaves[index * size..(index + 1) * size],
+                        )
+                        .unwrap();
+                        assert_eq!(subtree_root, rebuilt.root());
+                    }
+
+                    let proof = tree.generate_subtree_proof(level, index).unwrap();
+                    assert_eq!(proof.level(), level);
+                    assert!(proof
+                        .verify(&two_to_one_params, &root, &subtree_root)
+                        .unwrap());
+                    assert!(!proof
+                        .verify(&two_to_one_params, &root, &(subtree_root + F::one()))
+                        .unwrap());
+                    // the index should fit in the level
+                    let mut moved = proof.clone();
+                    moved.index = index + (1 << level);
+                    assert!(!moved
+                        .verify(&two_to_one_params, &root, &subtree_root)
+                        .unwrap());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn graft_subtree_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+
+        // sharded provers each build a subtree, which a coordinator grafts into the global tree
+        let leaves: Vec<Vec<F>> = (0..16).map(|_| vec![F::rand(&mut rng)]).collect();
+        let tree = FieldMT::new(&leaf_crh_params, &two_to_one_params, &leaves).unwrap();
+        let mut global = FieldMT::blank(&leaf_crh_params, &two_to_one_params, 5)
+            .unwrap()
+            .with_root_history(8);
+        for (index, shard) in leaves.chunks(4).enumerate() {
+            let subtree = FieldMT::new(&leaf_crh_params, &two_to_one_params, shard).unwrap();
+            global.graft_subtree(2, index, &subtree).unwrap();
+            assert!(global.is_known_root(&global.root()));
+        }
+        assert_eq!(global.root(), tree.root());
+        assert!(global.verify_nodes().unwrap());
+        assert_eq!(global.root_history().unwrap().len(), 5);
+
+        let num_leaves = 13;
+        let mut leaves: Vec<Vec<F>> = (0..num_leaves)
+            .map(|_| vec![F::rand(&mut rng), F::rand(&mut rng)])
+            .collect();
+        let paddings = [
+            LeafPadding::DefaultDigest,
+            LeafPadding::Sentinel(F::rand(&mut rng)),
+            LeafPadding::DuplicateOdd,
+        ];
+        for padding in paddings {
+            let mut tree = FieldMT::new_with_padding(
+                &leaf_crh_params,
+                &two_to_one_params,
+                &leaves,
+                padding.clone(),
+            )
+            .unwrap();
+            for leaf in &mut leaves[8..12] {
+                *leaf = vec![F::rand(&mut rng)];
+            }
+            let expected = FieldMT::new_with_padding(
+                &leaf_crh_params,
+                &two_to_one_params,
+                &leaves,
+                padding.clone(),
+            )
+            .unwrap();
+
+            tree.graft_subtree(2, 2, &expected.subtree(2, 2).unwrap())
+                .unwrap();
+            assert_eq!(tree.root(), expected.root());
+            assert!(tree.verify_nodes().unwrap());
+            for i in 0..num_leaves {
+                assert_eq!(
+                    tree.generate_proof(i).unwrap(),
+                    expected.generate_proof(i).unwrap()
+                );
+            }
+        }
+    }
+}
+
+mod streaming_tests {
+    use crate::{
+        crh::poseidon,
+        merkle_tree::{
+            tests::test_utils::poseidon_parameters, Config, IdentityDigestConverter,
+            MerkleRootBuilder, MerkleTree,
+        },
+    };
+    use ark_std::{test_rng, UniformRand};
+
+    type F = ark_ed_on_bls12_381::Fr;
+    type H = poseidon::CRH<F>;
+    type TwoToOneH = poseidon::TwoToOneCRH<F>;
+
+    struct FieldMTConfig;
+    impl Config for FieldMTConfig {
+        type Leaf = [F];
+        type LeafDigest = F;
+        type LeafInnerDigestConverter = IdentityDigestConverter<F>;
+        type InnerDigest = F;
+        type LeafHash = H;
+        type TwoToOneHash = TwoToOneH;
+    }
+
+    type FieldMT = MerkleTree<FieldMTConfig>;
+
+    #[test]
+    fn matches_dense_tree_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+
+        for num_leaves in [1usize, 2, 3, 5, 8, 13, 17, 32] {
+            let leaves: Vec<Vec<F>> = (0..num_leaves)
+                .map(|_| vec![F::rand(&mut rng), F::rand(&mut rng)])
+                .collect();
+            let tree = FieldMT::new(&leaf_crh_params, &two_to_one_params, &leaves).unwrap();
+            let tracked = [num_leaves - 1, 0, num_leaves / 2, 0];
+
+            let mut builder =
+                MerkleRootBuilder::<FieldMTConfig>::new(&leaf_crh_params, &two_to_one_params)
+                    .with_tracked_indices(tracked);
+            builder.extend(&leaves).unwrap();
+            assert_eq!(builder.num_leaves(), num_leaves);
+            let (root, paths) = builder.finish().unwrap();
+            assert_eq!(root, tree.root());
+
+            let mut indices = tracked.to_vec();
+            indices.sort_unstable();
+            indices.dedup();
+            assert_eq!(paths.len(), indices.len());
+            for (path, index) in paths.iter().zip(indices) {
+                assert_eq!(path, &tree.generate_proof(index).unwrap());
+                assert!(path
+                    .verify(
+                        &leaf_crh_params,
+                        &two_to_one_params,
+                        &root,
+                        leaves[index].as_slice()
+                    )
+                    .unwrap());
+            }
+
+            // pushing the leaves one by one gives the same root
+            let mut builder =
+                MerkleRootBuilder::<FieldMTConfig>::new(&leaf_crh_params, &two_to_one_params);
+            for leaf in &leaves {
+                builder.push(leaf).unwrap();
+            }
+            assert_eq!(builder.finish().unwrap().0, tree.root());
+        }
+    }
+}
+
+mod batch_update_tests {
+    use crate::{
+        crh::poseidon,
+        merkle_tree::{
+            tests::test_utils::poseidon_parameters, Config, IdentityDigestConverter, LeafPadding,
+            MerkleTree,
+        },
+    };
+    use ark_std::{test_rng, One, UniformRand};
+
+    type F = ark_ed_on_bls12_381::Fr;
+    type H = poseidon::CRH<F>;
+    type TwoToOneH = poseidon::TwoToOneCRH<F>;
+
+    struct FieldMTConfig;
+    impl Config for FieldMTConfig {
+        type Leaf = [F];
+        type LeafDigest = F;
+        type LeafInnerDigestConverter = IdentityDigestConverter<F>;
+        type InnerDigest = F;
+        type LeafHash = H;
+        type TwoToOneHash = TwoToOneH;
+    }
+
+    type FieldMT = MerkleTree<FieldMTConfig>;
+
+    #[test]
+    fn batch_update_proof_test() {
+        let mut rng = test_rng();
+        let leaf_crh_params = poseidon_parameters();
+        let two_to_one_params = leaf_crh_params.clone();
+        let paddings = [
+            LeafPadding::DefaultDigest,
+            LeafPadding::Sentinel(F::rand(&mut rng)),
+        ];
+        for padding in paddings {
+            let mut leaves: Vec<Vec<F>> = (0..7)
+                .map(|_| vec![F::rand(&mut rng), F::rand(&mut rng)])
+                .collect();
+            let mut tree =
+                FieldMT::new_with_padding(&leaf_crh_params, &two_to_one_params, &leaves, padding)
+                    .unwrap();
+            let mut expected = tree.clone();
+            let old_root = tree.root();
+
+            // the same index may be updated several times
+            let updates: Vec<(usize, Vec<F>)> = [2, 5, 2, 6]
+                .iter()
+                .map(|&i| (i, vec![F::rand(&mut rng)]))
+                .collect();
+            let mut old_leaves = Vec::new();
+            let mut new_leaves = Vec::new();
+            for (i, leaf) in &updates {
+                old_leaves.push(leaves[*i].clone());
+                new_leaves.push(leaf.clone());
+                leaves[*i] = leaf.clone();
+                expected.update(*i, leaf).unwrap();
+            }
+
+            let proof = tree.update_with_proof(&updates).unwrap();
+            assert_eq!(proof.len(), updates.len());
+            assert_eq!(tree.root(), expected.root());
+            let new_root = tree.root();
+            assert!(proof
+                .verify(
+                    &leaf_crh_params,
+                    &two_to_one_params,
+                    &old_root,
+                    &new_root,
+                    &old_leaves,
+                    &new_leaves
+                )
+                .unwrap());
+
+            // a wrong root, a wrong old leaf or reordered updates are rejected
+            assert!(!proof
+                .verify(
+                    &leaf_crh_params,
+                    &two_to_one_params,
+                    &old_root,
+                    &(new_root + F::one()),
+                    &old_leaves,
+                    &new_leaves
+                )
+                .unwrap());
+            let mut wrong_leaves = old_leaves.clone();
+            wrong_leaves[1][0] += F::one();
+            assert!(!proof
+                .verify(
+                    &leaf_crh_params,
+                    &two_to_one_params,
+                    &old_root,
+                    &new_root,
+                    &wrong_leaves,
+                    &new_leaves
+                )
+                .unwrap());
+            let mut reordered = proof.clone();
+            reordered.paths.swap(0, 1);
+            old_leaves.swap(0, 1);
+            new_leaves.swap(0, 1);
+            assert!(!reordered
+                .verify(
+                    &leaf_crh_params,
+                    &two_to_one_params,
+                    &old_root,
+                    &new_root,
+                    &old_leaves,
+                    &new_leaves
+                )
+                .unwrap());
+        }
+    }
+}
@@ -0,0 +1,394 @@
+use crate::sponge::anemoi::{AnemoiConfig, AnemoiSponge};
+use crate::sponge::constraints::AbsorbGadget;
+use crate::sponge::constraints::{CryptographicSpongeVar, SpongeWithGadget};
+use crate::sponge::DuplexSpongeMode;
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::gr1cs::{ConstraintSystemRef, SynthesisError};
+
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+
+#[derive(Clone)]
+/// Constraints for the Anemoi sponge.
+pub struct AnemoiSpongeVar<F: PrimeField> {
+    /// Constraint system
+    pub cs: ConstraintSystemRef<F>,
+
+    /// Sponge Parameters
+    pub parameters: AnemoiConfig<F>,
+
+    /// The sponge's state
+    pub state: Vec<FpVar<F>>,
+    /// The mode
+    pub mode: DuplexSpongeMode,
+}
+
+impl<F: PrimeField> SpongeWithGadget<F> for AnemoiSponge<F> {
+    type Var = AnemoiSpongeVar<F>;
+}
+
+impl<F: PrimeField> AnemoiSpongeVar<F> {
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn apply_mds(&self, elems: &[FpVar<F>]) -> Vec<FpVar<F>> {
+        self.parameters
+            .mds
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .zip(elems)
+                    .fold(FpVar::zero(), |acc, (m, elem)| acc + elem * *m)
+            })
+            .collect()
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn apply_linear_layer(&self, state: &mut [FpVar<F>]) -> Result<(), SynthesisError> {
+        let num_columns = state.len() / 2;
+        let (x, y) = state.split_at_mut(num_columns);
+        y.rotate_left(1);
+        let new_x = self.apply_mds(x);
+        let new_y = self.apply_mds(y);
+        for i in 0..num_columns {
+            y[i] = &new_x[i] + &new_y[i];
+            x[i] = &new_x[i] + &y[i];
+        }
+        Ok(())
+    }
+
+    /// Apply the open Flystel to every pair `(x_i, y_i)` of the state. The root
+    /// `t = (x_i - beta * y_i^2)^(1/alpha)` is allocated as a witness, and checked by raising it
+    /// to the power `alpha`.
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn apply_s_box(&self, state: &mut [FpVar<F>]) -> Result<(), SynthesisError> {
+        let num_columns = state.len() / 2;
+        let alpha_inv = self.parameters.alpha_inv.to_u64_digits();
+        let (x, y) = state.split_at_mut(num_columns);
+        for (x, y) in x.iter_mut().zip(y) {
+            *x -= y.square()? * self.parameters.beta;
+            let t = if let FpVar::Constant(c) = &*x {
+                // If the element is a constant, we can just raise it to alpha_inv.
+                FpVar::Constant(c.pow(&alpha_inv))
+            } else {
+                let t = FpVar::new_witness(x.cs(), || x.value().map(|e| e.pow(&alpha_inv)))?;
+                t.pow_by_constant([self.parameters.alpha])?
+                    .enforce_equal(x)?;
+                t
+            };
+            *y -= t;
+            *x += y.square()? * self.parameters.beta + self.parameters.delta;
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn apply_arc(&self, state: &mut [FpVar<F>], round_number: usize) -> Result<(), SynthesisError> {
+        let num_columns = state.len() / 2;
+        let (x, y) = state.split_at_mut(num_columns);
+        for (elem, rc) in x
+            .iter_mut()
+            .zip(&self.parameters.round_constants_x[round_number])
+        {
+            *elem += *rc;
+        }
+        for (elem, rc) in y
+            .iter_mut()
+            .zip(&self.parameters.round_constants_y[round_number])
+        {
+            *elem += *rc;
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn permute(&mut self) -> Result<(), SynthesisError> {
+        let mut state = self.state.clone();
+        for round in 0..self.parameters.rounds {
+            self.apply_arc(&mut state, round)?;
+            self.apply_linear_layer(&mut state)?;
+            self.apply_s_box(&mut state)?;
+        }
+        self.apply_linear_layer(&mut state)?;
+        self.state = state;
+        Ok(())
+    }
+
+    /// Compress `input`, of `rate + capacity` elements, into half as many elements with the Jive
+    /// mode, as in [`AnemoiSponge::jive`].
+    #[tracing::instrument(target = "gr1cs", skip(cs, parameters))]
+    pub fn jive(
+        cs: ConstraintSystemRef<F>,
+        parameters: &AnemoiConfig<F>,
+        input: &[FpVar<F>],
+    ) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        assert_eq!(input.len(), parameters.rate + parameters.capacity);
+        let num_columns = input.len() / 2;
+
+        let mut sponge = Self::new(cs, parameters);
+        sponge.state = input.to_vec();
+        sponge.permute()?;
+        Ok((0..num_columns)
+            .map(|i| {
+                &input[i]
+                    + &input[num_columns + i]
+                    + &sponge.state[i]
+                    + &sponge.state[num_columns + i]
+            })
+            .collect())
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn absorb_internal(
+        &mut self,
+        mut rate_start_index: usize,
+        elements: &[FpVar<F>],
+    ) -> Result<(), SynthesisError> {
+        let mut remaining_elements = elements;
+        loop {
+            // if we can finish in this call
+            if rate_start_index + remaining_elements.len() <= self.parameters.rate {
+                for (i, element) in remaining_elements.iter().enumerate() {
+                    self.state[self.parameters.capacity + i + rate_start_index] += element;
+                }
+                self.mode = DuplexSpongeMode::Absorbing {
+                    next_absorb_index: rate_start_index + remaining_elements.len(),
+                };
+
+                return Ok(());
+            }
+            // otherwise absorb (rate - rate_start_index) elements
+            let num_elements_absorbed = self.parameters.rate - rate_start_index;
+            for (i, element) in remaining_elements
+                .iter()
+                .enumerate()
+                .take(num_elements_absorbed)
+            {
+                self.state[self.parameters.capacity + i + rate_start_index] += element;
+            }
+            self.permute()?;
+            // the input elements got truncated by num elements absorbed
+            remaining_elements = &remaining_elements[num_elements_absorbed..];
+            rate_start_index = 0;
+        }
+    }
+
+    // Squeeze |output| many elements. This does not end in a squeeze
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn squeeze_internal(
+        &mut self,
+        mut rate_start_index: usize,
+        output: &mut [FpVar<F>],
+    ) -> Result<(), SynthesisError> {
+        let mut remaining_output = output;
+        loop {
+            // if we can finish in this call
+            if rate_start_index + remaining_output.len() <= self.parameters.rate {
+                remaining_output.clone_from_slice(
+                    &self.state[self.parameters.capacity + rate_start_index
+                        ..(self.parameters.capacity + remaining_output.len() + rate_start_index)],
+                );
+                self.mode = DuplexSpongeMode::Squeezing {
+                    next_squeeze_index: rate_start_index + remaining_output.len(),
+                };
+                return Ok(());
+            }
+            // otherwise squeeze (rate - rate_start_index) elements
+            let num_elements_squeezed = self.parameters.rate - rate_start_index;
+            remaining_output[..num_elements_squeezed].clone_from_slice(
+                &self.state[self.parameters.capacity + rate_start_index
+                    ..(self.parameters.capacity + num_elements_squeezed + rate_start_index)],
+            );
+
+            // Unless we are done with squeezing in this call, permute.
+            if remaining_output.len() != self.parameters.rate {
+                self.permute()?;
+            }
+            // Repeat with updated output slices and rate start index
+            remaining_output = &mut remaining_output[num_elements_squeezed..];
+            rate_start_index = 0;
+        }
+    }
+}
+
+impl<F: PrimeField> CryptographicSpongeVar<F, AnemoiSponge<F>> for AnemoiSpongeVar<F> {
+    type Parameters = AnemoiConfig<F>;
+
+    fn new(cs: ConstraintSystemRef<F>, parameters: &AnemoiConfig<F>) -> Self {
+        let zero = FpVar::<F>::zero();
+        let state = vec![zero; parameters.rate + parameters.capacity];
+        let mode = DuplexSpongeMode::Absorbing {
+            next_absorb_index: 0,
+        };
+
+        Self {
+            cs,
+            parameters: parameters.clone(),
+            state,
+            mode,
+        }
+    }
+
+    fn cs(&self) -> ConstraintSystemRef<F> {
+        self.cs.clone()
+    }
+
+    fn absorb(&mut self, input: &impl AbsorbGadget<F>) -> Result<(), SynthesisError> {
+        let input = input.to_sponge_field_elements()?;
+        if input.is_empty() {
+            return Ok(());
+        }
+
+        match self.mode {
+            DuplexSpongeMode::Absorbing { next_absorb_index } => {
+                let mut absorb_index = next_absorb_index;
+                if absorb_index == self.parameters.rate {
+                    self.permute()?;
+                    absorb_index = 0;
+                }
+                self.absorb_internal(absorb_index, input.as_slice())?;
+            }
+            DuplexSpongeMode::Squeezing {
+                next_squeeze_index: _,
+            } => {
+                self.permute()?;
+                self.absorb_internal(0, input.as_slice())?;
+            }
+        };
+
+        Ok(())
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        let usable_bytes = ((F::MODULUS_BIT_SIZE - 1) / 8) as usize;
+
+        let num_elements = (num_bytes + usable_bytes - 1) / usable_bytes;
+        let src_elements = self.squeeze_field_elements(num_elements)?;
+
+        let mut bytes: Vec<UInt8<F>> = Vec::with_capacity(usable_bytes * num_elements);
+        for elem in &src_elements {
+            bytes.extend_from_slice(&elem.to_bytes_le()?[..usable_bytes]);
+        }
+
+        bytes.truncate(num_bytes);
+        Ok(bytes)
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn squeeze_bits(&mut self, num_bits: usize) -> Result<Vec<Boolean<F>>, SynthesisError> {
+        let usable_bits = (F::MODULUS_BIT_SIZE - 1) as usize;
+
+        let num_elements = (num_bits + usable_bits - 1) / usable_bits;
+        let src_elements = self.squeeze_field_elements(num_elements)?;
+
+        let mut bits: Vec<Boolean<F>> = Vec::with_capacity(usable_bits * num_elements);
+        for elem in &src_elements {
+            bits.extend_from_slice(&elem.to_bits_le()?[..usable_bits]);
+        }
+
+        bits.truncate(num_bits);
+        Ok(bits)
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn squeeze_field_elements(
+        &mut self,
+        num_elements: usize,
+    ) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        let zero = FpVar::zero();
+        let mut squeezed_elems = vec![zero; num_elements];
+        match self.mode {
+            DuplexSpongeMode::Absorbing {
+                next_absorb_index: _,
+            } => {
+                self.permute()?;
+                self.squeeze_internal(0, &mut squeezed_elems)?;
+            }
+            DuplexSpongeMode::Squeezing { next_squeeze_index } => {
+                let mut squeeze_index = next_squeeze_index;
+                if squeeze_index == self.parameters.rate {
+                    self.permute()?;
+                    squeeze_index = 0;
+                }
+                self.squeeze_internal(squeeze_index, &mut squeezed_elems)?;
+            }
+        };
+
+        Ok(squeezed_elems)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sponge::anemoi::constraints::AnemoiSpongeVar;
+    use crate::sponge::anemoi::{AnemoiConfig, AnemoiSponge};
+    use crate::sponge::constraints::CryptographicSpongeVar;
+    use crate::sponge::test::Fr;
+    use crate::sponge::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_ff::UniformRand;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::gr1cs::ConstraintSystem;
+    use ark_relations::*;
+    use ark_std::test_rng;
+
+    #[test]
+    fn absorb_test() {
+        let mut rng = test_rng();
+        for (rate, capacity) in [(1, 1), (3, 1)] {
+            let cs = ConstraintSystem::new_ref();
+            let sponge_params = AnemoiConfig::<Fr>::generate(rate, capacity, 128).unwrap();
+
+            let absorb1: Vec<_> = (0..10).map(|_| Fr::rand(&mut rng)).collect();
+            let absorb1_var: Vec<_> = absorb1
+                .iter()
+                .map(|v| FpVar::new_input(ns!(cs, "absorb1"), || Ok(*v)).unwrap())
+                .collect();
+
+            let absorb2: Vec<_> = (0..8).map(|i| vec![i, i + 1, i + 2]).collect();
+            let absorb2_var: Vec<_> = absorb2
+                .iter()
+                .map(|v| UInt8::new_input_vec(ns!(cs, "absorb2"), v).unwrap())
+                .collect();
+
+            let mut native_sponge = AnemoiSponge::<Fr>::new(&sponge_params);
+            let mut constraint_sponge = AnemoiSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+
+            native_sponge.absorb(&absorb1);
+            constraint_sponge.absorb(&absorb1_var).unwrap();
+
+            let squeeze1 = native_sponge.squeeze_native_field_elements(1);
+            let squeeze2 = constraint_sponge.squeeze_field_elements(1).unwrap();
+
+            assert_eq!(squeeze2.value().unwrap(), squeeze1);
+            assert!(cs.is_satisfied().unwrap());
+
+            native_sponge.absorb(&absorb2);
+            constraint_sponge.absorb(&absorb2_var).unwrap();
+
+            let squeeze1 = native_sponge.squeeze_native_field_elements(5);
+            let squeeze2 = constraint_sponge.squeeze_field_elements(5).unwrap();
+
+            assert_eq!(squeeze2.value().unwrap(), squeeze1);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn jive_test() {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+        let params = AnemoiConfig::<Fr>::generate(2, 2, 128).unwrap();
+        let input: Vec<_> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+        let input_var: Vec<_> = input
+            .iter()
+            .map(|v| FpVar::new_witness(ns!(cs, "input"), || Ok(*v)).unwrap())
+            .collect();
+
+        let output = AnemoiSponge::jive(&params, &input);
+        let output_var = AnemoiSpongeVar::jive(cs.clone(), &params, &input_var).unwrap();
+        assert_eq!(output_var.value().unwrap(), output);
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
@@ -0,0 +1,749 @@
+use crate::{
+    sponge::{
+        field_cast,
+        poseidon::find_poseidon_alpha,
+        rescue::{binomial, find_alpha_inv},
+        squeeze_field_elements_with_sizes_default_impl, Absorb, CryptographicSponge,
+        DuplexSpongeMode, FieldBasedCryptographicSponge, FieldElementSize, SpongeExt,
+    },
+    Error,
+};
+use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+use ark_std::{any::TypeId, boxed::Box};
+use num_bigint::BigUint;
+
+/// constraints for Anemoi
+#[cfg(feature = "constraints")]
+pub mod constraints;
+
+/// The first 76 decimal digits of pi, from which the round constants are derived.
+const PI_0: &[u8] = b"1415926535897932384626433832795028841971693993751058209749445923078164062862";
+/// The next 76 decimal digits of pi, from which the round constants are derived.
+const PI_1: &[u8] = b"0899862803482534211706798214808651328230664709384460955058223172535940812848";
+
+/// Config for the Anemoi permutation, as specified in the [paper](https://eprint.iacr.org/2022/840.pdf).
+///
+/// The state of `rate + capacity` elements is split into two halves `X` and `Y` of `l` elements,
+/// and every round applies the open Flystel to each pair `(x_i, y_i)`, with the quadratic
+/// functions `Q_gamma(y) = beta * y^2` and `Q_delta(y) = beta * y^2 + delta`.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct AnemoiConfig<F: PrimeField> {
+    /// Number of rounds.
+    pub rounds: usize,
+    /// Exponent used in the Flystel.
+    pub alpha: u64,
+    /// Inverse of `alpha` modulo `p - 1`.
+    pub alpha_inv: BigUint,
+    /// Multiplier of the quadratic functions of the Flystel, which is the generator `g` of the
+    /// paper.
+    pub beta: F,
+    /// Constant of the quadratic function closing the Flystel, which is `g^-1` in the paper.
+    pub delta: F,
+    /// The `l x l` matrix applied to `X`, and to `Y` rotated by one element.
+    pub mds: Vec<Vec<F>>,
+    /// Additive round constants of `X`, indexed by `round_constants_x[round_num][column]`.
+    pub round_constants_x: Vec<Vec<F>>,
+    /// Additive round constants of `Y`, indexed by `round_constants_y[round_num][column]`.
+    pub round_constants_y: Vec<Vec<F>>,
+    /// The rate (in terms of number of field elements).
+    pub rate: usize,
+    /// The capacity (in terms of number of field elements).
+    pub capacity: usize,
+}
+
+impl<F: PrimeField> AnemoiConfig<F> {
+    /// Initialize the parameter for Anemoi Sponge.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        rounds: usize,
+        alpha: u64,
+        alpha_inv: BigUint,
+        beta: F,
+        delta: F,
+        mds: Vec<Vec<F>>,
+        round_constants_x: Vec<Vec<F>>,
+        round_constants_y: Vec<Vec<F>>,
+        rate: usize,
+        capacity: usize,
+    ) -> Self {
+        let width = rate + capacity;
+        assert_eq!(width % 2, 0, "the width should be even");
+        let num_columns = width / 2;
+        assert_eq!(mds.len(), num_columns);
+        for item in &mds {
+            assert_eq!(item.len(), num_columns);
+        }
+        assert_eq!(round_constants_x.len(), rounds);
+        assert_eq!(round_constants_y.len(), rounds);
+        for item in round_constants_x.iter().chain(&round_constants_y) {
+            assert_eq!(item.len(), num_columns);
+        }
+        Self {
+            rounds,
+            alpha,
+            alpha_inv,
+            beta,
+            delta,
+            mds,
+            round_constants_x,
+            round_constants_y,
+            rate,
+            capacity,
+        }
+    }
+
+    /// Generate the parameters of Anemoi for a state of `rate + capacity` elements and a security
+    /// level of `security_level` bits, as in the reference implementation of the
+    /// [paper](https://eprint.iacr.org/2022/840.pdf).
+    ///
+    /// `alpha` is the smallest integer coprime with `p - 1`, `g` is `F::GENERATOR`, the number of
+    /// rounds is given by [`find_anemoi_rounds`], the matrix by [`find_anemoi_mds`] and the round
+    /// constants by [`find_anemoi_round_constants`]. For the scalar fields of BLS12-381 and BN254,
+    /// this gives `alpha = 5`, and for the one of BLS12-377, `alpha = 11`.
+    ///
+    /// Returns an error if the width is odd, if it is more than 8, or if `alpha` is more than 11.
+    pub fn generate(rate: usize, capacity: usize, security_level: u64) -> Result<Self, Error> {
+        let width = rate + capacity;
+        if width % 2 == 1 {
+            return Err(anemoi_error("the width should be even"));
+        }
+        let num_columns = width / 2;
+
+        let alpha = find_poseidon_alpha::<F>();
+        let alpha_inv = find_alpha_inv::<F>(alpha);
+        let rounds = find_anemoi_rounds(num_columns, alpha, security_level)?;
+        let mds = find_anemoi_mds(num_columns)?;
+        let (round_constants_x, round_constants_y) =
+            find_anemoi_round_constants(num_columns, alpha, rounds);
+        Ok(Self::new(
+            rounds,
+            alpha,
+            alpha_inv,
+            F::GENERATOR,
+            F::GENERATOR.inverse().unwrap(),
+            mds,
+            round_constants_x,
+            round_constants_y,
+            rate,
+            capacity,
+        ))
+    }
+}
+
+/// Returns an error with the message `msg` for unsupported parameters of Anemoi.
+fn anemoi_error(msg: &'static str) -> Error {
+    let err: Box<dyn ark_std::error::Error + Send + Sync> = msg.into();
+    Error::GenericError(err)
+}
+
+/// Internal function that computes the number of rounds of Anemoi with `num_columns` columns and
+/// the S-box `x^alpha`, for a security level of `security_level` bits.
+///
+/// This is the smallest number of rounds resisting the Gröbner basis attack analyzed in the
+/// [paper](https://eprint.iacr.org/2022/840.pdf), plus 2 rounds for its second model and a margin
+/// of `min(5, num_columns + 1)` rounds, and at least 8.
+///
+/// Returns an error unless `alpha` is 3, 5, 7, 9 or 11.
+pub fn find_anemoi_rounds(
+    num_columns: usize,
+    alpha: u64,
+    security_level: u64,
+) -> Result<usize, Error> {
+    // the degree of regularity of the system depends on alpha through this constant
+    let kappa = match alpha {
+        3 => 1,
+        5 => 2,
+        7 => 4,
+        9 => 7,
+        11 => 9,
+        _ => return Err(anemoi_error("alpha should be 3, 5, 7, 9 or 11")),
+    };
+    let num_columns = num_columns as u64;
+    let target = BigUint::from(1u8) << security_level;
+    let rounds = (1..)
+        .find(|&rounds| {
+            let cost = binomial(4 * num_columns * rounds + kappa, 2 * num_columns * rounds);
+            &cost * &cost >= target
+        })
+        .unwrap();
+    Ok((rounds + 2 + (num_columns + 1).min(5)).max(8) as usize)
+}
+
+/// Internal function that computes the matrix of Anemoi with `num_columns` columns, which is the
+/// matrix of the [paper](https://eprint.iacr.org/2022/840.pdf) for `g = F::GENERATOR`.
+///
+/// Returns an error unless there are 1 to 4 columns.
+pub fn find_anemoi_mds<F: PrimeField>(num_columns: usize) -> Result<Vec<Vec<F>>, Error> {
+    let g = F::GENERATOR;
+    let one = F::one();
+    Ok(match num_columns {
+        1 => vec![vec![one]],
+        2 => vec![vec![one, g], vec![g, g.square() + one]],
+        3 => vec![
+            vec![g + one, one, g + one],
+            vec![one, one, g],
+            vec![g, one, one],
+        ],
+        4 => vec![
+            vec![one, g + one, g, g],
+            vec![g.square(), g + g.square(), g + one, g + g + one],
+            vec![g.square(), g.square(), one, g + one],
+            vec![g + one, g + g + one, g, g + one],
+        ],
+        _ => return Err(anemoi_error("only 1 to 4 columns are supported")),
+    })
+}
+
+/// Internal function that computes the round constants of Anemoi with `num_columns` columns and
+/// the S-box `x^alpha`, for `rounds` rounds.
+///
+/// For the round `r` and the column `i`, the constants are
+/// `C[r][i] = g * pi_0^(2r) + (pi_0^r + pi_1^i)^alpha` and
+/// `D[r][i] = g * pi_1^(2i) + (pi_0^r + pi_1^i)^alpha + g^-1`, where `g` is `F::GENERATOR`,
+/// and `pi_0` and `pi_1` are read from the decimal digits of pi.
+///
+/// Returns the constants `C` of `X` and `D` of `Y`.
+pub fn find_anemoi_round_constants<F: PrimeField>(
+    num_columns: usize,
+    alpha: u64,
+    rounds: usize,
+) -> (Vec<Vec<F>>, Vec<Vec<F>>) {
+    let g = F::GENERATOR;
+    let g_inv = g.inverse().unwrap();
+    let pi_0 = F::from(BigUint::parse_bytes(PI_0, 10).unwrap());
+    let pi_1 = F::from(BigUint::parse_bytes(PI_1, 10).unwrap());
+
+    let mut round_constants_x = Vec::with_capacity(rounds);
+    let mut round_constants_y = Vec::with_capacity(rounds);
+    for r in 0..rounds {
+        let pi_0_r = pi_0.pow([r as u64]);
+        let (x, y): (Vec<F>, Vec<F>) = (0..num_columns)
+            .map(|i| {
+                let pi_1_i = pi_1.pow([i as u64]);
+                let pow_alpha = (pi_0_r + pi_1_i).pow([alpha]);
+                (
+                    g * pi_0_r.square() + pow_alpha,
+                    g * pi_1_i.square() + pow_alpha + g_inv,
+                )
+            })
+            .unzip();
+        round_constants_x.push(x);
+        round_constants_y.push(y);
+    }
+    (round_constants_x, round_constants_y)
+}
+
+#[derive(Clone)]
+/// A duplex sponge based using the Anemoi permutation.
+pub struct AnemoiSponge<F: PrimeField> {
+    /// Sponge Config
+    pub parameters: AnemoiConfig<F>,
+
+    // Sponge State
+    /// Current sponge's state (current elements in the permutation block)
+    pub state: Vec<F>,
+    /// Current mode (whether its absorbing or squeezing)
+    pub mode: DuplexSpongeMode,
+}
+
+impl<F: PrimeField> AnemoiSponge<F> {
+    /// Multiply `elems` with the matrix stored in `AnemoiConfig.mds`.
+    fn apply_mds(&self, elems: &[F]) -> Vec<F> {
+        self.parameters
+            .mds
+            .iter()
+            .map(|row| row.iter().zip(elems).map(|(m, elem)| *m * elem).sum())
+            .collect()
+    }
+
+    /// Apply the linear layer to the state: `X` is multiplied with the matrix, and so is `Y`
+    /// rotated by one element, then the halves are mixed with the Pseudo-Hadamard transform
+    /// `(X, Y) -> (2X + Y, X + Y)`.
+    fn apply_linear_layer(&self, state: &mut [F]) {
+        let num_columns = state.len() / 2;
+        let (x, y) = state.split_at_mut(num_columns);
+        y.rotate_left(1);
+        let new_x = self.apply_mds(x);
+        let new_y = self.apply_mds(y);
+        for i in 0..num_columns {
+            y[i] = new_x[i] + new_y[i];
+            x[i] = new_x[i] + y[i];
+        }
+    }
+
+    /// Apply the open Flystel to every pair `(x_i, y_i)` of the state.
+    fn apply_s_box(&self, state: &mut [F]) {
+        let num_columns = state.len() / 2;
+        let alpha_inv = self.parameters.alpha_inv.to_u64_digits();
+        let (x, y) = state.split_at_mut(num_columns);
+        for (x, y) in x.iter_mut().zip(y) {
+            *x -= self.parameters.beta * y.square();
+            *y -= x.pow(&alpha_inv);
+            *x += self.parameters.beta * y.square() + self.parameters.delta;
+        }
+    }
+
+    /// Apply the additive round constants of the round `round_number` to the state.
+    fn apply_arc(&self, state: &mut [F], round_number: usize) {
+        let num_columns = state.len() / 2;
+        let (x, y) = state.split_at_mut(num_columns);
+        for (elem, rc) in x
+            .iter_mut()
+            .zip(&self.parameters.round_constants_x[round_number])
+        {
+            *elem += rc;
+        }
+        for (elem, rc) in y
+            .iter_mut()
+            .zip(&self.parameters.round_constants_y[round_number])
+        {
+            *elem += rc;
+        }
+    }
+
+    /// The permutation function of the Anemoi Sponge: every round adds the round constants, then
+    /// applies the linear layer and the Flystel, and the linear layer is applied once more at the
+    /// end.
+    fn permute(&mut self) {
+        let mut state = self.state.clone();
+        for round in 0..self.parameters.rounds {
+            self.apply_arc(&mut state, round);
+            self.apply_linear_layer(&mut state);
+            self.apply_s_box(&mut state);
+        }
+        self.apply_linear_layer(&mut state);
+
+        self.state = state;
+    }
+
+    /// Compress `input`, of `rate + capacity` elements, into half as many elements with the Jive
+    /// mode of the [paper](https://eprint.iacr.org/2022/840.pdf): the `i`th output is
+    /// `x_i + y_i + u_i + v_i`, where `(U, V)` is the permutation of `(X, Y) = input`.
+    pub fn jive(parameters: &AnemoiConfig<F>, input: &[F]) -> Vec<F> {
+        assert_eq!(input.len(), parameters.rate + parameters.capacity);
+        let num_columns = input.len() / 2;
+
+        let mut sponge = Self::new(parameters);
+        sponge.state = input.to_vec();
+        sponge.permute();
+        (0..num_columns)
+            .map(|i| {
+                input[i] + input[num_columns + i] + sponge.state[i] + sponge.state[num_columns + i]
+            })
+            .collect()
+    }
+
+    // Absorbs everything in elements, this does not end in an absorbtion.
+    fn absorb_internal(&mut self, mut rate_start_index: usize, elements: &[F]) {
+        let mut remaining_elements = elements;
+
+        loop {
+            // if we can finish in this call
+            if rate_start_index + remaining_elements.len() <= self.parameters.rate {
+                for (i, element) in remaining_elements.iter().enumerate() {
+                    self.state[self.parameters.capacity + i + rate_start_index] += element;
+                }
+                self.mode = DuplexSpongeMode::Absorbing {
+                    next_absorb_index: rate_start_index + remaining_elements.len(),
+                };
+                return;
+            }
+            // otherwise absorb (rate - rate_start_index) elements
+            let num_elements_absorbed = self.parameters.rate - rate_start_index;
+            for (i, element) in remaining_elements
+                .iter()
+                .enumerate()
+                .take(num_elements_absorbed)
+            {
+                self.state[self.parameters.capacity + i + rate_start_index] += element;
+            }
+            self.permute();
+            // the input elements got truncated by num elements absorbed
+            remaining_elements = &remaining_elements[num_elements_absorbed..];
+            rate_start_index = 0;
+        }
+    }
+
+    // Squeeze |output| many elements. This does not end in a squeeze
+    fn squeeze_internal(&mut self, mut rate_start_index: usize, output: &mut [F]) {
+        let mut output_remaining = output;
+        loop {
+            // if we can finish in this call
+            if rate_start_index + output_remaining.len() <= self.parameters.rate {
+                output_remaining.clone_from_slice(
+                    &self.state[self.parameters.capacity + rate_start_index
+                        ..(self.parameters.capacity + output_remaining.len() + rate_start_index)],
+                );
+                self.mode = DuplexSpongeMode::Squeezing {
+                    next_squeeze_index: rate_start_index + output_remaining.len(),
+                };
+                return;
+            }
+            // otherwise squeeze (rate - rate_start_index) elements
+            let num_elements_squeezed = self.parameters.rate - rate_start_index;
+            output_remaining[..num_elements_squeezed].clone_from_slice(
+                &self.state[self.parameters.capacity + rate_start_index
+                    ..(self.parameters.capacity + num_elements_squeezed + rate_start_index)],
+            );
+
+            // Unless we are done with squeezing in this call, permute.
+            if output_remaining.len() != self.parameters.rate {
+                self.permute();
+            }
+            // Repeat with updated output slices
+            output_remaining = &mut output_remaining[num_elements_squeezed..];
+            rate_start_index = 0;
+        }
+    }
+}
+
+impl<F: PrimeField> CryptographicSponge for AnemoiSponge<F> {
+    type Config = AnemoiConfig<F>;
+
+    fn new(parameters: &Self::Config) -> Self {
+        // The initial state of the sponge is all zeros
+        let state = vec![F::zero(); parameters.rate + parameters.capacity];
+
+        // The mode of the sponge is initially set to Absorbing the first element
+        let mode = DuplexSpongeMode::Absorbing {
+            next_absorb_index: 0,
+        };
+
+        Self {
+            parameters: parameters.clone(),
+            state,
+            mode,
+        }
+    }
+
+    fn absorb(&mut self, input: &impl Absorb) {
+        let elems: Vec<F> = input.to_sponge_field_elements_as_vec::<F>();
+        if elems.is_empty() {
+            return;
+        }
+        match self.mode {
+            DuplexSpongeMode::Absorbing { next_absorb_index } => {
+                let mut absorb_index = next_absorb_index;
+                if absorb_index == self.parameters.rate {
+                    self.permute();
+                    absorb_index = 0;
+                }
+
+                self.absorb_internal(absorb_index, elems.as_slice());
+            }
+            DuplexSpongeMode::Squeezing {
+                next_squeeze_index: _,
+            } => {
+                self.permute();
+                self.absorb_internal(0, elems.as_slice());
+            }
+        };
+    }
+
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Vec<u8> {
+        let usable_bytes = ((F::MODULUS_BIT_SIZE - 1) / 8) as usize;
+
+        let num_elements = num_bytes.div_ceil(usable_bytes);
+        let src_elements = self.squeeze_native_field_elements(num_elements);
+
+        let mut bytes: Vec<u8> = Vec::with_capacity(usable_bytes * num_elements);
+        for elem in &src_elements {
+            let elem_bytes = elem.into_bigint().to_bytes_le();
+            bytes.extend_from_slice(&elem_bytes[..usable_bytes]);
+        }
+
+        bytes.truncate(num_bytes);
+        bytes
+    }
+
+    fn squeeze_bits(&mut self, num_bits: usize) -> Vec<bool> {
+        let usable_bits = (F::MODULUS_BIT_SIZE - 1) as usize;
+
+        let num_elements = num_bits.div_ceil(usable_bits);
+        let src_elements = self.squeeze_native_field_elements(num_elements);
+
+        let mut bits: Vec<bool> = Vec::with_capacity(usable_bits * num_elements);
+        for elem in &src_elements {
+            let elem_bits = elem.into_bigint().to_bits_le();
+            bits.extend_from_slice(&elem_bits[..usable_bits]);
+        }
+
+        bits.truncate(num_bits);
+        bits
+    }
+
+    fn squeeze_field_elements_with_sizes<F2: PrimeField>(
+        &mut self,
+        sizes: &[FieldElementSize],
+    ) -> Vec<F2> {
+        if F::characteristic() == F2::characteristic() {
+            // native case
+            let mut buf = Vec::with_capacity(sizes.len());
+            field_cast(
+                &self.squeeze_native_field_elements_with_sizes(sizes),
+                &mut buf,
+            )
+            .unwrap();
+            buf
+        } else {
+            squeeze_field_elements_with_sizes_default_impl(self, sizes)
+        }
+    }
+
+    fn squeeze_field_elements<F2: PrimeField>(&mut self, num_elements: usize) -> Vec<F2> {
+        if TypeId::of::<F>() == TypeId::of::<F2>() {
+            let result = self.squeeze_native_field_elements(num_elements);
+            let mut cast = Vec::with_capacity(result.len());
+            field_cast(&result, &mut cast).unwrap();
+            cast
+        } else {
+            self.squeeze_field_elements_with_sizes::<F2>(
+                vec![FieldElementSize::Full; num_elements].as_slice(),
+            )
+        }
+    }
+}
+
+impl<F: PrimeField> FieldBasedCryptographicSponge<F> for AnemoiSponge<F> {
+    fn squeeze_native_field_elements(&mut self, num_elements: usize) -> Vec<F> {
+        let mut squeezed_elems = vec![F::zero(); num_elements];
+        match self.mode {
+            DuplexSpongeMode::Absorbing {
+                next_absorb_index: _,
+            } => {
+                self.permute();
+                self.squeeze_internal(0, &mut squeezed_elems);
+            }
+            DuplexSpongeMode::Squeezing { next_squeeze_index } => {
+                let mut squeeze_index = next_squeeze_index;
+                if squeeze_index == self.parameters.rate {
+                    self.permute();
+                    squeeze_index = 0;
+                }
+                self.squeeze_internal(squeeze_index, &mut squeezed_elems);
+            }
+        };
+
+        squeezed_elems
+    }
+}
+
+#[derive(Clone)]
+/// Stores the state of an Anemoi Sponge. Does not store any parameter.
+pub struct AnemoiSpongeState<F: PrimeField> {
+    state: Vec<F>,
+    mode: DuplexSpongeMode,
+}
+
+impl<CF: PrimeField> SpongeExt for AnemoiSponge<CF> {
+    type State = AnemoiSpongeState<CF>;
+
+    fn from_state(state: Self::State, params: &Self::Config) -> Self {
+        let mut sponge = Self::new(params);
+        sponge.mode = state.mode;
+        sponge.state = state.state;
+        sponge
+    }
+
+    fn into_state(self) -> Self::State {
+        Self::State {
+            state: self.state,
+            mode: self.mode,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sponge::anemoi::{find_anemoi_mds, find_anemoi_rounds, AnemoiConfig, AnemoiSponge};
+    use crate::sponge::test::{Bn254Fr, Fr};
+    use crate::sponge::{CryptographicSponge, FieldBasedCryptographicSponge, SpongeExt};
+    use ark_ff::{Field, MontFp, PrimeField, UniformRand};
+    use ark_std::test_rng;
+
+    #[test]
+    fn anemoi_rounds_test() {
+        // the numbers of rounds of the paper for 1 to 4 columns
+        for (alpha, rounds) in [(5, [21, 14, 12, 12]), (11, [19, 13, 11, 11])] {
+            for (num_columns, rounds) in rounds.into_iter().enumerate() {
+                assert_eq!(
+                    find_anemoi_rounds(num_columns + 1, alpha, 128).unwrap(),
+                    rounds
+                );
+            }
+        }
+    }
+
+    fn anemoi_parameters_test<F: PrimeField>(alpha: u64, rounds: usize) {
+        let params = AnemoiConfig::<F>::generate(1, 1, 128).unwrap();
+        assert_eq!(params.alpha, alpha);
+        assert_eq!(params.rounds, rounds);
+        assert_eq!(params.beta * params.delta, F::one());
+        // with one column, the first constants are `g + 2^alpha` and `g + 2^alpha + g^-1`
+        let first_x = params.beta + F::from(2u8).pow([alpha]);
+        assert_eq!(params.round_constants_x[0][0], first_x);
+        assert_eq!(params.round_constants_y[0][0], first_x + params.delta);
+
+        let x = F::from(5u8);
+        let alpha_inv = params.alpha_inv.to_u64_digits();
+        assert_eq!(x.pow([alpha]).pow(&alpha_inv), x);
+    }
+
+    #[test]
+    fn bls12_381_fr_anemoi_parameters_test() {
+        anemoi_parameters_test::<Fr>(5, 21);
+    }
+
+    #[test]
+    fn bn254_fr_anemoi_parameters_test() {
+        anemoi_parameters_test::<Bn254Fr>(5, 21);
+    }
+
+    #[test]
+    fn bls12_377_fr_anemoi_parameters_test() {
+        anemoi_parameters_test::<ark_bls12_377::Fr>(11, 19);
+    }
+
+    #[test]
+    fn flystel_test() {
+        // the open Flystel maps (x, y) to (u, v) such that, for t = y - v,
+        // x = beta * y^2 + t^alpha and u = beta * v^2 + t^alpha + delta
+        let mut rng = test_rng();
+        let params = AnemoiConfig::<Fr>::generate(2, 2, 128).unwrap();
+        let sponge = AnemoiSponge::new(&params);
+        let input: Vec<_> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+        let mut output = input.clone();
+        sponge.apply_s_box(&mut output);
+        for i in 0..2 {
+            let (x, y, u, v) = (input[i], input[2 + i], output[i], output[2 + i]);
+            let t_alpha = (y - v).pow([params.alpha]);
+            assert_eq!(x, params.beta * y.square() + t_alpha);
+            assert_eq!(u, params.beta * v.square() + t_alpha + params.delta);
+        }
+    }
+
+    #[test]
+    fn generate_unsupported_test() {
+        assert!(AnemoiConfig::<Fr>::generate(2, 1, 128).is_err());
+        assert!(AnemoiConfig::<Fr>::generate(6, 4, 128).is_err());
+        assert!(find_anemoi_rounds(1, 13, 128).is_err());
+        assert!(find_anemoi_mds::<Fr>(5).is_err());
+    }
+
+    /// Checks the permutation and Jive on the input `0, 1, ..., 2 * num_columns - 1`, against
+    /// values computed with a transcription of the reference implementation of Anemoi.
+    fn anemoi_known_answer_test<F: PrimeField>(num_columns: usize, permutation: &[F], jive: &[F]) {
+        let params = AnemoiConfig::<F>::generate(num_columns, num_columns, 128).unwrap();
+        let input: Vec<_> = (0..2 * num_columns as u64).map(F::from).collect();
+
+        let mut sponge = AnemoiSponge::new(&params);
+        sponge.state = input.clone();
+        sponge.permute();
+        assert_eq!(sponge.state, permutation);
+        assert_eq!(AnemoiSponge::jive(&params, &input), jive);
+    }
+
+    #[test]
+    fn bls12_381_fr_anemoi_known_answer_test() {
+        anemoi_known_answer_test::<Fr>(
+            1,
+            &[
+                MontFp!(
+                    "37939566644748044112433280596073510239782669724025356885235776141741744656328"
+                ),
+                MontFp!(
+                    "13951524311856575411018134259397944466431314853510073603694247882922617763507"
+                ),
+            ],
+            &[MontFp!(
+                "51891090956604619523451414855471454706213984577535430488930024024664362419836"
+            )],
+        );
+        anemoi_known_answer_test::<Fr>(
+            2,
+            &[
+                MontFp!(
+                    "44021340120927626749518453559201134796083279743274081231376256348239775429588"
+                ),
+                MontFp!(
+                    "8337128767000451538129446834772159785466506474580365023382029707195530950333"
+                ),
+                MontFp!(
+                    "39837535471376377428146138317846898080269508531029198824338351556283198641218"
+                ),
+                MontFp!(
+                    "46355861594495246006657713890026012146093012570244922877575834543868841963169"
+                ),
+            ],
+            &[
+                MontFp!(
+                    "31423000417177813698216851368862067038662235773775642233110949204584392886295"
+                ),
+                MontFp!(
+                    "2257115186369507065339420216612206093868966544297650078354205551125791728993"
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn bn254_fr_anemoi_known_answer_test() {
+        anemoi_known_answer_test::<Bn254Fr>(
+            1,
+            &[
+                MontFp!(
+                    "6995676070754298073505697149365187377474094050341312582060694136940159650392"
+                ),
+                MontFp!(
+                    "10444101291739529727188999719572991644093567638232125661335875254003912219036"
+                ),
+            ],
+            &[MontFp!(
+                "17439777362493827800694696868938179021567661688573438243396569390944071869429"
+            )],
+        );
+        anemoi_known_answer_test::<Bn254Fr>(
+            2,
+            &[
+                MontFp!(
+                    "16840360341793359378305936304739730728922958113794239688884616781695281626552"
+                ),
+                MontFp!(
+                    "8559946082104763382128189704788641827186649700482329949876657288559812253584"
+                ),
+                MontFp!(
+                    "2645438872106503422351409233256724201723945814979984642666585905818087677743"
+                ),
+                MontFp!(
+                    "13276480680103599745210071034985113919798525255444116017040110312083702531605"
+                ),
+            ],
+            &[
+                MontFp!(
+                    "19485799213899862800657345537996454930646903928774224331551202687513369304297"
+                ),
+                MontFp!(
+                    "21836426762208363127338260739773755746985174955926445966916767600643514785193"
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_anemoi_sponge_ext() {
+        let mut rng = test_rng();
+        let params = AnemoiConfig::<Fr>::generate(1, 1, 128).unwrap();
+        let input: Vec<_> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+
+        let mut sponge = AnemoiSponge::new(&params);
+        sponge.absorb(&input);
+        let mut sponge2 = AnemoiSponge::from_state(sponge.clone().into_state(), &params);
+        assert_eq!(
+            sponge.squeeze_native_field_elements(3),
+            sponge2.squeeze_native_field_elements(3)
+        );
+    }
+}
@@ -0,0 +1,461 @@
+use crate::sponge::constraints::AbsorbGadget;
+use crate::sponge::constraints::{CryptographicSpongeVar, SpongeWithGadget};
+use crate::sponge::griffin::{GriffinConfig, GriffinSponge};
+use crate::sponge::poseidon2::constraints::Poseidon2SpongeVar;
+use crate::sponge::rescue::constraints::RESCUE_PREDICATE;
+use crate::sponge::DuplexSpongeMode;
+use ark_ff::PrimeField;
+use ark_r1cs_std::fields::fp::FpVar;
+use ark_r1cs_std::prelude::*;
+use ark_relations::gr1cs::{ConstraintSystemRef, SynthesisError};
+
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+
+/// The predicate `a^5 = b` used for the two first S-boxes when `d = 5`, which is the one of
+/// Rescue.
+pub const GRIFFIN_PREDICATE: &str = RESCUE_PREDICATE;
+
+#[derive(Clone)]
+/// Constraints for the Griffin sponge.
+pub struct GriffinSpongeVar<F: PrimeField> {
+    /// Constraint system
+    pub cs: ConstraintSystemRef<F>,
+
+    /// Sponge Parameters
+    pub parameters: GriffinConfig<F>,
+
+    /// The sponge's state
+    pub state: Vec<FpVar<F>>,
+    /// The mode
+    pub mode: DuplexSpongeMode,
+}
+
+impl<F: PrimeField> SpongeWithGadget<F> for GriffinSponge<F> {
+    type Var = GriffinSpongeVar<F>;
+}
+
+impl<F: PrimeField> GriffinSpongeVar<F> {
+    /// Returns `x^d`, or `x^(1/d)` if `is_inverse` is set. When `d = 5` and the constraint system
+    /// registers [`GRIFFIN_PREDICATE`], this costs a single predicate constraint.
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn apply_s_box(&self, x: &FpVar<F>, is_inverse: bool) -> Result<FpVar<F>, SynthesisError> {
+        let d = self.parameters.d;
+        let d_inv = self.parameters.d_inv.to_u64_digits();
+        let FpVar::Var(fp) = x else {
+            // If the input is a constant, we can just raise it to the power.
+            return if is_inverse {
+                x.pow_by_constant(&d_inv)
+            } else {
+                x.pow_by_constant([d])
+            };
+        };
+
+        if d == 5 && fp.cs.has_predicate(GRIFFIN_PREDICATE) {
+            use ark_relations::lc;
+
+            let output = FpVar::new_witness(fp.cs.clone(), || {
+                x.value().map(|e| {
+                    if is_inverse {
+                        e.pow(&d_inv)
+                    } else {
+                        e.pow([d])
+                    }
+                })
+            })?;
+            let FpVar::Var(ref new_fp) = output else {
+                return Err(SynthesisError::AssignmentMissing);
+            };
+            // the predicate enforces that its first argument to the power 5 is the second one
+            let (base, power) = if is_inverse {
+                (new_fp, fp)
+            } else {
+                (fp, new_fp)
+            };
+            fp.cs.enforce_constraint_arity_2(
+                GRIFFIN_PREDICATE,
+                || lc![base.variable],
+                || lc![power.variable],
+            )?;
+            Ok(output)
+        } else if is_inverse {
+            let output = FpVar::new_witness(fp.cs.clone(), || x.value().map(|e| e.pow(&d_inv)))?;
+            output.pow_by_constant([d])?.enforce_equal(x)?;
+            Ok(output)
+        } else {
+            x.pow_by_constant([d])
+        }
+    }
+
+    /// Apply the nonlinear layer to the state. Besides the two S-boxes, every other element costs
+    /// two multiplications.
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn apply_nonlinear_layer(&self, state: &mut [FpVar<F>]) -> Result<(), SynthesisError> {
+        let input = state.to_vec();
+        state[0] = self.apply_s_box(&input[0], true)?;
+        state[1] = self.apply_s_box(&input[1], false)?;
+        let (y_0, y_1) = (state[0].clone(), state[1].clone());
+        for i in 2..state.len() {
+            // L_i = (i - 1) * y_0 + y_1 + x_(i - 1), where the last term is dropped for i = 2
+            let mut l = &y_0 * F::from((i - 1) as u64) + &y_1;
+            if i > 2 {
+                l += &input[i - 1];
+            }
+            let factor =
+                l.square()? + &l * self.parameters.alpha[i - 2] + self.parameters.beta[i - 2];
+            state[i] *= factor;
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn apply_arc(&self, state: &mut [FpVar<F>], round_number: usize) -> Result<(), SynthesisError> {
+        for (elem, rc) in state
+            .iter_mut()
+            .zip(&self.parameters.round_constants[round_number])
+        {
+            *elem += *rc;
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn permute(&mut self) -> Result<(), SynthesisError> {
+        let mut state = self.state.clone();
+        Poseidon2SpongeVar::apply_external_matrix(&mut state)?;
+        for round in 0..self.parameters.rounds {
+            self.apply_nonlinear_layer(&mut state)?;
+            Poseidon2SpongeVar::apply_external_matrix(&mut state)?;
+            if round < self.parameters.rounds - 1 {
+                self.apply_arc(&mut state, round)?;
+            }
+        }
+        self.state = state;
+        Ok(())
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn absorb_internal(
+        &mut self,
+        mut rate_start_index: usize,
+        elements: &[FpVar<F>],
+    ) -> Result<(), SynthesisError> {
+        let mut remaining_elements = elements;
+        loop {
+            // if we can finish in this call
+            if rate_start_index + remaining_elements.len() <= self.parameters.rate {
+                for (i, element) in remaining_elements.iter().enumerate() {
+                    self.state[self.parameters.capacity + i + rate_start_index] += element;
+                }
+                self.mode = DuplexSpongeMode::Absorbing {
+                    next_absorb_index: rate_start_index + remaining_elements.len(),
+                };
+
+                return Ok(());
+            }
+            // otherwise absorb (rate - rate_start_index) elements
+            let num_elements_absorbed = self.parameters.rate - rate_start_index;
+            for (i, element) in remaining_elements
+                .iter()
+                .enumerate()
+                .take(num_elements_absorbed)
+            {
+                self.state[self.parameters.capacity + i + rate_start_index] += element;
+            }
+            self.permute()?;
+            // the input elements got truncated by num elements absorbed
+            remaining_elements = &remaining_elements[num_elements_absorbed..];
+            rate_start_index = 0;
+        }
+    }
+
+    // Squeeze |output| many elements. This does not end in a squeeze
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn squeeze_internal(
+        &mut self,
+        mut rate_start_index: usize,
+        output: &mut [FpVar<F>],
+    ) -> Result<(), SynthesisError> {
+        let mut remaining_output = output;
+        loop {
+            // if we can finish in this call
+            if rate_start_index + remaining_output.len() <= self.parameters.rate {
+                remaining_output.clone_from_slice(
+                    &self.state[self.parameters.capacity + rate_start_index
+                        ..(self.parameters.capacity + remaining_output.len() + rate_start_index)],
+                );
+                self.mode = DuplexSpongeMode::Squeezing {
+                    next_squeeze_index: rate_start_index + remaining_output.len(),
+                };
+                return Ok(());
+            }
+            // otherwise squeeze (rate - rate_start_index) elements
+            let num_elements_squeezed = self.parameters.rate - rate_start_index;
+            remaining_output[..num_elements_squeezed].clone_from_slice(
+                &self.state[self.parameters.capacity + rate_start_index
+                    ..(self.parameters.capacity + num_elements_squeezed + rate_start_index)],
+            );
+
+            // Unless we are done with squeezing in this call, permute.
+            if remaining_output.len() != self.parameters.rate {
+                self.permute()?;
+            }
+            // Repeat with updated output slices and rate start index
+            remaining_output = &mut remaining_output[num_elements_squeezed..];
+            rate_start_index = 0;
+        }
+    }
+}
+
+impl<F: PrimeField> CryptographicSpongeVar<F, GriffinSponge<F>> for GriffinSpongeVar<F> {
+    type Parameters = GriffinConfig<F>;
+
+    fn new(cs: ConstraintSystemRef<F>, parameters: &GriffinConfig<F>) -> Self {
+        let zero = FpVar::<F>::zero();
+        let state = vec![zero; parameters.rate + parameters.capacity];
+        let mode = DuplexSpongeMode::Absorbing {
+            next_absorb_index: 0,
+        };
+
+        Self {
+            cs,
+            parameters: parameters.clone(),
+            state,
+            mode,
+        }
+    }
+
+    fn cs(&self) -> ConstraintSystemRef<F> {
+        self.cs.clone()
+    }
+
+    fn absorb(&mut self, input: &impl AbsorbGadget<F>) -> Result<(), SynthesisError> {
+        let input = input.to_sponge_field_elements()?;
+        if input.is_empty() {
+            return Ok(());
+        }
+
+        match self.mode {
+            DuplexSpongeMode::Absorbing { next_absorb_index } => {
+                let mut absorb_index = next_absorb_index;
+                if absorb_index == self.parameters.rate {
+                    self.permute()?;
+                    absorb_index = 0;
+                }
+                self.absorb_internal(absorb_index, input.as_slice())?;
+            }
+            DuplexSpongeMode::Squeezing {
+                next_squeeze_index: _,
+            } => {
+                self.permute()?;
+                self.absorb_internal(0, input.as_slice())?;
+            }
+        };
+
+        Ok(())
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        let usable_bytes = ((F::MODULUS_BIT_SIZE - 1) / 8) as usize;
+
+        let num_elements = (num_bytes + usable_bytes - 1) / usable_bytes;
+        let src_elements = self.squeeze_field_elements(num_elements)?;
+
+        let mut bytes: Vec<UInt8<F>> = Vec::with_capacity(usable_bytes * num_elements);
+        for elem in &src_elements {
+            bytes.extend_from_slice(&elem.to_bytes_le()?[..usable_bytes]);
+        }
+
+        bytes.truncate(num_bytes);
+        Ok(bytes)
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn squeeze_bits(&mut self, num_bits: usize) -> Result<Vec<Boolean<F>>, SynthesisError> {
+        let usable_bits = (F::MODULUS_BIT_SIZE - 1) as usize;
+
+        let num_elements = (num_bits + usable_bits - 1) / usable_bits;
+        let src_elements = self.squeeze_field_elements(num_elements)?;
+
+        let mut bits: Vec<Boolean<F>> = Vec::with_capacity(usable_bits * num_elements);
+        for elem in &src_elements {
+            bits.extend_from_slice(&elem.to_bits_le()?[..usable_bits]);
+        }
+
+        bits.truncate(num_bits);
+        Ok(bits)
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn squeeze_field_elements(
+        &mut self,
+        num_elements: usize,
+    ) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        let zero = FpVar::zero();
+        let mut squeezed_elems = vec![zero; num_elements];
+        match self.mode {
+            DuplexSpongeMode::Absorbing {
+                next_absorb_index: _,
+            } => {
+                self.permute()?;
+                self.squeeze_internal(0, &mut squeezed_elems)?;
+            }
+            DuplexSpongeMode::Squeezing { next_squeeze_index } => {
+                let mut squeeze_index = next_squeeze_index;
+                if squeeze_index == self.parameters.rate {
+                    self.permute()?;
+                    squeeze_index = 0;
+                }
+                self.squeeze_internal(squeeze_index, &mut squeezed_elems)?;
+            }
+        };
+
+        Ok(squeezed_elems)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sponge::constraints::CryptographicSpongeVar;
+    use crate::sponge::griffin::constraints::{GriffinSpongeVar, GRIFFIN_PREDICATE};
+    use crate::sponge::griffin::{GriffinConfig, GriffinSponge};
+    use crate::sponge::test::Fr;
+    use crate::sponge::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_ff::{Field, UniformRand};
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::gr1cs::{
+        predicate::PredicateConstraintSystem, ConstraintSystem, ConstraintSystemRef,
+    };
+    use ark_relations::*;
+    use ark_std::test_rng;
+
+    #[test]
+    fn absorb_test() {
+        let mut rng = test_rng();
+        // a width of 3 uses the small linear layer, and a width of 8 the one built from `M4`
+        for rate in [2, 7] {
+            let cs = ConstraintSystem::new_ref();
+            let sponge_params = GriffinConfig::<Fr>::generate(rate, 1, 128).unwrap();
+
+            let absorb1: Vec<_> = (0..20).map(|_| Fr::rand(&mut rng)).collect();
+            let absorb1_var: Vec<_> = absorb1
+                .iter()
+                .map(|v| FpVar::new_input(ns!(cs, "absorb1"), || Ok(*v)).unwrap())
+                .collect();
+
+            let absorb2: Vec<_> = (0..8).map(|i| vec![i, i + 1, i + 2]).collect();
+            let absorb2_var: Vec<_> = absorb2
+                .iter()
+                .map(|v| UInt8::new_input_vec(ns!(cs, "absorb2"), v).unwrap())
+                .collect();
+
+            let mut native_sponge = GriffinSponge::<Fr>::new(&sponge_params);
+            let mut constraint_sponge = GriffinSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+
+            native_sponge.absorb(&absorb1);
+            constraint_sponge.absorb(&absorb1_var).unwrap();
+
+            let squeeze1 = native_sponge.squeeze_native_field_elements(1);
+            let squeeze2 = constraint_sponge.squeeze_field_elements(1).unwrap();
+
+            assert_eq!(squeeze2.value().unwrap(), squeeze1);
+            assert!(cs.is_satisfied().unwrap());
+
+            native_sponge.absorb(&absorb2);
+            constraint_sponge.absorb(&absorb2_var).unwrap();
+
+            let squeeze1 = native_sponge.squeeze_native_field_elements(10);
+            let squeeze2 = constraint_sponge.squeeze_field_elements(10).unwrap();
+
+            assert_eq!(squeeze2.value().unwrap(), squeeze1);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+
+    #[test]
+    fn constant_test() {
+        let mut rng = test_rng();
+        let sponge_params = GriffinConfig::<Fr>::generate(3, 1, 128).unwrap();
+        let input: Vec<_> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
+        let input_var: Vec<_> = input.iter().map(|v| FpVar::Constant(*v)).collect();
+
+        let mut native_sponge = GriffinSponge::<Fr>::new(&sponge_params);
+        let mut constraint_sponge =
+            GriffinSpongeVar::<Fr>::new(ConstraintSystemRef::None, &sponge_params);
+        native_sponge.absorb(&input);
+        constraint_sponge.absorb(&input_var).unwrap();
+
+        let squeeze1 = native_sponge.squeeze_native_field_elements(2);
+        let squeeze2 = constraint_sponge.squeeze_field_elements(2).unwrap();
+        assert!(squeeze2.iter().all(|elem| elem.is_constant()));
+        assert_eq!(squeeze2.value().unwrap(), squeeze1);
+    }
+
+    /// Register the predicate `a^5 = b` as [`GRIFFIN_PREDICATE`].
+    fn register_griffin_predicate(cs: &ConstraintSystemRef<Fr>) {
+        cs.register_predicate(
+            GRIFFIN_PREDICATE,
+            PredicateConstraintSystem::new_polynomial_predicate_cs(
+                2,
+                vec![
+                    (Fr::from(1u8), vec![(0, 5)]),
+                    (-Fr::from(1u8), vec![(1, 1)]),
+                ],
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn predicate_test() {
+        let mut rng = test_rng();
+        let sponge_params = GriffinConfig::<Fr>::generate(2, 1, 128).unwrap();
+        let absorb: Vec<_> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
+
+        let mut native_sponge = GriffinSponge::<Fr>::new(&sponge_params);
+        native_sponge.absorb(&absorb);
+        let squeeze = native_sponge.squeeze_native_field_elements(3);
+
+        let mut num_constraints = Vec::new();
+        for use_predicate in [false, true] {
+            let cs = ConstraintSystem::new_ref();
+            if use_predicate {
+                register_griffin_predicate(&cs);
+            }
+            let absorb_var: Vec<_> = absorb
+                .iter()
+                .map(|v| FpVar::new_witness(ns!(cs, "absorb"), || Ok(*v)).unwrap())
+                .collect();
+
+            let mut constraint_sponge = GriffinSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+            constraint_sponge.absorb(&absorb_var).unwrap();
+            let squeeze_var = constraint_sponge.squeeze_field_elements(3).unwrap();
+
+            assert_eq!(squeeze_var.value().unwrap(), squeeze);
+            assert!(cs.is_satisfied().unwrap());
+            num_constraints.push(cs.num_constraints());
+        }
+        // with a width of 3, a round costs 4 constraints for the inverse S-box, 3 for the S-box and
+        // 2 for the last element, or a single predicate constraint for each S-box
+        assert_eq!(4 * num_constraints[0], 9 * num_constraints[1]);
+    }
+
+    #[test]
+    fn predicate_inverse_s_box_test() {
+        // the inverse S-box is enforced with the output as the base of the predicate
+        let mut rng = test_rng();
+        let sponge_params = GriffinConfig::<Fr>::generate(2, 1, 128).unwrap();
+        let cs = ConstraintSystem::new_ref();
+        register_griffin_predicate(&cs);
+        let constraint_sponge = GriffinSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+
+        let x = Fr::rand(&mut rng);
+        let x_var = FpVar::new_witness(cs.clone(), || Ok(x)).unwrap();
+        let y_var = constraint_sponge.apply_s_box(&x_var, true).unwrap();
+        assert_eq!(y_var.value().unwrap().pow([5]), x);
+        assert_eq!(cs.num_constraints(), 1);
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
@@ -0,0 +1,675 @@
+use crate::{
+    sponge::{
+        field_cast,
+        poseidon::find_poseidon_alpha,
+        poseidon2::Poseidon2Sponge,
+        rescue::{binomial, find_alpha_inv},
+        squeeze_field_elements_with_sizes_default_impl, Absorb, CryptographicSponge,
+        DuplexSpongeMode, FieldBasedCryptographicSponge, FieldElementSize, SpongeExt,
+    },
+    Error,
+};
+use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+use ark_std::{any::TypeId, boxed::Box};
+use num_bigint::BigUint;
+use sha3::{
+    digest::{ExtendableOutput, Update, XofReader},
+    Shake128,
+};
+
+/// constraints for Griffin
+#[cfg(feature = "constraints")]
+pub mod constraints;
+
+/// Config for the Griffin permutation, as specified in the [paper](https://eprint.iacr.org/2022/403.pdf).
+///
+/// The width `rate + capacity` of the state should be 3 or a multiple of 4, which fixes the
+/// linear layer. The nonlinear layer maps `x_0` to `x_0^(1/d)`, `x_1` to `x_1^d`, and every other
+/// `x_i` to `x_i * (L_i^2 + alpha_i * L_i + beta_i)`, where `L_i` is a linear combination of the
+/// two first outputs and of `x_(i - 1)`.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct GriffinConfig<F: PrimeField> {
+    /// Number of rounds.
+    pub rounds: usize,
+    /// Exponent used in the two first S-boxes.
+    pub d: u64,
+    /// Inverse of `d` modulo `p - 1`.
+    pub d_inv: BigUint,
+    /// Coefficients `alpha_i` of the nonlinear layer, for the state elements `i >= 2`.
+    pub alpha: Vec<F>,
+    /// Coefficients `beta_i` of the nonlinear layer, for the state elements `i >= 2`.
+    pub beta: Vec<F>,
+    /// Additive round constants, which are added after the linear layer of every round but the
+    /// last one. They are indexed by `round_constants[round_num][state_element_index]`.
+    pub round_constants: Vec<Vec<F>>,
+    /// The rate (in terms of number of field elements).
+    pub rate: usize,
+    /// The capacity (in terms of number of field elements).
+    pub capacity: usize,
+}
+
+impl<F: PrimeField> GriffinConfig<F> {
+    /// Initialize the parameter for Griffin Sponge.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        rounds: usize,
+        d: u64,
+        d_inv: BigUint,
+        alpha: Vec<F>,
+        beta: Vec<F>,
+        round_constants: Vec<Vec<F>>,
+        rate: usize,
+        capacity: usize,
+    ) -> Self {
+        let width = rate + capacity;
+        assert!(
+            width == 3 || width % 4 == 0,
+            "the width should be 3 or a multiple of 4"
+        );
+        assert!(rounds > 0, "there should be at least one round");
+        assert_eq!(alpha.len(), width - 2);
+        assert_eq!(beta.len(), width - 2);
+        assert_eq!(round_constants.len(), rounds - 1);
+        for item in &round_constants {
+            assert_eq!(item.len(), width);
+        }
+        Self {
+            rounds,
+            d,
+            d_inv,
+            alpha,
+            beta,
+            round_constants,
+            rate,
+            capacity,
+        }
+    }
+
+    /// Generate the parameters of Griffin for a state of `rate + capacity` elements and a security
+    /// level of `security_level` bits.
+    ///
+    /// `d` is the smallest integer coprime with `p - 1`, the number of rounds is given by
+    /// [`find_griffin_rounds`], and the round constants and the coefficients of the nonlinear
+    /// layer by [`find_griffin_constants`]. Returns an error if `rate + capacity` is not 3 or a
+    /// multiple of 4.
+    pub fn generate(rate: usize, capacity: usize, security_level: u64) -> Result<Self, Error> {
+        let width = rate + capacity;
+        if width != 3 && (width == 0 || width % 4 != 0) {
+            let err: Box<dyn ark_std::error::Error + Send + Sync> =
+                "the width should be 3 or a multiple of 4".into();
+            return Err(Error::GenericError(err));
+        }
+        let d = find_poseidon_alpha::<F>();
+        let d_inv = find_alpha_inv::<F>(d);
+        let rounds = find_griffin_rounds(width, d, security_level);
+        let (round_constants, alpha, beta) = find_griffin_constants(width, rounds);
+        Ok(Self::new(
+            rounds,
+            d,
+            d_inv,
+            alpha,
+            beta,
+            round_constants,
+            rate,
+            capacity,
+        ))
+    }
+}
+
+/// Internal function that computes the number of rounds of Griffin for a state of `width`
+/// elements and the S-box `x^d`, for a security level of `security_level` bits.
+///
+/// This is the smallest number of rounds `R_GB` resisting the Gröbner basis attack analyzed in
+/// the [paper](https://eprint.iacr.org/2022/403.pdf), with a security margin of 20%:
+/// `ceil(1.2 * max(6, R_GB + 1))`.
+pub fn find_griffin_rounds(width: usize, d: u64, security_level: u64) -> usize {
+    let width = width as u64;
+    let target = BigUint::from(1u8) << security_level;
+    let rounds = (1..)
+        .find(|&rounds| {
+            let cost = binomial(rounds * (d + width) + 1, 1 + width * rounds);
+            &cost * &cost >= target
+        })
+        .unwrap();
+    (6 * (rounds + 1).max(6) as usize).div_ceil(5)
+}
+
+/// Internal function that computes the constants of Griffin for a state of `width` elements and
+/// `rounds` rounds.
+///
+/// The elements are sampled by rejection from the output of SHAKE128 on `Griffin` followed by the
+/// 64-bit little-endian limbs of `p`: first the `rounds - 1` round constants, then a nonzero
+/// `alpha_2`, and a nonzero `beta_2` distinct from it, which is redrawn until
+/// `alpha_2^2 - 4 * beta_2` is not a square, as in the reference implementation. The other
+/// coefficients are `alpha_i = (i - 1) * alpha_2` and
+/// `beta_i = (i - 1)^2 * beta_2`.
+///
+/// Returns the round constants, and the coefficients `alpha_i` and `beta_i`.
+pub fn find_griffin_constants<F: PrimeField>(
+    width: usize,
+    rounds: usize,
+) -> (Vec<Vec<F>>, Vec<F>, Vec<F>) {
+    let mut shake = Shake128::default();
+    shake.update(b"Griffin");
+    for limb in F::MODULUS.as_ref() {
+        shake.update(&limb.to_le_bytes());
+    }
+    let mut reader = shake.finalize_xof();
+
+    let modulus: BigUint = F::MODULUS.into();
+    let num_bits = F::MODULUS_BIT_SIZE as usize;
+    let mut bytes = vec![0u8; num_bits.div_ceil(8)];
+    let mut sample = || loop {
+        reader.read(&mut bytes);
+        // clear the bits above the size of the modulus
+        if num_bits % 8 != 0 {
+            *bytes.last_mut().unwrap() &= (1u8 << (num_bits % 8)) - 1;
+        }
+        let elem = BigUint::from_bytes_le(&bytes);
+        if elem < modulus {
+            return F::from(elem);
+        }
+    };
+
+    let round_constants = (1..rounds)
+        .map(|_| (0..width).map(|_| sample()).collect())
+        .collect();
+
+    let mut sample_nonzero = || loop {
+        let elem = sample();
+        if !elem.is_zero() {
+            return elem;
+        }
+    };
+    let alpha_2 = sample_nonzero();
+    let mut beta_2 = sample_nonzero();
+    while beta_2 == alpha_2 {
+        beta_2 = sample_nonzero();
+    }
+    while !(alpha_2.square() - F::from(4u8) * beta_2)
+        .legendre()
+        .is_qnr()
+    {
+        beta_2 = sample_nonzero();
+    }
+    let (alpha, beta): (Vec<F>, Vec<F>) = (2..width)
+        .map(|i| {
+            let gamma = F::from((i - 1) as u64);
+            (gamma * alpha_2, gamma.square() * beta_2)
+        })
+        .unzip();
+
+    (round_constants, alpha, beta)
+}
+
+#[derive(Clone)]
+/// A duplex sponge based using the Griffin permutation.
+pub struct GriffinSponge<F: PrimeField> {
+    /// Sponge Config
+    pub parameters: GriffinConfig<F>,
+
+    // Sponge State
+    /// Current sponge's state (current elements in the permutation block)
+    pub state: Vec<F>,
+    /// Current mode (whether its absorbing or squeezing)
+    pub mode: DuplexSpongeMode,
+}
+
+impl<F: PrimeField> GriffinSponge<F> {
+    /// Multiply the state with the matrix of the linear layer, which is the external matrix of
+    /// Poseidon2: `circ(2, 1, 1)` for a width of 3, and otherwise the matrix `M4` applied to every
+    /// chunk of 4 elements, to which the sum of the chunks is added.
+    fn apply_linear_layer(state: &mut [F]) {
+        Poseidon2Sponge::apply_external_matrix(state);
+    }
+
+    /// Apply the nonlinear layer to the state.
+    fn apply_nonlinear_layer(&self, state: &mut [F]) {
+        let input = state.to_vec();
+        state[0] = state[0].pow(self.parameters.d_inv.to_u64_digits());
+        state[1] = state[1].pow([self.parameters.d]);
+        let (y_0, y_1) = (state[0], state[1]);
+        for i in 2..state.len() {
+            // L_i = (i - 1) * y_0 + y_1 + x_(i - 1), where the last term is dropped for i = 2
+            let mut l = F::from((i - 1) as u64) * y_0 + y_1;
+            if i > 2 {
+                l += input[i - 1];
+            }
+            state[i] *= l.square() + self.parameters.alpha[i - 2] * l + self.parameters.beta[i - 2];
+        }
+    }
+
+    /// Add the round constants of the round `round_number` to the state.
+    fn apply_arc(&self, state: &mut [F], round_number: usize) {
+        for (elem, rc) in state
+            .iter_mut()
+            .zip(&self.parameters.round_constants[round_number])
+        {
+            *elem += rc;
+        }
+    }
+
+    /// The permutation function of the Griffin Sponge: the linear layer is applied to the input,
+    /// then every round applies the nonlinear and linear layers, and adds the round constants
+    /// unless it is the last one.
+    fn permute(&mut self) {
+        let mut state = self.state.clone();
+        Self::apply_linear_layer(&mut state);
+        for round in 0..self.parameters.rounds {
+            self.apply_nonlinear_layer(&mut state);
+            Self::apply_linear_layer(&mut state);
+            if round < self.parameters.rounds - 1 {
+                self.apply_arc(&mut state, round);
+            }
+        }
+
+        self.state = state;
+    }
+
+    // Absorbs everything in elements, this does not end in an absorbtion.
+    fn absorb_internal(&mut self, mut rate_start_index: usize, elements: &[F]) {
+        let mut remaining_elements = elements;
+
+        loop {
+            // if we can finish in this call
+            if rate_start_index + remaining_elements.len() <= self.parameters.rate {
+                for (i, element) in remaining_elements.iter().enumerate() {
+                    self.state[self.parameters.capacity + i + rate_start_index] += element;
+                }
+                self.mode = DuplexSpongeMode::Absorbing {
+                    next_absorb_index: rate_start_index + remaining_elements.len(),
+                };
+                return;
+            }
+            // otherwise absorb (rate - rate_start_index) elements
+            let num_elements_absorbed = self.parameters.rate - rate_start_index;
+            for (i, element) in remaining_elements
+                .iter()
+                .enumerate()
+                .take(num_elements_absorbed)
+            {
+                self.state[self.parameters.capacity + i + rate_start_index] += element;
+            }
+            self.permute();
+            // the input elements got truncated by num elements absorbed
+            remaining_elements = &remaining_elements[num_elements_absorbed..];
+            rate_start_index = 0;
+        }
+    }
+
+    // Squeeze |output| many elements. This does not end in a squeeze
+    fn squeeze_internal(&mut self, mut rate_start_index: usize, output: &mut [F]) {
+        let mut output_remaining = output;
+        loop {
+            // if we can finish in this call
+            if rate_start_index + output_remaining.len() <= self.parameters.rate {
+                output_remaining.clone_from_slice(
+                    &self.state[self.parameters.capacity + rate_start_index
+                        ..(self.parameters.capacity + output_remaining.len() + rate_start_index)],
+                );
+                self.mode = DuplexSpongeMode::Squeezing {
+                    next_squeeze_index: rate_start_index + output_remaining.len(),
+                };
+                return;
+            }
+            // otherwise squeeze (rate - rate_start_index) elements
+            let num_elements_squeezed = self.parameters.rate - rate_start_index;
+            output_remaining[..num_elements_squeezed].clone_from_slice(
+                &self.state[self.parameters.capacity + rate_start_index
+                    ..(self.parameters.capacity + num_elements_squeezed + rate_start_index)],
+            );
+
+            // Unless we are done with squeezing in this call, permute.
+            if output_remaining.len() != self.parameters.rate {
+                self.permute();
+            }
+            // Repeat with updated output slices
+            output_remaining = &mut output_remaining[num_elements_squeezed..];
+            rate_start_index = 0;
+        }
+    }
+}
+
+impl<F: PrimeField> CryptographicSponge for GriffinSponge<F> {
+    type Config = GriffinConfig<F>;
+
+    fn new(parameters: &Self::Config) -> Self {
+        // The initial state of the sponge is all zeros
+        let state = vec![F::zero(); parameters.rate + parameters.capacity];
+
+        // The mode of the sponge is initially set to Absorbing the first element
+        let mode = DuplexSpongeMode::Absorbing {
+            next_absorb_index: 0,
+        };
+
+        Self {
+            parameters: parameters.clone(),
+            state,
+            mode,
+        }
+    }
+
+    fn absorb(&mut self, input: &impl Absorb) {
+        let elems: Vec<F> = input.to_sponge_field_elements_as_vec::<F>();
+        if elems.is_empty() {
+            return;
+        }
+        match self.mode {
+            DuplexSpongeMode::Absorbing { next_absorb_index } => {
+                let mut absorb_index = next_absorb_index;
+                if absorb_index == self.parameters.rate {
+                    self.permute();
+                    absorb_index = 0;
+                }
+
+                self.absorb_internal(absorb_index, elems.as_slice());
+            }
+            DuplexSpongeMode::Squeezing {
+                next_squeeze_index: _,
+            } => {
+                self.permute();
+                self.absorb_internal(0, elems.as_slice());
+            }
+        };
+    }
+
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Vec<u8> {
+        let usable_bytes = ((F::MODULUS_BIT_SIZE - 1) / 8) as usize;
+
+        let num_elements = num_bytes.div_ceil(usable_bytes);
+        let src_elements = self.squeeze_native_field_elements(num_elements);
+
+        let mut bytes: Vec<u8> = Vec::with_capacity(usable_bytes * num_elements);
+        for elem in &src_elements {
+            let elem_bytes = elem.into_bigint().to_bytes_le();
+            bytes.extend_from_slice(&elem_bytes[..usable_bytes]);
+        }
+
+        bytes.truncate(num_bytes);
+        bytes
+    }
+
+    fn squeeze_bits(&mut self, num_bits: usize) -> Vec<bool> {
+        let usable_bits = (F::MODULUS_BIT_SIZE - 1) as usize;
+
+        let num_elements = num_bits.div_ceil(usable_bits);
+        let src_elements = self.squeeze_native_field_elements(num_elements);
+
+        let mut bits: Vec<bool> = Vec::with_capacity(usable_bits * num_elements);
+        for elem in &src_elements {
+            let elem_bits = elem.into_bigint().to_bits_le();
+            bits.extend_from_slice(&elem_bits[..usable_bits]);
+        }
+
+        bits.truncate(num_bits);
+        bits
+    }
+
+    fn squeeze_field_elements_with_sizes<F2: PrimeField>(
+        &mut self,
+        sizes: &[FieldElementSize],
+    ) -> Vec<F2> {
+        if F::characteristic() == F2::characteristic() {
+            // native case
+            let mut buf = Vec::with_capacity(sizes.len());
+            field_cast(
+                &self.squeeze_native_field_elements_with_sizes(sizes),
+                &mut buf,
+            )
+            .unwrap();
+            buf
+        } else {
+            squeeze_field_elements_with_sizes_default_impl(self, sizes)
+        }
+    }
+
+    fn squeeze_field_elements<F2: PrimeField>(&mut self, num_elements: usize) -> Vec<F2> {
+        if TypeId::of::<F>() == TypeId::of::<F2>() {
+            let result = self.squeeze_native_field_elements(num_elements);
+            let mut cast = Vec::with_capacity(result.len());
+            field_cast(&result, &mut cast).unwrap();
+            cast
+        } else {
+            self.squeeze_field_elements_with_sizes::<F2>(
+                vec![FieldElementSize::Full; num_elements].as_slice(),
+            )
+        }
+    }
+}
+
+impl<F: PrimeField> FieldBasedCryptographicSponge<F> for GriffinSponge<F> {
+    fn squeeze_native_field_elements(&mut self, num_elements: usize) -> Vec<F> {
+        let mut squeezed_elems = vec![F::zero(); num_elements];
+        match self.mode {
+            DuplexSpongeMode::Absorbing {
+                next_absorb_index: _,
+            } => {
+                self.permute();
+                self.squeeze_internal(0, &mut squeezed_elems);
+            }
+            DuplexSpongeMode::Squeezing { next_squeeze_index } => {
+                let mut squeeze_index = next_squeeze_index;
+                if squeeze_index == self.parameters.rate {
+                    self.permute();
+                    squeeze_index = 0;
+                }
+                self.squeeze_internal(squeeze_index, &mut squeezed_elems);
+            }
+        };
+
+        squeezed_elems
+    }
+}
+
+#[derive(Clone)]
+/// Stores the state of an Griffin Sponge. Does not store any parameter.
+pub struct GriffinSpongeState<F: PrimeField> {
+    state: Vec<F>,
+    mode: DuplexSpongeMode,
+}
+
+impl<CF: PrimeField> SpongeExt for GriffinSponge<CF> {
+    type State = GriffinSpongeState<CF>;
+
+    fn from_state(state: Self::State, params: &Self::Config) -> Self {
+        let mut sponge = Self::new(params);
+        sponge.mode = state.mode;
+        sponge.state = state.state;
+        sponge
+    }
+
+    fn into_state(self) -> Self::State {
+        Self::State {
+            state: self.state,
+            mode: self.mode,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sponge::griffin::{
+        find_griffin_constants, find_griffin_rounds, GriffinConfig, GriffinSponge,
+    };
+    use crate::sponge::test::{Bn254Fr, Fr};
+    use crate::sponge::{CryptographicSponge, FieldBasedCryptographicSponge, SpongeExt};
+    use ark_ff::{
+        fields::Fp64, AdditiveGroup, Field, MontBackend, MontConfig, MontFp, PrimeField,
+        UniformRand,
+    };
+    use ark_std::test_rng;
+
+    #[derive(MontConfig)]
+    #[modulus = "103"]
+    #[generator = "5"]
+    pub struct F103Backend;
+    type F103 = Fp64<MontBackend<F103Backend, 1>>;
+
+    #[test]
+    fn griffin_rounds_test() {
+        // the numbers of rounds of the paper for a 128-bit security level
+        for (width, rounds) in [(3, 12), (4, 11), (8, 9)] {
+            assert_eq!(find_griffin_rounds(width, 5, 128), rounds);
+        }
+        assert_eq!(find_griffin_rounds(3, 3, 128), 16);
+    }
+
+    fn griffin_parameters_test<F: PrimeField>(d: u64) {
+        let params = GriffinConfig::<F>::generate(7, 1, 128).unwrap();
+        assert_eq!(params.d, d);
+        assert_eq!(params.rounds, 9);
+        assert_eq!(params.round_constants.len(), 8);
+
+        let (alpha_2, beta_2) = (params.alpha[0], params.beta[0]);
+        assert_ne!(alpha_2, beta_2);
+        assert!((alpha_2.square() - F::from(4u8) * beta_2)
+            .legendre()
+            .is_qnr());
+        for i in 2..8 {
+            let gamma = F::from((i - 1) as u64);
+            assert_eq!(params.alpha[i - 2], gamma * alpha_2);
+            assert_eq!(params.beta[i - 2], gamma.square() * beta_2);
+        }
+
+        let x = F::from(5u8);
+        let d_inv = params.d_inv.to_u64_digits();
+        assert_eq!(x.pow([d]).pow(&d_inv), x);
+    }
+
+    #[test]
+    fn griffin_constants_test() {
+        // `alpha_2^2 - 4 * beta_2` is a square for the first pair drawn modulo 103, so the
+        // reference implementation keeps `alpha_2` and redraws `beta_2`
+        let (_, alpha, beta) = find_griffin_constants::<F103>(3, 2);
+        assert_eq!((alpha[0], beta[0]), (F103::from(25u8), F103::from(6u8)));
+
+        assert!(GriffinConfig::<Fr>::generate(4, 1, 128).is_err());
+        assert!(GriffinConfig::<Fr>::generate(0, 0, 128).is_err());
+    }
+
+    #[test]
+    fn bls12_381_fr_griffin_parameters_test() {
+        griffin_parameters_test::<Fr>(5);
+    }
+
+    #[test]
+    fn bn254_fr_griffin_parameters_test() {
+        griffin_parameters_test::<Bn254Fr>(5);
+    }
+
+    #[test]
+    fn nonlinear_layer_test() {
+        let mut rng = test_rng();
+        let params = GriffinConfig::<Fr>::generate(3, 1, 128).unwrap();
+        let sponge = GriffinSponge::new(&params);
+        let input: Vec<_> = (0..4).map(|_| Fr::rand(&mut rng)).collect();
+        let mut output = input.clone();
+        sponge.apply_nonlinear_layer(&mut output);
+
+        assert_eq!(output[0].pow([params.d]), input[0]);
+        assert_eq!(output[1], input[1].pow([params.d]));
+        let quadratic = |i: usize, l: Fr| l.square() + params.alpha[i - 2] * l + params.beta[i - 2];
+        let l_2 = output[0] + output[1];
+        assert_eq!(output[2], input[2] * quadratic(2, l_2));
+        let l_3 = output[0].double() + output[1] + input[2];
+        assert_eq!(output[3], input[3] * quadratic(3, l_3));
+    }
+
+    /// Checks the permutation on the input `0, 1, ..., width - 1`, against values computed with a
+    /// transcription of the reference implementation of Griffin.
+    fn griffin_known_answer_test<F: PrimeField>(width: usize, permutation: &[F]) {
+        let params = GriffinConfig::<F>::generate(width - 1, 1, 128).unwrap();
+        let mut sponge = GriffinSponge::new(&params);
+        sponge.state = (0..width as u64).map(F::from).collect();
+        sponge.permute();
+        assert_eq!(sponge.state, permutation);
+    }
+
+    #[test]
+    fn bls12_381_fr_griffin_known_answer_test() {
+        griffin_known_answer_test::<Fr>(
+            3,
+            &[
+                MontFp!(
+                    "27379052990992335868007513827616442821891910747512806453448496790052721925738"
+                ),
+                MontFp!(
+                    "24772506163846602410384726533562269659751910620421046453035480040071869301700"
+                ),
+                MontFp!(
+                    "49516412382145609386411188123247611096923270094753177293676945877936768369921"
+                ),
+            ],
+        );
+        griffin_known_answer_test::<Fr>(
+            4,
+            &[
+                MontFp!(
+                    "1840882047755991808373896731338851644736518835427627038957840708411211557540"
+                ),
+                MontFp!(
+                    "25303746546464412429635732420540713335656633548547699580695943833690163355437"
+                ),
+                MontFp!(
+                    "46621439134472835611882535646326582223982828914404616254684103805522339863760"
+                ),
+                MontFp!(
+                    "32592122530591571852875477308938313413954296723163843045081828717940413717807"
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn bn254_fr_griffin_known_answer_test() {
+        griffin_known_answer_test::<Bn254Fr>(
+            3,
+            &[
+                MontFp!(
+                    "15862405785128810275837435502653224425290071258167230490599117376332100235254"
+                ),
+                MontFp!(
+                    "13220756517509979517684528785753328587257706928708746278499548208567338458968"
+                ),
+                MontFp!(
+                    "15550532036911446928426039913328049280239190234626561457568755196858003615133"
+                ),
+            ],
+        );
+        griffin_known_answer_test::<Bn254Fr>(
+            4,
+            &[
+                MontFp!(
+                    "20544038859331404544787671729519018048570470556436814419606674327514183827748"
+                ),
+                MontFp!(
+                    "18272846494271370174275802462566920586625361160015507010935091827383045262603"
+                ),
+                MontFp!(
+                    "14530297028229149652519529419090474658462197462092744782952905773137380666366"
+                ),
+                MontFp!(
+                    "3542500498176385048986584510998669743025996611278823874446117404515672700688"
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn test_griffin_sponge_ext() {
+        let mut rng = test_rng();
+        let params = GriffinConfig::<Fr>::generate(2, 1, 128).unwrap();
+        let input: Vec<_> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+
+        let mut sponge = GriffinSponge::new(&params);
+        sponge.absorb(&input);
+        let mut sponge2 = GriffinSponge::from_state(sponge.clone().into_state(), &params);
+        assert_eq!(
+            sponge.squeeze_native_field_elements(3),
+            sponge2.squeeze_native_field_elements(3)
+        );
+    }
+}
@@ -22,11 +22,34 @@ pub mod rescue;
 /// [cos]: https://eprint.iacr.org/2019/1076
 pub mod poseidon;
 
+/// The sponge for [Poseidon2][p2]
+///
+/// [p2]: https://eprint.iacr.org/2023/323
+pub mod poseidon2;
+
+/// The sponge for [Anemoi][anemoi]
+///
+/// [anemoi]: https://eprint.iacr.org/2022/840
+pub mod anemoi;
+
+/// The sponge for [Griffin][griffin]
+///
+/// [griffin]: https://eprint.iacr.org/2022/403
+pub mod griffin;
+
 /// The sponge for [Merlin][merlin]
 ///
 /// [merlin]: https://merlin.cool/
 pub mod merlin;
 
+/// The [SAFE][safe] API (Sponge API for Field Elements)
+///
+/// [safe]: https://eprint.iacr.org/2023/522
+pub mod safe;
+
+/// Labeled Fiat-Shamir transcripts over Merlin and the algebraic sponges
+pub mod transcript;
+
 #[cfg(test)]
 mod test;
 
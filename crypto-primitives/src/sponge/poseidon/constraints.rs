@@ -1,6 +1,7 @@
 use crate::sponge::{
     constraints::{AbsorbGadget, CryptographicSpongeVar, SpongeWithGadget},
     poseidon::{PoseidonConfig, PoseidonSponge},
+    rescue::constraints::RESCUE_PREDICATE,
     DuplexSpongeMode,
 };
 use ark_ff::PrimeField;
@@ -9,6 +10,9 @@ use ark_relations::gr1cs::{ConstraintSystemRef, SynthesisError};
 #[cfg(not(feature = "std"))]
 use ark_std::vec::Vec;
 
+/// The predicate `a^5 = b` used for the S-boxes when `alpha = 5`, which is the one of Rescue.
+pub const POSEIDON_PREDICATE: &str = RESCUE_PREDICATE;
+
 #[derive(Clone)]
 /// the gadget for Poseidon sponge
 ///
@@ -44,17 +48,49 @@ impl<F: PrimeField> PoseidonSpongeVar<F> {
         // Full rounds apply the S Box (x^alpha) to every element of state
         if is_full_round {
             for state_item in state.iter_mut() {
-                *state_item = state_item.pow_by_constant(&[self.parameters.alpha])?;
+                self.apply_s_box_to_item(state_item)?;
             }
         }
         // Partial rounds apply the S Box (x^alpha) to just the first element of state
         else {
-            state[0] = state[0].pow_by_constant(&[self.parameters.alpha])?;
+            self.apply_s_box_to_item(&mut state[0])?;
         }
 
         Ok(())
     }
 
+    /// Raise `state_item` to the power `alpha`.
+    ///
+    /// When `alpha = 5` and the constraint system registers [`POSEIDON_PREDICATE`], the output is
+    /// a witness related to the input by a single predicate constraint. The input is the linear
+    /// combination of the previous S-box outputs built by the MDS matrix and the round constants,
+    /// so they are folded into the predicate and cost no constraint.
+    fn apply_s_box_to_item(&self, state_item: &mut FpVar<F>) -> Result<(), SynthesisError> {
+        let alpha = self.parameters.alpha;
+        if let FpVar::Var(fp) = &*state_item {
+            if alpha == 5 && fp.cs.has_predicate(POSEIDON_PREDICATE) {
+                use ark_relations::lc;
+
+                let new_state_item =
+                    FpVar::new_witness(fp.cs.clone(), || fp.value().map(|e| e.pow([alpha])))?;
+                let FpVar::Var(ref new_fp) = new_state_item else {
+                    return Err(SynthesisError::AssignmentMissing);
+                };
+                fp.cs.enforce_constraint_arity_2(
+                    POSEIDON_PREDICATE,
+                    || lc![fp.variable],
+                    || lc![new_fp.variable],
+                )?;
+                *state_item = new_state_item;
+                return Ok(());
+            }
+        }
+        // Otherwise, or if the state item is a constant, the S-box is computed with
+        // multiplications.
+        *state_item = state_item.pow_by_constant([alpha])?;
+        Ok(())
+    }
+
     #[tracing::instrument(target = "gr1cs", skip(self))]
     fn apply_ark(&self, state: &mut [FpVar<F>], round_number: usize) -> Result<(), SynthesisError> {
         for (i, state_elem) in state.iter_mut().enumerate() {
@@ -293,15 +329,17 @@ impl<F: PrimeField> CryptographicSpongeVar<F, PoseidonSponge<F>> for PoseidonSpo
 #[cfg(test)]
 mod tests {
     use crate::sponge::constraints::CryptographicSpongeVar;
-    use crate::sponge::poseidon::constraints::PoseidonSpongeVar;
+    use crate::sponge::poseidon::constraints::{PoseidonSpongeVar, POSEIDON_PREDICATE};
     use crate::sponge::poseidon::tests::poseidon_parameters_for_test;
-    use crate::sponge::poseidon::PoseidonSponge;
+    use crate::sponge::poseidon::{find_poseidon_parameters, PoseidonSponge};
     use crate::sponge::test::Fr;
     use crate::sponge::{CryptographicSponge, FieldBasedCryptographicSponge, FieldElementSize};
     use ark_ff::{Field, PrimeField, UniformRand};
     use ark_r1cs_std::fields::fp::FpVar;
     use ark_r1cs_std::prelude::*;
-    use ark_relations::gr1cs::ConstraintSystem;
+    use ark_relations::gr1cs::{
+        predicate::PredicateConstraintSystem, ConstraintSystem, ConstraintSystemRef,
+    };
     use ark_relations::*;
     use ark_std::test_rng;
 
@@ -380,4 +418,72 @@ mod tests {
         let bits = &bits[0];
         assert_eq!(bits.len() as u32, Fr::MODULUS_BIT_SIZE - 1);
     }
+
+    /// Register the predicate `a^5 = b` as [`POSEIDON_PREDICATE`].
+    fn register_poseidon_predicate(cs: &ConstraintSystemRef<Fr>) {
+        cs.register_predicate(
+            POSEIDON_PREDICATE,
+            PredicateConstraintSystem::new_polynomial_predicate_cs(
+                2,
+                vec![
+                    (Fr::from(1u8), vec![(0, 5)]),
+                    (-Fr::from(1u8), vec![(1, 1)]),
+                ],
+            ),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn predicate_test() {
+        let mut rng = test_rng();
+        let sponge_params = find_poseidon_parameters::<Fr>(2, 5, 128);
+        let absorb: Vec<_> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
+
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        native_sponge.absorb(&absorb);
+        let squeeze = native_sponge.squeeze_native_field_elements(3);
+
+        let mut num_constraints = Vec::new();
+        for use_predicate in [false, true] {
+            let cs = ConstraintSystem::new_ref();
+            if use_predicate {
+                register_poseidon_predicate(&cs);
+            }
+            let absorb_var: Vec<_> = absorb
+                .iter()
+                .map(|v| FpVar::new_witness(ns!(cs, "absorb"), || Ok(*v)).unwrap())
+                .collect();
+
+            let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+            constraint_sponge.absorb(&absorb_var).unwrap();
+            let squeeze_var = constraint_sponge.squeeze_field_elements(3).unwrap();
+
+            assert_eq!(squeeze_var.value().unwrap(), squeeze);
+            assert!(cs.is_satisfied().unwrap());
+            num_constraints.push(cs.num_constraints());
+        }
+        // an S-box costs 3 multiplications, or a single predicate constraint
+        assert_eq!(num_constraints[0], 3 * num_constraints[1]);
+    }
+
+    #[test]
+    fn predicate_constant_test() {
+        // constant state elements do not need the predicate
+        let sponge_params = find_poseidon_parameters::<Fr>(2, 5, 128);
+        let cs = ConstraintSystem::new_ref();
+        register_poseidon_predicate(&cs);
+
+        let mut native_sponge = PoseidonSponge::<Fr>::new(&sponge_params);
+        let mut constraint_sponge = PoseidonSpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+        native_sponge.absorb(&Fr::from(3u8));
+        constraint_sponge
+            .absorb(&FpVar::Constant(Fr::from(3u8)))
+            .unwrap();
+
+        let squeeze = native_sponge.squeeze_native_field_elements(2);
+        let squeeze_var = constraint_sponge.squeeze_field_elements(2).unwrap();
+        assert_eq!(squeeze_var.value().unwrap(), squeeze);
+        assert_eq!(cs.num_constraints(), 0);
+    }
 }
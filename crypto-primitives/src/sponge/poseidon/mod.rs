@@ -20,7 +20,7 @@ pub(crate) use tests::poseidon_parameters_for_test;
 pub mod traits;
 pub use traits::*;
 
-mod grain_lfsr;
+pub(super) mod grain_lfsr;
 
 /// Config and RNG used
 #[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
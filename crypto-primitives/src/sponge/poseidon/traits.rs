@@ -145,6 +145,219 @@ pub fn find_poseidon_ark_and_mds<F: PrimeField>(
     (ark, mds)
 }
 
+/// Internal function that computes the Poseidon parameters for a state of `rate + 1` elements and
+/// the S-box `x^alpha`, with the round numbers of [`find_poseidon_round_numbers`] for a security
+/// level of `security_level` bits, and the ark and mds of [`find_poseidon_ark_and_mds`].
+///
+/// No matrix is skipped, as is the case for all the default parameters of this crate.
+pub fn find_poseidon_parameters<F: PrimeField>(
+    rate: usize,
+    alpha: u64,
+    security_level: u64,
+) -> PoseidonConfig<F> {
+    let (full_rounds, partial_rounds) =
+        find_poseidon_round_numbers::<F>(rate, alpha, security_level);
+    let (ark, mds) = find_poseidon_ark_and_mds::<F>(
+        F::MODULUS_BIT_SIZE as u64,
+        rate,
+        full_rounds as u64,
+        partial_rounds as u64,
+        0,
+    );
+    PoseidonConfig::new(full_rounds, partial_rounds, alpha, mds, ark, rate, 1)
+}
+
+/// Returns the smallest `alpha >= 3` such that `x^alpha` is a permutation of `F`, that is such
+/// that `alpha` is coprime with `p - 1`.
+pub fn find_poseidon_alpha<F: PrimeField>() -> u64 {
+    (3..)
+        .step_by(2)
+        .find(|&alpha| gcd(modulus_minus_one_mod::<F>(alpha), alpha) == 1)
+        .unwrap()
+}
+
+/// Internal function that computes the number of full and partial rounds of Poseidon for a state
+/// of `rate + 1` elements and the S-box `x^alpha`, for a security level of `security_level` bits.
+///
+/// This is a port of `calc_round_numbers.py` from the
+/// [reference implementation](https://extgit.iaik.tugraz.at/krypto/hadeshash): among the round
+/// numbers resisting the statistical, interpolation and Gröbner basis attacks analyzed there, it
+/// finds the ones minimizing the number of S-boxes, then adds the security margin of two full
+/// rounds and 7.5% of partial rounds.
+pub fn find_poseidon_round_numbers<F: PrimeField>(
+    rate: usize,
+    alpha: u64,
+    security_level: u64,
+) -> (usize, usize) {
+    assert!(rate > 0, "rate should be positive");
+    assert_eq!(
+        gcd(modulus_minus_one_mod::<F>(alpha), alpha),
+        1,
+        "x^alpha should be a permutation of the field"
+    );
+    let prime_bits = F::MODULUS_BIT_SIZE as u64;
+    let log2_p = log2_of_modulus::<F>();
+    let width = rate as u64 + 1;
+
+    // (cost, full rounds, partial rounds)
+    let mut best: Option<(u64, u64, u64)> = None;
+    for partial_rounds in 1..500 {
+        // adding full rounds only increases the cost, so only the fewest secure ones are kept
+        let full_rounds = (4..100).step_by(2).find(|&full_rounds| {
+            poseidon_rounds_are_secure(
+                prime_bits,
+                log2_p,
+                width,
+                full_rounds,
+                partial_rounds,
+                alpha,
+                security_level,
+            )
+        });
+        if let Some(full_rounds) = full_rounds {
+            let full_rounds = full_rounds + 2;
+            let partial_rounds = ceil(partial_rounds as f64 * 1.075) as u64;
+            let cost = width * full_rounds + partial_rounds;
+            if best.map_or(true, |(best_cost, best_full_rounds, _)| {
+                cost < best_cost || (cost == best_cost && full_rounds < best_full_rounds)
+            }) {
+                best = Some((cost, full_rounds, partial_rounds));
+            }
+        }
+    }
+
+    let (_, full_rounds, partial_rounds) = best.expect("no secure round numbers were found");
+    (full_rounds as usize, partial_rounds as usize)
+}
+
+/// Returns whether the round numbers resist the attacks of `sat_inequiv_alpha` in
+/// `calc_round_numbers.py`, including the Gröbner basis attack of
+/// [ABM23](https://eprint.iacr.org/2023/537).
+fn poseidon_rounds_are_secure(
+    prime_bits: u64,
+    log2_p: f64,
+    width: u64,
+    full_rounds: u64,
+    partial_rounds: u64,
+    alpha: u64,
+    security_level: u64,
+) -> bool {
+    let (t, r_p, m) = (width as f64, partial_rounds as f64, security_level as f64);
+    let log2_alpha = log2(alpha as f64);
+
+    // statistical attack, where `floor(log2(p)) = prime_bits - 1` as `alpha` is odd
+    let r_f_1 = if security_level as i64
+        <= (prime_bits as i64 - 1 - (alpha as i64 - 1) / 2) * (width as i64 + 1)
+    {
+        6
+    } else {
+        10
+    };
+    // interpolation attack, where `ceil(log_alpha(t))` is computed exactly
+    let mut log_alpha_width = 0;
+    let mut power = 1u64;
+    while power < width {
+        power = power.saturating_mul(alpha);
+        log_alpha_width += 1;
+    }
+    let r_f_2 =
+        1 + ceil(m.min(prime_bits as f64) / log2_alpha) + log_alpha_width - partial_rounds as i64;
+    // Gröbner basis attacks
+    let r_f_3 = ceil(m.min(log2_p) / log2_alpha - r_p);
+    let r_f_4 = ceil(t - 1.0 + (m / (t + 1.0)).min(log2_p / 2.0) / log2_alpha - r_p);
+    let r_f_5 = ceil((t - 2.0 + m / (2.0 * log2_alpha) - r_p) / (t - 1.0));
+    let r_f_max = r_f_1.max(r_f_2).max(r_f_3).max(r_f_4).max(r_f_5);
+    if (full_rounds as i64) < r_f_max {
+        return false;
+    }
+
+    // Gröbner basis attack of ABM23, whose cost is `2 * log2(binomial(over, under))`
+    let r_temp = width / 3;
+    let over = (full_rounds - 1) * width
+        + partial_rounds
+        + r_temp
+        + r_temp * full_rounds / 2
+        + partial_rounds
+        + alpha;
+    let under = r_temp * full_rounds / 2 + partial_rounds + alpha;
+    // `ceil(cost) >= security_level`
+    2.0 * log2_binomial(over, under) > m - 1.0
+}
+
+/// Returns `(p - 1) mod n`, where `p` is the modulus of `F`.
+fn modulus_minus_one_mod<F: PrimeField>(n: u64) -> u64 {
+    let p_mod_n = F::MODULUS
+        .as_ref()
+        .iter()
+        .rev()
+        .fold(0u128, |acc, &limb| ((acc << 64) + limb as u128) % n as u128);
+    ((p_mod_n + n as u128 - 1) % n as u128) as u64
+}
+
+fn gcd(mut a: u64, mut b: u64) -> u64 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Returns the binary logarithm of the modulus of `F`.
+fn log2_of_modulus<F: PrimeField>() -> f64 {
+    let modulus = F::MODULUS;
+    let limbs = modulus.as_ref();
+    let top = limbs.iter().rposition(|&limb| limb != 0).unwrap();
+    let mut value = limbs[top] as f64;
+    if top > 0 {
+        value += limbs[top - 1] as f64 / TWO_TO_64;
+    }
+    (64 * top) as f64 + log2(value)
+}
+
+/// Returns the binary logarithm of `binomial(n, k)`, which may not fit in a `f64`.
+fn log2_binomial(n: u64, k: u64) -> f64 {
+    let mut value = 1f64;
+    let mut exponent = 0f64;
+    for i in 1..=k {
+        value = value * (n - k + i) as f64 / i as f64;
+        if value > TWO_TO_64 {
+            value /= TWO_TO_64;
+            exponent += 64.0;
+        }
+    }
+    exponent + log2(value)
+}
+
+const TWO_TO_64: f64 = 18446744073709551616.0;
+
+/// Returns the binary logarithm of a normal `x > 0`, as `f64::log2` requires `std`.
+fn log2(x: f64) -> f64 {
+    // x = mantissa * 2^exponent, with mantissa in [1, 2)
+    let bits = x.to_bits();
+    let exponent = ((bits >> 52) & 0x7ff) as i64 - 1023;
+    let mantissa = f64::from_bits((bits & ((1 << 52) - 1)) | (1023 << 52));
+
+    // ln(mantissa) = 2 atanh(z), where z = (mantissa - 1) / (mantissa + 1) < 1/3
+    let z = (mantissa - 1.0) / (mantissa + 1.0);
+    let z2 = z * z;
+    let mut term = z;
+    let mut atanh = 0f64;
+    for k in 0..30 {
+        atanh += term / (2 * k + 1) as f64;
+        term *= z2;
+    }
+    exponent as f64 + 2.0 * atanh * core::f64::consts::LOG2_E
+}
+
+/// Returns the ceiling of `x`, as `f64::ceil` requires `std`.
+fn ceil(x: f64) -> i64 {
+    let truncated = x as i64;
+    if (truncated as f64) < x {
+        truncated + 1
+    } else {
+        truncated
+    }
+}
+
 impl<const N: usize, P: PoseidonDefaultConfig<N>> PoseidonDefaultConfigField for Fp<P, N> {
     fn get_default_poseidon_parameters(
         rate: usize,
@@ -156,9 +369,33 @@ impl<const N: usize, P: PoseidonDefaultConfig<N>> PoseidonDefaultConfigField for
 
 #[cfg(test)]
 mod test {
-    use crate::sponge::poseidon::PoseidonDefaultConfigField;
+    use crate::sponge::poseidon::{
+        find_poseidon_alpha, find_poseidon_parameters, find_poseidon_round_numbers,
+        PoseidonDefaultConfig, PoseidonDefaultConfigField,
+    };
     use crate::sponge::test::*;
-    use ark_ff::MontFp;
+    use ark_ff::{MontBackend, MontFp};
+
+    #[test]
+    fn bls12_381_fr_poseidon_round_numbers_test() {
+        assert_eq!(find_poseidon_alpha::<Fr>(), 5);
+
+        // the default parameters optimized for constraints were computed with the reference script
+        let entries =
+            <MontBackend<FrBackend, 4> as PoseidonDefaultConfig<4>>::PARAMS_OPT_FOR_CONSTRAINTS;
+        for entry in entries.iter() {
+            assert_eq!(
+                find_poseidon_round_numbers::<Fr>(entry.rate, entry.alpha as u64, 128),
+                (entry.full_rounds, entry.partial_rounds)
+            );
+        }
+
+        let default = Fr::get_default_poseidon_parameters(2, false).unwrap();
+        let found = find_poseidon_parameters::<Fr>(2, 17, 128);
+        assert_eq!((found.full_rounds, found.partial_rounds), (8, 31));
+        assert_eq!(found.ark, default.ark);
+        assert_eq!(found.mds, default.mds);
+    }
 
     #[test]
     fn bls12_381_fr_poseidon_default_parameters_test() {
@@ -0,0 +1,362 @@
+use crate::sponge::{
+    constraints::{AbsorbGadget, CryptographicSpongeVar, SpongeWithGadget},
+    poseidon2::{Poseidon2Config, Poseidon2Sponge},
+    DuplexSpongeMode,
+};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_relations::gr1cs::{ConstraintSystemRef, SynthesisError};
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+
+#[derive(Clone)]
+/// the gadget for Poseidon2 sponge
+pub struct Poseidon2SpongeVar<F: PrimeField> {
+    /// Constraint system
+    pub cs: ConstraintSystemRef<F>,
+
+    /// Sponge Parameters
+    pub parameters: Poseidon2Config<F>,
+
+    // Sponge State
+    /// The sponge's state
+    pub state: Vec<FpVar<F>>,
+    /// The mode
+    pub mode: DuplexSpongeMode,
+}
+
+impl<F: PrimeField> SpongeWithGadget<F> for Poseidon2Sponge<F> {
+    type Var = Poseidon2SpongeVar<F>;
+}
+
+impl<F: PrimeField> Poseidon2SpongeVar<F> {
+    /// Multiply the state with the external matrix. The matrices of Poseidon2 only have small
+    /// coefficients, so this costs no constraint.
+    #[tracing::instrument(target = "gr1cs", skip(state))]
+    pub(crate) fn apply_external_matrix(state: &mut [FpVar<F>]) -> Result<(), SynthesisError> {
+        let width = state.len();
+        if width <= 3 {
+            let sum: FpVar<F> = state.iter().sum();
+            for state_elem in state.iter_mut() {
+                *state_elem += &sum;
+            }
+            return Ok(());
+        }
+
+        for chunk in state.chunks_exact_mut(4) {
+            let t0 = &chunk[0] + &chunk[1];
+            let t1 = &chunk[2] + &chunk[3];
+            let t2 = chunk[1].double()? + &t1;
+            let t3 = chunk[3].double()? + &t0;
+            let t4 = t1.double()?.double()? + &t3;
+            let t5 = t0.double()?.double()? + &t2;
+            let t6 = &t3 + &t5;
+            let t7 = &t2 + &t4;
+            chunk.clone_from_slice(&[t6, t5, t7, t4]);
+        }
+        if width > 4 {
+            let sums: Vec<FpVar<F>> = (0..4)
+                .map(|i| state.iter().skip(i).step_by(4).sum())
+                .collect();
+            for (i, state_elem) in state.iter_mut().enumerate() {
+                *state_elem += &sums[i % 4];
+            }
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn apply_internal_matrix(&self, state: &mut [FpVar<F>]) -> Result<(), SynthesisError> {
+        let sum: FpVar<F> = state.iter().sum();
+        for (state_elem, diag) in state.iter_mut().zip(&self.parameters.internal_diag_m_1) {
+            *state_elem *= *diag;
+            *state_elem += &sum;
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn apply_full_round(
+        &self,
+        state: &mut [FpVar<F>],
+        round_number: usize,
+    ) -> Result<(), SynthesisError> {
+        for (state_elem, rc) in state
+            .iter_mut()
+            .zip(&self.parameters.external_rc[round_number])
+        {
+            *state_elem += *rc;
+            *state_elem = state_elem.pow_by_constant([self.parameters.alpha])?;
+        }
+        Self::apply_external_matrix(state)
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn apply_partial_round(
+        &self,
+        state: &mut [FpVar<F>],
+        round_number: usize,
+    ) -> Result<(), SynthesisError> {
+        state[0] += self.parameters.internal_rc[round_number];
+        state[0] = state[0].pow_by_constant([self.parameters.alpha])?;
+        self.apply_internal_matrix(state)
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn permute(&mut self) -> Result<(), SynthesisError> {
+        let full_rounds_over_2 = self.parameters.full_rounds / 2;
+        let mut state = self.state.clone();
+        Self::apply_external_matrix(&mut state)?;
+        for i in 0..full_rounds_over_2 {
+            self.apply_full_round(&mut state, i)?;
+        }
+        for i in 0..self.parameters.partial_rounds {
+            self.apply_partial_round(&mut state, i)?;
+        }
+        for i in full_rounds_over_2..self.parameters.full_rounds {
+            self.apply_full_round(&mut state, i)?;
+        }
+        self.state = state;
+        Ok(())
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn absorb_internal(
+        &mut self,
+        mut rate_start_index: usize,
+        elements: &[FpVar<F>],
+    ) -> Result<(), SynthesisError> {
+        let mut remaining_elements = elements;
+        loop {
+            // if we can finish in this call
+            if rate_start_index + remaining_elements.len() <= self.parameters.rate {
+                for (i, element) in remaining_elements.iter().enumerate() {
+                    self.state[self.parameters.capacity + i + rate_start_index] += element;
+                }
+                self.mode = DuplexSpongeMode::Absorbing {
+                    next_absorb_index: rate_start_index + remaining_elements.len(),
+                };
+
+                return Ok(());
+            }
+            // otherwise absorb (rate - rate_start_index) elements
+            let num_elements_absorbed = self.parameters.rate - rate_start_index;
+            for (i, element) in remaining_elements
+                .iter()
+                .enumerate()
+                .take(num_elements_absorbed)
+            {
+                self.state[self.parameters.capacity + i + rate_start_index] += element;
+            }
+            self.permute()?;
+            // the input elements got truncated by num elements absorbed
+            remaining_elements = &remaining_elements[num_elements_absorbed..];
+            rate_start_index = 0;
+        }
+    }
+
+    // Squeeze |output| many elements. This does not end in a squeeze
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn squeeze_internal(
+        &mut self,
+        mut rate_start_index: usize,
+        output: &mut [FpVar<F>],
+    ) -> Result<(), SynthesisError> {
+        let mut remaining_output = output;
+        loop {
+            // if we can finish in this call
+            if rate_start_index + remaining_output.len() <= self.parameters.rate {
+                remaining_output.clone_from_slice(
+                    &self.state[self.parameters.capacity + rate_start_index
+                        ..(self.parameters.capacity + remaining_output.len() + rate_start_index)],
+                );
+                self.mode = DuplexSpongeMode::Squeezing {
+                    next_squeeze_index: rate_start_index + remaining_output.len(),
+                };
+                return Ok(());
+            }
+            // otherwise squeeze (rate - rate_start_index) elements
+            let num_elements_squeezed = self.parameters.rate - rate_start_index;
+            remaining_output[..num_elements_squeezed].clone_from_slice(
+                &self.state[self.parameters.capacity + rate_start_index
+                    ..(self.parameters.capacity + num_elements_squeezed + rate_start_index)],
+            );
+
+            // Unless we are done with squeezing in this call, permute.
+            if remaining_output.len() != self.parameters.rate {
+                self.permute()?;
+            }
+            // Repeat with updated output slices and rate start index
+            remaining_output = &mut remaining_output[num_elements_squeezed..];
+            rate_start_index = 0;
+        }
+    }
+}
+
+impl<F: PrimeField> CryptographicSpongeVar<F, Poseidon2Sponge<F>> for Poseidon2SpongeVar<F> {
+    type Parameters = Poseidon2Config<F>;
+
+    fn new(cs: ConstraintSystemRef<F>, parameters: &Poseidon2Config<F>) -> Self {
+        let zero = FpVar::<F>::zero();
+        let state = vec![zero; parameters.rate + parameters.capacity];
+        let mode = DuplexSpongeMode::Absorbing {
+            next_absorb_index: 0,
+        };
+
+        Self {
+            cs,
+            parameters: parameters.clone(),
+            state,
+            mode,
+        }
+    }
+
+    fn cs(&self) -> ConstraintSystemRef<F> {
+        self.cs.clone()
+    }
+
+    fn absorb(&mut self, input: &impl AbsorbGadget<F>) -> Result<(), SynthesisError> {
+        let input = input.to_sponge_field_elements()?;
+        if input.is_empty() {
+            return Ok(());
+        }
+
+        match self.mode {
+            DuplexSpongeMode::Absorbing { next_absorb_index } => {
+                let mut absorb_index = next_absorb_index;
+                if absorb_index == self.parameters.rate {
+                    self.permute()?;
+                    absorb_index = 0;
+                }
+                self.absorb_internal(absorb_index, input.as_slice())?;
+            }
+            DuplexSpongeMode::Squeezing {
+                next_squeeze_index: _,
+            } => {
+                self.permute()?;
+                self.absorb_internal(0, input.as_slice())?;
+            }
+        };
+
+        Ok(())
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Result<Vec<UInt8<F>>, SynthesisError> {
+        let usable_bytes = ((F::MODULUS_BIT_SIZE - 1) / 8) as usize;
+
+        let num_elements = num_bytes.div_ceil(usable_bytes);
+        let src_elements = self.squeeze_field_elements(num_elements)?;
+
+        let mut bytes: Vec<UInt8<F>> = Vec::with_capacity(usable_bytes * num_elements);
+        for elem in &src_elements {
+            bytes.extend_from_slice(&elem.to_bytes_le()?[..usable_bytes]);
+        }
+
+        bytes.truncate(num_bytes);
+        Ok(bytes)
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn squeeze_bits(&mut self, num_bits: usize) -> Result<Vec<Boolean<F>>, SynthesisError> {
+        let usable_bits = (F::MODULUS_BIT_SIZE - 1) as usize;
+
+        let num_elements = num_bits.div_ceil(usable_bits);
+        let src_elements = self.squeeze_field_elements(num_elements)?;
+
+        let mut bits: Vec<Boolean<F>> = Vec::with_capacity(usable_bits * num_elements);
+        for elem in &src_elements {
+            bits.extend_from_slice(&elem.to_bits_le()?[..usable_bits]);
+        }
+
+        bits.truncate(num_bits);
+        Ok(bits)
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn squeeze_field_elements(
+        &mut self,
+        num_elements: usize,
+    ) -> Result<Vec<FpVar<F>>, SynthesisError> {
+        let zero = FpVar::zero();
+        let mut squeezed_elems = vec![zero; num_elements];
+        match self.mode {
+            DuplexSpongeMode::Absorbing {
+                next_absorb_index: _,
+            } => {
+                self.permute()?;
+                self.squeeze_internal(0, &mut squeezed_elems)?;
+            }
+            DuplexSpongeMode::Squeezing { next_squeeze_index } => {
+                let mut squeeze_index = next_squeeze_index;
+                if squeeze_index == self.parameters.rate {
+                    self.permute()?;
+                    squeeze_index = 0;
+                }
+                self.squeeze_internal(squeeze_index, &mut squeezed_elems)?;
+            }
+        };
+
+        Ok(squeezed_elems)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sponge::constraints::CryptographicSpongeVar;
+    use crate::sponge::poseidon2::constraints::Poseidon2SpongeVar;
+    use crate::sponge::poseidon2::test::poseidon2_parameters_for_test;
+    use crate::sponge::poseidon2::Poseidon2Sponge;
+    use crate::sponge::test::Fr;
+    use crate::sponge::{CryptographicSponge, FieldBasedCryptographicSponge};
+    use ark_ff::UniformRand;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::gr1cs::ConstraintSystem;
+    use ark_relations::*;
+    use ark_std::test_rng;
+
+    #[test]
+    fn absorb_test() {
+        let mut rng = test_rng();
+        // a width of 3 uses the small external matrix, and a width of 8 the one built from `M4`
+        for rate in [2, 7] {
+            let cs = ConstraintSystem::new_ref();
+            let diag: Vec<Fr> = (0..rate + 1).map(|_| Fr::rand(&mut rng)).collect();
+            let sponge_params = poseidon2_parameters_for_test(rate, diag);
+
+            let absorb1: Vec<_> = (0..20).map(|_| Fr::rand(&mut rng)).collect();
+            let absorb1_var: Vec<_> = absorb1
+                .iter()
+                .map(|v| FpVar::new_input(ns!(cs, "absorb1"), || Ok(*v)).unwrap())
+                .collect();
+
+            let absorb2: Vec<_> = (0..8).map(|i| vec![i, i + 1, i + 2]).collect();
+            let absorb2_var: Vec<_> = absorb2
+                .iter()
+                .map(|v| UInt8::new_input_vec(ns!(cs, "absorb2"), v).unwrap())
+                .collect();
+
+            let mut native_sponge = Poseidon2Sponge::<Fr>::new(&sponge_params);
+            let mut constraint_sponge = Poseidon2SpongeVar::<Fr>::new(cs.clone(), &sponge_params);
+
+            native_sponge.absorb(&absorb1);
+            constraint_sponge.absorb(&absorb1_var).unwrap();
+
+            let squeeze1 = native_sponge.squeeze_native_field_elements(1);
+            let squeeze2 = constraint_sponge.squeeze_field_elements(1).unwrap();
+
+            assert_eq!(squeeze2.value().unwrap(), squeeze1);
+            assert!(cs.is_satisfied().unwrap());
+
+            native_sponge.absorb(&absorb2);
+            constraint_sponge.absorb(&absorb2_var).unwrap();
+
+            let squeeze1 = native_sponge.squeeze_native_field_elements(10);
+            let squeeze2 = constraint_sponge.squeeze_field_elements(10).unwrap();
+
+            assert_eq!(squeeze2.value().unwrap(), squeeze1);
+            assert!(cs.is_satisfied().unwrap());
+        }
+    }
+}
@@ -0,0 +1,565 @@
+use crate::{
+    sponge::{
+        field_cast,
+        poseidon::{find_poseidon_round_numbers, grain_lfsr::PoseidonGrainLFSR},
+        squeeze_field_elements_with_sizes_default_impl, Absorb, CryptographicSponge,
+        DuplexSpongeMode, FieldBasedCryptographicSponge, FieldElementSize, SpongeExt,
+    },
+    Error,
+};
+use ark_ff::{BigInteger, PrimeField};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+use ark_std::{any::TypeId, boxed::Box};
+
+/// constraints for Poseidon2
+#[cfg(feature = "constraints")]
+pub mod constraints;
+
+/// Config for the Poseidon2 permutation, as specified in the [paper](https://eprint.iacr.org/2023/323.pdf).
+///
+/// The width `rate + capacity` of the state should be 2, 3 or a multiple of 4, which fixes the
+/// external matrix.
+#[derive(Clone, Debug, CanonicalSerialize, CanonicalDeserialize)]
+pub struct Poseidon2Config<F: PrimeField> {
+    /// Number of full rounds, half of which are applied before the partial rounds.
+    pub full_rounds: usize,
+    /// Number of partial rounds.
+    pub partial_rounds: usize,
+    /// Exponent used in S-boxes.
+    pub alpha: u64,
+    /// Additive round constants of the full rounds.
+    /// They are indexed by `external_rc[round_num][state_element_index]`
+    pub external_rc: Vec<Vec<F>>,
+    /// Additive round constants of the partial rounds, which are only added to the first state
+    /// element.
+    pub internal_rc: Vec<F>,
+    /// The diagonal of the internal matrix minus the identity, so that the internal matrix is
+    /// `1 + diag(internal_diag_m_1)` where `1` is the all-ones matrix.
+    pub internal_diag_m_1: Vec<F>,
+    /// The rate (in terms of number of field elements).
+    pub rate: usize,
+    /// The capacity (in terms of number of field elements).
+    pub capacity: usize,
+}
+
+impl<F: PrimeField> Poseidon2Config<F> {
+    /// Initialize the parameter for Poseidon2 Sponge.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        full_rounds: usize,
+        partial_rounds: usize,
+        alpha: u64,
+        external_rc: Vec<Vec<F>>,
+        internal_rc: Vec<F>,
+        internal_diag_m_1: Vec<F>,
+        rate: usize,
+        capacity: usize,
+    ) -> Self {
+        let width = rate + capacity;
+        assert!(
+            width == 2 || width == 3 || width % 4 == 0,
+            "the width should be 2, 3 or a multiple of 4"
+        );
+        assert_eq!(full_rounds % 2, 0);
+        assert_eq!(external_rc.len(), full_rounds);
+        for item in &external_rc {
+            assert_eq!(item.len(), width);
+        }
+        assert_eq!(internal_rc.len(), partial_rounds);
+        assert_eq!(internal_diag_m_1.len(), width);
+        Self {
+            full_rounds,
+            partial_rounds,
+            alpha,
+            external_rc,
+            internal_rc,
+            internal_diag_m_1,
+            rate,
+            capacity,
+        }
+    }
+}
+
+/// Internal function that computes the round constants from the Poseidon Grain LFSR, in the
+/// order of the rounds: `width` constants per full round and one per partial round.
+///
+/// Returns the constants of the full rounds and of the partial rounds.
+pub fn find_poseidon2_round_constants<F: PrimeField>(
+    prime_bits: u64,
+    width: usize,
+    full_rounds: u64,
+    partial_rounds: u64,
+) -> (Vec<Vec<F>>, Vec<F>) {
+    let mut lfsr =
+        PoseidonGrainLFSR::new(false, prime_bits, width as u64, full_rounds, partial_rounds);
+
+    let mut external_rc = Vec::<Vec<F>>::with_capacity(full_rounds as usize);
+    for _ in 0..full_rounds / 2 {
+        external_rc.push(lfsr.get_field_elements_rejection_sampling(width));
+    }
+    let internal_rc = (0..partial_rounds)
+        .map(|_| lfsr.get_field_elements_rejection_sampling(1)[0])
+        .collect();
+    for _ in 0..full_rounds / 2 {
+        external_rc.push(lfsr.get_field_elements_rejection_sampling(width));
+    }
+
+    (external_rc, internal_rc)
+}
+
+/// Internal function that computes the Poseidon2 parameters for a state of `rate + 1` elements and
+/// the S-box `x^alpha`, for a security level of `security_level` bits.
+///
+/// As in the reference implementation, the round numbers are those of Poseidon, see
+/// [`find_poseidon_round_numbers`], the round constants are those of
+/// [`find_poseidon2_round_constants`], and the internal matrix is `circ(2, 1)` plus `diag(0, 1)`
+/// for a width of 2, or `circ(2, 1, 1)` plus `diag(0, 0, 1)` for a width of 3. Larger widths need
+/// an internal matrix found by a search which is not implemented, so their parameters should be
+/// specified manually.
+///
+/// Returns an error if `rate` is not 1 or 2. Panics if `x^alpha` is not a permutation of `F`.
+pub fn find_poseidon2_parameters<F: PrimeField>(
+    rate: usize,
+    alpha: u64,
+    security_level: u64,
+) -> Result<Poseidon2Config<F>, Error> {
+    let internal_diag_m_1 = match rate {
+        1 => vec![F::one(), F::from(2u8)],
+        2 => vec![F::one(), F::one(), F::from(2u8)],
+        _ => {
+            let err: Box<dyn ark_std::error::Error + Send + Sync> =
+                "only widths 2 and 3 have a default internal matrix".into();
+            return Err(Error::GenericError(err));
+        }
+    };
+    let (full_rounds, partial_rounds) =
+        find_poseidon_round_numbers::<F>(rate, alpha, security_level);
+    let (external_rc, internal_rc) = find_poseidon2_round_constants(
+        F::MODULUS_BIT_SIZE as u64,
+        rate + 1,
+        full_rounds as u64,
+        partial_rounds as u64,
+    );
+    Ok(Poseidon2Config::new(
+        full_rounds,
+        partial_rounds,
+        alpha,
+        external_rc,
+        internal_rc,
+        internal_diag_m_1,
+        rate,
+        1,
+    ))
+}
+
+#[derive(Clone)]
+/// A duplex sponge based using the Poseidon2 permutation.
+pub struct Poseidon2Sponge<F: PrimeField> {
+    /// Sponge Config
+    pub parameters: Poseidon2Config<F>,
+
+    // Sponge State
+    /// Current sponge's state (current elements in the permutation block)
+    pub state: Vec<F>,
+    /// Current mode (whether its absorbing or squeezing)
+    pub mode: DuplexSpongeMode,
+}
+
+impl<F: PrimeField> Poseidon2Sponge<F> {
+    /// Multiply the state with the external matrix.
+    ///
+    /// For a width of 2 or 3, this is `circ(2, 1)` or `circ(2, 1, 1)`. Otherwise, every chunk of
+    /// 4 elements is multiplied by the matrix `M4` of the paper, and the sum of the chunks is added
+    /// to each of them.
+    pub(crate) fn apply_external_matrix(state: &mut [F]) {
+        let width = state.len();
+        if width <= 3 {
+            let sum: F = state.iter().sum();
+            for state_elem in state.iter_mut() {
+                *state_elem += sum;
+            }
+            return;
+        }
+
+        for chunk in state.chunks_exact_mut(4) {
+            let t0 = chunk[0] + chunk[1];
+            let t1 = chunk[2] + chunk[3];
+            let t2 = chunk[1].double() + t1;
+            let t3 = chunk[3].double() + t0;
+            let t4 = t1.double().double() + t3;
+            let t5 = t0.double().double() + t2;
+            let t6 = t3 + t5;
+            let t7 = t2 + t4;
+            chunk.copy_from_slice(&[t6, t5, t7, t4]);
+        }
+        if width > 4 {
+            let sums: Vec<F> = (0..4)
+                .map(|i| state.iter().skip(i).step_by(4).sum())
+                .collect();
+            for (i, state_elem) in state.iter_mut().enumerate() {
+                *state_elem += sums[i % 4];
+            }
+        }
+    }
+
+    /// Multiply the state with the internal matrix `1 + diag(internal_diag_m_1)`.
+    fn apply_internal_matrix(&self, state: &mut [F]) {
+        let sum: F = state.iter().sum();
+        for (state_elem, diag) in state.iter_mut().zip(&self.parameters.internal_diag_m_1) {
+            *state_elem *= diag;
+            *state_elem += sum;
+        }
+    }
+
+    fn apply_full_round(&self, state: &mut [F], round_number: usize) {
+        for (state_elem, rc) in state
+            .iter_mut()
+            .zip(&self.parameters.external_rc[round_number])
+        {
+            *state_elem += rc;
+            *state_elem = state_elem.pow([self.parameters.alpha]);
+        }
+        Self::apply_external_matrix(state);
+    }
+
+    fn apply_partial_round(&self, state: &mut [F], round_number: usize) {
+        state[0] += self.parameters.internal_rc[round_number];
+        state[0] = state[0].pow([self.parameters.alpha]);
+        self.apply_internal_matrix(state);
+    }
+
+    fn permute(&mut self) {
+        let full_rounds_over_2 = self.parameters.full_rounds / 2;
+        let mut state = self.state.clone();
+        Self::apply_external_matrix(&mut state);
+        for i in 0..full_rounds_over_2 {
+            self.apply_full_round(&mut state, i);
+        }
+        for i in 0..self.parameters.partial_rounds {
+            self.apply_partial_round(&mut state, i);
+        }
+        for i in full_rounds_over_2..self.parameters.full_rounds {
+            self.apply_full_round(&mut state, i);
+        }
+        self.state = state;
+    }
+
+    // Absorbs everything in elements, this does not end in an absorbtion.
+    fn absorb_internal(&mut self, mut rate_start_index: usize, elements: &[F]) {
+        let mut remaining_elements = elements;
+
+        loop {
+            // if we can finish in this call
+            if rate_start_index + remaining_elements.len() <= self.parameters.rate {
+                for (i, element) in remaining_elements.iter().enumerate() {
+                    self.state[self.parameters.capacity + i + rate_start_index] += element;
+                }
+                self.mode = DuplexSpongeMode::Absorbing {
+                    next_absorb_index: rate_start_index + remaining_elements.len(),
+                };
+                return;
+            }
+            // otherwise absorb (rate - rate_start_index) elements
+            let num_elements_absorbed = self.parameters.rate - rate_start_index;
+            for (i, element) in remaining_elements
+                .iter()
+                .enumerate()
+                .take(num_elements_absorbed)
+            {
+                self.state[self.parameters.capacity + i + rate_start_index] += element;
+            }
+            self.permute();
+            // the input elements got truncated by num elements absorbed
+            remaining_elements = &remaining_elements[num_elements_absorbed..];
+            rate_start_index = 0;
+        }
+    }
+
+    // Squeeze |output| many elements. This does not end in a squeeze
+    fn squeeze_internal(&mut self, mut rate_start_index: usize, output: &mut [F]) {
+        let mut output_remaining = output;
+        loop {
+            // if we can finish in this call
+            if rate_start_index + output_remaining.len() <= self.parameters.rate {
+                output_remaining.clone_from_slice(
+                    &self.state[self.parameters.capacity + rate_start_index
+                        ..(self.parameters.capacity + output_remaining.len() + rate_start_index)],
+                );
+                self.mode = DuplexSpongeMode::Squeezing {
+                    next_squeeze_index: rate_start_index + output_remaining.len(),
+                };
+                return;
+            }
+            // otherwise squeeze (rate - rate_start_index) elements
+            let num_elements_squeezed = self.parameters.rate - rate_start_index;
+            output_remaining[..num_elements_squeezed].clone_from_slice(
+                &self.state[self.parameters.capacity + rate_start_index
+                    ..(self.parameters.capacity + num_elements_squeezed + rate_start_index)],
+            );
+
+            // Unless we are done with squeezing in this call, permute.
+            if output_remaining.len() != self.parameters.rate {
+                self.permute();
+            }
+            // Repeat with updated output slices
+            output_remaining = &mut output_remaining[num_elements_squeezed..];
+            rate_start_index = 0;
+        }
+    }
+}
+
+impl<F: PrimeField> CryptographicSponge for Poseidon2Sponge<F> {
+    type Config = Poseidon2Config<F>;
+
+    fn new(parameters: &Self::Config) -> Self {
+        let state = vec![F::zero(); parameters.rate + parameters.capacity];
+        let mode = DuplexSpongeMode::Absorbing {
+            next_absorb_index: 0,
+        };
+
+        Self {
+            parameters: parameters.clone(),
+            state,
+            mode,
+        }
+    }
+
+    fn absorb(&mut self, input: &impl Absorb) {
+        let elems: Vec<F> = input.to_sponge_field_elements_as_vec::<F>();
+        if elems.is_empty() {
+            return;
+        }
+        match self.mode {
+            DuplexSpongeMode::Absorbing { next_absorb_index } => {
+                let mut absorb_index = next_absorb_index;
+                if absorb_index == self.parameters.rate {
+                    self.permute();
+                    absorb_index = 0;
+                }
+
+                self.absorb_internal(absorb_index, elems.as_slice());
+            }
+            DuplexSpongeMode::Squeezing {
+                next_squeeze_index: _,
+            } => {
+                self.permute();
+                self.absorb_internal(0, elems.as_slice());
+            }
+        };
+    }
+
+    fn squeeze_bytes(&mut self, num_bytes: usize) -> Vec<u8> {
+        let usable_bytes = ((F::MODULUS_BIT_SIZE - 1) / 8) as usize;
+
+        let num_elements = num_bytes.div_ceil(usable_bytes);
+        let src_elements = self.squeeze_native_field_elements(num_elements);
+
+        let mut bytes: Vec<u8> = Vec::with_capacity(usable_bytes * num_elements);
+        for elem in &src_elements {
+            let elem_bytes = elem.into_bigint().to_bytes_le();
+            bytes.extend_from_slice(&elem_bytes[..usable_bytes]);
+        }
+
+        bytes.truncate(num_bytes);
+        bytes
+    }
+
+    fn squeeze_bits(&mut self, num_bits: usize) -> Vec<bool> {
+        let usable_bits = (F::MODULUS_BIT_SIZE - 1) as usize;
+
+        let num_elements = num_bits.div_ceil(usable_bits);
+        let src_elements = self.squeeze_native_field_elements(num_elements);
+
+        let mut bits: Vec<bool> = Vec::with_capacity(usable_bits * num_elements);
+        for elem in &src_elements {
+            let elem_bits = elem.into_bigint().to_bits_le();
+            bits.extend_from_slice(&elem_bits[..usable_bits]);
+        }
+
+        bits.truncate(num_bits);
+        bits
+    }
+
+    fn squeeze_field_elements_with_sizes<F2: PrimeField>(
+        &mut self,
+        sizes: &[FieldElementSize],
+    ) -> Vec<F2> {
+        if F::characteristic() == F2::characteristic() {
+            // native case
+            let mut buf = Vec::with_capacity(sizes.len());
+            field_cast(
+                &self.squeeze_native_field_elements_with_sizes(sizes),
+                &mut buf,
+            )
+            .unwrap();
+            buf
+        } else {
+            squeeze_field_elements_with_sizes_default_impl(self, sizes)
+        }
+    }
+
+    fn squeeze_field_elements<F2: PrimeField>(&mut self, num_elements: usize) -> Vec<F2> {
+        if TypeId::of::<F>() == TypeId::of::<F2>() {
+            let result = self.squeeze_native_field_elements(num_elements);
+            let mut cast = Vec::with_capacity(result.len());
+            field_cast(&result, &mut cast).unwrap();
+            cast
+        } else {
+            self.squeeze_field_elements_with_sizes::<F2>(
+                vec![FieldElementSize::Full; num_elements].as_slice(),
+            )
+        }
+    }
+}
+
+impl<F: PrimeField> FieldBasedCryptographicSponge<F> for Poseidon2Sponge<F> {
+    fn squeeze_native_field_elements(&mut self, num_elements: usize) -> Vec<F> {
+        let mut squeezed_elems = vec![F::zero(); num_elements];
+        match self.mode {
+            DuplexSpongeMode::Absorbing {
+                next_absorb_index: _,
+            } => {
+                self.permute();
+                self.squeeze_internal(0, &mut squeezed_elems);
+            }
+            DuplexSpongeMode::Squeezing { next_squeeze_index } => {
+                let mut squeeze_index = next_squeeze_index;
+                if squeeze_index == self.parameters.rate {
+                    self.permute();
+                    squeeze_index = 0;
+                }
+                self.squeeze_internal(squeeze_index, &mut squeezed_elems);
+            }
+        };
+
+        squeezed_elems
+    }
+}
+
+#[derive(Clone)]
+/// Stores the state of a Poseidon2 Sponge. Does not store any parameter.
+pub struct Poseidon2SpongeState<F: PrimeField> {
+    state: Vec<F>,
+    mode: DuplexSpongeMode,
+}
+
+impl<CF: PrimeField> SpongeExt for Poseidon2Sponge<CF> {
+    type State = Poseidon2SpongeState<CF>;
+
+    fn from_state(state: Self::State, params: &Self::Config) -> Self {
+        let mut sponge = Self::new(params);
+        sponge.mode = state.mode;
+        sponge.state = state.state;
+        sponge
+    }
+
+    fn into_state(self) -> Self::State {
+        Self::State {
+            state: self.state,
+            mode: self.mode,
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    use crate::sponge::poseidon2::{
+        find_poseidon2_parameters, find_poseidon2_round_constants, Poseidon2Config, Poseidon2Sponge,
+    };
+    use crate::sponge::test::{Bn254Fr, Fr};
+    use crate::sponge::{CryptographicSponge, FieldBasedCryptographicSponge, SpongeExt};
+    use ark_ff::{MontFp, PrimeField, UniformRand};
+    use ark_std::test_rng;
+
+    /// Returns the parameters of the reference implementation for a state of `rate + 1`
+    /// elements, with 8 full rounds and 56 partial rounds.
+    pub(crate) fn poseidon2_parameters_for_test<F: PrimeField>(
+        rate: usize,
+        internal_diag_m_1: Vec<F>,
+    ) -> Poseidon2Config<F> {
+        let (external_rc, internal_rc) =
+            find_poseidon2_round_constants(F::MODULUS_BIT_SIZE as u64, rate + 1, 8, 56);
+        Poseidon2Config::new(
+            8,
+            56,
+            5,
+            external_rc,
+            internal_rc,
+            internal_diag_m_1,
+            rate,
+            1,
+        )
+    }
+
+    #[test]
+    fn test_poseidon2_permutation_vector() {
+        // the test vector of the reference implementation over the BN254 scalar field
+        let diag = vec![Bn254Fr::from(1u8), Bn254Fr::from(1u8), Bn254Fr::from(2u8)];
+        let params = poseidon2_parameters_for_test(2, diag);
+        assert_eq!(
+            params.external_rc[0][0],
+            MontFp!(
+                "13128406282895484157369354038809433636203389051939936481821261911791933663254"
+            )
+        );
+
+        let mut sponge = Poseidon2Sponge::<Bn254Fr>::new(&params);
+        sponge.state = vec![Bn254Fr::from(0u8), Bn254Fr::from(1u8), Bn254Fr::from(2u8)];
+        sponge.permute();
+        assert_eq!(
+            sponge.state,
+            vec![
+                MontFp!(
+                    "5297208644449048816064511434384511824916970985131888684874823260532015509555"
+                ),
+                MontFp!(
+                    "21816030159894113985964609355246484851575571273661473159848781012394295965040"
+                ),
+                MontFp!(
+                    "13940986381491601233448981668101586453321811870310341844570924906201623195336"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_poseidon2_parameters() {
+        // the parameters of the test vector of the reference implementation
+        let diag = vec![Bn254Fr::from(1u8), Bn254Fr::from(1u8), Bn254Fr::from(2u8)];
+        let expected = poseidon2_parameters_for_test(2, diag);
+        let params = find_poseidon2_parameters::<Bn254Fr>(2, 5, 128).unwrap();
+        assert_eq!((params.full_rounds, params.partial_rounds), (8, 56));
+        assert_eq!(params.alpha, expected.alpha);
+        assert_eq!(params.external_rc, expected.external_rc);
+        assert_eq!(params.internal_rc, expected.internal_rc);
+        assert_eq!(params.internal_diag_m_1, expected.internal_diag_m_1);
+        assert_eq!((params.rate, params.capacity), (2, 1));
+
+        let params = find_poseidon2_parameters::<Bn254Fr>(1, 5, 128).unwrap();
+        assert_eq!(
+            params.internal_diag_m_1,
+            vec![Bn254Fr::from(1u8), Bn254Fr::from(2u8)]
+        );
+        assert_eq!(params.external_rc[0].len(), 2);
+
+        assert!(find_poseidon2_parameters::<Bn254Fr>(3, 5, 128).is_err());
+    }
+
+    #[test]
+    fn test_poseidon2_sponge_state() {
+        let mut rng = test_rng();
+        let diag: Vec<Fr> = (0..8).map(|_| Fr::rand(&mut rng)).collect();
+        let params = poseidon2_parameters_for_test(7, diag);
+        let input: Vec<Fr> = (0..10).map(|_| Fr::rand(&mut rng)).collect();
+
+        let mut sponge = Poseidon2Sponge::<Fr>::new(&params);
+        sponge.absorb(&input);
+        let mut resumed = Poseidon2Sponge::from_state(sponge.clone().into_state(), &params);
+        assert_eq!(
+            sponge.squeeze_native_field_elements(9),
+            resumed.squeeze_native_field_elements(9)
+        );
+    }
+}
@@ -135,13 +135,15 @@ impl<F: PrimeField> RescueSpongeVar<F> {
     #[tracing::instrument(target = "gr1cs", skip(self))]
     fn permute(&mut self) -> Result<(), SynthesisError> {
         let mut state = self.state.clone();
-        self.apply_ark(&mut state, &self.parameters.arc[0])?;
-        for (round, round_key) in self.parameters.arc[1..].iter().enumerate() {
-            if (round % 2) == 0 {
-                self.apply_s_box(&mut state, self.parameters.alpha, false)?;
-            } else {
-                self.apply_s_box(&mut state, self.parameters.alpha, true)?;
-            }
+        let round_keys = if self.parameters.is_rescue_prime() {
+            &self.parameters.arc[..]
+        } else {
+            self.apply_ark(&mut state, &self.parameters.arc[0])?;
+            &self.parameters.arc[1..]
+        };
+        for (round, round_key) in round_keys.iter().enumerate() {
+            let is_forward_pass = ((round % 2) == 1) != self.parameters.is_rescue_prime();
+            self.apply_s_box(&mut state, self.parameters.alpha, is_forward_pass)?;
             self.apply_mds(&mut state)?;
             self.apply_ark(&mut state, round_key)?;
         }
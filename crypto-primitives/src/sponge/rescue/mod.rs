@@ -1,13 +1,21 @@
-use crate::sponge::{
-    field_cast, squeeze_field_elements_with_sizes_default_impl, Absorb, CryptographicSponge,
-    DuplexSpongeMode, FieldBasedCryptographicSponge, FieldElementSize, SpongeExt,
+use crate::{
+    sponge::{
+        field_cast, poseidon::find_poseidon_alpha, squeeze_field_elements_with_sizes_default_impl,
+        Absorb, CryptographicSponge, DuplexSpongeMode, FieldBasedCryptographicSponge,
+        FieldElementSize, SpongeExt,
+    },
+    Error,
 };
 use ark_ff::{BigInteger, PrimeField};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use ark_std::any::TypeId;
 #[cfg(not(feature = "std"))]
 use ark_std::vec::Vec;
+use ark_std::{any::TypeId, boxed::Box};
 use num_bigint::BigUint;
+use sha3::{
+    digest::{ExtendableOutput, Update, XofReader},
+    Shake256,
+};
 /// constraints for Rescue
 #[cfg(feature = "constraints")]
 pub mod constraints;
@@ -50,7 +58,8 @@ pub struct RescueSponge<F: PrimeField> {
 impl<F: PrimeField> RescueSponge<F> {
     /// Apply the S-box to the state. the exponent can be `alpha` or `alpha_inv` depending on the position of the s-box in the permutation.
     fn apply_s_box(&self, state: &mut [F], round: usize) {
-        if (round % 2) == 0 {
+        // Rescue starts with `alpha_inv`, and Rescue-Prime with `alpha`
+        if ((round % 2) == 1) == self.parameters.is_rescue_prime() {
             for elem in state {
                 *elem = elem.pow(self.parameters.alpha_inv.to_u64_digits());
             }
@@ -82,15 +91,20 @@ impl<F: PrimeField> RescueSponge<F> {
         state.clone_from_slice(&new_state[..state.len()])
     }
 
-    /// The permutation function of the Rescue Sponge. It corresponds to the Algorithm 3 in the [paper](https://eprint.iacr.org/2020/1143.pdf)
+    /// The permutation function of the Rescue Sponge. For Rescue-Prime, it corresponds to the
+    /// Algorithm 1 in the [paper](https://eprint.iacr.org/2020/1143.pdf)
     fn permute(&mut self) {
         let mut state = self.state.clone();
-        assert_eq!(self.parameters.rounds * 2 + 1, self.parameters.arc.len());
-        self.apply_arc(&mut state, 0);
-        for (round, _round_key) in self.parameters.arc[1..].iter().enumerate() {
+        let first_round_key = if self.parameters.is_rescue_prime() {
+            0
+        } else {
+            self.apply_arc(&mut state, 0);
+            1
+        };
+        for round in 0..2 * self.parameters.rounds {
             self.apply_s_box(&mut state, round);
             self.apply_mds(&mut state);
-            self.apply_arc(&mut state, round + 1);
+            self.apply_arc(&mut state, first_round_key + round);
         }
 
         self.state = state;
@@ -161,7 +175,7 @@ impl<F: PrimeField> RescueSponge<F> {
 }
 
 impl<F: PrimeField> RescueConfig<F> {
-    /// Initialize the parameter for Rescue Sponge.
+    /// Initialize the parameter for Rescue Sponge, with the `2 * rounds + 1` round keys of Rescue.
     pub fn new(
         rounds: usize,
         alpha: u64,
@@ -189,6 +203,211 @@ impl<F: PrimeField> RescueConfig<F> {
             capacity,
         }
     }
+
+    /// Initialize the parameter for Rescue Sponge, with the `2 * rounds` round constants of
+    /// Rescue-Prime.
+    pub fn new_rescue_prime(
+        rounds: usize,
+        alpha: u64,
+        alpha_inv: BigUint,
+        mds: Vec<Vec<F>>,
+        arc: Vec<Vec<F>>,
+        rate: usize,
+        capacity: usize,
+    ) -> Self {
+        assert_eq!(arc.len(), 2 * rounds);
+        for item in &arc {
+            assert_eq!(item.len(), rate + capacity);
+        }
+        assert_eq!(mds.len(), rate + capacity);
+        for item in &mds {
+            assert_eq!(item.len(), rate + capacity);
+        }
+        Self {
+            rounds,
+            alpha,
+            alpha_inv,
+            mds,
+            arc,
+            rate,
+            capacity,
+        }
+    }
+
+    /// Returns whether the parameters are those of Rescue-Prime, whose `2 * rounds` rounds start
+    /// with the S-box `x^alpha`, rather than those of Rescue, which first adds `arc[0]` and whose
+    /// rounds start with the S-box `x^alpha_inv`. The two are told apart by the number of round
+    /// keys, `2 * rounds` for Rescue-Prime and `2 * rounds + 1` for Rescue.
+    pub fn is_rescue_prime(&self) -> bool {
+        self.arc.len() == 2 * self.rounds
+    }
+
+    /// Generate the parameters of Rescue-Prime for a state of `rate + capacity` elements and a
+    /// security level of `security_level` bits, as in the reference implementation of the
+    /// [paper](https://eprint.iacr.org/2020/1143.pdf).
+    ///
+    /// `alpha` is the smallest integer coprime with `p - 1`, the number of rounds is given by
+    /// [`find_rescue_rounds`], the MDS matrix by [`find_rescue_mds`] and the round constants by
+    /// [`find_rescue_round_constants`]. Returns an error if the MDS matrix cannot be the reference
+    /// one.
+    pub fn generate(rate: usize, capacity: usize, security_level: u64) -> Result<Self, Error> {
+        let alpha = find_poseidon_alpha::<F>();
+        let alpha_inv = find_alpha_inv::<F>(alpha);
+
+        let rounds = find_rescue_rounds(rate, capacity, alpha, security_level);
+        let mds = find_rescue_mds(rate + capacity)?;
+        let arc = find_rescue_round_constants(rate, capacity, security_level, rounds);
+        Ok(Self::new_rescue_prime(
+            rounds, alpha, alpha_inv, mds, arc, rate, capacity,
+        ))
+    }
+}
+
+/// Returns the inverse of `alpha` modulo `p - 1`, so that `x -> x^alpha_inv` is the inverse of
+/// the S-box `x -> x^alpha`.
+pub(crate) fn find_alpha_inv<F: PrimeField>(alpha: u64) -> BigUint {
+    let modulus: BigUint = F::MODULUS.into();
+    let p_minus_one = modulus - 1u8;
+    // alpha_inv = (k (p - 1) + 1) / alpha, for the k < alpha making it an integer
+    let p_minus_one_mod_alpha = (&p_minus_one % alpha)
+        .to_u64_digits()
+        .first()
+        .copied()
+        .unwrap_or(0) as u128;
+    let k = (1..alpha)
+        .find(|&k| (p_minus_one_mod_alpha * k as u128 + 1) % alpha as u128 == 0)
+        .expect("alpha should be coprime with p - 1");
+    (p_minus_one * k + 1u8) / alpha
+}
+
+/// Internal function that computes the number of rounds of Rescue-Prime for a state of
+/// `rate + capacity` elements and the S-box `x^alpha`, for a security level of `security_level`
+/// bits.
+///
+/// This is the smallest number of rounds resisting the Gröbner basis attack analyzed in the
+/// [paper](https://eprint.iacr.org/2020/1143.pdf), and at least 5, plus a margin of 50%.
+pub fn find_rescue_rounds(rate: usize, capacity: usize, alpha: u64, security_level: u64) -> usize {
+    let width = (rate + capacity) as u64;
+    let target = BigUint::from(1u8) << security_level;
+    let rounds = (1..25)
+        .find(|&rounds| {
+            // the degree of the constraints and the number of variables of the attack
+            let degree = (alpha - 1) * width * (rounds - 1) / 2 + 2;
+            let variables = width * (rounds - 1) + rate as u64;
+            let cost = binomial(variables + degree, variables);
+            &cost * &cost > target
+        })
+        .unwrap_or(24);
+    (3 * rounds.max(5) as usize).div_ceil(2)
+}
+
+/// Internal function that computes the MDS matrix of Rescue-Prime for a state of `width`
+/// elements.
+///
+/// It is the transpose of the right half of the systematic generator matrix `[I | A]` of the
+/// Reed-Solomon code generated by `V[i][j] = g^(i * j)`, where `g` is the smallest primitive
+/// element of `F`, as in the reference implementation.
+///
+/// Finding the smallest primitive element requires factoring `p - 1`, so `g` is taken to be
+/// `F::GENERATOR`, and an error is returned unless every smaller element `c` is shown not to be
+/// primitive, by a divisor `d > 1` of `p - 1` below `2^16` such that `c^((p - 1) / d) = 1`.
+pub fn find_rescue_mds<F: PrimeField>(width: usize) -> Result<Vec<Vec<F>>, Error> {
+    if !generator_is_smallest_primitive_element::<F>() {
+        let err: Box<dyn ark_std::error::Error + Send + Sync> =
+            "F::GENERATOR is not known to be the smallest primitive element of F".into();
+        return Err(Error::GenericError(err));
+    }
+
+    let mut matrix: Vec<Vec<F>> = (0..width)
+        .map(|i| {
+            (0..2 * width)
+                .map(|j| F::GENERATOR.pow([(i * j) as u64]))
+                .collect()
+        })
+        .collect();
+
+    // reduce the matrix to its echelon form, whose left half is the identity
+    for col in 0..width {
+        let pivot = (col..width)
+            .find(|&row| !matrix[row][col].is_zero())
+            .expect("the left half of the matrix is invertible");
+        matrix.swap(col, pivot);
+        let inv = matrix[col][col].inverse().unwrap();
+        matrix[col].iter_mut().for_each(|elem| *elem *= inv);
+        let pivot_row = matrix[col].clone();
+        for (row, elems) in matrix.iter_mut().enumerate() {
+            let factor = elems[col];
+            if row != col && !factor.is_zero() {
+                for (elem, pivot_elem) in elems.iter_mut().zip(&pivot_row) {
+                    *elem -= factor * pivot_elem;
+                }
+            }
+        }
+    }
+
+    Ok((0..width)
+        .map(|i| (0..width).map(|j| matrix[j][width + i]).collect())
+        .collect())
+}
+
+/// Returns whether `F::GENERATOR` is shown to be the smallest primitive element of `F`, assuming
+/// that it is primitive.
+///
+/// An element `c` is not primitive if `c^((p - 1) / d) = 1` for a divisor `d > 1` of `p - 1`. Only
+/// the divisors below `2^16` are tried, which is enough for the scalar fields of the usual curves.
+fn generator_is_smallest_primitive_element<F: PrimeField>() -> bool {
+    let modulus: BigUint = F::MODULUS.into();
+    let p_minus_one = modulus - 1u8;
+    let exponents: Vec<Vec<u64>> = (2..1u32 << 16)
+        .filter(|&d| (&p_minus_one % d).to_u64_digits().is_empty())
+        .map(|d| (&p_minus_one / d).to_u64_digits())
+        .collect();
+    let generator: BigUint = F::GENERATOR.into_bigint().into();
+    (2u64..)
+        .take_while(|&c| BigUint::from(c) < generator)
+        .all(|c| exponents.iter().any(|exp| F::from(c).pow(exp).is_one()))
+}
+
+/// Internal function that computes the `2 * rounds` round constants of Rescue-Prime for a state of
+/// `rate + capacity` elements and a security level of `security_level` bits.
+///
+/// The constants are read from the output of SHAKE256 on the string
+/// `Rescue-XLIX(p,width,capacity,security_level)`, each from `ceil(log2(p) / 8) + 1` little-endian
+/// bytes reduced modulo `p`.
+pub fn find_rescue_round_constants<F: PrimeField>(
+    rate: usize,
+    capacity: usize,
+    security_level: u64,
+    rounds: usize,
+) -> Vec<Vec<F>> {
+    let width = rate + capacity;
+    let modulus: BigUint = F::MODULUS.into();
+    let seed = format!(
+        "Rescue-XLIX({},{},{},{})",
+        modulus, width, capacity, security_level
+    );
+    let mut shake = Shake256::default();
+    shake.update(seed.as_bytes());
+    let mut reader = shake.finalize_xof();
+
+    let bytes_per_elem = (F::MODULUS_BIT_SIZE as usize).div_ceil(8) + 1;
+    let mut bytes = vec![0u8; bytes_per_elem];
+    (0..2 * rounds)
+        .map(|_| {
+            (0..width)
+                .map(|_| {
+                    reader.read(&mut bytes);
+                    F::from_le_bytes_mod_order(&bytes)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Returns `binomial(n, k)`.
+pub(crate) fn binomial(n: u64, k: u64) -> BigUint {
+    // the product of `i` consecutive integers is divisible by `i!`, so the divisions are exact
+    (1..=k).fold(BigUint::from(1u8), |acc, i| acc * (n - k + i) / i)
 }
 
 impl<F: PrimeField> CryptographicSponge for RescueSponge<F> {
@@ -346,3 +565,79 @@ impl<CF: PrimeField> SpongeExt for RescueSponge<CF> {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::sponge::rescue::{find_rescue_mds, RescueConfig, RescueSponge};
+    use crate::sponge::test::{Bn254Fr, Fr};
+    use crate::sponge::CryptographicSponge;
+    use ark_ff::{fields::Fp64, MontBackend, MontConfig, MontFp};
+    use num_bigint::BigUint;
+
+    #[derive(MontConfig)]
+    #[modulus = "101"]
+    #[generator = "3"]
+    pub struct F101Backend;
+    type F101 = Fp64<MontBackend<F101Backend, 1>>;
+
+    #[test]
+    fn bls12_381_fr_rescue_prime_parameters_test() {
+        // the values of the reference implementation of Rescue-Prime
+        let params = RescueConfig::<Fr>::generate(2, 1, 128).unwrap();
+        assert!(params.is_rescue_prime());
+        assert_eq!(params.alpha, 5);
+        assert_eq!(
+            params.alpha_inv,
+            BigUint::parse_bytes(
+                b"20974350070050476191779096203274386335076221000211055129041463479975432473805",
+                10
+            )
+            .unwrap()
+        );
+        assert_eq!(params.rounds, 14);
+        assert_eq!(params.mds[0][0], Fr::from(343u16));
+        assert_eq!(params.mds[1][2], Fr::from(2850u16));
+        assert_eq!(params.arc.len(), 28);
+        assert_eq!(
+            params.arc[0][0],
+            MontFp!(
+                "35495817390819093545263349384941809089491580678942832859579453034368810736263"
+            )
+        );
+        assert_eq!(
+            params.arc[27][2],
+            MontFp!("966835047744911231490794763166379188555949592683359886287393788918898119684")
+        );
+    }
+
+    #[test]
+    fn bls12_381_fr_rescue_prime_permutation_test() {
+        // computed with a transcription of `rescue_XLIX_permutation` from the reference
+        // implementation of Rescue-Prime
+        let params = RescueConfig::<Fr>::generate(2, 1, 128).unwrap();
+        let mut sponge = RescueSponge::new(&params);
+        sponge.state = vec![Fr::from(0u8), Fr::from(1u8), Fr::from(2u8)];
+        sponge.permute();
+        assert_eq!(
+            sponge.state,
+            vec![
+                MontFp!(
+                    "20837336434853470849910909576721791703386530727763098803394615300550680488910"
+                ),
+                MontFp!(
+                    "25771045850287316209319297577315389859184751579565922583267218707663223737221"
+                ),
+                MontFp!(
+                    "47778332175771177523183464148522719206884558815624567948365727904575578981390"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn rescue_mds_generator_test() {
+        // 2 is the smallest primitive element modulo 101, so the matrix cannot be the reference one
+        assert!(find_rescue_mds::<F101>(3).is_err());
+        assert!(find_rescue_mds::<Bn254Fr>(3).is_ok());
+    }
+}
@@ -0,0 +1,182 @@
+use crate::sponge::{
+    constraints::CryptographicSpongeVar,
+    poseidon::{constraints::PoseidonSpongeVar, PoseidonSponge},
+    poseidon2::{constraints::Poseidon2SpongeVar, Poseidon2Sponge},
+    rescue::{constraints::RescueSpongeVar, RescueSponge},
+    safe::{IOPattern, PatternTracker, SafeError, SpongeOp, TaggedSponge},
+};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{fields::fp::FpVar, prelude::*};
+use ark_relations::gr1cs::ConstraintSystemRef;
+use ark_std::marker::PhantomData;
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+
+/// A sponge gadget whose capacity can be initialized with the tag of a [`SafeSpongeVar`].
+pub trait TaggedSpongeVar<F: PrimeField, S: TaggedSponge<F>>: CryptographicSpongeVar<F, S> {
+    /// Returns a sponge gadget whose first capacity element is the constant `tag`, and whose
+    /// other state elements are zero, or an error if the sponge has no capacity.
+    fn new_with_tag(
+        cs: ConstraintSystemRef<F>,
+        params: &Self::Parameters,
+        tag: F,
+    ) -> Result<Self, SafeError>;
+}
+
+impl<F: PrimeField> TaggedSpongeVar<F, PoseidonSponge<F>> for PoseidonSpongeVar<F> {
+    fn new_with_tag(
+        cs: ConstraintSystemRef<F>,
+        params: &Self::Parameters,
+        tag: F,
+    ) -> Result<Self, SafeError> {
+        if params.capacity == 0 {
+            return Err(SafeError::NoCapacity);
+        }
+        let mut sponge = Self::new(cs, params);
+        sponge.state[0] = FpVar::constant(tag);
+        Ok(sponge)
+    }
+}
+
+impl<F: PrimeField> TaggedSpongeVar<F, RescueSponge<F>> for RescueSpongeVar<F> {
+    fn new_with_tag(
+        cs: ConstraintSystemRef<F>,
+        params: &Self::Parameters,
+        tag: F,
+    ) -> Result<Self, SafeError> {
+        if params.capacity == 0 {
+            return Err(SafeError::NoCapacity);
+        }
+        let mut sponge = Self::new(cs, params);
+        sponge.state[0] = FpVar::constant(tag);
+        Ok(sponge)
+    }
+}
+
+impl<F: PrimeField> TaggedSpongeVar<F, Poseidon2Sponge<F>> for Poseidon2SpongeVar<F> {
+    fn new_with_tag(
+        cs: ConstraintSystemRef<F>,
+        params: &Self::Parameters,
+        tag: F,
+    ) -> Result<Self, SafeError> {
+        if params.capacity == 0 {
+            return Err(SafeError::NoCapacity);
+        }
+        let mut sponge = Self::new(cs, params);
+        sponge.state[0] = FpVar::constant(tag);
+        Ok(sponge)
+    }
+}
+
+/// The gadget for [`SafeSponge`](super::SafeSponge).
+///
+/// The IO pattern is public, so a call that does not follow it is reported when the constraints
+/// are generated, and does not depend on the witnesses.
+#[derive(Clone)]
+pub struct SafeSpongeVar<F: PrimeField, S: TaggedSponge<F>, SV: TaggedSpongeVar<F, S>> {
+    sponge: SV,
+    tracker: PatternTracker,
+    _sponge: PhantomData<(F, S)>,
+}
+
+impl<F: PrimeField, S: TaggedSponge<F>, SV: TaggedSpongeVar<F, S>> SafeSpongeVar<F, S, SV> {
+    /// Returns a sponge gadget for `io_pattern` and `domain_separator`, or an error if the sponge
+    /// has no capacity.
+    pub fn new(
+        cs: ConstraintSystemRef<F>,
+        params: &SV::Parameters,
+        io_pattern: &IOPattern,
+        domain_separator: &[u8],
+    ) -> Result<Self, SafeError> {
+        let tag = io_pattern.tag(domain_separator);
+        Ok(Self {
+            sponge: SV::new_with_tag(cs, params, tag)?,
+            tracker: PatternTracker::new(io_pattern),
+            _sponge: PhantomData,
+        })
+    }
+
+    /// Absorbs `input`, if the pattern expects the absorption of at least `input.len()` elements.
+    #[tracing::instrument(target = "gr1cs", skip(self, input))]
+    pub fn absorb(&mut self, input: &[FpVar<F>]) -> Result<(), SafeError> {
+        let len = u32::try_from(input.len()).map_err(|_| SafeError::InputTooLong(input.len()))?;
+        self.tracker.check(SpongeOp::Absorb(len))?;
+        self.sponge.absorb(&input)?;
+        Ok(())
+    }
+
+    /// Squeezes `len` field elements, if the pattern expects the squeeze of at least `len`
+    /// elements.
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    pub fn squeeze(&mut self, len: u32) -> Result<Vec<FpVar<F>>, SafeError> {
+        self.tracker.check(SpongeOp::Squeeze(len))?;
+        Ok(self.sponge.squeeze_field_elements(len as usize)?)
+    }
+
+    /// Consumes the sponge gadget, checking that the whole pattern was followed.
+    pub fn finish(self) -> Result<(), SafeError> {
+        self.tracker.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sponge::poseidon::{
+        constraints::PoseidonSpongeVar, PoseidonDefaultConfigField, PoseidonSponge,
+    };
+    use crate::sponge::safe::{constraints::SafeSpongeVar, IOPattern, SafeError, SafeSponge};
+    use crate::sponge::test::Fr;
+    use ark_ff::UniformRand;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::gr1cs::ConstraintSystem;
+    use ark_relations::*;
+    use ark_std::test_rng;
+
+    #[test]
+    fn safe_sponge_consistency_test() {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+        let params = Fr::get_default_poseidon_parameters(2, false).unwrap();
+        let pattern = IOPattern::new().absorb(3).squeeze(1).absorb(2).squeeze(3);
+
+        let input: Vec<_> = (0..5).map(|_| Fr::rand(&mut rng)).collect();
+        let input_var: Vec<_> = input
+            .iter()
+            .map(|v| FpVar::new_witness(ns!(cs, "input"), || Ok(*v)).unwrap())
+            .collect();
+
+        let mut native_sponge =
+            SafeSponge::<Fr, PoseidonSponge<Fr>>::new(&params, &pattern, b"test").unwrap();
+        let mut constraint_sponge =
+            SafeSpongeVar::<Fr, PoseidonSponge<Fr>, PoseidonSpongeVar<Fr>>::new(
+                cs.clone(),
+                &params,
+                &pattern,
+                b"test",
+            )
+            .unwrap();
+
+        native_sponge.absorb(&input[..3]).unwrap();
+        constraint_sponge.absorb(&input_var[..3]).unwrap();
+        assert_eq!(
+            constraint_sponge.squeeze(1).unwrap().value().unwrap(),
+            native_sponge.squeeze(1).unwrap()
+        );
+
+        native_sponge.absorb(&input[3..]).unwrap();
+        constraint_sponge.absorb(&input_var[3..]).unwrap();
+        assert_eq!(
+            constraint_sponge.squeeze(3).unwrap().value().unwrap(),
+            native_sponge.squeeze(3).unwrap()
+        );
+
+        assert!(matches!(
+            constraint_sponge.clone().absorb(&input_var),
+            Err(SafeError::UnexpectedOp { expected: None, .. })
+        ));
+        native_sponge.finish().unwrap();
+        constraint_sponge.finish().unwrap();
+        assert!(cs.is_satisfied().unwrap());
+    }
+}
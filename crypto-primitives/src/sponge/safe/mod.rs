@@ -0,0 +1,411 @@
+use crate::sponge::{
+    poseidon::PoseidonSponge, poseidon2::Poseidon2Sponge, rescue::RescueSponge, Absorb,
+    CryptographicSponge, FieldBasedCryptographicSponge,
+};
+use ark_ff::PrimeField;
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+use ark_std::{fmt, marker::PhantomData};
+use sha2::{Digest, Sha256};
+
+/// constraints for the SAFE API
+#[cfg(feature = "constraints")]
+pub mod constraints;
+
+/// An operation of an IO pattern.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpongeOp {
+    /// Absorb the given number of field elements.
+    Absorb(u32),
+    /// Squeeze the given number of field elements.
+    Squeeze(u32),
+}
+
+impl SpongeOp {
+    /// Returns the encoding of the operation in the tag: the most significant bit is set for
+    /// absorptions, and the others are the number of field elements.
+    fn encode(&self) -> u32 {
+        match *self {
+            SpongeOp::Absorb(len) => 0x8000_0000 | len,
+            SpongeOp::Squeeze(len) => len,
+        }
+    }
+
+    fn len(&self) -> u32 {
+        match *self {
+            SpongeOp::Absorb(len) | SpongeOp::Squeeze(len) => len,
+        }
+    }
+
+    fn with_len(&self, len: u32) -> Self {
+        match self {
+            SpongeOp::Absorb(_) => SpongeOp::Absorb(len),
+            SpongeOp::Squeeze(_) => SpongeOp::Squeeze(len),
+        }
+    }
+
+    fn is_same_kind(&self, other: &Self) -> bool {
+        matches!(
+            (self, other),
+            (SpongeOp::Absorb(_), SpongeOp::Absorb(_))
+                | (SpongeOp::Squeeze(_), SpongeOp::Squeeze(_))
+        )
+    }
+}
+
+/// The sequence of absorptions and squeezes of a [`SafeSponge`], declared before any call.
+///
+/// Consecutive operations of the same kind are aggregated, as in the
+/// [SAFE](https://eprint.iacr.org/2023/522) specification, so that absorbing 1 then 2 elements
+/// is the same pattern as absorbing 3 elements.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct IOPattern(Vec<SpongeOp>);
+
+impl IOPattern {
+    /// Returns an empty pattern.
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Appends the absorption of `len` field elements to the pattern.
+    pub fn absorb(self, len: u32) -> Self {
+        self.push(SpongeOp::Absorb(len))
+    }
+
+    /// Appends the squeeze of `len` field elements to the pattern.
+    pub fn squeeze(self, len: u32) -> Self {
+        self.push(SpongeOp::Squeeze(len))
+    }
+
+    fn push(mut self, op: SpongeOp) -> Self {
+        assert!(op.len() > 0, "operations should have a positive length");
+        match self.0.last_mut() {
+            Some(last) if last.is_same_kind(&op) => {
+                let len = last
+                    .len()
+                    .checked_add(op.len())
+                    .expect("the length of the operation overflows");
+                *last = last.with_len(len);
+            }
+            _ => self.0.push(op),
+        }
+        self
+    }
+
+    /// Returns the aggregated operations of the pattern.
+    pub fn ops(&self) -> &[SpongeOp] {
+        &self.0
+    }
+
+    /// Returns the tag of the pattern for `domain_separator`, which is the first 128 bits of the
+    /// SHA-256 hash of the big-endian encodings of the operations followed by the domain
+    /// separator, read as a little-endian integer.
+    pub fn tag<F: PrimeField>(&self, domain_separator: &[u8]) -> F {
+        let mut hasher = Sha256::new();
+        for op in &self.0 {
+            hasher.update(op.encode().to_be_bytes());
+        }
+        hasher.update(domain_separator);
+        F::from_le_bytes_mod_order(&hasher.finalize()[..16])
+    }
+}
+
+/// The error returned when a [`SafeSponge`] has no capacity, or when its calls do not follow its
+/// IO pattern.
+#[derive(Debug)]
+pub enum SafeError {
+    /// The call does not match the next operation of the pattern, or there is none.
+    UnexpectedOp {
+        /// The rest of the next operation of the pattern.
+        expected: Option<SpongeOp>,
+        /// The operation of the call.
+        found: SpongeOp,
+    },
+    /// The sponge was finished before the end of the pattern.
+    UnfinishedPattern {
+        /// The rest of the next operation of the pattern.
+        expected: SpongeOp,
+    },
+    /// The input of the call has more than `u32::MAX` elements, so no pattern can expect it.
+    InputTooLong(usize),
+    /// The sponge has no capacity element to hold the tag of the pattern.
+    NoCapacity,
+    /// The constraints of the call could not be generated.
+    #[cfg(feature = "constraints")]
+    SynthesisError(ark_relations::gr1cs::SynthesisError),
+}
+
+impl fmt::Display for SafeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnexpectedOp {
+                expected: Some(expected),
+                found,
+            } => write!(f, "expected {expected:?}, found {found:?}"),
+            Self::UnexpectedOp {
+                expected: None,
+                found,
+            } => write!(f, "expected the end of the pattern, found {found:?}"),
+            Self::UnfinishedPattern { expected } => {
+                write!(
+                    f,
+                    "finished before the end of the pattern, expected {expected:?}"
+                )
+            }
+            Self::InputTooLong(len) => {
+                write!(f, "the input has {len} elements, more than u32::MAX")
+            }
+            Self::NoCapacity => write!(f, "the sponge has no capacity for the tag"),
+            #[cfg(feature = "constraints")]
+            Self::SynthesisError(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl ark_std::error::Error for SafeError {}
+
+#[cfg(feature = "constraints")]
+impl From<ark_relations::gr1cs::SynthesisError> for SafeError {
+    fn from(e: ark_relations::gr1cs::SynthesisError) -> Self {
+        Self::SynthesisError(e)
+    }
+}
+
+/// Tracks the position of the calls of a sponge in its IO pattern.
+#[derive(Clone, Debug)]
+pub(crate) struct PatternTracker {
+    ops: Vec<SpongeOp>,
+    /// The index of the next operation in `ops`.
+    next: usize,
+    /// The number of field elements already absorbed or squeezed by the next operation.
+    done: u32,
+}
+
+impl PatternTracker {
+    pub(crate) fn new(io_pattern: &IOPattern) -> Self {
+        Self {
+            ops: io_pattern.ops().to_vec(),
+            next: 0,
+            done: 0,
+        }
+    }
+
+    /// Returns the rest of the next operation of the pattern.
+    fn expected(&self) -> Option<SpongeOp> {
+        self.ops
+            .get(self.next)
+            .map(|op| op.with_len(op.len() - self.done))
+    }
+
+    /// Checks that `found` is allowed by the pattern, and moves past it.
+    pub(crate) fn check(&mut self, found: SpongeOp) -> Result<(), SafeError> {
+        let expected = self.expected();
+        match expected {
+            Some(op) if op.is_same_kind(&found) && found.len() <= op.len() => {
+                self.done += found.len();
+                if found.len() == op.len() {
+                    self.next += 1;
+                    self.done = 0;
+                }
+                Ok(())
+            }
+            _ => Err(SafeError::UnexpectedOp { expected, found }),
+        }
+    }
+
+    /// Checks that the whole pattern was followed.
+    pub(crate) fn finish(&self) -> Result<(), SafeError> {
+        match self.expected() {
+            Some(expected) => Err(SafeError::UnfinishedPattern { expected }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A sponge whose capacity can be initialized with the tag of a [`SafeSponge`].
+pub trait TaggedSponge<F: PrimeField>: FieldBasedCryptographicSponge<F> {
+    /// Returns a sponge whose first capacity element is `tag`, and whose other state elements
+    /// are zero, or an error if the sponge has no capacity.
+    fn new_with_tag(params: &Self::Config, tag: F) -> Result<Self, SafeError>;
+}
+
+impl<F: PrimeField> TaggedSponge<F> for PoseidonSponge<F> {
+    fn new_with_tag(params: &Self::Config, tag: F) -> Result<Self, SafeError> {
+        if params.capacity == 0 {
+            return Err(SafeError::NoCapacity);
+        }
+        let mut sponge = Self::new(params);
+        sponge.state[0] = tag;
+        Ok(sponge)
+    }
+}
+
+impl<F: PrimeField> TaggedSponge<F> for RescueSponge<F> {
+    fn new_with_tag(params: &Self::Config, tag: F) -> Result<Self, SafeError> {
+        if params.capacity == 0 {
+            return Err(SafeError::NoCapacity);
+        }
+        let mut sponge = Self::new(params);
+        sponge.state[0] = tag;
+        Ok(sponge)
+    }
+}
+
+impl<F: PrimeField> TaggedSponge<F> for Poseidon2Sponge<F> {
+    fn new_with_tag(params: &Self::Config, tag: F) -> Result<Self, SafeError> {
+        if params.capacity == 0 {
+            return Err(SafeError::NoCapacity);
+        }
+        let mut sponge = Self::new(params);
+        sponge.state[0] = tag;
+        Ok(sponge)
+    }
+}
+
+/// A sponge following the [SAFE](https://eprint.iacr.org/2023/522) API: the sequence of
+/// absorptions and squeezes is declared up front as an [`IOPattern`], whose tag initializes the
+/// capacity of the sponge.
+///
+/// Two instances with different patterns or domain separators thus behave as unrelated sponges,
+/// and a call that does not follow the pattern returns a [`SafeError`] instead of silently
+/// producing an output that may collide with the output of another protocol.
+#[derive(Clone)]
+pub struct SafeSponge<F: PrimeField, S: TaggedSponge<F>> {
+    sponge: S,
+    tracker: PatternTracker,
+    _field: PhantomData<F>,
+}
+
+impl<F: PrimeField + Absorb, S: TaggedSponge<F>> SafeSponge<F, S> {
+    /// Returns a sponge for `io_pattern` and `domain_separator`, or an error if the sponge has no
+    /// capacity.
+    pub fn new(
+        params: &S::Config,
+        io_pattern: &IOPattern,
+        domain_separator: &[u8],
+    ) -> Result<Self, SafeError> {
+        let tag = io_pattern.tag(domain_separator);
+        Ok(Self {
+            sponge: S::new_with_tag(params, tag)?,
+            tracker: PatternTracker::new(io_pattern),
+            _field: PhantomData,
+        })
+    }
+
+    /// Absorbs `input`, if the pattern expects the absorption of at least `input.len()` elements.
+    pub fn absorb(&mut self, input: &[F]) -> Result<(), SafeError> {
+        let len = u32::try_from(input.len()).map_err(|_| SafeError::InputTooLong(input.len()))?;
+        self.tracker.check(SpongeOp::Absorb(len))?;
+        self.sponge.absorb(&input);
+        Ok(())
+    }
+
+    /// Squeezes `len` field elements, if the pattern expects the squeeze of at least `len`
+    /// elements.
+    pub fn squeeze(&mut self, len: u32) -> Result<Vec<F>, SafeError> {
+        self.tracker.check(SpongeOp::Squeeze(len))?;
+        Ok(self.sponge.squeeze_native_field_elements(len as usize))
+    }
+
+    /// Consumes the sponge, checking that the whole pattern was followed.
+    pub fn finish(self) -> Result<(), SafeError> {
+        self.tracker.finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sponge::poseidon::{PoseidonDefaultConfigField, PoseidonSponge};
+    use crate::sponge::safe::{IOPattern, SafeError, SafeSponge, SpongeOp};
+    use crate::sponge::test::Fr;
+    use ark_ff::UniformRand;
+    use ark_std::test_rng;
+
+    type Sponge = SafeSponge<Fr, PoseidonSponge<Fr>>;
+
+    #[test]
+    fn io_pattern_test() {
+        let pattern = IOPattern::new().absorb(1).absorb(2).squeeze(1).absorb(1);
+        assert_eq!(
+            pattern.ops(),
+            &[
+                SpongeOp::Absorb(3),
+                SpongeOp::Squeeze(1),
+                SpongeOp::Absorb(1)
+            ]
+        );
+        assert_eq!(pattern, IOPattern::new().absorb(3).squeeze(1).absorb(1));
+        assert_ne!(
+            pattern.tag::<Fr>(b"domain"),
+            IOPattern::new().absorb(3).squeeze(1).tag::<Fr>(b"domain")
+        );
+        assert_ne!(pattern.tag::<Fr>(b"domain"), pattern.tag::<Fr>(b"other"));
+    }
+
+    #[test]
+    fn safe_sponge_test() {
+        let mut rng = test_rng();
+        let params = Fr::get_default_poseidon_parameters(2, false).unwrap();
+        let pattern = IOPattern::new().absorb(3).squeeze(2);
+        let input: Vec<_> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+
+        let mut sponge = Sponge::new(&params, &pattern, b"test").unwrap();
+        sponge.absorb(&input).unwrap();
+        let output = sponge.squeeze(2).unwrap();
+        sponge.finish().unwrap();
+
+        // calls may split the operations of the pattern
+        let mut sponge = Sponge::new(&params, &pattern, b"test").unwrap();
+        sponge.absorb(&input[..1]).unwrap();
+        sponge.absorb(&input[1..]).unwrap();
+        let mut split_output = sponge.squeeze(1).unwrap();
+        split_output.extend(sponge.squeeze(1).unwrap());
+        sponge.finish().unwrap();
+        assert_eq!(output, split_output);
+
+        // the domain separator changes the output
+        let mut sponge = Sponge::new(&params, &pattern, b"other").unwrap();
+        sponge.absorb(&input).unwrap();
+        assert_ne!(output, sponge.squeeze(2).unwrap());
+    }
+
+    #[test]
+    fn safe_sponge_errors_test() {
+        let params = Fr::get_default_poseidon_parameters(2, false).unwrap();
+        let pattern = IOPattern::new().absorb(2).squeeze(1);
+        let input = vec![Fr::from(1u8); 3];
+
+        let mut sponge = Sponge::new(&params, &pattern, b"test").unwrap();
+        assert!(matches!(
+            sponge.squeeze(1),
+            Err(SafeError::UnexpectedOp {
+                expected: Some(SpongeOp::Absorb(2)),
+                found: SpongeOp::Squeeze(1),
+            })
+        ));
+        assert!(sponge.absorb(&input).is_err());
+        sponge.absorb(&input[..1]).unwrap();
+        assert!(matches!(
+            sponge.clone().finish(),
+            Err(SafeError::UnfinishedPattern {
+                expected: SpongeOp::Absorb(1),
+            })
+        ));
+        sponge.absorb(&input[..1]).unwrap();
+        sponge.squeeze(1).unwrap();
+        assert!(matches!(
+            sponge.squeeze(1),
+            Err(SafeError::UnexpectedOp { expected: None, .. })
+        ));
+        sponge.finish().unwrap();
+
+        // the tag needs a capacity element
+        let mut params = params;
+        params.rate += params.capacity;
+        params.capacity = 0;
+        assert!(matches!(
+            Sponge::new(&params, &pattern, b"test"),
+            Err(SafeError::NoCapacity)
+        ));
+    }
+}
@@ -10,6 +10,14 @@ pub struct FrBackend;
 type FrConfig = MontBackend<FrBackend, 4>;
 pub type Fr = Fp256<FrConfig>;
 
+#[derive(MontConfig)]
+#[modulus = "21888242871839275222246405745257275088548364400416034343698204186575808495617"]
+#[generator = "5"]
+pub struct Bn254FrBackend;
+
+/// The scalar field of BN254, over which reference test vectors are commonly given.
+pub type Bn254Fr = Fp256<MontBackend<Bn254FrBackend, 4>>;
+
 impl PoseidonDefaultConfig<4> for FrConfig {
     const PARAMS_OPT_FOR_CONSTRAINTS: [PoseidonDefaultConfigEntry; 7] = [
         PoseidonDefaultConfigEntry::new(2, 17, 8, 31, 0),
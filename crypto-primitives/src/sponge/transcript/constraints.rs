@@ -0,0 +1,232 @@
+use crate::sponge::{
+    constraints::{AbsorbGadget, CryptographicSpongeVar},
+    transcript::label_bytes,
+    Absorb, CryptographicSponge,
+};
+use ark_ff::PrimeField;
+use ark_r1cs_std::{fields::fp::FpVar, uint8::UInt8};
+use ark_relations::gr1cs::{ConstraintSystemRef, SynthesisError};
+use ark_std::marker::PhantomData;
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+
+/// The interface for the gadget of a [`Transcript`](super::Transcript).
+///
+/// The labels are constants of the circuit, and a gadget derives the same challenges as its
+/// native counterpart for the same calls.
+pub trait TranscriptVar<CF: PrimeField>: Clone {
+    /// Parameters used by the transcript.
+    type Parameters;
+
+    /// Initialize a new instance of the transcript.
+    fn new(cs: ConstraintSystemRef<CF>, params: &Self::Parameters) -> Self;
+
+    /// Returns a ConstraintSystemRef that the transcript is operating in.
+    fn cs(&self) -> ConstraintSystemRef<CF>;
+
+    /// Append `bytes` to the transcript, under `label`.
+    fn append_bytes(
+        &mut self,
+        label: &'static [u8],
+        bytes: &[UInt8<CF>],
+    ) -> Result<(), SynthesisError>;
+
+    /// Append `elems` to the transcript, under `label`.
+    fn append_field_elements(
+        &mut self,
+        label: &'static [u8],
+        elems: &[FpVar<CF>],
+    ) -> Result<(), SynthesisError>;
+
+    /// Append `elems` to the transcript, under `label`.
+    fn append_group_elements<G: AbsorbGadget<CF>>(
+        &mut self,
+        label: &'static [u8],
+        elems: &[G],
+    ) -> Result<(), SynthesisError>;
+
+    /// Returns a challenge of `num_bytes` bytes, bound to `label`.
+    fn challenge_bytes(
+        &mut self,
+        label: &'static [u8],
+        num_bytes: usize,
+    ) -> Result<Vec<UInt8<CF>>, SynthesisError>;
+
+    /// Returns a challenge of `num_elements` field elements, bound to `label`.
+    fn challenge_field_elements(
+        &mut self,
+        label: &'static [u8],
+        num_elements: usize,
+    ) -> Result<Vec<FpVar<CF>>, SynthesisError>;
+}
+
+/// The gadget for [`SpongeTranscript`](super::SpongeTranscript).
+#[derive(Clone)]
+pub struct SpongeTranscriptVar<
+    CF: PrimeField,
+    S: CryptographicSponge,
+    SV: CryptographicSpongeVar<CF, S>,
+> {
+    /// The underlying sponge gadget
+    pub sponge: SV,
+    _sponge: PhantomData<(CF, S)>,
+}
+
+impl<CF: PrimeField, S: CryptographicSponge, SV: CryptographicSpongeVar<CF, S>>
+    SpongeTranscriptVar<CF, S, SV>
+{
+    fn absorb_label(&mut self, label: &[u8], num_items: usize) -> Result<(), SynthesisError> {
+        let elems: Vec<CF> = label_bytes(label, num_items).to_sponge_field_elements_as_vec();
+        let elem_vars = elems.into_iter().map(FpVar::Constant).collect::<Vec<_>>();
+        self.sponge.absorb(&elem_vars)
+    }
+}
+
+impl<CF: PrimeField, S: CryptographicSponge, SV: CryptographicSpongeVar<CF, S>> TranscriptVar<CF>
+    for SpongeTranscriptVar<CF, S, SV>
+{
+    type Parameters = SV::Parameters;
+
+    fn new(cs: ConstraintSystemRef<CF>, params: &Self::Parameters) -> Self {
+        Self {
+            sponge: SV::new(cs, params),
+            _sponge: PhantomData,
+        }
+    }
+
+    fn cs(&self) -> ConstraintSystemRef<CF> {
+        self.sponge.cs()
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self, bytes))]
+    fn append_bytes(
+        &mut self,
+        label: &'static [u8],
+        bytes: &[UInt8<CF>],
+    ) -> Result<(), SynthesisError> {
+        self.absorb_label(label, bytes.len())?;
+        self.sponge.absorb(&bytes)
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self, elems))]
+    fn append_field_elements(
+        &mut self,
+        label: &'static [u8],
+        elems: &[FpVar<CF>],
+    ) -> Result<(), SynthesisError> {
+        self.absorb_label(label, elems.len())?;
+        self.sponge.absorb(&elems)
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self, elems))]
+    fn append_group_elements<G: AbsorbGadget<CF>>(
+        &mut self,
+        label: &'static [u8],
+        elems: &[G],
+    ) -> Result<(), SynthesisError> {
+        self.absorb_label(label, elems.len())?;
+        self.sponge.absorb(&elems)
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn challenge_bytes(
+        &mut self,
+        label: &'static [u8],
+        num_bytes: usize,
+    ) -> Result<Vec<UInt8<CF>>, SynthesisError> {
+        self.absorb_label(label, num_bytes)?;
+        self.sponge.squeeze_bytes(num_bytes)
+    }
+
+    #[tracing::instrument(target = "gr1cs", skip(self))]
+    fn challenge_field_elements(
+        &mut self,
+        label: &'static [u8],
+        num_elements: usize,
+    ) -> Result<Vec<FpVar<CF>>, SynthesisError> {
+        self.absorb_label(label, num_elements)?;
+        self.sponge.squeeze_field_elements(num_elements)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sponge::poseidon::{
+        constraints::PoseidonSpongeVar, PoseidonDefaultConfigField, PoseidonSponge,
+    };
+    use crate::sponge::rescue::{constraints::RescueSpongeVar, RescueConfig, RescueSponge};
+    use crate::sponge::transcript::{
+        constraints::{SpongeTranscriptVar, TranscriptVar},
+        SpongeTranscript, Transcript,
+    };
+    use crate::sponge::{constraints::CryptographicSpongeVar, CryptographicSponge};
+    use ark_ec::CurveGroup;
+    use ark_ed_on_bls12_381::{constraints::EdwardsVar, EdwardsProjective, Fq as Fr};
+    use ark_ff::UniformRand;
+    use ark_r1cs_std::fields::fp::FpVar;
+    use ark_r1cs_std::prelude::*;
+    use ark_relations::gr1cs::ConstraintSystem;
+    use ark_relations::*;
+    use ark_std::test_rng;
+
+    fn consistency_test<S, SV>(params: &S::Config)
+    where
+        S: CryptographicSponge,
+        SV: CryptographicSpongeVar<Fr, S, Parameters = S::Config>,
+    {
+        let mut rng = test_rng();
+        let cs = ConstraintSystem::new_ref();
+
+        let bytes = b"message".to_vec();
+        let bytes_var = UInt8::new_witness_vec(ns!(cs, "bytes"), &bytes).unwrap();
+        let elems: Vec<_> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let elems_var: Vec<_> = elems
+            .iter()
+            .map(|v| FpVar::new_witness(ns!(cs, "elems"), || Ok(*v)).unwrap())
+            .collect();
+        let point = EdwardsProjective::rand(&mut rng);
+        let point_var = EdwardsVar::new_witness(ns!(cs, "point"), || Ok(point)).unwrap();
+
+        let mut native_transcript = SpongeTranscript::<S>::new(params);
+        let mut constraint_transcript = SpongeTranscriptVar::<Fr, S, SV>::new(cs.clone(), params);
+
+        native_transcript.append_bytes(b"bytes", &bytes);
+        native_transcript.append_field_elements(b"elems", &elems);
+        native_transcript.append_group_elements(b"point", &[point.into_affine()]);
+        constraint_transcript
+            .append_bytes(b"bytes", &bytes_var)
+            .unwrap();
+        constraint_transcript
+            .append_field_elements(b"elems", &elems_var)
+            .unwrap();
+        constraint_transcript
+            .append_group_elements(b"point", &[point_var])
+            .unwrap();
+
+        let challenge: Vec<Fr> = native_transcript.challenge_field_elements(b"challenge", 2);
+        let challenge_var = constraint_transcript
+            .challenge_field_elements(b"challenge", 2)
+            .unwrap();
+        assert_eq!(challenge_var.value().unwrap(), challenge);
+
+        let challenge = native_transcript.challenge_bytes(b"bytes", 16);
+        let challenge_var = constraint_transcript.challenge_bytes(b"bytes", 16).unwrap();
+        assert_eq!(challenge_var.value().unwrap(), challenge);
+
+        assert!(cs.is_satisfied().unwrap());
+    }
+
+    #[test]
+    fn poseidon_transcript_consistency_test() {
+        consistency_test::<PoseidonSponge<Fr>, PoseidonSpongeVar<Fr>>(
+            &Fr::get_default_poseidon_parameters(2, false).unwrap(),
+        );
+    }
+
+    #[test]
+    fn rescue_transcript_consistency_test() {
+        consistency_test::<RescueSponge<Fr>, RescueSpongeVar<Fr>>(
+            &RescueConfig::generate(2, 1, 128).unwrap(),
+        );
+    }
+}
@@ -0,0 +1,228 @@
+use crate::sponge::{
+    merlin::Transcript as MerlinTranscript, poseidon::PoseidonSponge, rescue::RescueSponge, Absorb,
+    CryptographicSponge,
+};
+use ark_ec::AffineRepr;
+use ark_ff::PrimeField;
+#[cfg(not(feature = "std"))]
+use ark_std::vec::Vec;
+
+/// constraints for transcripts
+#[cfg(feature = "constraints")]
+pub mod constraints;
+
+/// A Fiat-Shamir transcript, where every message and challenge is bound to a label.
+///
+/// Unlike [`CryptographicSponge`], which has no notion of labels, a transcript commits to the
+/// label and the length of every call, so that the same protocol code derives unambiguous
+/// challenges whatever the backend. `TranscriptVar` is the in-circuit counterpart.
+pub trait Transcript: Clone {
+    /// The configuration of the transcript.
+    type Config;
+
+    /// Initialize a new instance of the transcript.
+    fn new(params: &Self::Config) -> Self;
+
+    /// Append `bytes` to the transcript, under `label`.
+    fn append_bytes(&mut self, label: &'static [u8], bytes: &[u8]);
+
+    /// Append `elems` to the transcript, under `label`.
+    fn append_field_elements<F: PrimeField + Absorb>(&mut self, label: &'static [u8], elems: &[F]);
+
+    /// Append `elems` to the transcript, under `label`.
+    fn append_group_elements<G: AffineRepr + Absorb>(&mut self, label: &'static [u8], elems: &[G]);
+
+    /// Returns a challenge of `num_bytes` bytes, bound to `label`.
+    fn challenge_bytes(&mut self, label: &'static [u8], num_bytes: usize) -> Vec<u8>;
+
+    /// Returns a challenge of `num_elements` field elements, bound to `label`.
+    fn challenge_field_elements<F: PrimeField>(
+        &mut self,
+        label: &'static [u8],
+        num_elements: usize,
+    ) -> Vec<F>;
+}
+
+/// The number of additional bytes sampled for every field element of a Merlin challenge, so that
+/// its reduction modulo the characteristic is statistically close to uniform.
+const MERLIN_CHALLENGE_EXTRA_BYTES: usize = 16;
+
+impl Transcript for MerlinTranscript {
+    type Config = &'static [u8];
+
+    fn new(params: &Self::Config) -> Self {
+        MerlinTranscript::new(params)
+    }
+
+    fn append_bytes(&mut self, label: &'static [u8], bytes: &[u8]) {
+        self.append_message(label, bytes);
+    }
+
+    fn append_field_elements<F: PrimeField + Absorb>(&mut self, label: &'static [u8], elems: &[F]) {
+        self.append_message(label, &elems.to_sponge_bytes_as_vec());
+    }
+
+    fn append_group_elements<G: AffineRepr + Absorb>(&mut self, label: &'static [u8], elems: &[G]) {
+        self.append_message(label, &elems.to_sponge_bytes_as_vec());
+    }
+
+    fn challenge_bytes(&mut self, label: &'static [u8], num_bytes: usize) -> Vec<u8> {
+        let mut dest = vec![0; num_bytes];
+        MerlinTranscript::challenge_bytes(self, label, &mut dest);
+        dest
+    }
+
+    fn challenge_field_elements<F: PrimeField>(
+        &mut self,
+        label: &'static [u8],
+        num_elements: usize,
+    ) -> Vec<F> {
+        let bytes_per_element =
+            (F::MODULUS_BIT_SIZE as usize + 7) / 8 + MERLIN_CHALLENGE_EXTRA_BYTES;
+        let mut dest = vec![0; num_elements * bytes_per_element];
+        MerlinTranscript::challenge_bytes(self, label, &mut dest);
+        dest.chunks(bytes_per_element)
+            .map(F::from_le_bytes_mod_order)
+            .collect()
+    }
+}
+
+/// Returns the bytes absorbed before a call to a sponge-based transcript: the length of `label`,
+/// `label` itself, and the number of items of the call.
+pub(crate) fn label_bytes(label: &[u8], num_items: usize) -> Vec<u8> {
+    let mut bytes = Absorb::to_sponge_bytes_as_vec(&label.len());
+    bytes.extend_from_slice(label);
+    num_items.to_sponge_bytes(&mut bytes);
+    bytes
+}
+
+/// A transcript over any [`CryptographicSponge`], which absorbs the label of every call before its
+/// input, in the same way as [`CryptographicSponge::fork`].
+#[derive(Clone)]
+pub struct SpongeTranscript<S: CryptographicSponge> {
+    /// The underlying sponge
+    pub sponge: S,
+}
+
+/// A transcript over the Poseidon sponge.
+pub type PoseidonTranscript<F> = SpongeTranscript<PoseidonSponge<F>>;
+
+/// A transcript over the Rescue sponge.
+pub type RescueTranscript<F> = SpongeTranscript<RescueSponge<F>>;
+
+impl<S: CryptographicSponge> SpongeTranscript<S> {
+    fn absorb_label(&mut self, label: &[u8], num_items: usize) {
+        self.sponge.absorb(&label_bytes(label, num_items));
+    }
+}
+
+impl<S: CryptographicSponge> Transcript for SpongeTranscript<S> {
+    type Config = S::Config;
+
+    fn new(params: &Self::Config) -> Self {
+        Self {
+            sponge: S::new(params),
+        }
+    }
+
+    fn append_bytes(&mut self, label: &'static [u8], bytes: &[u8]) {
+        self.absorb_label(label, bytes.len());
+        self.sponge.absorb(&bytes);
+    }
+
+    fn append_field_elements<F: PrimeField + Absorb>(&mut self, label: &'static [u8], elems: &[F]) {
+        self.absorb_label(label, elems.len());
+        self.sponge.absorb(&elems);
+    }
+
+    fn append_group_elements<G: AffineRepr + Absorb>(&mut self, label: &'static [u8], elems: &[G]) {
+        self.absorb_label(label, elems.len());
+        self.sponge.absorb(&elems);
+    }
+
+    fn challenge_bytes(&mut self, label: &'static [u8], num_bytes: usize) -> Vec<u8> {
+        self.absorb_label(label, num_bytes);
+        self.sponge.squeeze_bytes(num_bytes)
+    }
+
+    fn challenge_field_elements<F: PrimeField>(
+        &mut self,
+        label: &'static [u8],
+        num_elements: usize,
+    ) -> Vec<F> {
+        self.absorb_label(label, num_elements);
+        self.sponge.squeeze_field_elements(num_elements)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::sponge::merlin::Transcript as MerlinTranscript;
+    use crate::sponge::poseidon::PoseidonDefaultConfigField;
+    use crate::sponge::rescue::RescueConfig;
+    use crate::sponge::test::Fr;
+    use crate::sponge::transcript::{PoseidonTranscript, RescueTranscript, Transcript};
+    use ark_ec::AffineRepr;
+    use ark_ed_on_bls12_381::EdwardsAffine;
+    use ark_ff::UniformRand;
+    use ark_std::test_rng;
+
+    fn labels_test<T: Transcript>(params: &T::Config) {
+        let mut rng = test_rng();
+        let elems: Vec<_> = (0..3).map(|_| Fr::rand(&mut rng)).collect();
+        let point = EdwardsAffine::generator();
+
+        let mut transcript = T::new(params);
+        transcript.append_bytes(b"bytes", b"message");
+        transcript.append_field_elements(b"elems", &elems);
+        transcript.append_group_elements(b"point", &[point]);
+
+        // the same calls give the same challenges
+        let mut same = transcript.clone();
+        let challenge: Vec<Fr> = transcript.challenge_field_elements(b"challenge", 2);
+        assert_eq!(challenge, same.challenge_field_elements(b"challenge", 2));
+        assert_eq!(
+            transcript.challenge_bytes(b"bytes", 16),
+            same.challenge_bytes(b"bytes", 16)
+        );
+
+        // a different label gives a different challenge
+        let mut other = T::new(params);
+        other.append_bytes(b"other", b"message");
+        other.append_field_elements(b"elems", &elems);
+        other.append_group_elements(b"point", &[point]);
+        assert_ne!(
+            challenge,
+            other.challenge_field_elements::<Fr>(b"challenge", 2)
+        );
+
+        // the same input split across calls gives a different challenge
+        let mut other = T::new(params);
+        other.append_bytes(b"bytes", b"mess");
+        other.append_bytes(b"bytes", b"age");
+        other.append_field_elements(b"elems", &elems);
+        other.append_group_elements(b"point", &[point]);
+        assert_ne!(
+            challenge,
+            other.challenge_field_elements::<Fr>(b"challenge", 2)
+        );
+    }
+
+    #[test]
+    fn merlin_transcript_test() {
+        let label: &'static [u8] = b"test";
+        labels_test::<MerlinTranscript>(&label);
+    }
+
+    #[test]
+    fn poseidon_transcript_test() {
+        labels_test::<PoseidonTranscript<Fr>>(
+            &Fr::get_default_poseidon_parameters(2, false).unwrap(),
+        );
+    }
+
+    #[test]
+    fn rescue_transcript_test() {
+        labels_test::<RescueTranscript<Fr>>(&RescueConfig::generate(2, 1, 128).unwrap());
+    }
+}